@@ -12,11 +12,37 @@ Abstract:
 
 --*/
 
+use crate::mailbox::CommandId;
+
 bitflags::bitflags! {
-    #[derive(Default, Copy, Clone, Debug)]
+    // Bits 0-63 describe ROM capabilities, bits 64-127 describe the mailbox
+    // commands compiled into Runtime Firmware.
+    #[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
     pub struct Capabilities : u128 {
         // Represents base capabilities present in Caliptra ROM v1.0
         const ROM_BASE = 0b0001;
+
+        // Runtime Firmware mailbox commands
+        const RT_FIRMWARE_LOAD = 1 << 64;
+        const RT_GET_IDEV_CERT = 1 << 65;
+        const RT_GET_IDEV_CSR = 1 << 66;
+        const RT_GET_IDEV_INFO = 1 << 67;
+        const RT_GET_LDEV_CERT = 1 << 68;
+        const RT_GET_CERT_CHAIN = 1 << 69;
+        const RT_INVOKE_DPE = 1 << 70;
+        const RT_ECDSA384_VERIFY = 1 << 71;
+        const RT_STASH_MEASUREMENT = 1 << 72;
+        const RT_DISABLE_ATTESTATION = 1 << 73;
+        const RT_FW_INFO = 1 << 74;
+        const RT_CAPABILITIES = 1 << 75;
+        const RT_VERSION = 1 << 76;
+        const RT_SHUTDOWN = 1 << 77;
+        const RT_SELF_TEST_START = 1 << 78;
+        const RT_SELF_TEST_GET_RESULTS = 1 << 79;
+        const RT_TEST_ONLY_GET_FMC_ALIAS_CERT = 1 << 80;
+        const RT_TEST_ONLY_HMAC384_VERIFY = 1 << 81;
+        const RT_GET_MEASUREMENT_LOG = 1 << 82;
+        const RT_QUOTE = 1 << 83;
     }
 }
 
@@ -25,6 +51,39 @@ impl Capabilities {
     pub fn to_bytes(&self) -> [u8; Capabilities::SIZE_IN_BYTES] {
         self.bits().to_be_bytes()
     }
+
+    /// Returns the Runtime Firmware capability bit for `cmd`, if there is one
+    pub fn from_rt_command(cmd: CommandId) -> Option<Self> {
+        let cap = match cmd {
+            CommandId::FIRMWARE_LOAD => Self::RT_FIRMWARE_LOAD,
+            CommandId::GET_IDEV_CERT => Self::RT_GET_IDEV_CERT,
+            CommandId::GET_IDEV_CSR => Self::RT_GET_IDEV_CSR,
+            CommandId::GET_IDEV_INFO => Self::RT_GET_IDEV_INFO,
+            CommandId::GET_LDEV_CERT => Self::RT_GET_LDEV_CERT,
+            CommandId::GET_CERT_CHAIN => Self::RT_GET_CERT_CHAIN,
+            CommandId::INVOKE_DPE => Self::RT_INVOKE_DPE,
+            CommandId::ECDSA384_VERIFY => Self::RT_ECDSA384_VERIFY,
+            CommandId::STASH_MEASUREMENT => Self::RT_STASH_MEASUREMENT,
+            CommandId::DISABLE_ATTESTATION => Self::RT_DISABLE_ATTESTATION,
+            CommandId::FW_INFO => Self::RT_FW_INFO,
+            CommandId::CAPABILITIES => Self::RT_CAPABILITIES,
+            CommandId::VERSION => Self::RT_VERSION,
+            CommandId::SHUTDOWN => Self::RT_SHUTDOWN,
+            CommandId::SELF_TEST_START => Self::RT_SELF_TEST_START,
+            CommandId::SELF_TEST_GET_RESULTS => Self::RT_SELF_TEST_GET_RESULTS,
+            CommandId::TEST_ONLY_GET_FMC_ALIAS_CERT => Self::RT_TEST_ONLY_GET_FMC_ALIAS_CERT,
+            CommandId::TEST_ONLY_HMAC384_VERIFY => Self::RT_TEST_ONLY_HMAC384_VERIFY,
+            CommandId::GET_MEASUREMENT_LOG => Self::RT_GET_MEASUREMENT_LOG,
+            CommandId::QUOTE => Self::RT_QUOTE,
+            _ => return None,
+        };
+        Some(cap)
+    }
+
+    /// Returns true if Runtime Firmware reports support for `cmd`
+    pub fn supports_rt_command(&self, cmd: CommandId) -> bool {
+        Self::from_rt_command(cmd).is_some_and(|cap| self.contains(cap))
+    }
 }
 
 impl TryFrom<&[u8]> for Capabilities {
@@ -5,6 +5,8 @@
 mod capabilities;
 mod checksum;
 pub mod mailbox;
+#[cfg(feature = "verify")]
+pub mod quote;
 
 pub use caliptra_error as error;
 pub use capabilities::Capabilities;
@@ -1,7 +1,7 @@
 // Licensed under the Apache-2.0 license
 
 use caliptra_error::{CaliptraError, CaliptraResult};
-use core::mem::size_of;
+use core::mem::{size_of, size_of_val};
 use zerocopy::{AsBytes, FromBytes, LayoutVerified};
 
 #[derive(PartialEq, Eq)]
@@ -17,9 +17,10 @@ impl CommandId {
     pub const INVOKE_DPE: Self = Self(0x44504543); // "DPEC"
     pub const DISABLE_ATTESTATION: Self = Self(0x4453424C); // "DSBL"
     pub const FW_INFO: Self = Self(0x494E464F); // "INFO"
+    pub const GET_CERT_CHAIN: Self = Self(0x43484E43); // "CHNC"
+    pub const GET_MEASUREMENT_LOG: Self = Self(0x4D4C4F47); // "MLOG"
+    pub const QUOTE: Self = Self(0x51554F54); // "QUOT"
 
-    // TODO: Remove this and merge with GET_LDEV_CERT once that is implemented
-    pub const TEST_ONLY_GET_LDEV_CERT: Self = Self(0x4345524c); // "CERL"
     pub const TEST_ONLY_GET_FMC_ALIAS_CERT: Self = Self(0x43455246); // "CERF"
     pub const TEST_ONLY_HMAC384_VERIFY: Self = Self(0x484D4143); // "HMAC"
 
@@ -58,12 +59,17 @@ pub enum MailboxResp {
     GetIdevCsr(GetIdevCsrResp),
     GetIdevInfo(GetIdevInfoResp),
     GetLdevCert(GetLdevCertResp),
+    GetCertChain(GetCertChainResp),
     StashMeasurement(StashMeasurementResp),
     InvokeDpeCommand(InvokeDpeResp),
     TestGetFmcAliasCert(TestGetFmcAliasCertResp),
     FipsVersion(FipsVersionResp),
     FwInfo(FwInfoResp),
     Capabilities(CapabilitiesResp),
+    RtCapabilities(RtCapabilitiesResp),
+    SelfTestGetResults(SelfTestGetResultsResp),
+    GetMeasurementLog(GetMeasurementLogResp),
+    Quote(QuoteResp),
 }
 
 impl MailboxResp {
@@ -74,12 +80,17 @@ impl MailboxResp {
             MailboxResp::GetIdevCsr(resp) => resp.as_bytes(),
             MailboxResp::GetIdevInfo(resp) => resp.as_bytes(),
             MailboxResp::GetLdevCert(resp) => resp.as_bytes(),
+            MailboxResp::GetCertChain(resp) => resp.as_bytes(),
             MailboxResp::StashMeasurement(resp) => resp.as_bytes(),
             MailboxResp::InvokeDpeCommand(resp) => resp.as_bytes_partial(),
             MailboxResp::TestGetFmcAliasCert(resp) => resp.as_bytes(),
             MailboxResp::FipsVersion(resp) => resp.as_bytes(),
             MailboxResp::FwInfo(resp) => resp.as_bytes(),
             MailboxResp::Capabilities(resp) => resp.as_bytes(),
+            MailboxResp::RtCapabilities(resp) => resp.as_bytes(),
+            MailboxResp::SelfTestGetResults(resp) => resp.as_bytes(),
+            MailboxResp::GetMeasurementLog(resp) => resp.as_bytes_partial(),
+            MailboxResp::Quote(resp) => resp.as_bytes(),
         }
     }
 
@@ -90,12 +101,17 @@ impl MailboxResp {
             MailboxResp::GetIdevCsr(resp) => resp.as_bytes_mut(),
             MailboxResp::GetIdevInfo(resp) => resp.as_bytes_mut(),
             MailboxResp::GetLdevCert(resp) => resp.as_bytes_mut(),
+            MailboxResp::GetCertChain(resp) => resp.as_bytes_mut(),
             MailboxResp::StashMeasurement(resp) => resp.as_bytes_mut(),
             MailboxResp::InvokeDpeCommand(resp) => resp.as_bytes_partial_mut(),
             MailboxResp::TestGetFmcAliasCert(resp) => resp.as_bytes_mut(),
             MailboxResp::FipsVersion(resp) => resp.as_bytes_mut(),
             MailboxResp::FwInfo(resp) => resp.as_bytes_mut(),
             MailboxResp::Capabilities(resp) => resp.as_bytes_mut(),
+            MailboxResp::RtCapabilities(resp) => resp.as_bytes_mut(),
+            MailboxResp::SelfTestGetResults(resp) => resp.as_bytes_mut(),
+            MailboxResp::GetMeasurementLog(resp) => resp.as_bytes_partial_mut(),
+            MailboxResp::Quote(resp) => resp.as_bytes_mut(),
         }
     }
 
@@ -213,6 +229,27 @@ impl GetLdevCertResp {
     pub const DATA_MAX_SIZE: usize = 1024;
 }
 
+// GET_CERT_CHAIN
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct GetCertChainReq {
+    pub hdr: MailboxReqHeader,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct GetCertChainResp {
+    pub hdr: MailboxRespHeader,
+    pub total_size: u32,
+    pub data_size: u32,
+    pub data: [u8; GetCertChainResp::DATA_MAX_SIZE], // variable length
+}
+impl GetCertChainResp {
+    pub const DATA_MAX_SIZE: usize = 1024;
+}
+
 // ECDSA384_SIGNATURE_VERIFY
 #[repr(C)]
 #[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
@@ -266,6 +303,172 @@ pub struct StashMeasurementResp {
     pub dpe_result: u32,
 }
 
+// GET_MEASUREMENT_LOG
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct GetMeasurementLogReq {
+    pub hdr: MailboxReqHeader,
+    pub nonce: [u8; 48],
+}
+
+impl Default for GetMeasurementLogReq {
+    fn default() -> Self {
+        Self {
+            hdr: MailboxReqHeader::default(),
+            nonce: [0u8; 48],
+        }
+    }
+}
+
+/// Measurement stashed with ROM or Runtime Firmware
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct RtMeasurementLogEntry {
+    /// Monotonic counter value. ROM-time measurements use the values
+    /// before the first runtime entry.
+    pub counter: u32,
+    /// PAUSER of the caller that stashed the measurement. Zero for
+    /// measurements stashed with ROM, which does not record the caller.
+    pub pauser: u32,
+    pub metadata: [u8; 4],
+    pub svn: u32,
+    pub measurement: [u8; 48],
+    pub context: [u8; 48],
+}
+
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct GetMeasurementLogResp {
+    pub hdr: MailboxRespHeader,
+    /// Signature of the SHA2-384 digest of `signed_data()` by the RT Alias
+    /// key
+    pub signature_r: [u8; 48],
+    pub signature_s: [u8; 48],
+    /// Nonce from the request
+    pub nonce: [u8; 48],
+    pub data_size: u32,
+    pub data: [u8; GetMeasurementLogResp::DATA_MAX_SIZE], // variable length
+}
+
+impl GetMeasurementLogResp {
+    /// 8 measurements stashed with ROM and 32 stashed with Runtime Firmware
+    pub const MAX_ENTRY_COUNT: usize = 40;
+    pub const DATA_MAX_SIZE: usize =
+        GetMeasurementLogResp::MAX_ENTRY_COUNT * size_of::<RtMeasurementLogEntry>();
+
+    /// Returns the bytes covered by the signature: `nonce`, `data_size` and
+    /// `data[..data_size]`
+    pub fn signed_data(&self) -> Option<&[u8]> {
+        let start = size_of::<MailboxRespHeader>()
+            + size_of_val(&self.signature_r)
+            + size_of_val(&self.signature_s);
+        let end = size_of::<Self>()
+            .checked_sub(Self::DATA_MAX_SIZE.checked_sub(self.data_size as usize)?)?;
+        self.as_bytes().get(start..end)
+    }
+
+    /// Returns the log entries, oldest first
+    pub fn entries(&self) -> Option<&[RtMeasurementLogEntry]> {
+        let data = self.data.get(..self.data_size as usize)?;
+        Some(LayoutVerified::<&[u8], [RtMeasurementLogEntry]>::new_slice(data)?.into_slice())
+    }
+
+    fn as_bytes_partial(&self) -> &[u8] {
+        let unused_byte_count = Self::DATA_MAX_SIZE.saturating_sub(self.data_size as usize);
+        &self.as_bytes()[..size_of::<Self>() - unused_byte_count]
+    }
+
+    fn as_bytes_partial_mut(&mut self) -> &mut [u8] {
+        let unused_byte_count = Self::DATA_MAX_SIZE.saturating_sub(self.data_size as usize);
+        &mut self.as_bytes_mut()[..size_of::<Self>() - unused_byte_count]
+    }
+}
+
+impl Default for GetMeasurementLogResp {
+    fn default() -> Self {
+        Self {
+            hdr: MailboxRespHeader::default(),
+            signature_r: [0u8; 48],
+            signature_s: [0u8; 48],
+            nonce: [0u8; 48],
+            data_size: 0,
+            data: [0u8; GetMeasurementLogResp::DATA_MAX_SIZE],
+        }
+    }
+}
+
+// QUOTE
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct QuoteReq {
+    pub hdr: MailboxReqHeader,
+    pub nonce: [u8; 48],
+    /// Bitmask of the PCRs to quote. Bit N selects PCR N.
+    pub pcr_selection: u32,
+}
+
+impl Default for QuoteReq {
+    fn default() -> Self {
+        Self {
+            hdr: MailboxReqHeader::default(),
+            nonce: [0u8; 48],
+            pcr_selection: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct QuoteResp {
+    pub hdr: MailboxRespHeader,
+    /// Nonce from the request
+    pub nonce: [u8; 48],
+    /// PCR selection from the request
+    pub pcr_selection: u32,
+    /// PCR values, indexed by PCR number. PCRs that were not selected are
+    /// zero.
+    pub pcrs: [[u8; 48]; QuoteResp::PCR_COUNT],
+    /// Signature of the SHA2-384 digest of `signed_data()` by the RT Alias
+    /// key
+    pub signature_r: [u8; 48],
+    pub signature_s: [u8; 48],
+}
+
+impl QuoteResp {
+    pub const PCR_COUNT: usize = 32;
+
+    /// Returns the bytes covered by the signature: `nonce`, `pcr_selection`
+    /// and `pcrs`
+    pub fn signed_data(&self) -> &[u8] {
+        let start = size_of::<MailboxRespHeader>();
+        let end =
+            size_of::<Self>() - size_of_val(&self.signature_r) - size_of_val(&self.signature_s);
+        &self.as_bytes()[start..end]
+    }
+
+    /// Returns the value of PCR `id` if it was selected
+    pub fn pcr(&self, id: usize) -> Option<&[u8; 48]> {
+        if id < Self::PCR_COUNT && self.pcr_selection & (1 << id) != 0 {
+            Some(&self.pcrs[id])
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for QuoteResp {
+    fn default() -> Self {
+        Self {
+            hdr: MailboxRespHeader::default(),
+            nonce: [0u8; 48],
+            pcr_selection: 0,
+            pcrs: [[0u8; 48]; QuoteResp::PCR_COUNT],
+            signature_r: [0u8; 48],
+            signature_s: [0u8; 48],
+        }
+    }
+}
+
 // DISABLE_ATTESTATION
 // No command-specific input args
 // No command-specific output args
@@ -338,10 +541,53 @@ impl TestGetFmcAliasCertResp {
     pub const DATA_MAX_SIZE: usize = 1024;
 }
 
-// FIPS_SELF_TEST
+// SELF_TEST_START
 // No command-specific input args
 // No command-specific output args
 
+/// Self tests run by SELF_TEST_START, in execution order. The value is the
+/// index of the test's entry in `SelfTestGetResultsResp::results`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SelfTestId(pub u32);
+
+impl SelfTestId {
+    pub const ROM_INTEGRITY: Self = Self(0);
+    pub const SHA1: Self = Self(1);
+    pub const SHA256: Self = Self(2);
+    pub const SHA384: Self = Self(3);
+    pub const SHA384_ACC: Self = Self(4);
+    pub const ECC384: Self = Self(5);
+    pub const HMAC384: Self = Self(6);
+    pub const LMS: Self = Self(7);
+    pub const IMAGE_VERIFY: Self = Self(8);
+
+    /// Number of self tests
+    pub const COUNT: usize = 9;
+}
+
+// SELF_TEST_GET_RESULTS
+// No command-specific input args
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct SelfTestGetResultsResp {
+    pub hdr: MailboxRespHeader,
+    /// Result of each self test, indexed by `SelfTestId`: 0 if the test
+    /// passed, otherwise the error code it failed with.
+    pub results: [u32; SelfTestId::COUNT],
+}
+
+impl SelfTestGetResultsResp {
+    /// Returns the result of a single self test
+    pub fn result(&self, id: SelfTestId) -> u32 {
+        self.results[id.0 as usize]
+    }
+
+    /// Returns true if every self test passed
+    pub fn all_passed(&self) -> bool {
+        self.results.iter().all(|&r| r == 0)
+    }
+}
+
 // FIPS_GET_VERSION
 // No command-specific input args
 #[repr(C)]
@@ -376,3 +622,16 @@ pub struct CapabilitiesResp {
     pub hdr: MailboxRespHeader,
     pub capabilities: [u8; crate::capabilities::Capabilities::SIZE_IN_BYTES],
 }
+
+// CAPABILITIES (Runtime Firmware)
+// No command-specific input args
+#[repr(C)]
+#[derive(Debug, AsBytes, FromBytes, PartialEq, Eq)]
+pub struct RtCapabilitiesResp {
+    pub hdr: MailboxRespHeader,
+    pub capabilities: [u8; crate::capabilities::Capabilities::SIZE_IN_BYTES],
+    pub dpe_profile: u32,
+    pub dpe_support: u32,
+    pub fmc_version: u32,
+    pub app_version: u32,
+}
@@ -0,0 +1,148 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    quote.rs
+
+Abstract:
+
+    Host-side verification of QUOTE responses
+
+--*/
+
+use crate::mailbox::QuoteResp;
+use p384::ecdsa::signature::hazmat::PrehashVerifier;
+use p384::ecdsa::{Signature, VerifyingKey};
+use p384::elliptic_curve::generic_array::GenericArray;
+use p384::EncodedPoint;
+use sha2::{Digest, Sha384};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteVerifyError {
+    /// The quote does not answer the caller's nonce
+    NonceMismatch,
+    /// The RT Alias public key is not a valid P-384 point
+    InvalidPublicKey,
+    /// The signature is not a valid ECDSA P-384 signature
+    InvalidSignature,
+    /// The signature does not match the quoted data
+    SignatureMismatch,
+}
+
+impl QuoteResp {
+    /// Verify that the quote answers `nonce` and was signed by the RT Alias
+    /// key with public key (`pub_key_x`, `pub_key_y`).
+    ///
+    /// The caller is responsible for validating the RT Alias certificate the
+    /// public key was taken from.
+    pub fn verify(
+        &self,
+        nonce: &[u8; 48],
+        pub_key_x: &[u8; 48],
+        pub_key_y: &[u8; 48],
+    ) -> Result<(), QuoteVerifyError> {
+        if &self.nonce != nonce {
+            return Err(QuoteVerifyError::NonceMismatch);
+        }
+
+        let pub_key = EncodedPoint::from_affine_coordinates(
+            GenericArray::from_slice(pub_key_x),
+            GenericArray::from_slice(pub_key_y),
+            false,
+        );
+        let verifying_key = VerifyingKey::from_encoded_point(&pub_key)
+            .map_err(|_| QuoteVerifyError::InvalidPublicKey)?;
+        let signature = Signature::from_scalars(
+            GenericArray::clone_from_slice(&self.signature_r),
+            GenericArray::clone_from_slice(&self.signature_s),
+        )
+        .map_err(|_| QuoteVerifyError::InvalidSignature)?;
+
+        let digest = Sha384::digest(self.signed_data());
+        verifying_key
+            .verify_prehash(&digest, &signature)
+            .map_err(|_| QuoteVerifyError::SignatureMismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p384::ecdsa::signature::hazmat::PrehashSigner;
+    use p384::ecdsa::SigningKey;
+
+    fn signed_quote(key: &SigningKey) -> QuoteResp {
+        let mut resp = QuoteResp {
+            nonce: [0x5a; 48],
+            pcr_selection: 0b101,
+            ..Default::default()
+        };
+        resp.pcrs[0] = [0x11; 48];
+        resp.pcrs[2] = [0x22; 48];
+
+        let digest = Sha384::digest(resp.signed_data());
+        let signature: Signature = key.sign_prehash(&digest).unwrap();
+        let (r, s) = signature.split_bytes();
+        resp.signature_r.copy_from_slice(&r);
+        resp.signature_s.copy_from_slice(&s);
+        resp
+    }
+
+    fn pub_key(key: &SigningKey) -> ([u8; 48], [u8; 48]) {
+        let point = key.verifying_key().to_encoded_point(false);
+        (
+            point.x().unwrap().as_slice().try_into().unwrap(),
+            point.y().unwrap().as_slice().try_into().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        let key = SigningKey::from_bytes(&[0x42; 48]).unwrap();
+        let (x, y) = pub_key(&key);
+        let resp = signed_quote(&key);
+
+        assert_eq!(resp.verify(&[0x5a; 48], &x, &y), Ok(()));
+        assert_eq!(resp.pcr(0), Some(&[0x11; 48]));
+        assert_eq!(resp.pcr(1), None);
+        assert_eq!(resp.pcr(2), Some(&[0x22; 48]));
+    }
+
+    #[test]
+    fn test_verify_nonce_mismatch() {
+        let key = SigningKey::from_bytes(&[0x42; 48]).unwrap();
+        let (x, y) = pub_key(&key);
+
+        assert_eq!(
+            signed_quote(&key).verify(&[0xa5; 48], &x, &y),
+            Err(QuoteVerifyError::NonceMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_tampered() {
+        let key = SigningKey::from_bytes(&[0x42; 48]).unwrap();
+        let (x, y) = pub_key(&key);
+        let mut resp = signed_quote(&key);
+        resp.pcrs[2][0] ^= 1;
+
+        assert_eq!(
+            resp.verify(&[0x5a; 48], &x, &y),
+            Err(QuoteVerifyError::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_wrong_key() {
+        let key = SigningKey::from_bytes(&[0x42; 48]).unwrap();
+        let other_key = SigningKey::from_bytes(&[0x43; 48]).unwrap();
+        let (x, y) = pub_key(&other_key);
+
+        assert_eq!(
+            signed_quote(&key).verify(&[0x5a; 48], &x, &y),
+            Err(QuoteVerifyError::SignatureMismatch)
+        );
+    }
+}
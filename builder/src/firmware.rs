@@ -41,6 +41,24 @@ pub const APP_WITH_UART: FwId = FwId {
     features: &["emu", "test_only_commands", "fips_self_test"],
 };
 
+pub const APP_WITH_UART_NO_OPTIONAL_CMDS: FwId = FwId {
+    crate_name: "caliptra-runtime",
+    bin_name: "caliptra-runtime",
+    features: &["emu"],
+};
+
+pub const APP_WITH_UART_TEST_ONLY_CMDS: FwId = FwId {
+    crate_name: "caliptra-runtime",
+    bin_name: "caliptra-runtime",
+    features: &["emu", "test_only_commands"],
+};
+
+pub const APP_WITH_UART_FIPS_SELF_TEST: FwId = FwId {
+    crate_name: "caliptra-runtime",
+    bin_name: "caliptra-runtime",
+    features: &["emu", "fips_self_test"],
+};
+
 pub mod caliptra_builder_tests {
     use super::*;
 
@@ -338,6 +356,9 @@ pub const REGISTERED_FW: &[&FwId] = &[
     &FMC_WITH_UART,
     &FMC_FAKE_WITH_UART,
     &APP_WITH_UART,
+    &APP_WITH_UART_NO_OPTIONAL_CMDS,
+    &APP_WITH_UART_TEST_ONLY_CMDS,
+    &APP_WITH_UART_FIPS_SELF_TEST,
     &caliptra_builder_tests::FWID,
     &hw_model_tests::MAILBOX_RESPONDER,
     &hw_model_tests::MAILBOX_SENDER,
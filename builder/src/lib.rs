@@ -13,7 +13,8 @@ use std::sync::{Arc, Mutex};
 
 use caliptra_image_elf::ElfExecutable;
 use caliptra_image_gen::{
-    ImageGenerator, ImageGeneratorConfig, ImageGeneratorOwnerConfig, ImageGeneratorVendorConfig,
+    ImageGenerator, ImageGeneratorAuxImage, ImageGeneratorConfig, ImageGeneratorOwnerConfig,
+    ImageGeneratorVendorConfig,
 };
 use caliptra_image_openssl::OsslCrypto;
 use caliptra_image_types::{ImageBundle, ImageRevision, RomInfo};
@@ -128,7 +129,13 @@ pub fn build_firmware_elfs_uncached<'a>(
     fwids: &'a [&'a FwId<'a>],
 ) -> io::Result<Vec<(&'a FwId<'a>, Vec<u8>)>> {
     const TARGET: &str = "riscv32imc-unknown-none-elf";
-    const PROFILE: &str = "firmware";
+    // Only coverage runs need line tables; the frozen ROM is always built
+    // with the plain firmware profile.
+    let profile = if std::env::var_os("CPTRA_COVERAGE_PATH").is_some() {
+        "firmware-coverage"
+    } else {
+        "firmware"
+    };
 
     let cargo_invocations = cargo_invocations_from_fwids(fwids)?;
 
@@ -175,7 +182,7 @@ pub fn build_firmware_elfs_uncached<'a>(
             .arg(features_csv)
             .arg("--no-default-features")
             .arg("--profile")
-            .arg(PROFILE);
+            .arg(profile);
 
         cmd.arg("-p").arg(invocation.crate_name);
         for &fwid in invocation.fwids.iter() {
@@ -192,7 +199,7 @@ pub fn build_firmware_elfs_uncached<'a>(
         for &fwid in invocation.fwids.iter() {
             result_map.insert(
                 fwid,
-                fs::read(target_dir.join(TARGET).join(PROFILE).join(fwid.bin_name))?,
+                fs::read(target_dir.join(TARGET).join(profile).join(fwid.bin_name))?,
             );
         }
     }
@@ -415,6 +422,7 @@ pub struct ImageOptions {
     pub app_svn: u32,
     pub vendor_config: ImageGeneratorVendorConfig,
     pub owner_config: Option<ImageGeneratorOwnerConfig>,
+    pub aux: Vec<ImageGeneratorAuxImage>,
 }
 impl Default for ImageOptions {
     fn default() -> Self {
@@ -427,6 +435,7 @@ impl Default for ImageOptions {
             app_svn: Default::default(),
             vendor_config: caliptra_image_fake_keys::VENDOR_CONFIG_KEY_0,
             owner_config: Some(caliptra_image_fake_keys::OWNER_CONFIG),
+            aux: vec![],
         }
     }
 }
@@ -456,6 +465,7 @@ pub fn build_and_sign_image(
         )?,
         vendor_config: opts.vendor_config,
         owner_config: opts.owner_config,
+        aux: opts.aux,
     })?;
     Ok(image)
 }
@@ -9,9 +9,11 @@ core::arch::global_asm!(include_str!("nmi.S"));
 #[cfg(feature = "riscv")]
 core::arch::global_asm!(include_str!("trap.S"));
 
+pub mod pic;
 pub mod trap;
 
 use caliptra_registers::soc_ifc::SocIfcReg;
+pub use pic::{wait_for_interrupt, IntSource, Pic};
 pub use trap::{Exception, Interrupt, Trap, TrapRecord};
 
 pub fn log_trap_record(trap_record: &TrapRecord, err_interrupt_status: Option<u32>) {
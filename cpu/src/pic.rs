@@ -0,0 +1,72 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    pic.rs
+
+Abstract:
+
+    File contains helpers for the VeeR EL2 Programmable Interrupt Controller
+    and for waiting on external interrupts.
+
+--*/
+
+/// Base address of the VeeR PIC memory-mapped registers
+const PIC_BASE: usize = 0x6000_0000;
+
+/// External Interrupt Priority Level Registers
+const MEIPL_OFFSET: usize = 0x0000;
+
+/// External Interrupt Enable Registers
+const MEIE_OFFSET: usize = 0x2000;
+
+/// Caliptra interrupt sources wired into the PIC
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IntSource {
+    SocIfcError = 19,
+    SocIfcNotif = 20,
+}
+
+pub struct Pic {}
+
+impl Pic {
+    /// Enable an interrupt source at the given priority (1 to 15). The
+    /// interrupt wakes the core from `wfi`; it is only taken as a trap if
+    /// mstatus.MIE is set.
+    pub fn enable_interrupt(source: IntSource, priority: u32) {
+        let source = source as usize;
+        let meipl = (PIC_BASE + MEIPL_OFFSET + 4 * source) as *mut u32;
+        let meie = (PIC_BASE + MEIE_OFFSET + 4 * source) as *mut u32;
+        unsafe {
+            core::ptr::write_volatile(meipl, priority & 0xf);
+            core::ptr::write_volatile(meie, 1);
+        }
+        Self::enable_external_interrupts();
+    }
+
+    /// Disable an interrupt source
+    pub fn disable_interrupt(source: IntSource) {
+        let meie = (PIC_BASE + MEIE_OFFSET + 4 * source as usize) as *mut u32;
+        unsafe {
+            core::ptr::write_volatile(meie, 0);
+        }
+    }
+
+    fn enable_external_interrupts() {
+        // MIE.MEIE (machine external interrupt enable)
+        #[cfg(feature = "riscv")]
+        unsafe {
+            core::arch::asm!("csrs mie, {0}", in(reg) 1u32 << 11);
+        }
+    }
+}
+
+/// Stall the core until an enabled interrupt is pending
+pub fn wait_for_interrupt() {
+    #[cfg(feature = "riscv")]
+    unsafe {
+        core::arch::asm!("wfi");
+    }
+}
@@ -37,16 +37,18 @@ pub const CFI_VAL_ORG: u32 = 0x500003E4;
 pub const CFI_MASK_ORG: u32 = 0x500003E8;
 pub const BOOT_STATUS_ORG: u32 = 0x500003FC;
 pub const MAN1_ORG: u32 = 0x50000400;
-pub const MAN2_ORG: u32 = 0x50001C00;
-pub const FHT_ORG: u32 = 0x50003400;
-pub const LDEVID_TBS_ORG: u32 = 0x50003C00;
-pub const FMCALIAS_TBS_ORG: u32 = 0x50004000;
-pub const RTALIAS_TBS_ORG: u32 = 0x50004400;
-pub const PCR_LOG_ORG: u32 = 0x50004800;
-pub const MEASUREMENT_LOG_ORG: u32 = 0x50004C00;
-pub const FUSE_LOG_ORG: u32 = 0x50005000;
-pub const DPE_ORG: u32 = 0x50005400;
-pub const DATA_ORG: u32 = 0x50006400;
+pub const MAN2_ORG: u32 = 0x50002000;
+pub const FHT_ORG: u32 = 0x50003C00;
+pub const LDEVID_TBS_ORG: u32 = 0x50004400;
+pub const FMCALIAS_TBS_ORG: u32 = 0x50004800;
+pub const RTALIAS_TBS_ORG: u32 = 0x50004C00;
+pub const PCR_LOG_ORG: u32 = 0x50005000;
+pub const MEASUREMENT_LOG_ORG: u32 = 0x50005400;
+pub const FUSE_LOG_ORG: u32 = 0x50005800;
+pub const DPE_ORG: u32 = 0x50005C00;
+pub const IDEVID_CSR_ORG: u32 = 0x50006C00;
+pub const RT_MEASUREMENT_LOG_ORG: u32 = 0x50007000;
+pub const DATA_ORG: u32 = 0x50008000;
 pub const STACK_ORG: u32 = 0x5001A000;
 pub const ESTACK_ORG: u32 = 0x5001F800;
 pub const NSTACK_ORG: u32 = 0x5001FC00;
@@ -59,8 +61,8 @@ pub const ROM_SIZE: u32 = 48 * 1024;
 pub const MBOX_SIZE: u32 = 128 * 1024;
 pub const ICCM_SIZE: u32 = 128 * 1024;
 pub const DCCM_SIZE: u32 = 128 * 1024;
-pub const MAN1_SIZE: u32 = 6 * 1024;
-pub const MAN2_SIZE: u32 = 6 * 1024;
+pub const MAN1_SIZE: u32 = 7 * 1024;
+pub const MAN2_SIZE: u32 = 7 * 1024;
 pub const FHT_SIZE: u32 = 2 * 1024;
 pub const LDEVID_TBS_SIZE: u32 = 1024;
 pub const FMCALIAS_TBS_SIZE: u32 = 1024;
@@ -69,7 +71,9 @@ pub const PCR_LOG_SIZE: u32 = 1024;
 pub const MEASUREMENT_LOG_SIZE: u32 = 1024;
 pub const FUSE_LOG_SIZE: u32 = 1024;
 pub const DPE_SIZE: u32 = 4 * 1024;
-pub const DATA_SIZE: u32 = 79 * 1024;
+pub const IDEVID_CSR_SIZE: u32 = 1024;
+pub const RT_MEASUREMENT_LOG_SIZE: u32 = 4 * 1024;
+pub const DATA_SIZE: u32 = 72 * 1024;
 pub const STACK_SIZE: u32 = 22 * 1024;
 pub const ESTACK_SIZE: u32 = 1024;
 pub const NSTACK_SIZE: u32 = 1024;
@@ -135,7 +139,19 @@ fn mem_layout_test_fuselog() {
 #[test]
 #[allow(clippy::assertions_on_constants)]
 fn mem_layout_test_dpe() {
-    assert_eq!((DATA_ORG - DPE_ORG), DPE_SIZE);
+    assert_eq!((IDEVID_CSR_ORG - DPE_ORG), DPE_SIZE);
+}
+
+#[test]
+#[allow(clippy::assertions_on_constants)]
+fn mem_layout_test_idevid_csr() {
+    assert_eq!((RT_MEASUREMENT_LOG_ORG - IDEVID_CSR_ORG), IDEVID_CSR_SIZE);
+}
+
+#[test]
+#[allow(clippy::assertions_on_constants)]
+fn mem_layout_test_rt_measurement_log() {
+    assert_eq!((DATA_ORG - RT_MEASUREMENT_LOG_ORG), RT_MEASUREMENT_LOG_SIZE);
 }
 
 #[test]
@@ -32,6 +32,8 @@ pub enum PcrLogEntryId {
     StashMeasurement = 5, // data size = 48 bytes
     RtTci = 6,            // data size = 48 bytes
     FwImageManifest = 7,  // data size = 48 bytes
+    AuxImageDigest = 8,   // data size = 48 bytes
+    AuxImageInfo = 9,     // data size = 8 bytes
 }
 
 impl From<u16> for PcrLogEntryId {
@@ -45,6 +47,8 @@ impl From<u16> for PcrLogEntryId {
             5 => PcrLogEntryId::StashMeasurement,
             6 => PcrLogEntryId::RtTci,
             7 => PcrLogEntryId::FwImageManifest,
+            8 => PcrLogEntryId::AuxImageDigest,
+            9 => PcrLogEntryId::AuxImageInfo,
             _ => PcrLogEntryId::Invalid,
         }
     }
@@ -77,6 +81,8 @@ impl PcrLogEntry {
             PcrLogEntryId::StashMeasurement => 48,
             PcrLogEntryId::RtTci => 48,
             PcrLogEntryId::FwImageManifest => 48,
+            PcrLogEntryId::AuxImageDigest => 48,
+            PcrLogEntryId::AuxImageInfo => 8,
         };
 
         &self.pcr_data.as_bytes()[..data_len]
@@ -94,5 +100,22 @@ pub struct MeasurementLogEntry {
     pub reserved0: [u8; 4],
 }
 
+/// Runtime measurement log entry, recorded for each STASH_MEASUREMENT
+/// command handled by runtime firmware
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, Debug, FromBytes, Zeroize)]
+pub struct RtMeasurementLogEntry {
+    /// Monotonic counter value assigned to this entry
+    pub counter: u32,
+
+    /// PAUSER of the mailbox caller that stashed the measurement
+    pub pauser: u32,
+
+    pub metadata: [u8; 4],
+    pub svn: u32,
+    pub measurement: [u8; 48],
+    pub context: [u8; 48],
+}
+
 pub const RT_FW_CURRENT_PCR: PcrId = PcrId::PcrId2;
 pub const RT_FW_JOURNEY_PCR: PcrId = PcrId::PcrId3;
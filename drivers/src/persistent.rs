@@ -11,18 +11,99 @@ use zeroize::Zeroize;
 use crate::{
     fuse_log::FuseLogEntry,
     memory_layout,
-    pcr_log::{MeasurementLogEntry, PcrLogEntry},
+    pcr_log::{MeasurementLogEntry, PcrLogEntry, RtMeasurementLogEntry},
     FirmwareHandoffTable,
 };
 
 pub const PCR_LOG_MAX_COUNT: usize = 17;
 pub const FUSE_LOG_MAX_COUNT: usize = 62;
 pub const MEASUREMENT_MAX_COUNT: usize = 8;
+pub const RT_MEASUREMENT_MAX_COUNT: usize = 32;
+pub const MAX_CSR_SIZE: usize = 512;
 
 pub type PcrLogArray = [PcrLogEntry; PCR_LOG_MAX_COUNT];
 pub type FuseLogArray = [FuseLogEntry; FUSE_LOG_MAX_COUNT];
 pub type StashMeasurementArray = [MeasurementLogEntry; MEASUREMENT_MAX_COUNT];
 
+/// IDevID Certificate Signing Request generated by ROM
+#[derive(FromBytes, AsBytes, Zeroize)]
+#[repr(C)]
+pub struct IdevIdCsr {
+    csr_len: u32,
+    csr: [u8; MAX_CSR_SIZE],
+}
+
+impl Default for IdevIdCsr {
+    fn default() -> Self {
+        Self {
+            csr_len: 0,
+            csr: [0; MAX_CSR_SIZE],
+        }
+    }
+}
+
+impl IdevIdCsr {
+    /// Create a new `IdevIdCsr` from a DER-encoded CSR
+    ///
+    /// Returns `None` if `csr` does not fit in the buffer
+    pub fn new(csr: &[u8]) -> Option<Self> {
+        let mut result = Self::default();
+        result.csr.get_mut(..csr.len())?.copy_from_slice(csr);
+        result.csr_len = csr.len() as u32;
+        Some(result)
+    }
+
+    /// Get the CSR bytes
+    ///
+    /// Returns `None` if ROM did not generate a CSR during the last cold boot
+    pub fn get(&self) -> Option<&[u8]> {
+        match self.csr_len {
+            0 => None,
+            len => self.csr.get(..len as usize),
+        }
+    }
+}
+
+/// Measurements stashed with runtime firmware since the last cold boot
+#[derive(FromBytes, AsBytes, Zeroize)]
+#[repr(C)]
+pub struct RtMeasurementLog {
+    entry_count: u32,
+    next_counter: u32,
+    entries: [RtMeasurementLogEntry; RT_MEASUREMENT_MAX_COUNT],
+}
+
+impl RtMeasurementLog {
+    /// Remove all entries. The next entry will be assigned `first_counter`.
+    pub fn reset(&mut self, first_counter: u32) {
+        self.zeroize();
+        self.next_counter = first_counter;
+    }
+
+    /// Append `entry`, assigning it the next counter value
+    ///
+    /// Returns `None` if the log is full
+    pub fn push(&mut self, mut entry: RtMeasurementLogEntry) -> Option<u32> {
+        let dst = self.entries.get_mut(self.entry_count as usize)?;
+        entry.counter = self.next_counter;
+        *dst = entry;
+        self.entry_count += 1;
+        self.next_counter = self.next_counter.wrapping_add(1);
+        Some(entry.counter)
+    }
+
+    /// Get the logged entries, oldest first
+    pub fn entries(&self) -> &[RtMeasurementLogEntry] {
+        // An out of range count is treated as an empty log.
+        self.entries.get(..self.entry_count as usize).unwrap_or(&[])
+    }
+
+    /// Counter value that will be assigned to the next entry
+    pub fn next_counter(&self) -> u32 {
+        self.next_counter
+    }
+}
+
 #[derive(FromBytes, AsBytes, Zeroize)]
 #[repr(C)]
 pub struct PersistentData {
@@ -57,6 +138,13 @@ pub struct PersistentData {
     reserved6: [u8; memory_layout::DPE_SIZE as usize - size_of::<DpeInstance>()],
     #[cfg(not(feature = "runtime"))]
     dpe: [u8; memory_layout::DPE_SIZE as usize],
+
+    pub idevid_csr: IdevIdCsr,
+    reserved7: [u8; memory_layout::IDEVID_CSR_SIZE as usize - size_of::<IdevIdCsr>()],
+
+    pub rt_measurement_log: RtMeasurementLog,
+    reserved8:
+        [u8; memory_layout::RT_MEASUREMENT_LOG_SIZE as usize - size_of::<RtMeasurementLog>()],
 }
 impl PersistentData {
     pub fn assert_matches_layout() {
@@ -76,9 +164,17 @@ impl PersistentData {
             );
             assert_eq!(addr_of!((*P).fuse_log) as u32, memory_layout::FUSE_LOG_ORG);
             assert_eq!(addr_of!((*P).dpe) as u32, memory_layout::DPE_ORG);
+            assert_eq!(
+                addr_of!((*P).idevid_csr) as u32,
+                memory_layout::IDEVID_CSR_ORG
+            );
+            assert_eq!(
+                addr_of!((*P).rt_measurement_log) as u32,
+                memory_layout::RT_MEASUREMENT_LOG_ORG
+            );
             assert_eq!(
                 P.add(1) as u32,
-                memory_layout::DPE_ORG + memory_layout::DPE_SIZE
+                memory_layout::RT_MEASUREMENT_LOG_ORG + memory_layout::RT_MEASUREMENT_LOG_SIZE
             );
         }
     }
@@ -294,6 +294,26 @@ impl SocIfc {
         let ext_info = soc_ifc_regs.cptra_fw_extended_error_info();
         ext_info.at(0).write(|_| err);
     }
+
+    /// Enable the notification interrupt raised when the SoC hands a
+    /// mailbox command to the uC
+    pub fn enable_mbox_notif_interrupts(&mut self) {
+        let soc_ifc_regs = self.soc_ifc.regs_mut();
+        let intr_block = soc_ifc_regs.intr_block_rf();
+        intr_block
+            .notif_intr_en_r()
+            .modify(|w| w.notif_cmd_avail_en(true));
+        intr_block.global_intr_en_r().modify(|w| w.notif_en(true));
+    }
+
+    /// Clear the mailbox command-available notification
+    pub fn clear_mbox_notif_status(&mut self) {
+        self.soc_ifc
+            .regs_mut()
+            .intr_block_rf()
+            .notif_internal_intr_r()
+            .write(|w| w.notif_cmd_avail_sts(true));
+    }
 }
 
 bitflags::bitflags! {
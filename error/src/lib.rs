@@ -259,6 +259,20 @@ impl CaliptraError {
         CaliptraError::new_const(0x000b0040);
     pub const IMAGE_VERIFIER_ERR_DIGEST_OUT_OF_BOUNDS: CaliptraError =
         CaliptraError::new_const(0x000b0041);
+    pub const IMAGE_VERIFIER_ERR_AUX_SIZE_ZERO: CaliptraError =
+        CaliptraError::new_const(0x000b0042);
+    pub const IMAGE_VERIFIER_ERR_AUX_TYPE_INVALID: CaliptraError =
+        CaliptraError::new_const(0x000b0043);
+    pub const IMAGE_VERIFIER_ERR_AUX_ID_INVALID: CaliptraError =
+        CaliptraError::new_const(0x000b0044);
+    pub const IMAGE_VERIFIER_ERR_AUX_INCORRECT_ORDER: CaliptraError =
+        CaliptraError::new_const(0x000b0045);
+    pub const IMAGE_VERIFIER_ERR_AUX_DIGEST_FAILURE: CaliptraError =
+        CaliptraError::new_const(0x000b0046);
+    pub const IMAGE_VERIFIER_ERR_AUX_DIGEST_MISMATCH: CaliptraError =
+        CaliptraError::new_const(0x000b0047);
+    pub const IMAGE_VERIFIER_ERR_AUX_SVN_LESS_THAN_MIN_SUPPORTED: CaliptraError =
+        CaliptraError::new_const(0x000b0048);
 
     /// Driver Error: LMS
     pub const DRIVER_LMS_INVALID_LMS_ALGO_TYPE: CaliptraError =
@@ -351,6 +365,16 @@ impl CaliptraError {
     pub const RUNTIME_PL1_USED_DPE_CONTEXT_THRESHOLD_EXCEEDED: CaliptraError =
         CaliptraError::new_const(0x000E001E);
     pub const RUNTIME_GLOBAL_WDT_EXPIRED: CaliptraError = CaliptraError::new_const(0x000E001F);
+    pub const RUNTIME_GET_IDEV_CSR_UNPROVISIONED: CaliptraError =
+        CaliptraError::new_const(0x000E0020);
+    pub const RUNTIME_UPDATE_INVALID_IMAGE_SIZE: CaliptraError =
+        CaliptraError::new_const(0x000E0021);
+    pub const RUNTIME_UPDATE_MANIFEST_READ_FAILURE: CaliptraError =
+        CaliptraError::new_const(0x000E0022);
+    pub const RUNTIME_UNAUTHORIZED_PAUSER: CaliptraError = CaliptraError::new_const(0x000E0023);
+    pub const RUNTIME_MEASUREMENT_LOG_FULL: CaliptraError = CaliptraError::new_const(0x000E0024);
+    pub const RUNTIME_PAUSER_PRIVILEGES_TABLE_INVALID: CaliptraError =
+        CaliptraError::new_const(0x000E0025);
 
     /// FMC Errors
     pub const FMC_GLOBAL_NMI: CaliptraError = CaliptraError::new_const(0x000F0001);
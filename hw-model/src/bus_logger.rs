@@ -9,7 +9,11 @@ use std::{
 };
 
 use caliptra_emu_bus::{Bus, BusError};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
+
+use crate::recording::ResetKind;
 
 #[derive(Clone)]
 pub struct LogFile(Rc<RefCell<BufWriter<File>>>);
@@ -49,10 +53,17 @@ impl Bus for NullBus {
 pub struct BusLogger<TBus: Bus> {
     pub bus: TBus,
     pub log: Option<LogFile>,
+
+    /// Reset since the last time this was taken
+    pub reset: Option<ResetKind>,
 }
 impl<TBus: Bus> BusLogger<TBus> {
     pub fn new(bus: TBus) -> Self {
-        Self { bus, log: None }
+        Self {
+            bus,
+            log: None,
+            reset: None,
+        }
     }
     pub fn log_read(
         &mut self,
@@ -125,9 +136,23 @@ impl<TBus: Bus> Bus for BusLogger<TBus> {
         self.bus.poll();
     }
     fn warm_reset(&mut self) {
+        self.reset = Some(ResetKind::Warm);
         self.bus.warm_reset();
     }
+    fn peripheral_name(&self, addr: RvAddr) -> Option<&'static str> {
+        self.bus.peripheral_name(addr)
+    }
     fn update_reset(&mut self) {
+        self.reset = Some(ResetKind::Update);
         self.bus.update_reset();
     }
 }
+impl<TBus: Bus + Snapshot> Snapshot for BusLogger<TBus> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.bus.save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.bus.restore_state(r)
+    }
+}
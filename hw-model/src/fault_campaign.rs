@@ -0,0 +1,339 @@
+// Licensed under the Apache-2.0 license
+
+use std::fmt;
+
+use caliptra_emu_cpu::{Fault, FiredFault};
+use caliptra_error::CaliptraError;
+
+use crate::{HwModel, ModelEmulated, EXPECTED_CALIPTRA_BOOT_TIME_IN_CYCLES};
+
+/// Returns true if `code` is a fatal error reported by the ROM when a CFI
+/// check fails
+fn is_rom_cfi_error(code: u32) -> bool {
+    (u32::from(CaliptraError::ROM_CFI_PANIC_UNKNOWN)
+        ..=u32::from(CaliptraError::ROM_CFI_PANIC_FAKE_TRNG_USED_WITH_DEBUG_LOCK))
+        .contains(&code)
+}
+
+/// Number of steps between checks for the end of the boot
+const POLL_INTERVAL: u64 = 1000;
+
+/// Progress of the boot, as decided by the campaign's check function
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BootState {
+    /// The boot has not completed yet
+    Booting,
+
+    /// The boot completed with the expected result
+    Booted,
+
+    /// The boot completed, but with a result that differs from an unfaulted
+    /// boot (such as a wrong boot status, measurement or key)
+    WrongBoot,
+}
+
+/// Effect of an injected fault on the boot
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FaultOutcome {
+    /// The trigger of the fault was not reached
+    NotTriggered,
+
+    /// The boot completed with the expected result
+    NoEffect,
+
+    /// The firmware detected the fault with a CFI check, and reported this
+    /// fatal error
+    CaughtByCfi(u32),
+
+    /// The firmware reported this (non-CFI) fatal error
+    FatalError(u32),
+
+    /// The boot completed with the wrong result, without reporting an error
+    SilentWrongBoot,
+
+    /// The boot neither completed nor reported an error within the step limit
+    Hang,
+}
+
+impl fmt::Display for FaultOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTriggered => write!(f, "not triggered"),
+            Self::NoEffect => write!(f, "no effect"),
+            Self::CaughtByCfi(code) => write!(f, "caught by CFI ({code:#010x})"),
+            Self::FatalError(code) => write!(f, "fatal error ({code:#010x})"),
+            Self::SilentWrongBoot => write!(f, "SILENT WRONG BOOT"),
+            Self::Hang => write!(f, "hang"),
+        }
+    }
+}
+
+/// Result of a single fault of a campaign
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FaultResult {
+    pub fault: Fault,
+
+    /// Where the fault was applied, if it was triggered
+    pub fired: Option<FiredFault>,
+
+    pub outcome: FaultOutcome,
+}
+
+/// Results of a fault injection campaign
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FaultReport {
+    pub results: Vec<FaultResult>,
+}
+
+impl FaultReport {
+    /// Faults that changed the result of the boot without being detected.
+    /// A glitch-resistant boot flow has none.
+    pub fn silent_wrong_boots(&self) -> impl Iterator<Item = &FaultResult> {
+        self.results
+            .iter()
+            .filter(|r| r.outcome == FaultOutcome::SilentWrongBoot)
+    }
+
+    /// Faults detected by a CFI check
+    pub fn caught_by_cfi(&self) -> impl Iterator<Item = &FaultResult> {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.outcome, FaultOutcome::CaughtByCfi(_)))
+    }
+
+    /// Number of faults with the given outcome
+    pub fn count(&self, outcome: FaultOutcome) -> usize {
+        self.results.iter().filter(|r| r.outcome == outcome).count()
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<48} {:>12} {:>10}  outcome", "fault", "cycle", "pc")?;
+        for result in self.results.iter() {
+            match &result.fired {
+                Some(fired) => write!(
+                    f,
+                    "{:<48} {:>12} {:>#10x}",
+                    result.fault.to_string(),
+                    fired.cycle,
+                    fired.pc
+                )?,
+                None => write!(
+                    f,
+                    "{:<48} {:>12} {:>10}",
+                    result.fault.to_string(),
+                    "-",
+                    "-"
+                )?,
+            }
+            writeln!(f, "  {}", result.outcome)?;
+        }
+        let count_if = |pred: fn(&FaultOutcome) -> bool| {
+            self.results.iter().filter(|r| pred(&r.outcome)).count()
+        };
+        writeln!(
+            f,
+            "{} faults: {} caught by CFI, {} other fatal errors, {} silent wrong boots, \
+             {} hangs, {} no effect, {} not triggered",
+            self.results.len(),
+            count_if(|o| matches!(o, FaultOutcome::CaughtByCfi(_))),
+            count_if(|o| matches!(o, FaultOutcome::FatalError(_))),
+            count_if(|o| *o == FaultOutcome::SilentWrongBoot),
+            count_if(|o| *o == FaultOutcome::Hang),
+            count_if(|o| *o == FaultOutcome::NoEffect),
+            count_if(|o| *o == FaultOutcome::NotTriggered),
+        )
+    }
+}
+
+/// Boots a fresh emulated model once for each fault, and classifies what the
+/// fault did to the boot.
+///
+/// The boot ends when the firmware reports a fatal error, when the check
+/// function returns something other than [`BootState::Booting`], or when the
+/// step limit is reached.
+pub struct FaultCampaign {
+    faults: Vec<Fault>,
+    max_steps: u64,
+}
+
+impl FaultCampaign {
+    pub fn new(faults: impl IntoIterator<Item = Fault>) -> Self {
+        Self {
+            faults: faults.into_iter().collect(),
+            max_steps: EXPECTED_CALIPTRA_BOOT_TIME_IN_CYCLES,
+        }
+    }
+
+    /// Steps after which a boot that has neither completed nor failed is
+    /// considered hung
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Run the campaign.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_model` - Creates the model to inject a fault into
+    /// * `check` - Called periodically to decide whether the boot completed,
+    ///   and if so, whether its result is correct
+    pub fn run(
+        &self,
+        mut new_model: impl FnMut() -> ModelEmulated,
+        mut check: impl FnMut(&mut ModelEmulated) -> BootState,
+    ) -> FaultReport {
+        let mut report = FaultReport::default();
+        for fault in self.faults.iter() {
+            let mut model = new_model();
+            model.inject_fault(fault.clone());
+            let outcome = self.run_one(&mut model, &mut check);
+            report.results.push(FaultResult {
+                fault: fault.clone(),
+                fired: model.fired_faults().first().cloned(),
+                outcome,
+            });
+        }
+        report
+    }
+
+    fn run_one(
+        &self,
+        model: &mut ModelEmulated,
+        check: &mut impl FnMut(&mut ModelEmulated) -> BootState,
+    ) -> FaultOutcome {
+        let mut steps = 0;
+        let outcome = loop {
+            if steps % POLL_INTERVAL == 0 {
+                let fatal = model.soc_ifc().cptra_fw_error_fatal().read();
+                if is_rom_cfi_error(fatal) {
+                    break FaultOutcome::CaughtByCfi(fatal);
+                }
+                if fatal != 0 {
+                    break FaultOutcome::FatalError(fatal);
+                }
+                match check(model) {
+                    BootState::Booting => {}
+                    BootState::Booted => break FaultOutcome::NoEffect,
+                    BootState::WrongBoot => break FaultOutcome::SilentWrongBoot,
+                }
+                if steps >= self.max_steps {
+                    break FaultOutcome::Hang;
+                }
+            }
+            model.step();
+            steps += 1;
+        };
+        if model.fired_faults().is_empty() {
+            return FaultOutcome::NotTriggered;
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmio::Rv32GenMmio;
+    use crate::{BootParams, FaultKind, FaultTrigger, InitParams, XReg};
+    use caliptra_registers::soc_ifc;
+
+    const BOOT_STATUS: u32 = 0x600;
+
+    /// ROM that writes `val` to CPTRA_BOOT_STATUS, or to
+    /// CPTRA_FW_ERROR_FATAL if `fatal` is set
+    fn rom(val: u32, fatal: bool) -> Vec<u8> {
+        let rv32_gen = Rv32GenMmio::new();
+        let soc_ifc =
+            unsafe { soc_ifc::RegisterBlock::new_with_mmio(0x3003_0000 as *mut u32, &rv32_gen) };
+        if fatal {
+            soc_ifc.cptra_fw_error_fatal().write(|_| val);
+        } else {
+            soc_ifc.cptra_boot_status().write(|_| val);
+        }
+        rv32_gen.into_inner().empty_loop().build()
+    }
+
+    fn check(model: &mut ModelEmulated) -> BootState {
+        match model.soc_ifc().cptra_boot_status().read() {
+            0 => BootState::Booting,
+            BOOT_STATUS => BootState::Booted,
+            _ => BootState::WrongBoot,
+        }
+    }
+
+    fn new_model(rom: &[u8]) -> ModelEmulated {
+        ModelEmulated::new(BootParams {
+            init_params: InitParams {
+                rom,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fault_campaign() {
+        // The ROM is `lui t0; lui t1; addi t1; sw t1, 0(t0)`, and the first
+        // instruction executes while the model is created
+        let flip_t1 = Fault::new(
+            FaultTrigger::Instr(2),
+            FaultKind::FlipXRegBit {
+                reg: XReg::X6,
+                bit: 0,
+            },
+        );
+        let skip_sw = Fault::new(FaultTrigger::Instr(2), FaultKind::SkipInstr);
+        let flip_t2 = Fault::new(
+            FaultTrigger::Instr(0),
+            FaultKind::FlipXRegBit {
+                reg: XReg::X7,
+                bit: 0,
+            },
+        );
+        let late = Fault::new(FaultTrigger::Instr(1_000_000), FaultKind::SkipInstr);
+
+        let boot_rom = rom(BOOT_STATUS, false);
+        let report = FaultCampaign::new([flip_t1, skip_sw, flip_t2.clone(), late])
+            .with_max_steps(10_000)
+            .run(|| new_model(&boot_rom), check);
+        assert_eq!(
+            report.results.iter().map(|r| r.outcome).collect::<Vec<_>>(),
+            vec![
+                FaultOutcome::SilentWrongBoot,
+                FaultOutcome::Hang,
+                FaultOutcome::NoEffect,
+                FaultOutcome::NotTriggered
+            ]
+        );
+        assert_eq!(report.silent_wrong_boots().count(), 1);
+        assert_eq!(report.results[0].fired.as_ref().unwrap().pc, 0xc);
+        assert!(report.results[3].fired.is_none());
+        assert!(report.to_string().ends_with(
+            "4 faults: 0 caught by CFI, 0 other fatal errors, 1 silent wrong boots, \
+             1 hangs, 1 no effect, 1 not triggered\n"
+        ));
+
+        let cfi_rom = rom(CaliptraError::ROM_CFI_PANIC_COUNTER_MISMATCH.into(), true);
+        let report = FaultCampaign::new([flip_t2.clone()])
+            .with_max_steps(10_000)
+            .run(|| new_model(&cfi_rom), check);
+        assert_eq!(
+            report.results[0].outcome,
+            FaultOutcome::CaughtByCfi(0x0104_0054)
+        );
+        assert_eq!(report.caught_by_cfi().count(), 1);
+
+        let error_rom = rom(CaliptraError::ROM_GLOBAL_PANIC.into(), true);
+        let report = FaultCampaign::new([flip_t2])
+            .with_max_steps(10_000)
+            .run(|| new_model(&error_rom), check);
+        assert_eq!(
+            report.results[0].outcome,
+            FaultOutcome::FatalError(0x0105_0003)
+        );
+    }
+}
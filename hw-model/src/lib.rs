@@ -22,6 +22,7 @@ use caliptra_registers::soc_ifc::regs::{
 
 use rand::{rngs::StdRng, SeedableRng};
 
+mod fault_campaign;
 pub mod mmio;
 mod model_emulated;
 
@@ -33,14 +34,20 @@ mod model_verilated;
 mod model_fpga_realtime;
 
 mod output;
+mod recording;
 mod rv32_builder;
 
 pub use caliptra_emu_bus::BusMmio;
+pub use caliptra_emu_cpu::xreg_file::XReg;
+pub use caliptra_emu_cpu::{Fault, FaultKind, FaultTrigger, FiredFault};
+pub use caliptra_emu_periph::{BootPhase, BootTiming, CryptoLatency};
 pub use caliptra_hw_model_types::{DeviceLifecycle, Fuses, SecurityState, U4};
 use output::ExitStatus;
 pub use output::Output;
 
+pub use fault_campaign::{BootState, FaultCampaign, FaultOutcome, FaultReport, FaultResult};
 pub use model_emulated::ModelEmulated;
+pub use recording::{Recording, ReplayError, ResetKind, SocEvent};
 
 #[cfg(feature = "verilator")]
 pub use model_verilated::ModelVerilated;
@@ -145,6 +152,15 @@ pub struct InitParams<'a> {
     pub trng_mode: Option<TrngMode>,
 
     pub wdt_timeout_cycles: u64,
+
+    // Latencies of the emulated crypto peripherals. When None, use the
+    // CPTRA_CRYPTO_LATENCY environment variable. Ignored by the other models.
+    pub crypto_latency: Option<CryptoLatency>,
+
+    // Record the SoC interactions and the entropy consumed, for
+    // ModelEmulated::replay. Also enabled by the CPTRA_RECORD_PATH environment
+    // variable. Ignored by the other models.
+    pub record: bool,
 }
 
 impl<'a> Default for InitParams<'a> {
@@ -175,6 +191,8 @@ impl<'a> Default for InitParams<'a> {
             etrng_responses,
             trng_mode: Default::default(),
             wdt_timeout_cycles: EXPECTED_CALIPTRA_BOOT_TIME_IN_CYCLES,
+            crypto_latency: None,
+            record: false,
         }
     }
 }
@@ -923,7 +941,9 @@ mod tests {
     use caliptra_builder::firmware;
     use caliptra_emu_bus::Bus;
     use caliptra_emu_types::RvSize;
+    use caliptra_hw_model_types::{RandomEtrngResponses, RandomNibbles};
     use caliptra_registers::{mbox::enums::MboxStatusE, soc_ifc};
+    use rand::{rngs::StdRng, SeedableRng};
 
     use crate as caliptra_hw_model;
 
@@ -1122,6 +1142,54 @@ mod tests {
         model.step_until_output("hii").unwrap();
     }
 
+    #[test]
+    fn test_emulated_snapshot() {
+        let rom = caliptra_builder::build_firmware_rom(&firmware::ROM_WITH_UART).unwrap();
+        let image = caliptra_builder::build_and_sign_image(
+            &firmware::FMC_WITH_UART,
+            &firmware::APP_WITH_UART,
+            Default::default(),
+        )
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+        let init_params = || InitParams {
+            rom: &rom,
+            itrng_nibbles: Box::new(RandomNibbles(StdRng::seed_from_u64(0x1234))),
+            etrng_responses: Box::new(RandomEtrngResponses(StdRng::seed_from_u64(0x1234))),
+            ..Default::default()
+        };
+
+        // Snapshot while the ROM is verifying the firmware
+        let mut model: crate::ModelEmulated = HwModel::new(BootParams {
+            init_params: init_params(),
+            fw_image: Some(&image),
+            ..Default::default()
+        })
+        .unwrap();
+        model.output().take(usize::MAX);
+        let snapshot = model.save_snapshot().unwrap();
+        let timing_start = model.boot_timing().changes().len();
+
+        // The fork resumes without repeating the boot sequence
+        let mut fork = crate::ModelEmulated::new_unbooted(init_params()).unwrap();
+        fork.restore_snapshot(&snapshot).unwrap();
+
+        const RT_READY: &str = "Caliptra RT listening for mailbox commands...";
+        model.step_until_output_contains(RT_READY).unwrap();
+        fork.step_until_output_contains(RT_READY).unwrap();
+
+        // Both reach the runtime on the same cycles with the same output
+        assert_eq!(
+            &model.boot_timing().changes()[timing_start..],
+            fork.boot_timing().changes()
+        );
+        assert_eq!(
+            model.output().take(usize::MAX),
+            fork.output().take(usize::MAX)
+        );
+    }
+
     #[test]
     fn test_output_failure() {
         let mut model = caliptra_hw_model::new(BootParams {
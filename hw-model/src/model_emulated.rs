@@ -3,21 +3,29 @@
 use std::cell::Cell;
 use std::env;
 use std::error::Error;
-use std::io::Write;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use caliptra_emu_bus::Clock;
+use caliptra_emu_bus::{BusMaster, Clock, TraceEvent};
+use caliptra_emu_cpu::cpu::CodeCoverage;
 use caliptra_emu_cpu::Cpu;
 use caliptra_emu_cpu::InstrTracer;
+use caliptra_emu_cpu::{Fault, FiredFault};
 use caliptra_emu_periph::ActionCb;
 use caliptra_emu_periph::ReadyForFwCb;
-use caliptra_emu_periph::{CaliptraRootBus, CaliptraRootBusArgs, SocToCaliptraBus, TbServicesCb};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_periph::{
+    BootTiming, CaliptraRootBus, CaliptraRootBusArgs, CryptoLatency, SocToCaliptraBus, TbServicesCb,
+};
+use caliptra_emu_types::{RvAddr, RvData, RvSize, SnapshotError, SnapshotReader, SnapshotWriter};
 use caliptra_hw_model_types::ErrorInjectionMode;
 
 use crate::bus_logger::BusLogger;
 use crate::bus_logger::LogFile;
+use crate::recording::{Recorder, Recording, ReplayError, SocEvent};
+use crate::HwModel;
 use crate::InitParams;
 use crate::ModelError;
 use crate::Output;
@@ -31,7 +39,20 @@ pub struct EmulatedApbBus<'a> {
 impl<'a> Bus for EmulatedApbBus<'a> {
     fn read(&mut self, size: RvSize, addr: RvAddr) -> Result<RvData, caliptra_emu_bus::BusError> {
         let result = self.model.soc_to_caliptra_bus.read(size, addr);
+        self.model.record(SocEvent::ApbRead {
+            cycle: self.model.cpu.clock.now(),
+            size,
+            addr,
+            result,
+        });
         self.model.cpu.bus.log_read("SoC", size, addr, result);
+        self.model.cpu.clock.tracer().emit(TraceEvent::BusRead {
+            master: BusMaster::Soc,
+            periph: self.model.soc_to_caliptra_bus.peripheral_name(addr),
+            size,
+            addr,
+            result,
+        });
         result
     }
     fn write(
@@ -41,7 +62,22 @@ impl<'a> Bus for EmulatedApbBus<'a> {
         val: RvData,
     ) -> Result<(), caliptra_emu_bus::BusError> {
         let result = self.model.soc_to_caliptra_bus.write(size, addr, val);
+        self.model.record(SocEvent::ApbWrite {
+            cycle: self.model.cpu.clock.now(),
+            size,
+            addr,
+            val,
+            result,
+        });
         self.model.cpu.bus.log_write("SoC", size, addr, val, result);
+        self.model.cpu.clock.tracer().emit(TraceEvent::BusWrite {
+            master: BusMaster::Soc,
+            periph: self.model.soc_to_caliptra_bus.peripheral_name(addr),
+            size,
+            addr,
+            val,
+            result,
+        });
         result
     }
 }
@@ -54,21 +90,292 @@ pub struct ModelEmulated {
     trace_fn: Option<Box<InstrTracer<'static>>>,
     ready_for_fw: Rc<Cell<bool>>,
     cpu_enabled: Rc<Cell<bool>>,
+    /// Only present when recording was requested
+    recorder: Option<Recorder>,
 }
 
 impl ModelEmulated {
     pub fn code_coverage_bitmap(&self) -> &bit_vec::BitVec {
         self.cpu.code_coverage.code_coverage_bitmap()
     }
-}
 
-impl crate::HwModel for ModelEmulated {
-    type TBus<'a> = EmulatedApbBus<'a>;
+    /// Executed instruction address ranges, in ROM and ICCM
+    pub fn code_coverage(&self) -> &CodeCoverage {
+        &self.cpu.code_coverage
+    }
+
+    /// Cycles at which the firmware reported each boot status. With
+    /// CPTRA_CRYPTO_LATENCY=rtl, these approximate the boot time on silicon.
+    pub fn boot_timing(&self) -> BootTiming {
+        self.cpu.bus.bus.soc_reg.boot_timing()
+    }
+
+    /// Inject `fault` into the firmware execution when its trigger is
+    /// reached. See [`crate::FaultCampaign`] to run many faults.
+    pub fn inject_fault(&mut self, fault: Fault) {
+        self.cpu.inject_fault(fault);
+    }
+
+    /// Faults that have been applied to the firmware execution so far
+    pub fn fired_faults(&self) -> &[FiredFault] {
+        self.cpu.fired_faults()
+    }
+
+    /// Everything needed to replay the run so far with
+    /// [`ModelEmulated::replay`], if the model was created with
+    /// `InitParams::record` or with the CPTRA_RECORD_PATH environment
+    /// variable set
+    pub fn recording(&self) -> Option<Recording> {
+        let recorder = self.recorder.as_ref()?;
+        Some(recorder.recording(self.cpu.clock.now()))
+    }
+
+    fn record(&mut self, event: SocEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.push(event);
+        }
+    }
+
+    fn recorded_events(&self) -> &[SocEvent] {
+        self.recorder.as_ref().map_or(&[], |r| r.events())
+    }
+
+    /// Create a model from `recording`, and drive it through the recorded
+    /// SoC interactions until the end of the recording. The run is
+    /// bit-identical to the recorded one; the replay fails at the first
+    /// interaction with a different result.
+    pub fn replay(recording: &Recording) -> Result<Self, Box<dyn Error>> {
+        let mut model = Self::new_unbooted(InitParams {
+            rom: &recording.rom,
+            iccm: &recording.iccm,
+            dccm: &recording.dccm,
+            security_state: recording.security_state,
+            cptra_obf_key: recording.cptra_obf_key,
+            itrng_nibbles: Box::new(recording.itrng_nibbles.clone().into_iter()),
+            etrng_responses: Box::new(recording.etrng_responses.clone().into_iter()),
+            trng_mode: Some(if recording.itrng_enabled {
+                TrngMode::Internal
+            } else {
+                TrngMode::External
+            }),
+            crypto_latency: Some(recording.crypto_latency),
+            record: true,
+            ..Default::default()
+        })?;
+        for (index, &expected) in recording.events.iter().enumerate() {
+            model.step_until_replay_cycle(index, expected.cycle());
+            match expected {
+                SocEvent::ApbRead { size, addr, .. } => {
+                    let _ = model.apb_bus().read(size, addr);
+                }
+                SocEvent::ApbWrite {
+                    size, addr, val, ..
+                } => {
+                    let _ = model.apb_bus().write(size, addr, val);
+                }
+                SocEvent::SetApbPauser { pauser, .. } => model.set_apb_pauser(pauser),
+                SocEvent::EccErrorInjection { mode, .. } => model.ecc_error_injection(mode),
+                // Recorded by step() when the reset happens
+                SocEvent::Reset { .. } => {}
+            }
+            let actual = model.recorded_events().get(index).copied();
+            if actual != Some(expected) {
+                return Err(ReplayError::EventMismatch {
+                    index,
+                    expected: Some(expected),
+                    actual,
+                }
+                .into());
+            }
+        }
+        let event_count = recording.events.len();
+        model.step_until_replay_cycle(event_count, recording.end_cycle);
+        if let Some(&actual) = model.recorded_events().get(event_count) {
+            return Err(ReplayError::EventMismatch {
+                index: event_count,
+                expected: None,
+                actual: Some(actual),
+            }
+            .into());
+        }
+        if model.cpu.clock.now() != recording.end_cycle {
+            return Err(ReplayError::EndCycleMismatch {
+                expected: recording.end_cycle,
+                actual: model.cpu.clock.now(),
+            }
+            .into());
+        }
+        let (itrng_nibbles, etrng_responses) = model
+            .recorder
+            .as_ref()
+            .map_or((0, 0), |r| r.entropy_consumed());
+        if itrng_nibbles != recording.itrng_nibbles.len()
+            || etrng_responses != recording.etrng_responses.len()
+        {
+            return Err(ReplayError::EntropyMismatch {
+                itrng_nibbles,
+                etrng_responses,
+            }
+            .into());
+        }
+        Ok(model)
+    }
+
+    /// Step until `cycle`, stopping early if the model records another event
+    /// than the `event_count` ones expected so far
+    fn step_until_replay_cycle(&mut self, event_count: usize, cycle: u64) {
+        while self.cpu.clock.now() < cycle && self.recorded_events().len() == event_count {
+            let now = self.cpu.clock.now();
+            self.step();
+            if self.cpu.clock.now() == now {
+                // The CPU is disabled, so the clock doesn't advance
+                break;
+            }
+        }
+    }
+
+    /// Write the recording to a new file in the directory named by the
+    /// CPTRA_RECORD_PATH environment variable, if set. The file is named
+    /// after the test that created the model.
+    fn save_recording(&self) {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let Some(dir) = env::var_os("CPTRA_RECORD_PATH") else {
+            return;
+        };
+        // Under cargo test, the thread is named after the test
+        let name = match std::thread::current().name() {
+            Some(name) if name != "main" => name.replace("::", "-"),
+            _ => std::process::id().to_string(),
+        };
+        let path = Path::new(&dir).join(format!(
+            "{name}-{}.rec",
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let Some(recording) = self.recording() else {
+            return;
+        };
+        let data = match recording.to_bytes() {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Unable to save recording: {e}");
+                return;
+            }
+        };
+        let result = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, data));
+        if let Err(e) = result {
+            eprintln!("Unable to write recording to {path:?}: {e}");
+        }
+    }
+
+    /// Append the boot phase budgets to the file named by the
+    /// CPTRA_BOOT_TIMING_PATH environment variable, if set.
+    fn save_boot_timing(&self) {
+        let Some(path) = env::var_os("CPTRA_BOOT_TIMING_PATH") else {
+            return;
+        };
+        let timing = self.boot_timing();
+        if timing.changes().is_empty() {
+            return;
+        }
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| {
+                // Under cargo test, the thread is named after the test
+                let name = std::thread::current().name().unwrap_or("").to_string();
+                write!(file, "# {name}\n{timing}\n")
+            });
+        if let Err(e) = result {
+            eprintln!("Unable to write boot timing to {path:?}: {e}");
+        }
+    }
+
+    /// Write the coverage to a new file in the directory named by the
+    /// CPTRA_COVERAGE_PATH environment variable, if set. The files of all
+    /// models in a test run can be merged with `caliptra-emu-coverage`.
+    fn save_coverage(&self) {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let Some(dir) = env::var_os("CPTRA_COVERAGE_PATH") else {
+            return;
+        };
+        let path = Path::new(&dir).join(format!(
+            "{}-{}.cov",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = std::fs::create_dir_all(&dir)
+            .and_then(|_| File::create(&path))
+            .and_then(|file| {
+                let mut w = BufWriter::new(file);
+                self.cpu.code_coverage.save(&mut w)?;
+                w.flush()
+            });
+        if let Err(e) = result {
+            eprintln!("Unable to write coverage to {path:?}: {e}");
+        }
+    }
+
+    /// Write the structured trace to the file named by the
+    /// CPTRA_TRACE_JSON_PATH environment variable, if set.
+    fn json_tracing_hint(&mut self, enable: bool) {
+        let tracer = self.cpu.clock.tracer();
+        if enable == tracer.enabled() {
+            return;
+        }
+        tracer.set_output(None);
+        if !enable {
+            return;
+        }
+        let trace_path = env::var("CPTRA_TRACE_JSON_PATH").unwrap_or_else(|_| "".into());
+        if trace_path.is_empty() {
+            return;
+        }
+        match File::create(&trace_path) {
+            Ok(file) => tracer.set_output(Some(Box::new(BufWriter::new(file)))),
+            Err(e) => eprintln!("Unable to open file {trace_path:?}: {e}"),
+        }
+    }
+
+    /// Save the complete state of the model: the CPU, every peripheral and
+    /// all pending timer actions.
+    ///
+    /// Output already collected and the tracing configuration are not part
+    /// of the snapshot.
+    pub fn save_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut w = SnapshotWriter::new();
+        w.write_bool(self.ready_for_fw.get());
+        w.write_bool(self.cpu_enabled.get());
+        w.write_bytes(&self.cpu.save_snapshot()?);
+        w.finish()
+    }
+
+    /// Restore a snapshot taken with [`ModelEmulated::save_snapshot`].
+    ///
+    /// The model must have been created with the same `InitParams` as the
+    /// one that was saved. Execution resumes exactly where the saved model
+    /// left off, so a single booted model can be forked for many tests.
+    pub fn restore_snapshot(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+        let mut r = SnapshotReader::new(snapshot);
+        let ready_for_fw = r.read_bool()?;
+        let cpu_enabled = r.read_bool()?;
+        self.cpu.restore_snapshot(r.read_bytes()?)?;
+        r.finish()?;
+        self.ready_for_fw.set(ready_for_fw);
+        self.cpu_enabled.set(cpu_enabled);
+        Ok(())
+    }
+
+    fn new_with_crypto_latency(
+        mut params: InitParams,
+        crypto_latency: CryptoLatency,
+    ) -> Result<Self, Box<dyn Error>> {
+        let trng_mode = TrngMode::resolve(params.trng_mode.take());
+        let recorder = (params.record || env::var_os("CPTRA_RECORD_PATH").is_some())
+            .then(|| Recorder::new(&params, trng_mode == TrngMode::Internal, crypto_latency));
 
-    fn new_unbooted(params: InitParams) -> Result<Self, Box<dyn Error>>
-    where
-        Self: Sized,
-    {
         let clock = Clock::new();
         let timer = clock.timer();
 
@@ -97,13 +404,19 @@ impl crate::HwModel for ModelEmulated {
             security_state: params.security_state,
             cptra_obf_key: params.cptra_obf_key,
 
-            itrng_nibbles: Some(params.itrng_nibbles),
-            etrng_responses: params.etrng_responses,
+            itrng_nibbles: Some(match &recorder {
+                Some(recorder) => recorder.record_itrng_nibbles(params.itrng_nibbles),
+                None => params.itrng_nibbles,
+            }),
+            etrng_responses: match &recorder {
+                Some(recorder) => recorder.record_etrng_responses(params.etrng_responses),
+                None => params.etrng_responses,
+            },
+            crypto_latency,
             ..CaliptraRootBusArgs::default()
         };
         let mut root_bus = CaliptraRootBus::new(&clock, bus_args);
 
-        let trng_mode = TrngMode::resolve(params.trng_mode);
         root_bus.soc_reg.set_hw_config(match trng_mode {
             TrngMode::Internal => 1.into(),
             TrngMode::External => 0.into(),
@@ -122,7 +435,10 @@ impl crate::HwModel for ModelEmulated {
             dccm_dest.copy_from_slice(params.dccm);
         }
         let soc_to_caliptra_bus = root_bus.soc_to_caliptra_bus();
-        let cpu = Cpu::new(BusLogger::new(root_bus), clock);
+        let mut cpu = Cpu::new(BusLogger::new(root_bus), clock);
+        // FMC and runtime execute from ICCM
+        cpu.code_coverage
+            .add_region(CaliptraRootBus::ICCM_ORG, CaliptraRootBus::ICCM_SIZE);
 
         let mut m = ModelEmulated {
             output,
@@ -131,12 +447,28 @@ impl crate::HwModel for ModelEmulated {
             trace_fn: None,
             ready_for_fw,
             cpu_enabled,
+            recorder,
         };
-        // Turn tracing on if CPTRA_TRACE_PATH environment variable is set
+        // Turn tracing on if the CPTRA_TRACE_PATH or CPTRA_TRACE_JSON_PATH
+        // environment variables are set
         m.tracing_hint(true);
 
         Ok(m)
     }
+}
+
+impl crate::HwModel for ModelEmulated {
+    type TBus<'a> = EmulatedApbBus<'a>;
+
+    fn new_unbooted(params: InitParams) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        let crypto_latency = params
+            .crypto_latency
+            .unwrap_or_else(crypto_latency_from_env);
+        Self::new_with_crypto_latency(params, crypto_latency)
+    }
 
     fn ready_for_fw(&self) -> bool {
         self.ready_for_fw.get()
@@ -148,6 +480,12 @@ impl crate::HwModel for ModelEmulated {
     fn step(&mut self) {
         if self.cpu_enabled.get() {
             self.cpu.step(self.trace_fn.as_deref_mut());
+            if let Some(kind) = self.cpu.bus.reset.take() {
+                self.record(SocEvent::Reset {
+                    cycle: self.cpu.clock.now(),
+                    kind,
+                });
+            }
         }
     }
 
@@ -159,6 +497,7 @@ impl crate::HwModel for ModelEmulated {
     }
 
     fn tracing_hint(&mut self, enable: bool) {
+        self.json_tracing_hint(enable);
         if enable == self.trace_fn.is_some() {
             // No change
             return;
@@ -184,6 +523,10 @@ impl crate::HwModel for ModelEmulated {
     }
 
     fn ecc_error_injection(&mut self, mode: ErrorInjectionMode) {
+        self.record(SocEvent::EccErrorInjection {
+            cycle: self.cpu.clock.now(),
+            mode,
+        });
         match mode {
             ErrorInjectionMode::None => {
                 self.cpu.bus.bus.iccm.ram().borrow_mut().error_injection = 0;
@@ -198,7 +541,30 @@ impl crate::HwModel for ModelEmulated {
         }
     }
 
-    fn set_apb_pauser(&mut self, _pauser: u32) {
-        unimplemented!();
+    fn set_apb_pauser(&mut self, pauser: u32) {
+        self.record(SocEvent::SetApbPauser {
+            cycle: self.cpu.clock.now(),
+            pauser,
+        });
+        self.soc_to_caliptra_bus.set_apb_pauser(pauser);
+    }
+}
+
+impl Drop for ModelEmulated {
+    fn drop(&mut self) {
+        self.save_coverage();
+        self.save_boot_timing();
+        self.save_recording();
+    }
+}
+
+/// Crypto peripheral latencies from the CPTRA_CRYPTO_LATENCY environment
+/// variable, such as `rtl` or `rtl,ecc384_sign=1300000`.
+fn crypto_latency_from_env() -> CryptoLatency {
+    match env::var("CPTRA_CRYPTO_LATENCY") {
+        Ok(spec) => spec
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid CPTRA_CRYPTO_LATENCY: {e}")),
+        Err(_) => CryptoLatency::default(),
     }
 }
@@ -0,0 +1,622 @@
+// Licensed under the Apache-2.0 license
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use caliptra_emu_bus::BusError;
+use caliptra_emu_periph::CryptoLatency;
+use caliptra_emu_types::{RvAddr, RvData, RvSize, SnapshotError, SnapshotReader, SnapshotWriter};
+use caliptra_hw_model_types::{ErrorInjectionMode, EtrngResponse, SecurityState};
+
+use crate::InitParams;
+
+/// A reset of the microcontroller and its peripherals
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResetKind {
+    Warm,
+    Update,
+}
+
+/// An interaction between the SoC (or the test acting as the SoC) and the
+/// emulated Caliptra, at the clock cycle at which it happened
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SocEvent {
+    /// Read over the APB bus
+    ApbRead {
+        cycle: u64,
+        size: RvSize,
+        addr: RvAddr,
+        result: Result<RvData, BusError>,
+    },
+
+    /// Write over the APB bus
+    ApbWrite {
+        cycle: u64,
+        size: RvSize,
+        addr: RvAddr,
+        val: RvData,
+        result: Result<(), BusError>,
+    },
+
+    /// Change of the PAUSER used by subsequent APB transactions
+    SetApbPauser { cycle: u64, pauser: u32 },
+
+    /// Change of the ICCM/DCCM ECC error injection mode
+    EccErrorInjection {
+        cycle: u64,
+        mode: ErrorInjectionMode,
+    },
+
+    /// Reset of the microcontroller, such as the update reset requested by
+    /// the firmware. A replay checks that it happens at the same cycle.
+    Reset { cycle: u64, kind: ResetKind },
+}
+
+impl SocEvent {
+    pub fn cycle(&self) -> u64 {
+        match *self {
+            Self::ApbRead { cycle, .. }
+            | Self::ApbWrite { cycle, .. }
+            | Self::SetApbPauser { cycle, .. }
+            | Self::EccErrorInjection { cycle, .. }
+            | Self::Reset { cycle, .. } => cycle,
+        }
+    }
+}
+
+/// Everything outside of the emulator that influenced a run of
+/// [`crate::ModelEmulated`]: the initial memory contents and configuration,
+/// the entropy consumed by the TRNGs and every interaction from the SoC side.
+///
+/// [`crate::ModelEmulated::replay`] drives a fresh model through the same
+/// run. Faults injected with [`crate::ModelEmulated::inject_fault`] and
+/// snapshot restores are not recorded, so runs that use them can't be
+/// replayed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Recording {
+    pub rom: Vec<u8>,
+    pub iccm: Vec<u8>,
+    pub dccm: Vec<u8>,
+    pub security_state: SecurityState,
+    pub cptra_obf_key: [u32; 8],
+
+    /// The internal TRNG was selected with CPTRA_HW_CONFIG
+    pub itrng_enabled: bool,
+
+    pub crypto_latency: CryptoLatency,
+
+    /// Raw entropy consumed by the internal TRNG
+    pub itrng_nibbles: Vec<u8>,
+
+    /// Responses consumed through the external TRNG registers
+    pub etrng_responses: Vec<EtrngResponse>,
+
+    /// SoC interactions, in the order they happened
+    pub events: Vec<SocEvent>,
+
+    /// Clock cycle at which the recording was taken
+    pub end_cycle: u64,
+}
+
+impl Recording {
+    const MAGIC: u32 = 0x4352_4543; // "CREC"
+    const VERSION: u32 = 1;
+
+    /// Serialize the recording, to be written to a file
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut w = SnapshotWriter::new();
+        w.write_u32(Self::MAGIC);
+        w.write_u32(Self::VERSION);
+        w.write_bytes(&self.rom);
+        w.write_bytes(&self.iccm);
+        w.write_bytes(&self.dccm);
+        w.write_u32(self.security_state.into());
+        w.write(&self.cptra_obf_key);
+        w.write_bool(self.itrng_enabled);
+        for field in latency_fields(&self.crypto_latency) {
+            w.write_u64(field);
+        }
+        w.write_bytes(&self.itrng_nibbles);
+        w.write_usize(self.etrng_responses.len());
+        for response in self.etrng_responses.iter() {
+            w.write_u32(response.delay);
+            w.write(&response.data);
+        }
+        w.write_usize(self.events.len());
+        for event in self.events.iter() {
+            write_event(&mut w, event);
+        }
+        w.write_u64(self.end_cycle);
+        w.finish()
+    }
+
+    /// Deserialize a recording produced by [`Recording::to_bytes`]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SnapshotError> {
+        let mut r = SnapshotReader::new(data);
+        if r.read_u32()? != Self::MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        match r.read_u32()? {
+            Self::VERSION => {}
+            version => return Err(SnapshotError::UnsupportedVersion(version)),
+        }
+        let mut result = Self {
+            rom: r.read_bytes()?.to_vec(),
+            iccm: r.read_bytes()?.to_vec(),
+            dccm: r.read_bytes()?.to_vec(),
+            security_state: r.read_u32()?.into(),
+            ..Default::default()
+        };
+        r.read(&mut result.cptra_obf_key)?;
+        result.itrng_enabled = r.read_bool()?;
+        let mut latency = [0; 10];
+        r.read(&mut latency)?;
+        result.crypto_latency = latency_from_fields(latency);
+        result.itrng_nibbles = r.read_bytes()?.to_vec();
+        for _ in 0..r.read_usize()? {
+            let mut response = EtrngResponse {
+                delay: r.read_u32()?,
+                data: [0; 12],
+            };
+            r.read(&mut response.data)?;
+            result.etrng_responses.push(response);
+        }
+        for _ in 0..r.read_usize()? {
+            result.events.push(read_event(&mut r)?);
+        }
+        result.end_cycle = r.read_u64()?;
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Reason a replay did not reproduce its recording
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplayError {
+    /// The replayed model produced a different event than the recorded one
+    /// at `index` (or an event that is missing from either)
+    EventMismatch {
+        index: usize,
+        expected: Option<SocEvent>,
+        actual: Option<SocEvent>,
+    },
+
+    /// The replayed model stopped before the end of the recording
+    EndCycleMismatch { expected: u64, actual: u64 },
+
+    /// The replayed model consumed a different amount of entropy
+    EntropyMismatch {
+        itrng_nibbles: usize,
+        etrng_responses: usize,
+    },
+}
+
+impl std::error::Error for ReplayError {}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EventMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Replay diverged at event {index}: expected {expected:?}, got {actual:?}"
+            ),
+            Self::EndCycleMismatch { expected, actual } => write!(
+                f,
+                "Replay stopped at cycle {actual}, expected to reach {expected}"
+            ),
+            Self::EntropyMismatch {
+                itrng_nibbles,
+                etrng_responses,
+            } => write!(
+                f,
+                "Replay consumed a different amount of entropy: {itrng_nibbles} itrng \
+                 nibbles and {etrng_responses} etrng responses"
+            ),
+        }
+    }
+}
+
+/// Records the SoC interactions of a [`crate::ModelEmulated`] while it runs
+pub(crate) struct Recorder {
+    /// Recording without the entropy, which is collected separately by the
+    /// TRNG iterators
+    recording: Recording,
+    itrng_nibbles: Rc<RefCell<Vec<u8>>>,
+    etrng_responses: Rc<RefCell<Vec<EtrngResponse>>>,
+}
+
+impl Recorder {
+    pub fn new(params: &InitParams, itrng_enabled: bool, crypto_latency: CryptoLatency) -> Self {
+        Self {
+            recording: Recording {
+                rom: params.rom.to_vec(),
+                iccm: params.iccm.to_vec(),
+                dccm: params.dccm.to_vec(),
+                security_state: params.security_state,
+                cptra_obf_key: params.cptra_obf_key,
+                itrng_enabled,
+                crypto_latency,
+                ..Default::default()
+            },
+            itrng_nibbles: Default::default(),
+            etrng_responses: Default::default(),
+        }
+    }
+
+    /// Wrap the internal TRNG entropy source to record the nibbles it provides
+    pub fn record_itrng_nibbles(
+        &self,
+        nibbles: Box<dyn Iterator<Item = u8> + Send>,
+    ) -> Box<dyn Iterator<Item = u8>> {
+        let log = self.itrng_nibbles.clone();
+        Box::new(nibbles.inspect(move |&nibble| log.borrow_mut().push(nibble)))
+    }
+
+    /// Wrap the external TRNG responses to record the ones provided
+    pub fn record_etrng_responses(
+        &self,
+        responses: Box<dyn Iterator<Item = EtrngResponse> + Send>,
+    ) -> Box<dyn Iterator<Item = EtrngResponse>> {
+        let log = self.etrng_responses.clone();
+        Box::new(responses.inspect(move |&response| log.borrow_mut().push(response)))
+    }
+
+    pub fn push(&mut self, event: SocEvent) {
+        self.recording.events.push(event);
+    }
+
+    pub fn events(&self) -> &[SocEvent] {
+        &self.recording.events
+    }
+
+    /// Number of itrng nibbles and etrng responses consumed so far
+    pub fn entropy_consumed(&self) -> (usize, usize) {
+        (
+            self.itrng_nibbles.borrow().len(),
+            self.etrng_responses.borrow().len(),
+        )
+    }
+
+    pub fn recording(&self, end_cycle: u64) -> Recording {
+        Recording {
+            itrng_nibbles: self.itrng_nibbles.borrow().clone(),
+            etrng_responses: self.etrng_responses.borrow().clone(),
+            end_cycle,
+            ..self.recording.clone()
+        }
+    }
+}
+
+fn latency_fields(latency: &CryptoLatency) -> [u64; 10] {
+    [
+        latency.sha512_block,
+        latency.sha256_block,
+        latency.hmac384_block,
+        latency.ecc384_keygen,
+        latency.ecc384_sign,
+        latency.ecc384_verify,
+        latency.doe,
+        latency.sha512_acc_op,
+        latency.sha512_acc_block,
+        latency.key_vault_rw,
+    ]
+}
+
+fn latency_from_fields(fields: [u64; 10]) -> CryptoLatency {
+    let [sha512_block, sha256_block, hmac384_block, ecc384_keygen, ecc384_sign, ecc384_verify, doe, sha512_acc_op, sha512_acc_block, key_vault_rw] =
+        fields;
+    CryptoLatency {
+        sha512_block,
+        sha256_block,
+        hmac384_block,
+        ecc384_keygen,
+        ecc384_sign,
+        ecc384_verify,
+        doe,
+        sha512_acc_op,
+        sha512_acc_block,
+        key_vault_rw,
+    }
+}
+
+/// Encoding of a bus transaction result; 0 is success
+fn bus_error_code(err: BusError) -> u8 {
+    match err {
+        BusError::InstrAccessFault => 1,
+        BusError::LoadAddrMisaligned => 2,
+        BusError::LoadAccessFault => 3,
+        BusError::StoreAddrMisaligned => 4,
+        BusError::StoreAccessFault => 5,
+    }
+}
+
+fn bus_error_from_code(code: u8) -> Result<BusError, SnapshotError> {
+    Ok(match code {
+        1 => BusError::InstrAccessFault,
+        2 => BusError::LoadAddrMisaligned,
+        3 => BusError::LoadAccessFault,
+        4 => BusError::StoreAddrMisaligned,
+        5 => BusError::StoreAccessFault,
+        _ => return Err(SnapshotError::InvalidValue("bus error")),
+    })
+}
+
+fn read_size(r: &mut SnapshotReader) -> Result<RvSize, SnapshotError> {
+    match RvSize::from(usize::from(r.read_u8()?)) {
+        RvSize::Invalid => Err(SnapshotError::InvalidValue("access size")),
+        size => Ok(size),
+    }
+}
+
+fn write_event(w: &mut SnapshotWriter, event: &SocEvent) {
+    match *event {
+        SocEvent::ApbRead {
+            cycle,
+            size,
+            addr,
+            result,
+        } => {
+            w.write_u8(0);
+            w.write_u64(cycle);
+            w.write_u8(usize::from(size) as u8);
+            w.write_u32(addr);
+            match result {
+                Ok(val) => {
+                    w.write_u8(0);
+                    w.write_u32(val);
+                }
+                Err(err) => w.write_u8(bus_error_code(err)),
+            }
+        }
+        SocEvent::ApbWrite {
+            cycle,
+            size,
+            addr,
+            val,
+            result,
+        } => {
+            w.write_u8(1);
+            w.write_u64(cycle);
+            w.write_u8(usize::from(size) as u8);
+            w.write_u32(addr);
+            w.write_u32(val);
+            match result {
+                Ok(()) => w.write_u8(0),
+                Err(err) => w.write_u8(bus_error_code(err)),
+            }
+        }
+        SocEvent::SetApbPauser { cycle, pauser } => {
+            w.write_u8(2);
+            w.write_u64(cycle);
+            w.write_u32(pauser);
+        }
+        SocEvent::EccErrorInjection { cycle, mode } => {
+            w.write_u8(3);
+            w.write_u64(cycle);
+            w.write_u8(match mode {
+                ErrorInjectionMode::None => 0,
+                ErrorInjectionMode::IccmDoubleBitEcc => 1,
+                ErrorInjectionMode::DccmDoubleBitEcc => 2,
+            });
+        }
+        SocEvent::Reset { cycle, kind } => {
+            w.write_u8(4);
+            w.write_u64(cycle);
+            w.write_u8(match kind {
+                ResetKind::Warm => 0,
+                ResetKind::Update => 1,
+            });
+        }
+    }
+}
+
+fn read_event(r: &mut SnapshotReader) -> Result<SocEvent, SnapshotError> {
+    let tag = r.read_u8()?;
+    let cycle = r.read_u64()?;
+    Ok(match tag {
+        0 => {
+            let size = read_size(r)?;
+            let addr = r.read_u32()?;
+            let result = match r.read_u8()? {
+                0 => Ok(r.read_u32()?),
+                code => Err(bus_error_from_code(code)?),
+            };
+            SocEvent::ApbRead {
+                cycle,
+                size,
+                addr,
+                result,
+            }
+        }
+        1 => {
+            let size = read_size(r)?;
+            let addr = r.read_u32()?;
+            let val = r.read_u32()?;
+            let result = match r.read_u8()? {
+                0 => Ok(()),
+                code => Err(bus_error_from_code(code)?),
+            };
+            SocEvent::ApbWrite {
+                cycle,
+                size,
+                addr,
+                val,
+                result,
+            }
+        }
+        2 => SocEvent::SetApbPauser {
+            cycle,
+            pauser: r.read_u32()?,
+        },
+        3 => SocEvent::EccErrorInjection {
+            cycle,
+            mode: match r.read_u8()? {
+                0 => ErrorInjectionMode::None,
+                1 => ErrorInjectionMode::IccmDoubleBitEcc,
+                2 => ErrorInjectionMode::DccmDoubleBitEcc,
+                _ => return Err(SnapshotError::InvalidValue("error injection mode")),
+            },
+        },
+        4 => SocEvent::Reset {
+            cycle,
+            kind: match r.read_u8()? {
+                0 => ResetKind::Warm,
+                1 => ResetKind::Update,
+                _ => return Err(SnapshotError::InvalidValue("reset kind")),
+            },
+        },
+        _ => return Err(SnapshotError::InvalidValue("recording event")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmio::Rv32GenMmio;
+    use crate::{BootParams, HwModel, ModelEmulated};
+    use caliptra_api::mailbox::CommandId;
+    use caliptra_builder::firmware;
+    use caliptra_registers::soc_ifc;
+
+    const BOOT_STATUS: u32 = 0x600;
+
+    fn new_model(rom: &[u8]) -> ModelEmulated {
+        ModelEmulated::new(BootParams {
+            init_params: InitParams {
+                rom,
+                record: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let rv32_gen = Rv32GenMmio::new();
+        let soc_ifc =
+            unsafe { soc_ifc::RegisterBlock::new_with_mmio(0x3003_0000 as *mut u32, &rv32_gen) };
+        soc_ifc.cptra_boot_status().write(|_| BOOT_STATUS);
+        let rom = rv32_gen.into_inner().empty_loop().build();
+
+        let mut model = new_model(&rom);
+        model.set_apb_pauser(0x2);
+        model.step_until(|m| m.soc_ifc().cptra_boot_status().read() == BOOT_STATUS);
+        model.ecc_error_injection(ErrorInjectionMode::None);
+        for _ in 0..100 {
+            model.step();
+        }
+
+        let recording = model.recording().unwrap();
+        assert!(recording
+            .events
+            .iter()
+            .any(|e| matches!(e, SocEvent::SetApbPauser { pauser: 0x2, .. })));
+        assert_eq!(
+            Recording::from_bytes(&recording.to_bytes().unwrap()).unwrap(),
+            recording
+        );
+
+        let replayed = ModelEmulated::replay(&recording).unwrap();
+        assert_eq!(replayed.recording().as_ref(), Some(&recording));
+
+        // The firmware reports a different boot status than the recorded one
+        let mut diverged = recording.clone();
+        let index = diverged
+            .events
+            .iter()
+            .rposition(|e| matches!(e, SocEvent::ApbRead { .. }))
+            .unwrap();
+        let SocEvent::ApbRead { result, .. } = &mut diverged.events[index] else {
+            unreachable!();
+        };
+        *result = Ok(BOOT_STATUS + 1);
+        let err = ModelEmulated::replay(&diverged).err().unwrap();
+        assert_eq!(
+            err.downcast_ref::<ReplayError>(),
+            Some(&ReplayError::EventMismatch {
+                index,
+                expected: Some(diverged.events[index]),
+                actual: Some(recording.events[index]),
+            })
+        );
+
+        assert_eq!(
+            Recording::from_bytes(b"CAL\0"),
+            Err(SnapshotError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_not_recorded_by_default() {
+        if std::env::var_os("CPTRA_RECORD_PATH").is_some() {
+            return;
+        }
+        let rom = Rv32GenMmio::new().into_inner().empty_loop().build();
+        let model = ModelEmulated::new(BootParams {
+            init_params: InitParams {
+                rom: &rom,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(model.recording(), None);
+    }
+
+    #[test]
+    fn test_record_replay_rom_boot() {
+        const RT_READY: &str = "Caliptra RT listening for mailbox commands...";
+
+        let rom = caliptra_builder::build_firmware_rom(&firmware::ROM_WITH_UART).unwrap();
+        let image = caliptra_builder::build_and_sign_image(
+            &firmware::FMC_WITH_UART,
+            &firmware::APP_WITH_UART,
+            Default::default(),
+        )
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+        let mut model = ModelEmulated::new(BootParams {
+            init_params: InitParams {
+                rom: &rom,
+                record: true,
+                ..Default::default()
+            },
+            fw_image: Some(&image),
+            ..Default::default()
+        })
+        .unwrap();
+        model.step_until_output_contains(RT_READY).unwrap();
+
+        // Update the firmware, so the runtime requests an update reset
+        model.output().take(usize::MAX);
+        model
+            .mailbox_execute(CommandId::FIRMWARE_LOAD.into(), &image)
+            .unwrap();
+        model.step_until_output_contains(RT_READY).unwrap();
+
+        let recording = model.recording().unwrap();
+        if recording.itrng_enabled {
+            assert!(!recording.itrng_nibbles.is_empty());
+        } else {
+            assert!(!recording.etrng_responses.is_empty());
+        }
+        assert!(recording.events.iter().any(|e| matches!(
+            e,
+            SocEvent::Reset {
+                kind: ResetKind::Update,
+                ..
+            }
+        )));
+
+        let replayed = ModelEmulated::replay(&recording).unwrap();
+        assert_eq!(replayed.recording(), Some(recording));
+    }
+}
@@ -13,7 +13,7 @@ Abstract:
 --*/
 
 use anyhow::Context;
-use caliptra_image_types::{VENDOR_ECC_KEY_COUNT, VENDOR_LMS_KEY_COUNT};
+use caliptra_image_types::{MAX_AUX_TOC_ENTRY_COUNT, VENDOR_ECC_KEY_COUNT, VENDOR_LMS_KEY_COUNT};
 use serde_derive::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -59,3 +59,43 @@ pub(crate) fn load_key_config(path: &PathBuf) -> anyhow::Result<KeyConfig> {
 
     Ok(config)
 }
+
+/// Auxiliary Image Configuration
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct AuxImageConfig {
+    pub id: u32,
+
+    pub file: String,
+
+    pub version: u32,
+
+    pub svn: u32,
+
+    pub min_svn: u32,
+
+    pub rev: Option<String>,
+}
+
+/// Auxiliary Images Configuration
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct AuxConfig {
+    pub images: Vec<AuxImageConfig>,
+}
+
+/// Load Auxiliary Images Configuration from file
+pub(crate) fn load_aux_config(path: &PathBuf) -> anyhow::Result<AuxConfig> {
+    let config_str = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the config file {}", path.display()))?;
+
+    let config: AuxConfig = toml::from_str(&config_str)
+        .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+    if config.images.len() > MAX_AUX_TOC_ENTRY_COUNT {
+        anyhow::bail!(
+            "{}: more than {MAX_AUX_TOC_ENTRY_COUNT} auxiliary images",
+            path.display()
+        );
+    }
+
+    Ok(config)
+}
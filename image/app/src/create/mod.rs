@@ -21,14 +21,16 @@ use caliptra_image_openssl::ecc_priv_key_from_pem;
 use caliptra_image_openssl::ecc_pub_key_from_pem;
 use caliptra_image_openssl::lms_priv_key_from_pem;
 use caliptra_image_openssl::lms_pub_key_from_pem;
+use caliptra_image_openssl::{LmsKeyState, OsslCrypto, OsslStatefulCrypto};
 use caliptra_image_serde::ImageBundleWriter;
+use caliptra_image_signer::{owner_key_handles, vendor_key_handles, ExternalSigner};
 use caliptra_image_types::*;
 use clap::ArgMatches;
 use std::path::Path;
 use std::path::PathBuf;
 
 use caliptra_image_elf::ElfExecutable;
-use config::{OwnerKeyConfig, VendorKeyConfig};
+use config::{AuxConfig, KeyConfig, OwnerKeyConfig, VendorKeyConfig};
 
 use chrono::NaiveDate;
 
@@ -178,7 +180,7 @@ pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
         .parent()
         .with_context(|| "Invalid parent path")?;
 
-    let gen_config = ImageGeneratorConfig::<ElfExecutable> {
+    let mut gen_config = ImageGeneratorConfig::<ElfExecutable> {
         vendor_config: vendor_config(
             config_dir,
             &config.vendor,
@@ -190,10 +192,51 @@ pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
         owner_config: owner_config(config_dir, &config.owner, own_from_date, own_to_date)?,
         fmc,
         runtime,
+        aux: vec![],
     };
 
-    let gen = ImageGenerator::new(caliptra_image_openssl::OsslCrypto::default());
-    let image = gen.generate(&gen_config).unwrap();
+    if let Some(aux_config_path) = args.get_one::<PathBuf>("aux-config") {
+        let aux_config = config::load_aux_config(aux_config_path)?;
+        let aux_config_dir = aux_config_path
+            .parent()
+            .with_context(|| "Invalid parent path")?;
+        gen_config.aux = aux_images(aux_config_dir, &aux_config)?;
+    }
+
+    let signer = if let Some(socket_path) = args.get_one::<PathBuf>("signer") {
+        Some(ExternalSigner::connect(socket_path)?)
+    } else if let Some(signer_cmd) = args.get_one::<String>("signer-cmd") {
+        Some(ExternalSigner::spawn(
+            std::process::Command::new("sh").arg("-c").arg(signer_cmd),
+        )?)
+    } else {
+        None
+    };
+
+    let lms_key_states = if signer.is_none() {
+        let first_q = args.get_one::<u32>("lms-first-q").copied();
+        lms_key_states(config_dir, &config, *lms_key_idx, first_q)?
+    } else {
+        vec![]
+    };
+
+    let image = if let Some(signer) = signer {
+        // The signing agent holds the private keys
+        let vendor_config = &mut gen_config.vendor_config;
+        vendor_config.priv_keys = Some(vendor_key_handles(&vendor_config.pub_keys));
+        if let Some(owner_config) = gen_config.owner_config.as_mut() {
+            owner_config.priv_keys = Some(owner_key_handles(&owner_config.pub_keys));
+        }
+        ImageGenerator::new(signer).generate(&gen_config)?
+    } else if lms_key_states.is_empty() {
+        ImageGenerator::new(OsslCrypto::default()).generate(&gen_config)?
+    } else {
+        let mut crypto = OsslStatefulCrypto::default();
+        for state in &lms_key_states {
+            crypto.add_lms_key_state(state.clone());
+        }
+        ImageGenerator::new(crypto).generate(&gen_config)?
+    };
 
     let out_file = std::fs::OpenOptions::new()
         .create(true)
@@ -205,9 +248,63 @@ pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
     let mut writer = ImageBundleWriter::new(out_file);
     writer.write(&image)?;
 
+    // Digests for signing the image outside of the tool
+    let (vendor_digest, owner_digest) = crate::sign::header_digests(&image.manifest.header)?;
+    for (digest, path) in [
+        (vendor_digest, args.get_one::<PathBuf>("vendor-digest-out")),
+        (owner_digest, args.get_one::<PathBuf>("owner-digest-out")),
+    ] {
+        if let Some(path) = path {
+            std::fs::write(path, crate::sign::digest_bytes(&digest))
+                .with_context(|| format!("Failed to write file {}", path.display()))?;
+        }
+    }
+
+    for state in &lms_key_states {
+        println!(
+            "{}: {} LMS signatures remaining",
+            state.path().display(),
+            state.remaining()?
+        );
+    }
+
     Ok(())
 }
 
+/// Open the state of the LMS private keys used to sign the image. The state
+/// of a key is kept next to it, in a file with the `.state` extension appended.
+/// With `first_q`, the state files are created instead.
+fn lms_key_states(
+    path: &Path,
+    config: &KeyConfig,
+    lms_key_idx: u32,
+    first_q: Option<u32>,
+) -> anyhow::Result<Vec<LmsKeyState>> {
+    let mut priv_key_paths = vec![];
+    if let Some(lms_priv_keys) = &config.vendor.lms_priv_keys {
+        let pem_file = lms_priv_keys
+            .get(lms_key_idx as usize)
+            .with_context(|| "Invalid LMS key index")?;
+        priv_key_paths.push(path.join(pem_file));
+    }
+    if let Some(pem_file) = config.owner.as_ref().and_then(|o| o.lms_priv_key.as_ref()) {
+        priv_key_paths.push(path.join(pem_file));
+    }
+
+    priv_key_paths
+        .iter()
+        .map(|priv_key_path| {
+            let priv_key = lms_priv_key_from_pem(priv_key_path)?;
+            let mut state_path = priv_key_path.clone().into_os_string();
+            state_path.push(".state");
+            match first_q {
+                Some(first_q) => LmsKeyState::init(Path::new(&state_path), &priv_key, first_q),
+                None => LmsKeyState::open(Path::new(&state_path), &priv_key),
+            }
+        })
+        .collect()
+}
+
 /// Generate Vendor Config
 fn vendor_config(
     path: &Path,
@@ -273,6 +370,33 @@ fn vendor_config(
     Ok(gen_config)
 }
 
+/// Read the auxiliary images listed in the configuration
+fn aux_images(path: &Path, config: &AuxConfig) -> anyhow::Result<Vec<ImageGeneratorAuxImage>> {
+    let mut images = vec![];
+    for image in &config.images {
+        let image_path = path.join(&image.file);
+        let content = std::fs::read(&image_path)
+            .with_context(|| format!("Failed to read the image {}", image_path.display()))?;
+
+        let mut rev = ImageRevision::default();
+        if let Some(rev_str) = &image.rev {
+            let rev_bytes = hex::decode(rev_str)?;
+            let len = rev_bytes.len().min(IMAGE_REVISION_BYTE_SIZE);
+            rev[..len].copy_from_slice(&rev_bytes[..len]);
+        }
+
+        images.push(ImageGeneratorAuxImage {
+            id: image.id,
+            version: image.version,
+            svn: image.svn,
+            min_svn: image.min_svn,
+            rev,
+            content,
+        });
+    }
+    Ok(images)
+}
+
 /// Generate owner config
 fn owner_config(
     path: &Path,
@@ -0,0 +1,586 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   inspect.rs
+
+Abstract:
+
+    File contains implementation of the Caliptra Image inspection command.
+
+--*/
+
+use anyhow::{anyhow, Context};
+use caliptra_drivers::Array4x12;
+use caliptra_image_types::*;
+use clap::ArgMatches;
+use openssl::sha::Sha384;
+use serde_derive::Serialize;
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+use zerocopy::{AsBytes, FromBytes};
+
+#[derive(Serialize)]
+struct EccPubKeyInfo {
+    x: String,
+    y: String,
+}
+
+impl From<&ImageEccPubKey> for EccPubKeyInfo {
+    fn from(key: &ImageEccPubKey) -> Self {
+        Self {
+            x: words_hex(&key.x),
+            y: words_hex(&key.y),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LmsPubKeyInfo {
+    tree_type: u32,
+    ots_type: u32,
+    id: String,
+    digest: String,
+}
+
+impl From<&ImageLmsPublicKey> for LmsPubKeyInfo {
+    fn from(key: &ImageLmsPublicKey) -> Self {
+        Self {
+            tree_type: key.tree_type.0.get(),
+            ots_type: key.otstype.0.get(),
+            id: hex::encode(key.id),
+            digest: hex::encode(key.digest.as_bytes()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PreambleInfo {
+    vendor_ecc_pub_keys: Vec<EccPubKeyInfo>,
+    vendor_lms_pub_keys: Vec<LmsPubKeyInfo>,
+    vendor_ecc_pub_key_idx: u32,
+    vendor_lms_pub_key_idx: u32,
+    vendor_lms_sig_q: u32,
+    owner_ecc_pub_key: EccPubKeyInfo,
+    owner_lms_pub_key: LmsPubKeyInfo,
+    owner_lms_sig_q: u32,
+}
+
+#[derive(Serialize)]
+struct PauserPrivilegesInfo {
+    pauser: u32,
+    locality: u32,
+    pl0: bool,
+}
+
+#[derive(Serialize)]
+struct HeaderInfo {
+    revision: [u32; 2],
+    vendor_ecc_pub_key_idx: u32,
+    vendor_lms_pub_key_idx: u32,
+    flags: u32,
+    toc_len: u32,
+    pl0_pauser: u32,
+    toc_digest: String,
+    vendor_not_before: String,
+    vendor_not_after: String,
+    owner_not_before: String,
+    owner_not_after: String,
+}
+
+#[derive(Serialize)]
+struct TocEntryInfo {
+    id: u32,
+    #[serde(rename = "type")]
+    entry_type: u32,
+    revision: String,
+    version: u32,
+    svn: u32,
+    min_svn: u32,
+    load_addr: u32,
+    entry_point: u32,
+    offset: u32,
+    size: u32,
+    digest: String,
+}
+
+impl From<&ImageTocEntry> for TocEntryInfo {
+    fn from(entry: &ImageTocEntry) -> Self {
+        Self {
+            id: entry.id,
+            entry_type: entry.r#type,
+            revision: hex::encode(entry.revision),
+            version: entry.version,
+            svn: entry.svn,
+            min_svn: entry.min_svn,
+            load_addr: entry.load_addr,
+            entry_point: entry.entry_point,
+            offset: entry.offset,
+            size: entry.size,
+            digest: words_hex(&entry.digest),
+        }
+    }
+}
+
+/// Decoded image manifest
+#[derive(Serialize)]
+struct ManifestInfo {
+    marker: u32,
+    size: u32,
+
+    /// Digests to compare with the vendor and owner public key hash fuses
+    vendor_pub_keys_digest: String,
+    owner_pub_keys_digest: String,
+
+    preamble: PreambleInfo,
+    header: HeaderInfo,
+    toc: Vec<TocEntryInfo>,
+
+    /// PAUSER privilege table at the end of the runtime image
+    pauser_privileges: Vec<PauserPrivilegesInfo>,
+}
+
+impl ManifestInfo {
+    const PAUSER_PRIVILEGES_FLAG: u32 = 1 << 1;
+
+    fn new(manifest: &ImageManifest, image: &[u8]) -> Self {
+        let preamble = &manifest.preamble;
+        let header = &manifest.header;
+        Self {
+            marker: manifest.marker,
+            size: manifest.size,
+            vendor_pub_keys_digest: range_digest(manifest, ImageManifest::vendor_pub_keys_range()),
+            owner_pub_keys_digest: range_digest(manifest, ImageManifest::owner_pub_key_range()),
+            preamble: PreambleInfo {
+                vendor_ecc_pub_keys: preamble
+                    .vendor_pub_keys
+                    .ecc_pub_keys
+                    .iter()
+                    .map(EccPubKeyInfo::from)
+                    .collect(),
+                vendor_lms_pub_keys: preamble
+                    .vendor_pub_keys
+                    .lms_pub_keys
+                    .iter()
+                    .map(LmsPubKeyInfo::from)
+                    .collect(),
+                vendor_ecc_pub_key_idx: preamble.vendor_ecc_pub_key_idx,
+                vendor_lms_pub_key_idx: preamble.vendor_lms_pub_key_idx,
+                vendor_lms_sig_q: preamble.vendor_sigs.lms_sig.q.get(),
+                owner_ecc_pub_key: (&preamble.owner_pub_keys.ecc_pub_key).into(),
+                owner_lms_pub_key: (&preamble.owner_pub_keys.lms_pub_key).into(),
+                owner_lms_sig_q: preamble.owner_sigs.lms_sig.q.get(),
+            },
+            header: HeaderInfo {
+                revision: header.revision,
+                vendor_ecc_pub_key_idx: header.vendor_ecc_pub_key_idx,
+                vendor_lms_pub_key_idx: header.vendor_lms_pub_key_idx,
+                flags: header.flags,
+                toc_len: header.toc_len,
+                pl0_pauser: header.pl0_pauser,
+                toc_digest: words_hex(&header.toc_digest),
+                vendor_not_before: date(&header.vendor_data.vendor_not_before),
+                vendor_not_after: date(&header.vendor_data.vendor_not_after),
+                owner_not_before: date(&header.owner_data.owner_not_before),
+                owner_not_after: date(&header.owner_data.owner_not_after),
+            },
+            toc: [&manifest.fmc, &manifest.runtime]
+                .into_iter()
+                .chain(manifest.aux.iter().take(manifest.aux_count()))
+                .map(TocEntryInfo::from)
+                .collect(),
+            pauser_privileges: Self::pauser_privileges(manifest, image)
+                .iter()
+                .map(|p| PauserPrivilegesInfo {
+                    pauser: p.pauser,
+                    locality: p.locality,
+                    pl0: p.is_pl0(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Entries of the PAUSER privilege table, empty if the image has no
+    /// valid table
+    fn pauser_privileges(manifest: &ImageManifest, image: &[u8]) -> Vec<ImagePauserPrivileges> {
+        if manifest.header.flags & Self::PAUSER_PRIVILEGES_FLAG == 0 {
+            return vec![];
+        }
+        let end = manifest.runtime.offset as usize + manifest.runtime.size as usize;
+        end.checked_sub(core::mem::size_of::<ImagePauserPrivilegesTable>())
+            .and_then(|start| image.get(start..end))
+            .and_then(ImagePauserPrivilegesTable::read_from)
+            .and_then(|table| table.entries().map(<[_]>::to_vec))
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for ManifestInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.marker == MANIFEST_MARKER {
+            ""
+        } else {
+            " (INVALID)"
+        };
+        writeln!(f, "Marker: {:#010x}{marker}", self.marker)?;
+        writeln!(f, "Size: {}", self.size)?;
+        writeln!(
+            f,
+            "Vendor public keys digest: {}",
+            self.vendor_pub_keys_digest
+        )?;
+        writeln!(
+            f,
+            "Owner public keys digest: {}",
+            self.owner_pub_keys_digest
+        )?;
+
+        let preamble = &self.preamble;
+        writeln!(f, "Preamble:")?;
+        for (i, key) in preamble.vendor_ecc_pub_keys.iter().enumerate() {
+            writeln!(f, "  Vendor ECC public key {i}:")?;
+            write_ecc_key(f, key)?;
+        }
+        for (i, key) in preamble.vendor_lms_pub_keys.iter().enumerate() {
+            writeln!(f, "  Vendor LMS public key {i}:")?;
+            write_lms_key(f, key)?;
+        }
+        writeln!(
+            f,
+            "  Vendor ECC public key index: {}",
+            preamble.vendor_ecc_pub_key_idx
+        )?;
+        writeln!(
+            f,
+            "  Vendor LMS public key index: {}",
+            preamble.vendor_lms_pub_key_idx
+        )?;
+        writeln!(f, "  Vendor LMS signature q: {}", preamble.vendor_lms_sig_q)?;
+        writeln!(f, "  Owner ECC public key:")?;
+        write_ecc_key(f, &preamble.owner_ecc_pub_key)?;
+        writeln!(f, "  Owner LMS public key:")?;
+        write_lms_key(f, &preamble.owner_lms_pub_key)?;
+        writeln!(f, "  Owner LMS signature q: {}", preamble.owner_lms_sig_q)?;
+
+        let header = &self.header;
+        writeln!(f, "Header:")?;
+        writeln!(
+            f,
+            "  Revision: {:#010x} {:#010x}",
+            header.revision[0], header.revision[1]
+        )?;
+        writeln!(
+            f,
+            "  Vendor ECC public key index: {}",
+            header.vendor_ecc_pub_key_idx
+        )?;
+        writeln!(
+            f,
+            "  Vendor LMS public key index: {}",
+            header.vendor_lms_pub_key_idx
+        )?;
+        writeln!(f, "  Flags: {:#010x}", header.flags)?;
+        writeln!(f, "  TOC length: {}", header.toc_len)?;
+        writeln!(f, "  PL0 PAUSER: {:#010x}", header.pl0_pauser)?;
+        writeln!(f, "  TOC digest: {}", header.toc_digest)?;
+        writeln!(
+            f,
+            "  Vendor validity: {} - {}",
+            date_or_unset(&header.vendor_not_before),
+            date_or_unset(&header.vendor_not_after)
+        )?;
+        writeln!(
+            f,
+            "  Owner validity: {} - {}",
+            date_or_unset(&header.owner_not_before),
+            date_or_unset(&header.owner_not_after)
+        )?;
+
+        for (i, entry) in self.toc.iter().enumerate() {
+            writeln!(f, "TOC entry {i}:")?;
+            writeln!(f, "  ID: {}", entry.id)?;
+            writeln!(f, "  Type: {}", entry.entry_type)?;
+            writeln!(f, "  Revision: {}", entry.revision)?;
+            writeln!(f, "  Version: {:#010x}", entry.version)?;
+            writeln!(f, "  SVN: {}", entry.svn)?;
+            writeln!(f, "  Minimum SVN: {}", entry.min_svn)?;
+            writeln!(f, "  Load address: {:#010x}", entry.load_addr)?;
+            writeln!(f, "  Entry point: {:#010x}", entry.entry_point)?;
+            writeln!(f, "  Offset: {:#x}", entry.offset)?;
+            writeln!(f, "  Size: {:#x}", entry.size)?;
+            writeln!(f, "  Digest: {}", entry.digest)?;
+        }
+
+        if !self.pauser_privileges.is_empty() {
+            writeln!(f, "PAUSER privileges:")?;
+        }
+        for p in self.pauser_privileges.iter() {
+            writeln!(
+                f,
+                "  PAUSER {:#010x}: locality {:#010x}, {}",
+                p.pauser,
+                p.locality,
+                if p.pl0 { "PL0" } else { "PL1" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_ecc_key(f: &mut fmt::Formatter<'_>, key: &EccPubKeyInfo) -> fmt::Result {
+    writeln!(f, "    X: {}", key.x)?;
+    writeln!(f, "    Y: {}", key.y)
+}
+
+fn write_lms_key(f: &mut fmt::Formatter<'_>, key: &LmsPubKeyInfo) -> fmt::Result {
+    writeln!(f, "    Tree type: {}", key.tree_type)?;
+    writeln!(f, "    OTS type: {}", key.ots_type)?;
+    writeln!(f, "    ID: {}", key.id)?;
+    writeln!(f, "    Digest: {}", key.digest)
+}
+
+/// Hex string of a value in the hardware format (big-endian words)
+fn words_hex(words: &[u32; ECC384_SCALAR_WORD_SIZE]) -> String {
+    hex::encode(<[u8; ECC384_SCALAR_BYTE_SIZE]>::from(Array4x12::from(
+        words,
+    )))
+}
+
+/// SHA-384 digest of a range of the manifest, in the format of the fuses
+fn range_digest(manifest: &ImageManifest, range: Range<u32>) -> String {
+    let mut engine = Sha384::new();
+    engine.update(&manifest.as_bytes()[range.start as usize..range.end as usize]);
+    hex::encode(engine.finish())
+}
+
+/// Certificate validity date [YYYYMMDDHHMMSSZ], empty if not set
+fn date(date: &[u8; 15]) -> String {
+    String::from_utf8_lossy(date)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn date_or_unset(date: &str) -> &str {
+    if date.is_empty() {
+        "(not set)"
+    } else {
+        date
+    }
+}
+
+/// Run the command
+pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
+    let image_path: &PathBuf = args
+        .get_one::<PathBuf>("image")
+        .with_context(|| "image arg not specified")?;
+
+    let image = std::fs::read(image_path)
+        .with_context(|| format!("Failed to read the image {}", image_path.display()))?;
+
+    let manifest = ImageManifest::read_from_prefix(image.as_slice()).ok_or_else(|| {
+        anyhow!(
+            "Image is smaller than the manifest ({} < {IMAGE_MANIFEST_BYTE_SIZE} bytes)",
+            image.len()
+        )
+    })?;
+
+    let info = ManifestInfo::new(&manifest, &image);
+    if args.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print!("{info}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{test_image_bundle, test_image_bundle_with_aux};
+    use caliptra_image_fake_keys::{OWNER_CONFIG, VENDOR_CONFIG_KEY_1};
+    use caliptra_image_gen::{
+        ImageGeneratorAuxImage, ImageGeneratorOwnerConfig, ImageGeneratorVendorConfig,
+    };
+    use openssl::sha::sha384;
+    use serde_json::{json, Value};
+
+    fn inspect(image: &[u8]) -> ManifestInfo {
+        let manifest = ImageManifest::read_from_prefix(image).unwrap();
+        ManifestInfo::new(&manifest, image)
+    }
+
+    fn inspect_json(image: &[u8]) -> Value {
+        serde_json::to_value(inspect(image)).unwrap()
+    }
+
+    fn aux_images() -> Vec<ImageGeneratorAuxImage> {
+        vec![ImageGeneratorAuxImage {
+            id: ImageTocEntryId::AuxFirst.into(),
+            version: 7,
+            svn: 2,
+            min_svn: 1,
+            content: vec![0x5a; 0x80],
+            ..Default::default()
+        }]
+    }
+
+    fn pauser_privileges() -> Vec<ImagePauserPrivileges> {
+        vec![
+            ImagePauserPrivileges {
+                pauser: 0x1,
+                locality: 0x10,
+                flags: ImagePauserPrivileges::PL0_FLAG,
+            },
+            ImagePauserPrivileges {
+                pauser: 0x2,
+                locality: 0x20,
+                flags: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_inspect_json() {
+        let image = test_image_bundle(
+            ImageGeneratorVendorConfig {
+                not_before: *b"20230101000000Z",
+                not_after: *b"20330101000000Z",
+                ..VENDOR_CONFIG_KEY_1
+            },
+            Some(ImageGeneratorOwnerConfig {
+                not_before: *b"20240101000000Z",
+                ..OWNER_CONFIG
+            }),
+        );
+        let json = inspect_json(&image);
+
+        assert_eq!(json["marker"], MANIFEST_MARKER);
+        assert_eq!(json["size"], IMAGE_MANIFEST_BYTE_SIZE);
+        let vendor_range = ImageManifest::vendor_pub_keys_range();
+        assert_eq!(
+            json["vendor_pub_keys_digest"],
+            hex::encode(sha384(
+                &image[vendor_range.start as usize..vendor_range.end as usize]
+            ))
+        );
+
+        let preamble = &json["preamble"];
+        assert_eq!(preamble["vendor_ecc_pub_keys"].as_array().unwrap().len(), 4);
+        assert_eq!(
+            preamble["vendor_lms_pub_keys"].as_array().unwrap().len(),
+            32
+        );
+        assert_eq!(preamble["vendor_ecc_pub_key_idx"], 1);
+        assert_eq!(preamble["vendor_lms_pub_key_idx"], 1);
+
+        let header = &json["header"];
+        assert_eq!(header["vendor_ecc_pub_key_idx"], 1);
+        assert_eq!(header["vendor_lms_pub_key_idx"], 1);
+        assert_eq!(header["toc_len"], MIN_TOC_ENTRY_COUNT);
+        assert_eq!(header["pl0_pauser"], 1);
+        assert_eq!(header["vendor_not_before"], "20230101000000Z");
+        assert_eq!(header["vendor_not_after"], "20330101000000Z");
+        assert_eq!(header["owner_not_before"], "20240101000000Z");
+        assert_eq!(header["owner_not_after"], "");
+
+        let toc = json["toc"].as_array().unwrap();
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0]["id"], u32::from(ImageTocEntryId::Fmc));
+        assert_eq!(toc[0]["type"], u32::from(ImageTocEntryType::Executable));
+        assert_eq!(toc[0]["svn"], 3);
+        assert_eq!(toc[0]["load_addr"], 0x4000_0000);
+        assert_eq!(toc[0]["offset"], IMAGE_MANIFEST_BYTE_SIZE);
+        assert_eq!(toc[0]["size"], 0x100);
+        assert_eq!(toc[0]["digest"], hex::encode(sha384(&[0x13; 0x100])));
+        assert_eq!(toc[1]["id"], u32::from(ImageTocEntryId::Runtime));
+        assert_eq!(toc[1]["svn"], 5);
+        assert_eq!(toc[1]["load_addr"], 0x4000_1000);
+        assert_eq!(toc[1]["offset"], IMAGE_MANIFEST_BYTE_SIZE + 0x100);
+        assert_eq!(toc[1]["size"], 0x200);
+        assert_eq!(toc[1]["digest"], hex::encode(sha384(&[0x73; 0x200])));
+
+        assert_eq!(json["pauser_privileges"], json!([]));
+    }
+
+    #[test]
+    fn test_inspect_json_aux() {
+        let image =
+            test_image_bundle_with_aux(VENDOR_CONFIG_KEY_1, Some(OWNER_CONFIG), aux_images());
+        let json = inspect_json(&image);
+
+        assert_eq!(json["header"]["toc_len"], MIN_TOC_ENTRY_COUNT + 1);
+        let toc = json["toc"].as_array().unwrap();
+        assert_eq!(toc.len(), 3);
+        assert_eq!(toc[2]["id"], u32::from(ImageTocEntryId::AuxFirst));
+        assert_eq!(toc[2]["type"], u32::from(ImageTocEntryType::Auxiliary));
+        assert_eq!(toc[2]["version"], 7);
+        assert_eq!(toc[2]["svn"], 2);
+        assert_eq!(toc[2]["min_svn"], 1);
+        assert_eq!(toc[2]["offset"], IMAGE_MANIFEST_BYTE_SIZE + 0x300);
+        assert_eq!(toc[2]["size"], 0x80);
+        assert_eq!(toc[2]["digest"], hex::encode(sha384(&[0x5a; 0x80])));
+    }
+
+    #[test]
+    fn test_inspect_json_pauser_privileges() {
+        let image = test_image_bundle(
+            ImageGeneratorVendorConfig {
+                pauser_privileges: pauser_privileges(),
+                ..VENDOR_CONFIG_KEY_1
+            },
+            Some(OWNER_CONFIG),
+        );
+        let json = inspect_json(&image);
+
+        // The table is appended to the runtime image
+        assert_eq!(
+            json["toc"][1]["size"],
+            0x200 + core::mem::size_of::<ImagePauserPrivilegesTable>()
+        );
+        assert_eq!(
+            json["pauser_privileges"],
+            json!([
+                { "pauser": 0x1, "locality": 0x10, "pl0": true },
+                { "pauser": 0x2, "locality": 0x20, "pl0": false },
+            ])
+        );
+
+        // A truncated image has no table
+        let manifest = ImageManifest::read_from_prefix(image.as_slice()).unwrap();
+        let json = serde_json::to_value(ManifestInfo::new(&manifest, &image[..0x100])).unwrap();
+        assert_eq!(json["pauser_privileges"], json!([]));
+    }
+
+    #[test]
+    fn test_inspect_text() {
+        let image = test_image_bundle_with_aux(
+            ImageGeneratorVendorConfig {
+                pauser_privileges: pauser_privileges(),
+                ..VENDOR_CONFIG_KEY_1
+            },
+            Some(OWNER_CONFIG),
+            aux_images(),
+        );
+        let text = inspect(&image).to_string();
+
+        assert!(text.starts_with(&format!("Marker: {MANIFEST_MARKER:#010x}\n")));
+        assert!(text.contains("  Vendor ECC public key index: 1\n"));
+        assert!(text.contains("  Owner validity: (not set) - (not set)\n"));
+        assert!(text.contains("TOC entry 1:\n  ID: 2\n  Type: 1\n"));
+        assert!(text.contains("TOC entry 2:\n  ID: 256\n  Type: 2\n"));
+        assert!(text.contains(
+            "PAUSER privileges:\n  \
+             PAUSER 0x00000001: locality 0x00000010, PL0\n  \
+             PAUSER 0x00000002: locality 0x00000020, PL1\n"
+        ));
+
+        let mut manifest = ImageManifest::read_from_prefix(image.as_slice()).unwrap();
+        manifest.marker = 0;
+        let text = ManifestInfo::new(&manifest, &image).to_string();
+        assert!(text.starts_with("Marker: 0x00000000 (INVALID)\n"));
+    }
+}
@@ -13,9 +13,14 @@ Abstract:
 --*/
 use std::path::PathBuf;
 
-use clap::{arg, value_parser, Command};
+use clap::{arg, value_parser, ArgAction, Command};
 
 mod create;
+mod inspect;
+mod sign;
+#[cfg(test)]
+mod test_util;
+mod verify;
 
 /// Entry point
 fn main() {
@@ -110,6 +115,138 @@ fn main() {
             arg!(--"mfg-to-date" <String> "Certificate Validity End Date By Manufacturer [YYYYMMDDHHMMSS - Zulu Time]")
                 .required(false)
                 .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(--"aux-config" <FILE> "Auxiliary SoC Image Configuration file")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"lms-first-q" <U32> "Create the state files of the LMS keys, starting at this q value")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"signer" <FILE> "Unix socket of the signing agent holding the private keys")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"signer-cmd" <COMMAND> "Signing agent command serving requests on its stdin and stdout")
+                .required(false)
+                .conflicts_with("signer")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(--"vendor-digest-out" <FILE> "Output file for the header digest to be signed by the vendor")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"owner-digest-out" <FILE> "Output file for the header digest to be signed by the owner")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        ),
+    Command::new("sign")
+        .about("Add signatures produced outside of the tool to an unsigned firmware image bundle")
+        .arg(
+            arg!(--"image" <FILE> "Unsigned image bundle")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"vendor-ecc-sig" <FILE> "Vendor ECC signature [DER]")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"vendor-lms-sig" <FILE> "Vendor LMS signature")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"owner-ecc-sig" <FILE> "Owner ECC signature [DER]")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"owner-lms-sig" <FILE> "Owner LMS signature")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"out" <FILE> "Output file")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        ),
+    Command::new("inspect")
+        .about("Print the manifest of a firmware image bundle")
+        .arg(
+            arg!(--"image" <FILE> "Image bundle")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"json" "Print the manifest as JSON")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        ),
+    Command::new("verify")
+        .about("Verify a firmware image bundle the way the ROM does on cold boot")
+        .arg(
+            arg!(--"image" <FILE> "Image bundle")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"fuses" <FILE> "Fuse Configuration file")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"vendor-pk-hash" <SHA384HASH> "Vendor Public Key Hash Fuse")
+                .required(false)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(--"owner-pk-hash" <SHA384HASH> "Owner Public Key Hash Fuse")
+                .required(false)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(--"fmc-svn" <U32> "FMC Security Version Number Fuse")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"rt-svn" <U32> "Runtime Security Version Number Fuse")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"anti-rollback-disable" "Anti-Rollback Disable Fuse")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(--"vendor-ecc-revocation" <U32> "Vendor ECC Public Key Revocation Mask Fuse")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"vendor-lms-revocation" <U32> "Vendor LMS Public Key Revocation Mask Fuse")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"lifecycle" <String> "Device Lifecycle")
+                .required(false)
+                .value_parser(["unprovisioned", "manufacturing", "production"]),
+        )
+        .arg(
+            arg!(--"lms-verify" "Verify the LMS Signatures in addition to ECC")
+                .required(false)
+                .action(ArgAction::SetTrue),
         )];
 
     let cmd = Command::new("caliptra-image-app")
@@ -120,6 +257,9 @@ fn main() {
 
     let result = match cmd.subcommand().unwrap() {
         ("create", args) => create::run_cmd(args),
+        ("inspect", args) => inspect::run_cmd(args),
+        ("sign", args) => sign::run_cmd(args),
+        ("verify", args) => verify::run_cmd(args),
         (_, _) => unreachable!(),
     };
 
@@ -0,0 +1,311 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   sign.rs
+
+Abstract:
+
+    File contains implementation of the Caliptra Image signing command, which
+    injects signatures produced outside of the tool into an unsigned image
+    bundle.
+
+--*/
+
+use anyhow::{anyhow, bail, Context};
+use caliptra_drivers::{Array4x12, HashValue};
+use caliptra_image_gen::ImageGenerator;
+use caliptra_image_openssl::OsslCrypto;
+use caliptra_image_types::*;
+use clap::ArgMatches;
+use openssl::ecdsa::EcdsaSig;
+use std::path::PathBuf;
+use zerocopy::{AsBytes, FromBytes};
+
+use crate::verify::env;
+
+/// Signatures produced outside of the tool
+#[derive(Default)]
+struct DetachedSignatures {
+    vendor_ecc_sig: Option<ImageEccSignature>,
+
+    vendor_lms_sig: Option<ImageLmsSignature>,
+
+    owner_ecc_sig: Option<ImageEccSignature>,
+
+    owner_lms_sig: Option<ImageLmsSignature>,
+}
+
+/// Calculate the vendor and owner header digests to be signed
+pub(crate) fn header_digests(header: &ImageHeader) -> anyhow::Result<(ImageDigest, ImageDigest)> {
+    let gen = ImageGenerator::new(OsslCrypto::default());
+    Ok((
+        gen.header_digest_vendor(header)?,
+        gen.header_digest_owner(header)?,
+    ))
+}
+
+/// Bytes of a digest, as signed by the external signer
+pub(crate) fn digest_bytes(digest: &ImageDigest) -> [u8; SHA384_DIGEST_BYTE_SIZE] {
+    Array4x12::from(digest).into()
+}
+
+/// Validate the signatures against the public keys in the preamble, and
+/// store them in the manifest
+fn inject_signatures(
+    manifest: &mut ImageManifest,
+    sigs: &DetachedSignatures,
+) -> anyhow::Result<()> {
+    let (vendor_digest, owner_digest) = header_digests(&manifest.header)?;
+    let preamble = &mut manifest.preamble;
+
+    if let Some(sig) = &sigs.vendor_ecc_sig {
+        let pub_key = preamble
+            .vendor_pub_keys
+            .ecc_pub_keys
+            .get(preamble.vendor_ecc_pub_key_idx as usize)
+            .with_context(|| "Invalid vendor ECC public key index")?;
+        check_ecc_sig(&vendor_digest, pub_key, sig)
+            .with_context(|| "Invalid vendor ECC signature")?;
+        preamble.vendor_sigs.ecc_sig = *sig;
+    }
+
+    if let Some(sig) = &sigs.vendor_lms_sig {
+        let pub_key = preamble
+            .vendor_pub_keys
+            .lms_pub_keys
+            .get(preamble.vendor_lms_pub_key_idx as usize)
+            .with_context(|| "Invalid vendor LMS public key index")?;
+        check_lms_sig(&vendor_digest, pub_key, sig)
+            .with_context(|| "Invalid vendor LMS signature")?;
+        preamble.vendor_sigs.lms_sig = *sig;
+    }
+
+    if let Some(sig) = &sigs.owner_ecc_sig {
+        check_ecc_sig(&owner_digest, &preamble.owner_pub_keys.ecc_pub_key, sig)
+            .with_context(|| "Invalid owner ECC signature")?;
+        preamble.owner_sigs.ecc_sig = *sig;
+    }
+
+    if let Some(sig) = &sigs.owner_lms_sig {
+        check_lms_sig(&owner_digest, &preamble.owner_pub_keys.lms_pub_key, sig)
+            .with_context(|| "Invalid owner LMS signature")?;
+        preamble.owner_sigs.lms_sig = *sig;
+    }
+
+    Ok(())
+}
+
+/// Check an ECC signature the way the ROM does
+fn check_ecc_sig(
+    digest: &ImageDigest,
+    pub_key: &ImageEccPubKey,
+    sig: &ImageEccSignature,
+) -> anyhow::Result<()> {
+    let verify_r = env::ecc384_verify(digest, pub_key, sig).map_err(|err| {
+        anyhow!(
+            "ECC verification failed with error {:#010x}",
+            u32::from(err)
+        )
+    })?;
+    if verify_r != Array4x12::from(sig.r) {
+        bail!("Signature does not match the digest and public key");
+    }
+    Ok(())
+}
+
+/// Check an LMS signature the way the ROM does
+fn check_lms_sig(
+    digest: &ImageDigest,
+    pub_key: &ImageLmsPublicKey,
+    sig: &ImageLmsSignature,
+) -> anyhow::Result<()> {
+    let candidate_key = env::lms_verify(digest, pub_key, sig).map_err(|err| {
+        anyhow!(
+            "LMS verification failed with error {:#010x}",
+            u32::from(err)
+        )
+    })?;
+    if candidate_key != HashValue::from(pub_key.digest) {
+        bail!("Signature does not match the digest and public key");
+    }
+    Ok(())
+}
+
+/// Read a DER encoded ECDSA-384 signature
+fn ecc_sig_from_der(path: &PathBuf) -> anyhow::Result<ImageEccSignature> {
+    let der = std::fs::read(path)
+        .with_context(|| format!("Failed to read ECC signature file {}", path.display()))?;
+    let sig = EcdsaSig::from_der(&der)
+        .with_context(|| format!("Failed to parse ECC signature file {}", path.display()))?;
+    let r: [u8; ECC384_SCALAR_BYTE_SIZE] = sig
+        .r()
+        .to_vec_padded(ECC384_SCALAR_BYTE_SIZE as i32)?
+        .try_into()
+        .unwrap();
+    let s: [u8; ECC384_SCALAR_BYTE_SIZE] = sig
+        .s()
+        .to_vec_padded(ECC384_SCALAR_BYTE_SIZE as i32)?
+        .try_into()
+        .unwrap();
+    Ok(ImageEccSignature {
+        r: Array4x12::from(r).into(),
+        s: Array4x12::from(s).into(),
+    })
+}
+
+/// Read an LMS signature, in the format of RFC 8554
+fn lms_sig_from_file(path: &PathBuf) -> anyhow::Result<ImageLmsSignature> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read LMS signature file {}", path.display()))?;
+    ImageLmsSignature::read_from(bytes.as_slice())
+        .with_context(|| format!("Failed to parse LMS signature file {}", path.display()))
+}
+
+/// Run the command
+pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
+    let image_path: &PathBuf = args
+        .get_one::<PathBuf>("image")
+        .with_context(|| "image arg not specified")?;
+
+    let out_path: &PathBuf = args
+        .get_one::<PathBuf>("out")
+        .with_context(|| "out arg not specified")?;
+
+    let sigs = DetachedSignatures {
+        vendor_ecc_sig: args
+            .get_one::<PathBuf>("vendor-ecc-sig")
+            .map(ecc_sig_from_der)
+            .transpose()?,
+        vendor_lms_sig: args
+            .get_one::<PathBuf>("vendor-lms-sig")
+            .map(lms_sig_from_file)
+            .transpose()?,
+        owner_ecc_sig: args
+            .get_one::<PathBuf>("owner-ecc-sig")
+            .map(ecc_sig_from_der)
+            .transpose()?,
+        owner_lms_sig: args
+            .get_one::<PathBuf>("owner-lms-sig")
+            .map(lms_sig_from_file)
+            .transpose()?,
+    };
+
+    let mut image = std::fs::read(image_path)
+        .with_context(|| format!("Failed to read the image {}", image_path.display()))?;
+    let mut manifest = ImageManifest::read_from_prefix(image.as_slice())
+        .with_context(|| format!("{} is not an image bundle", image_path.display()))?;
+
+    inject_signatures(&mut manifest, &sigs)?;
+    image[..IMAGE_MANIFEST_BYTE_SIZE].copy_from_slice(manifest.as_bytes());
+
+    std::fs::write(out_path, image)
+        .with_context(|| format!("Failed to write file {}", out_path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_image_bundle;
+    use caliptra_image_fake_keys::{OWNER_CONFIG, VENDOR_CONFIG_KEY_1};
+    use caliptra_image_gen::{
+        ImageGeneratorCrypto, ImageGeneratorOwnerConfig, ImageGeneratorVendorConfig,
+    };
+
+    #[test]
+    fn test_inject_signatures() {
+        let image = test_image_bundle(
+            ImageGeneratorVendorConfig {
+                priv_keys: None,
+                ..VENDOR_CONFIG_KEY_1
+            },
+            Some(ImageGeneratorOwnerConfig {
+                priv_keys: None,
+                ..OWNER_CONFIG
+            }),
+        );
+        let mut manifest = ImageManifest::read_from_prefix(image.as_slice()).unwrap();
+        assert_eq!(
+            manifest.preamble.vendor_sigs.as_bytes(),
+            ImageSignatures::default().as_bytes()
+        );
+        assert_eq!(
+            manifest.preamble.owner_sigs.as_bytes(),
+            ImageSignatures::default().as_bytes()
+        );
+
+        // Sign the digests the way the signing ceremony would
+        let crypto = OsslCrypto::default();
+        let (vendor_digest, owner_digest) = header_digests(&manifest.header).unwrap();
+        let vendor_priv_keys = VENDOR_CONFIG_KEY_1.priv_keys.unwrap();
+        let owner_priv_keys = OWNER_CONFIG.priv_keys.unwrap();
+        let sigs = DetachedSignatures {
+            vendor_ecc_sig: Some(
+                crypto
+                    .ecdsa384_sign(
+                        &vendor_digest,
+                        &vendor_priv_keys.ecc_priv_keys[1],
+                        &VENDOR_CONFIG_KEY_1.pub_keys.ecc_pub_keys[1],
+                    )
+                    .unwrap(),
+            ),
+            vendor_lms_sig: Some(
+                crypto
+                    .lms_sign(&vendor_digest, &vendor_priv_keys.lms_priv_keys[1])
+                    .unwrap(),
+            ),
+            owner_ecc_sig: Some(
+                crypto
+                    .ecdsa384_sign(
+                        &owner_digest,
+                        &owner_priv_keys.ecc_priv_key,
+                        &OWNER_CONFIG.pub_keys.ecc_pub_key,
+                    )
+                    .unwrap(),
+            ),
+            owner_lms_sig: Some(
+                crypto
+                    .lms_sign(&owner_digest, &owner_priv_keys.lms_priv_key)
+                    .unwrap(),
+            ),
+        };
+
+        // A signature of the wrong digest is rejected
+        let swapped = DetachedSignatures {
+            owner_ecc_sig: sigs.vendor_ecc_sig,
+            ..Default::default()
+        };
+        assert!(inject_signatures(&mut manifest, &swapped).is_err());
+        let swapped = DetachedSignatures {
+            vendor_lms_sig: sigs.owner_lms_sig,
+            ..Default::default()
+        };
+        assert!(inject_signatures(&mut manifest, &swapped).is_err());
+        assert_eq!(
+            manifest.preamble.owner_sigs.as_bytes(),
+            ImageSignatures::default().as_bytes()
+        );
+
+        inject_signatures(&mut manifest, &sigs).unwrap();
+        assert_eq!(
+            manifest.preamble.vendor_sigs.ecc_sig,
+            sigs.vendor_ecc_sig.unwrap()
+        );
+        assert_eq!(
+            manifest.preamble.vendor_sigs.lms_sig,
+            sigs.vendor_lms_sig.unwrap()
+        );
+        assert_eq!(
+            manifest.preamble.owner_sigs.ecc_sig,
+            sigs.owner_ecc_sig.unwrap()
+        );
+        assert_eq!(
+            manifest.preamble.owner_sigs.lms_sig,
+            sigs.owner_lms_sig.unwrap()
+        );
+    }
+}
@@ -0,0 +1,98 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   test_util.rs
+
+Abstract:
+
+    File contains the image bundles shared by the tests of the commands.
+
+--*/
+
+use caliptra_image_gen::{
+    ImageGenerator, ImageGeneratorAuxImage, ImageGeneratorConfig, ImageGeneratorOwnerConfig,
+    ImageGeneratorVendorConfig, ImageGenratorExecutable,
+};
+use caliptra_image_openssl::OsslCrypto;
+use caliptra_image_types::ImageRevision;
+
+#[derive(Default)]
+struct TestExecutable {
+    load_addr: u32,
+    svn: u32,
+    rev: ImageRevision,
+    content: Vec<u8>,
+}
+
+impl ImageGenratorExecutable for TestExecutable {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn svn(&self) -> u32 {
+        self.svn
+    }
+
+    fn min_svn(&self) -> u32 {
+        0
+    }
+
+    fn rev(&self) -> &ImageRevision {
+        &self.rev
+    }
+
+    fn load_addr(&self) -> u32 {
+        self.load_addr
+    }
+
+    fn entry_point(&self) -> u32 {
+        self.load_addr
+    }
+
+    fn content(&self) -> &Vec<u8> {
+        &self.content
+    }
+
+    fn size(&self) -> u32 {
+        self.content.len() as u32
+    }
+}
+
+/// Image bundle with a small FMC and runtime
+pub(crate) fn test_image_bundle(
+    vendor_config: ImageGeneratorVendorConfig,
+    owner_config: Option<ImageGeneratorOwnerConfig>,
+) -> Vec<u8> {
+    test_image_bundle_with_aux(vendor_config, owner_config, vec![])
+}
+
+pub(crate) fn test_image_bundle_with_aux(
+    vendor_config: ImageGeneratorVendorConfig,
+    owner_config: Option<ImageGeneratorOwnerConfig>,
+    aux: Vec<ImageGeneratorAuxImage>,
+) -> Vec<u8> {
+    let gen = ImageGenerator::new(OsslCrypto::default());
+    let image = gen
+        .generate(&ImageGeneratorConfig {
+            vendor_config,
+            owner_config,
+            fmc: TestExecutable {
+                load_addr: 0x4000_0000,
+                svn: 3,
+                content: vec![0x13; 0x100],
+                ..Default::default()
+            },
+            runtime: TestExecutable {
+                load_addr: 0x4000_1000,
+                svn: 5,
+                content: vec![0x73; 0x200],
+                ..Default::default()
+            },
+            aux,
+        })
+        .unwrap();
+    image.to_bytes().unwrap()
+}
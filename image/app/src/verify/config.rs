@@ -0,0 +1,148 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   config.rs
+
+Abstract:
+
+    File contains utilities for parsing the fuse configuration file
+
+--*/
+
+use anyhow::{anyhow, Context};
+use caliptra_drivers::{Array4x12, Lifecycle};
+use caliptra_image_types::{ImageDigest, SHA384_DIGEST_BYTE_SIZE};
+use clap::ArgMatches;
+use serde_derive::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Fuse Configuration. Fuses that are not specified are not burned.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FuseConfig {
+    /// SHA-384 digest of the vendor public keys, as a hex string
+    pub vendor_pk_hash: Option<String>,
+
+    /// SHA-384 digest of the owner public keys, as a hex string
+    pub owner_pk_hash: Option<String>,
+
+    pub fmc_svn: Option<u32>,
+
+    pub runtime_svn: Option<u32>,
+
+    pub anti_rollback_disable: Option<bool>,
+
+    pub vendor_ecc_revocation: Option<u32>,
+
+    pub vendor_lms_revocation: Option<u32>,
+
+    /// "unprovisioned", "manufacturing" or "production"
+    pub lifecycle: Option<String>,
+
+    pub lms_verify: Option<bool>,
+}
+
+/// Fuse values checked by the ROM when verifying an image
+pub(crate) struct Fuses {
+    pub vendor_pk_hash: ImageDigest,
+
+    pub owner_pk_hash: ImageDigest,
+
+    pub fmc_svn: u32,
+
+    pub runtime_svn: u32,
+
+    pub anti_rollback_disable: bool,
+
+    pub vendor_ecc_revocation: u32,
+
+    pub vendor_lms_revocation: u32,
+
+    pub lifecycle: Lifecycle,
+
+    pub lms_verify: bool,
+}
+
+impl FuseConfig {
+    /// Override the configuration with the fuses given on the command line
+    pub(crate) fn apply_args(&mut self, args: &ArgMatches) {
+        if let Some(hash) = args.get_one::<String>("vendor-pk-hash") {
+            self.vendor_pk_hash = Some(hash.clone());
+        }
+        if let Some(hash) = args.get_one::<String>("owner-pk-hash") {
+            self.owner_pk_hash = Some(hash.clone());
+        }
+        if let Some(svn) = args.get_one::<u32>("fmc-svn") {
+            self.fmc_svn = Some(*svn);
+        }
+        if let Some(svn) = args.get_one::<u32>("rt-svn") {
+            self.runtime_svn = Some(*svn);
+        }
+        if args.get_flag("anti-rollback-disable") {
+            self.anti_rollback_disable = Some(true);
+        }
+        if let Some(mask) = args.get_one::<u32>("vendor-ecc-revocation") {
+            self.vendor_ecc_revocation = Some(*mask);
+        }
+        if let Some(mask) = args.get_one::<u32>("vendor-lms-revocation") {
+            self.vendor_lms_revocation = Some(*mask);
+        }
+        if let Some(lifecycle) = args.get_one::<String>("lifecycle") {
+            self.lifecycle = Some(lifecycle.clone());
+        }
+        if args.get_flag("lms-verify") {
+            self.lms_verify = Some(true);
+        }
+    }
+
+    /// Convert the configuration to fuse values
+    pub(crate) fn fuses(&self) -> anyhow::Result<Fuses> {
+        Ok(Fuses {
+            vendor_pk_hash: parse_digest(self.vendor_pk_hash.as_deref())
+                .with_context(|| "Invalid vendor public key hash")?,
+            owner_pk_hash: parse_digest(self.owner_pk_hash.as_deref())
+                .with_context(|| "Invalid owner public key hash")?,
+            fmc_svn: self.fmc_svn.unwrap_or_default(),
+            runtime_svn: self.runtime_svn.unwrap_or_default(),
+            anti_rollback_disable: self.anti_rollback_disable.unwrap_or_default(),
+            vendor_ecc_revocation: self.vendor_ecc_revocation.unwrap_or_default(),
+            vendor_lms_revocation: self.vendor_lms_revocation.unwrap_or_default(),
+            lifecycle: parse_lifecycle(self.lifecycle.as_deref().unwrap_or("unprovisioned"))?,
+            lms_verify: self.lms_verify.unwrap_or_default(),
+        })
+    }
+}
+
+/// Parse a SHA-384 digest from a hex string. An unburned fuse reads as zero.
+fn parse_digest(hash: Option<&str>) -> anyhow::Result<ImageDigest> {
+    let Some(hash) = hash else {
+        return Ok(ImageDigest::default());
+    };
+    let bytes: [u8; SHA384_DIGEST_BYTE_SIZE] = hex::decode(hash)?
+        .try_into()
+        .map_err(|_| anyhow!("Expected {SHA384_DIGEST_BYTE_SIZE} bytes"))?;
+    Ok(Array4x12::from(bytes).into())
+}
+
+fn parse_lifecycle(lifecycle: &str) -> anyhow::Result<Lifecycle> {
+    match lifecycle {
+        "unprovisioned" => Ok(Lifecycle::Unprovisioned),
+        "manufacturing" => Ok(Lifecycle::Manufacturing),
+        "production" => Ok(Lifecycle::Production),
+        _ => Err(anyhow!("Invalid lifecycle {lifecycle}")),
+    }
+}
+
+/// Load Fuse Configuration from file
+pub(crate) fn load_fuse_config(path: &PathBuf) -> anyhow::Result<FuseConfig> {
+    let config_str = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the fuse file {}", path.display()))?;
+
+    let config: FuseConfig = toml::from_str(&config_str)
+        .with_context(|| format!("Failed to parse fuse file {}", path.display()))?;
+
+    Ok(config)
+}
@@ -0,0 +1,252 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   env.rs
+
+Abstract:
+
+    File contains a software implementation of the image verification
+    environment, with the crypto accelerators of the ROM replaced by OpenSSL.
+
+--*/
+
+use caliptra_drivers::memory_layout::ICCM_RANGE;
+use caliptra_drivers::*;
+use caliptra_image_types::*;
+use caliptra_image_verify::ImageVerificationEnv;
+use core::ops::Range;
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{EcGroup, EcPoint};
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::sha::{Sha256, Sha384};
+use std::cmp::Ordering;
+
+use super::config::Fuses;
+
+/// Host Verification Environment
+pub(crate) struct HostImageVerificationEnv<'a> {
+    /// Contents of the mailbox the ROM verifies the image from
+    pub image: &'a [u8],
+
+    pub fuses: &'a Fuses,
+
+    /// Last extended error code set by the verifier
+    pub fw_extended_error: Option<u32>,
+}
+
+impl<'a> ImageVerificationEnv for &mut HostImageVerificationEnv<'a> {
+    /// Calculate Digest using OpenSSL
+    fn sha384_digest(&mut self, offset: u32, len: u32) -> CaliptraResult<ImageDigest> {
+        let err = CaliptraError::IMAGE_VERIFIER_ERR_DIGEST_OUT_OF_BOUNDS;
+        let data = self
+            .image
+            .get(offset as usize..)
+            .ok_or(err)?
+            .get(..len as usize)
+            .ok_or(err)?;
+        let mut engine = Sha384::new();
+        engine.update(data);
+        Ok(Array4x12::from(engine.finish()).into())
+    }
+
+    /// ECC-384 Verification routine
+    fn ecc384_verify(
+        &mut self,
+        digest: &ImageDigest,
+        pub_key: &ImageEccPubKey,
+        sig: &ImageEccSignature,
+    ) -> CaliptraResult<Array4xN<12, 48>> {
+        ecc384_verify(digest, pub_key, sig)
+    }
+
+    fn lms_verify(
+        &mut self,
+        digest: &ImageDigest,
+        pub_key: &ImageLmsPublicKey,
+        sig: &ImageLmsSignature,
+    ) -> CaliptraResult<HashValue<SHA192_DIGEST_WORD_SIZE>> {
+        lms_verify(digest, pub_key, sig)
+    }
+
+    fn vendor_pub_key_digest(&self) -> ImageDigest {
+        self.fuses.vendor_pk_hash
+    }
+
+    fn vendor_ecc_pub_key_revocation(&self) -> VendorPubKeyRevocation {
+        VendorPubKeyRevocation::from_bits_truncate(self.fuses.vendor_ecc_revocation)
+    }
+
+    fn vendor_lms_pub_key_revocation(&self) -> u32 {
+        self.fuses.vendor_lms_revocation
+    }
+
+    fn owner_pub_key_digest_fuses(&self) -> ImageDigest {
+        self.fuses.owner_pk_hash
+    }
+
+    fn anti_rollback_disable(&self) -> bool {
+        self.fuses.anti_rollback_disable
+    }
+
+    fn dev_lifecycle(&self) -> Lifecycle {
+        self.fuses.lifecycle
+    }
+
+    // The data vault is only checked on update reset
+    fn vendor_ecc_pub_key_idx_dv(&self) -> u32 {
+        0
+    }
+
+    fn vendor_lms_pub_key_idx_dv(&self) -> u32 {
+        0
+    }
+
+    fn owner_pub_key_digest_dv(&self) -> ImageDigest {
+        ImageDigest::default()
+    }
+
+    fn get_fmc_digest_dv(&self) -> ImageDigest {
+        ImageDigest::default()
+    }
+
+    fn fmc_fuse_svn(&self) -> u32 {
+        self.fuses.fmc_svn
+    }
+
+    fn runtime_fuse_svn(&self) -> u32 {
+        self.fuses.runtime_svn
+    }
+
+    fn iccm_range(&self) -> Range<u32> {
+        ICCM_RANGE
+    }
+
+    fn lms_verify_enabled(&self) -> bool {
+        self.fuses.lms_verify
+    }
+
+    fn set_fw_extended_error(&mut self, err: u32) {
+        self.fw_extended_error = Some(err);
+    }
+}
+
+/// Compute the R value of an ECDSA-384 signature, as the ECC-384 accelerator
+/// does. The signature is valid if it matches the R value of the signature.
+pub(crate) fn ecc384_verify(
+    digest: &ImageDigest,
+    pub_key: &ImageEccPubKey,
+    sig: &ImageEccSignature,
+) -> CaliptraResult<Array4x12> {
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    let mut ctx = BigNumContext::new().unwrap();
+    let mut order = BigNum::new().unwrap();
+    group.order(&mut order, &mut ctx).unwrap();
+
+    // If R or S are not in the range [1, N-1], the driver fails the check
+    // before starting the accelerator
+    let r = to_bignum(&sig.r);
+    let s = to_bignum(&sig.s);
+    for scalar in [&r, &s] {
+        if scalar.num_bits() == 0 || scalar.ucmp(&order) != Ordering::Less {
+            return Err(CaliptraError::DRIVER_ECC384_SCALAR_RANGE_CHECK_FAILED);
+        }
+    }
+
+    // The accelerator computes a garbage value from an invalid public
+    // key, which the verifier rejects as a signature mismatch.
+    let verify_r = ecc384_verify_r(&group, &order, &mut ctx, digest, pub_key, &r, &s)
+        .unwrap_or([0u8; ECC384_SCALAR_BYTE_SIZE]);
+    Ok(Array4x12::from(verify_r))
+}
+
+/// Compute the candidate public key of an LMS signature. The signature is
+/// valid if it matches the digest of the public key.
+pub(crate) fn lms_verify(
+    digest: &ImageDigest,
+    pub_key: &ImageLmsPublicKey,
+    sig: &ImageLmsSignature,
+) -> CaliptraResult<HashValue<SHA192_DIGEST_WORD_SIZE>> {
+    let message: [u8; SHA384_DIGEST_BYTE_SIZE] = Array4x12::from(digest).into();
+    Lms::default().verify_lms_signature_cfi_generic(
+        &mut Sha256SoftwareDriver,
+        &message,
+        pub_key,
+        sig,
+    )
+}
+
+fn to_bignum(scalar: &ImageScalar) -> BigNum {
+    let bytes: [u8; ECC384_SCALAR_BYTE_SIZE] = Array4x12::from(scalar).into();
+    BigNum::from_slice(&bytes).unwrap()
+}
+
+/// Compute the R value of the ECDSA verification performed by the ECC-384
+/// accelerator: the x coordinate of (e * s^-1) * G + (r * s^-1) * Q, modulo N
+fn ecc384_verify_r(
+    group: &EcGroup,
+    order: &BigNumRef,
+    ctx: &mut BigNumContext,
+    digest: &ImageDigest,
+    pub_key: &ImageEccPubKey,
+    r: &BigNumRef,
+    s: &BigNumRef,
+) -> Result<[u8; ECC384_SCALAR_BYTE_SIZE], ErrorStack> {
+    let mut point = EcPoint::new(group)?;
+    point.set_affine_coordinates_gfp(group, &to_bignum(&pub_key.x), &to_bignum(&pub_key.y), ctx)?;
+
+    let mut s_inv = BigNum::new()?;
+    s_inv.mod_inverse(s, order, ctx)?;
+    let mut u1 = BigNum::new()?;
+    u1.mod_mul(&to_bignum(digest), &s_inv, order, ctx)?;
+    let mut u2 = BigNum::new()?;
+    u2.mod_mul(r, &s_inv, order, ctx)?;
+
+    let mut result = EcPoint::new(group)?;
+    result.mul_full(group, &u1, &point, &u2, ctx)?;
+
+    let mut x = BigNum::new()?;
+    let mut y = BigNum::new()?;
+    result.affine_coordinates_gfp(group, &mut x, &mut y, ctx)?;
+    let mut verify_r = BigNum::new()?;
+    verify_r.nnmod(&x, order, ctx)?;
+
+    Ok(verify_r
+        .to_vec_padded(ECC384_SCALAR_BYTE_SIZE as i32)?
+        .try_into()
+        .unwrap())
+}
+
+/// SHA-256 used by the LMS verification
+struct Sha256SoftwareDriver;
+
+struct Sha256DigestOpSw(Sha256);
+
+impl<'a> Sha256DigestOp<'a> for Sha256DigestOpSw {
+    fn update(&mut self, data: &[u8]) -> CaliptraResult<()> {
+        self.0.update(data);
+        Ok(())
+    }
+
+    fn finalize(self, digest: &mut Array4x8) -> CaliptraResult<()> {
+        *digest = Array4x8::from(self.0.finish());
+        Ok(())
+    }
+}
+
+impl Sha256Alg for Sha256SoftwareDriver {
+    type DigestOp<'a> = Sha256DigestOpSw;
+
+    fn digest_init(&mut self) -> CaliptraResult<Self::DigestOp<'_>> {
+        Ok(Sha256DigestOpSw(Sha256::new()))
+    }
+
+    fn digest(&mut self, buf: &[u8]) -> CaliptraResult<Array4x8> {
+        let mut engine = Sha256::new();
+        engine.update(buf);
+        Ok(Array4x8::from(engine.finish()))
+    }
+}
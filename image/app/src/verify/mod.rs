@@ -0,0 +1,279 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   mod.rs
+
+Abstract:
+
+    File contains implementation of the Caliptra Image verification command.
+
+--*/
+
+mod config;
+pub(crate) mod env;
+
+use anyhow::Context;
+use caliptra_drivers::memory_layout::MBOX_SIZE;
+use caliptra_drivers::{Array4x12, CaliptraError, ResetReason};
+use caliptra_image_types::*;
+use caliptra_image_verify::{ImageVerificationExeInfo, ImageVerificationInfo, ImageVerifier};
+use clap::ArgMatches;
+use std::path::PathBuf;
+use zerocopy::FromBytes;
+
+use config::{FuseConfig, Fuses};
+use env::HostImageVerificationEnv;
+
+/// Error the ROM reports when it rejects the image
+pub(crate) struct VerifyError {
+    /// Fatal error code
+    pub code: CaliptraError,
+
+    /// Extended error info set by the verifier, if any
+    pub extended_error: Option<u32>,
+}
+
+impl From<CaliptraError> for VerifyError {
+    fn from(code: CaliptraError) -> Self {
+        Self {
+            code,
+            extended_error: None,
+        }
+    }
+}
+
+/// Verify an image bundle the way the ROM does on cold reset, after
+/// receiving it through the mailbox
+fn verify_bundle(image: &[u8], fuses: &Fuses) -> Result<ImageVerificationInfo, VerifyError> {
+    if image.is_empty() || image.len() > IMAGE_BYTE_SIZE {
+        Err(CaliptraError::FW_PROC_INVALID_IMAGE_SIZE)?;
+    }
+
+    // The ROM reads the image in place from the mailbox SRAM. Reads past the
+    // end of the image return zeros.
+    let mut mbox = vec![0u8; MBOX_SIZE as usize];
+    mbox[..image.len()].copy_from_slice(image);
+
+    let manifest = ImageManifest::read_from_prefix(mbox.as_slice()).unwrap();
+
+    let mut venv = HostImageVerificationEnv {
+        image: &mbox,
+        fuses,
+        fw_extended_error: None,
+    };
+    let mut verifier = ImageVerifier::new(&mut venv);
+    let result = verifier.verify(&manifest, image.len() as u32, ResetReason::ColdReset);
+    result.map_err(|code| VerifyError {
+        code,
+        extended_error: venv.fw_extended_error,
+    })
+}
+
+fn print_exe_info(name: &str, info: &ImageVerificationExeInfo) {
+    println!("{name}:");
+    println!("  Load address: {:#010x}", info.load_addr);
+    println!("  Entry point: {:#010x}", info.entry_point);
+    println!("  Size: {:#x}", info.size);
+    println!("  SVN: {}", info.svn);
+    println!("  Effective fuse SVN: {}", info.effective_fuse_svn);
+    println!(
+        "  Digest: {}",
+        hex::encode(<[u8; SHA384_DIGEST_BYTE_SIZE]>::from(Array4x12::from(
+            info.digest
+        )))
+    );
+}
+
+/// Run the command
+pub(crate) fn run_cmd(args: &ArgMatches) -> anyhow::Result<()> {
+    let image_path: &PathBuf = args
+        .get_one::<PathBuf>("image")
+        .with_context(|| "image arg not specified")?;
+
+    let mut fuse_config = match args.get_one::<PathBuf>("fuses") {
+        Some(path) => config::load_fuse_config(path)?,
+        None => FuseConfig::default(),
+    };
+    fuse_config.apply_args(args);
+    let fuses = fuse_config.fuses()?;
+
+    let image = std::fs::read(image_path)
+        .with_context(|| format!("Failed to read the image {}", image_path.display()))?;
+
+    let info = match verify_bundle(&image, &fuses) {
+        Ok(info) => info,
+        Err(err) => {
+            println!(
+                "Image verification failed with error {:#010x}",
+                u32::from(err.code)
+            );
+            if let Some(extended_error) = err.extended_error {
+                println!("Extended error: {extended_error:#010x}");
+            }
+            std::process::exit(1);
+        }
+    };
+
+    println!("Image verified");
+    println!(
+        "Vendor ECC public key index: {}",
+        info.vendor_ecc_pub_key_idx
+    );
+    if let Some(idx) = info.vendor_lms_pub_key_idx {
+        println!("Vendor LMS public key index: {idx}");
+    }
+    println!(
+        "Owner public keys digest: {} ({})",
+        hex::encode(<[u8; SHA384_DIGEST_BYTE_SIZE]>::from(Array4x12::from(
+            info.owner_pub_keys_digest
+        ))),
+        if info.owner_pub_keys_digest_in_fuses {
+            "in fuses"
+        } else {
+            "not in fuses"
+        }
+    );
+    print_exe_info("FMC", &info.fmc);
+    print_exe_info("Runtime", &info.runtime);
+    for aux in info.aux_images() {
+        println!("Auxiliary image {:#x}:", aux.id);
+        println!("  Size: {:#x}", aux.size);
+        println!("  SVN: {}", aux.svn);
+        println!(
+            "  Digest: {}",
+            hex::encode(<[u8; SHA384_DIGEST_BYTE_SIZE]>::from(Array4x12::from(
+                aux.digest
+            )))
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{test_image_bundle, test_image_bundle_with_aux};
+    use caliptra_drivers::Lifecycle;
+    use caliptra_image_fake_keys::{OWNER_CONFIG, VENDOR_CONFIG_KEY_1};
+    use caliptra_image_gen::{ImageGeneratorAuxImage, ImageGeneratorCrypto};
+    use caliptra_image_openssl::OsslCrypto;
+    use zerocopy::AsBytes;
+
+    fn test_image() -> Vec<u8> {
+        test_image_bundle(VENDOR_CONFIG_KEY_1, Some(OWNER_CONFIG))
+    }
+
+    fn digest(data: &[u8]) -> ImageDigest {
+        OsslCrypto::default().sha384_digest(data).unwrap()
+    }
+
+    #[test]
+    fn test_verify_bundle() {
+        let image = test_image();
+        let manifest = ImageManifest::read_from_prefix(image.as_slice()).unwrap();
+        let vendor_range = ImageManifest::vendor_pub_keys_range();
+        let vendor_pk_hash = digest(&image[vendor_range.start as usize..vendor_range.end as usize]);
+        let owner_range = ImageManifest::owner_pub_key_range();
+        let owner_pk_hash = digest(&image[owner_range.start as usize..owner_range.end as usize]);
+
+        let fuses = Fuses {
+            vendor_pk_hash,
+            owner_pk_hash,
+            fmc_svn: 3,
+            runtime_svn: 4,
+            anti_rollback_disable: false,
+            vendor_ecc_revocation: 0x1,
+            vendor_lms_revocation: 0x1,
+            lifecycle: Lifecycle::Production,
+            lms_verify: true,
+        };
+        let info = verify_bundle(&image, &fuses).ok().unwrap();
+        assert_eq!(info.vendor_ecc_pub_key_idx, 1);
+        assert_eq!(info.vendor_lms_pub_key_idx, Some(1));
+        assert!(info.owner_pub_keys_digest_in_fuses);
+        assert_eq!(info.fmc.load_addr, 0x4000_0000);
+        assert_eq!(info.fmc.digest, manifest.fmc.digest);
+        assert_eq!(info.runtime.svn, 5);
+        assert_eq!(info.runtime.effective_fuse_svn, 4);
+
+        let code = |image: &[u8], fuses: &Fuses| verify_bundle(image, fuses).err().unwrap().code;
+        assert_eq!(code(&[], &fuses), CaliptraError::FW_PROC_INVALID_IMAGE_SIZE);
+        assert_eq!(
+            code(&image[..image.len() - 4], &fuses),
+            CaliptraError::IMAGE_VERIFIER_ERR_IMAGE_LEN_MORE_THAN_BUNDLE_SIZE
+        );
+        assert_eq!(
+            code(
+                &image,
+                &Fuses {
+                    vendor_ecc_revocation: 0x2,
+                    ..fuses
+                }
+            ),
+            CaliptraError::IMAGE_VERIFIER_ERR_VENDOR_ECC_PUB_KEY_REVOKED
+        );
+        assert_eq!(
+            code(
+                &image,
+                &Fuses {
+                    runtime_svn: 6,
+                    ..fuses
+                }
+            ),
+            CaliptraError::IMAGE_VERIFIER_ERR_RUNTIME_SVN_LESS_THAN_FUSE
+        );
+
+        let mut corrupted_manifest = manifest;
+        corrupted_manifest.preamble.vendor_sigs.ecc_sig.r[0] ^= 1;
+        let mut corrupted = image.clone();
+        corrupted[..IMAGE_MANIFEST_BYTE_SIZE].copy_from_slice(corrupted_manifest.as_bytes());
+        assert_eq!(
+            code(&corrupted, &fuses),
+            CaliptraError::IMAGE_VERIFIER_ERR_VENDOR_ECC_SIGNATURE_INVALID
+        );
+    }
+
+    #[test]
+    fn test_verify_bundle_aux() {
+        let aux = vec![
+            ImageGeneratorAuxImage {
+                id: ImageTocEntryId::AuxFirst.into(),
+                svn: 2,
+                min_svn: 1,
+                content: vec![0x5a; 0x80],
+                ..Default::default()
+            },
+            ImageGeneratorAuxImage {
+                id: u32::from(ImageTocEntryId::AuxFirst) + 1,
+                svn: 7,
+                content: vec![0xa5; 0x40],
+                ..Default::default()
+            },
+        ];
+        let image = test_image_bundle_with_aux(VENDOR_CONFIG_KEY_1, Some(OWNER_CONFIG), aux);
+        let manifest = ImageManifest::read_from_prefix(image.as_slice()).unwrap();
+        assert_eq!(manifest.header.toc_len, MIN_TOC_ENTRY_COUNT + 2);
+        assert_eq!(image.len(), IMAGE_MANIFEST_BYTE_SIZE + 0x100 + 0x200 + 0xc0);
+
+        let fuses = FuseConfig::default().fuses().unwrap();
+        let info = verify_bundle(&image, &fuses).ok().unwrap();
+        let aux_images = info.aux_images();
+        assert_eq!(aux_images.len(), 2);
+        assert_eq!(aux_images[0].id, u32::from(ImageTocEntryId::AuxFirst));
+        assert_eq!(aux_images[0].svn, 2);
+        assert_eq!(aux_images[0].digest, digest(&[0x5a; 0x80]));
+        assert_eq!(aux_images[1].size, 0x40);
+        assert_eq!(aux_images[1].digest, digest(&[0xa5; 0x40]));
+
+        let mut corrupted = image.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_bundle(&corrupted, &fuses).err().unwrap().code,
+            CaliptraError::IMAGE_VERIFIER_ERR_AUX_DIGEST_MISMATCH
+        );
+    }
+}
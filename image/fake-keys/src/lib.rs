@@ -331,33 +331,29 @@ pub const OWNER_PRIVATE_KEYS: ImageOwnerPrivKeys = ImageOwnerPrivKeys {
     lms_priv_key: OWNER_LMS_KEY_PRIVATE,
 };
 
-pub const VENDOR_CONFIG_KEY_0: ImageGeneratorVendorConfig = ImageGeneratorVendorConfig {
-    pub_keys: VENDOR_PUBLIC_KEYS,
-    ecc_key_idx: 0,
-    lms_key_idx: 0,
-    priv_keys: Some(VENDOR_PRIVATE_KEYS),
-    not_before: [0u8; 15],
-    not_after: [0u8; 15],
-    pl0_pauser: Some(0x1),
-};
+/// Vendor configuration using the given key indexes. The configurations
+/// can't be derived from each other with `..`, as
+/// `ImageGeneratorVendorConfig` can't be dropped in a constant.
+const fn vendor_config(ecc_key_idx: u32, lms_key_idx: u32) -> ImageGeneratorVendorConfig {
+    ImageGeneratorVendorConfig {
+        pub_keys: VENDOR_PUBLIC_KEYS,
+        ecc_key_idx,
+        lms_key_idx,
+        priv_keys: Some(VENDOR_PRIVATE_KEYS),
+        not_before: [0u8; 15],
+        not_after: [0u8; 15],
+        pl0_pauser: Some(0x1),
+        pauser_privileges: Vec::new(),
+    }
+}
 
-pub const VENDOR_CONFIG_KEY_1: ImageGeneratorVendorConfig = ImageGeneratorVendorConfig {
-    ecc_key_idx: 1,
-    lms_key_idx: 1,
-    ..VENDOR_CONFIG_KEY_0
-};
+pub const VENDOR_CONFIG_KEY_0: ImageGeneratorVendorConfig = vendor_config(0, 0);
 
-pub const VENDOR_CONFIG_KEY_2: ImageGeneratorVendorConfig = ImageGeneratorVendorConfig {
-    ecc_key_idx: 2,
-    lms_key_idx: 2,
-    ..VENDOR_CONFIG_KEY_0
-};
+pub const VENDOR_CONFIG_KEY_1: ImageGeneratorVendorConfig = vendor_config(1, 1);
 
-pub const VENDOR_CONFIG_KEY_3: ImageGeneratorVendorConfig = ImageGeneratorVendorConfig {
-    ecc_key_idx: 3,
-    lms_key_idx: 3,
-    ..VENDOR_CONFIG_KEY_0
-};
+pub const VENDOR_CONFIG_KEY_2: ImageGeneratorVendorConfig = vendor_config(2, 2);
+
+pub const VENDOR_CONFIG_KEY_3: ImageGeneratorVendorConfig = vendor_config(3, 3);
 
 pub const OWNER_CONFIG: ImageGeneratorOwnerConfig = ImageGeneratorOwnerConfig {
     pub_keys: ImageOwnerPubKeys {
@@ -26,6 +26,7 @@ pub struct ImageGenerator<Crypto: ImageGeneratorCrypto> {
 impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
     const DEFAULT_FLAGS: u32 = 0;
     const PL0_PAUSER_FLAG: u32 = (1 << 0);
+    const PAUSER_PRIVILEGES_FLAG: u32 = (1 << 1);
 
     /// Create an instance `ImageGenerator`
     pub fn new(crypto: Crypto) -> Self {
@@ -45,8 +46,14 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
     where
         E: ImageGenratorExecutable,
     {
-        if IMAGE_MANIFEST_BYTE_SIZE as u32 + config.fmc.size() + config.runtime.size()
-            > IMAGE_BYTE_SIZE as u32
+        if config.aux.len() > MAX_AUX_TOC_ENTRY_COUNT {
+            bail!("More than {MAX_AUX_TOC_ENTRY_COUNT} auxiliary images");
+        }
+
+        let runtime_content = Self::gen_runtime_content(config)?;
+        let aux_size: usize = config.aux.iter().map(|aux| aux.content.len()).sum();
+        if IMAGE_MANIFEST_BYTE_SIZE + config.fmc.size() as usize + runtime_content.len() + aux_size
+            > IMAGE_BYTE_SIZE
         {
             bail!("Image larger than {IMAGE_BYTE_SIZE} bytes");
         }
@@ -54,12 +61,32 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
         // Create FMC TOC & Content
         let id = ImageTocEntryId::Fmc;
         let offset = IMAGE_MANIFEST_BYTE_SIZE as u32;
-        let (fmc_toc, fmc) = self.gen_image(&config.fmc, id, offset)?;
+        let (fmc_toc, fmc) =
+            self.gen_image(&config.fmc, config.fmc.content().clone(), id, offset)?;
 
         // Create Runtime TOC & Content
         let id = ImageTocEntryId::Runtime;
         let offset = offset + fmc_toc.size;
-        let (runtime_toc, runtime) = self.gen_image(&config.runtime, id, offset)?;
+        let (runtime_toc, runtime) =
+            self.gen_image(&config.runtime, runtime_content, id, offset)?;
+
+        // Create Auxiliary Image TOCs & Contents
+        let mut offset = offset + runtime_toc.size;
+        let mut aux_tocs = [ImageTocEntry::default(); MAX_AUX_TOC_ENTRY_COUNT];
+        let mut aux = vec![];
+        for (i, (aux_toc, image)) in aux_tocs.iter_mut().zip(config.aux.iter()).enumerate() {
+            if image.id < ImageTocEntryId::AuxFirst.into()
+                || config.aux[..i].iter().any(|other| other.id == image.id)
+            {
+                bail!("Invalid auxiliary image id 0x{:x}", image.id);
+            }
+            if image.content.is_empty() {
+                bail!("Auxiliary image 0x{:x} is empty", image.id);
+            }
+            *aux_toc = self.gen_aux_image(image, offset)?;
+            offset += aux_toc.size;
+            aux.push(image.content.clone());
+        }
 
         // Check if fmc and runtime image load address ranges don't overlap.
         if fmc_toc.overlaps(&runtime_toc) {
@@ -76,7 +103,7 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
         let lms_key_idx = config.vendor_config.lms_key_idx;
 
         // Create Header
-        let toc_digest = self.toc_digest(&fmc_toc, &runtime_toc)?;
+        let toc_digest = self.toc_digest(&fmc_toc, &runtime_toc, &aux_tocs[..config.aux.len()])?;
         let header = self.gen_header(config, ecc_key_idx, lms_key_idx, toc_digest)?;
 
         // Create Preamable
@@ -98,6 +125,7 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             header,
             fmc: fmc_toc,
             runtime: runtime_toc,
+            aux: aux_tocs,
         };
 
         // Create Image Bundle
@@ -105,6 +133,7 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             manifest,
             fmc,
             runtime,
+            aux,
         };
 
         Ok(image)
@@ -185,7 +214,7 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             vendor_ecc_pub_key_idx: ecc_key_idx,
             vendor_lms_pub_key_idx: lms_key_idx,
             flags: Self::DEFAULT_FLAGS,
-            toc_len: MAX_TOC_ENTRY_COUNT,
+            toc_len: MIN_TOC_ENTRY_COUNT + config.aux.len() as u32,
             toc_digest: digest,
             ..Default::default()
         };
@@ -198,6 +227,10 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             header.pl0_pauser = pauser;
         }
 
+        if !config.vendor_config.pauser_privileges.is_empty() {
+            header.flags |= Self::PAUSER_PRIVILEGES_FLAG;
+        }
+
         if let Some(owner_config) = &config.owner_config {
             header.owner_data.owner_not_before = owner_config.not_before;
             header.owner_data.owner_not_after = owner_config.not_after;
@@ -231,10 +264,39 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             .sha384_digest(preamble.vendor_pub_keys.as_bytes())
     }
 
+    /// Generate runtime image content
+    ///
+    /// The PAUSER privilege table, if any, is appended word aligned to the
+    /// runtime executable so the ROM loads and measures it without parsing it.
+    fn gen_runtime_content<E>(config: &ImageGeneratorConfig<E>) -> anyhow::Result<Vec<u8>>
+    where
+        E: ImageGenratorExecutable,
+    {
+        let mut content = config.runtime.content().clone();
+        let pauser_privileges = &config.vendor_config.pauser_privileges;
+        if pauser_privileges.is_empty() {
+            return Ok(content);
+        }
+        if pauser_privileges.len() > MAX_PAUSER_PRIVILEGES_COUNT {
+            bail!("More than {MAX_PAUSER_PRIVILEGES_COUNT} PAUSER privilege entries");
+        }
+
+        let mut table = ImagePauserPrivilegesTable {
+            len: pauser_privileges.len() as u32,
+            ..Default::default()
+        };
+        table.entries[..pauser_privileges.len()].copy_from_slice(pauser_privileges);
+
+        content.resize((content.len() + 3) & !3, 0);
+        content.extend_from_slice(table.as_bytes());
+        Ok(content)
+    }
+
     /// Generate image
     fn gen_image<E>(
         &self,
         image: &E,
+        content: Vec<u8>,
         id: ImageTocEntryId,
         offset: u32,
     ) -> anyhow::Result<(ImageTocEntry, Vec<u8>)>
@@ -242,7 +304,7 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
         E: ImageGenratorExecutable,
     {
         let r#type = ImageTocEntryType::Executable;
-        let digest = self.crypto.sha384_digest(image.content())?;
+        let digest = self.crypto.sha384_digest(&content)?;
 
         let entry = ImageTocEntry {
             id: id.into(),
@@ -254,11 +316,36 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
             load_addr: image.load_addr(),
             entry_point: image.entry_point(),
             offset,
-            size: image.content().len() as u32,
+            size: content.len() as u32,
+            digest,
+        };
+
+        Ok((entry, content))
+    }
+
+    /// Generate auxiliary image TOC
+    fn gen_aux_image(
+        &self,
+        image: &ImageGeneratorAuxImage,
+        offset: u32,
+    ) -> anyhow::Result<ImageTocEntry> {
+        let r#type = ImageTocEntryType::Auxiliary;
+        let digest = self.crypto.sha384_digest(&image.content)?;
+
+        let entry = ImageTocEntry {
+            id: image.id,
+            r#type: r#type.into(),
+            revision: image.rev,
+            version: image.version,
+            svn: image.svn,
+            min_svn: image.min_svn,
+            offset,
+            size: image.content.len() as u32,
             digest,
+            ..Default::default()
         };
 
-        Ok((entry, image.content().clone()))
+        Ok(entry)
     }
 
     /// Calculate TOC digest
@@ -266,10 +353,14 @@ impl<Crypto: ImageGeneratorCrypto> ImageGenerator<Crypto> {
         &self,
         fmc_toc: &ImageTocEntry,
         rt_toc: &ImageTocEntry,
+        aux_tocs: &[ImageTocEntry],
     ) -> anyhow::Result<ImageDigest> {
         let mut toc_content: Vec<u8> = Vec::new();
         toc_content.extend_from_slice(fmc_toc.as_bytes());
         toc_content.extend_from_slice(rt_toc.as_bytes());
+        for aux_toc in aux_tocs {
+            toc_content.extend_from_slice(aux_toc.as_bytes());
+        }
         self.crypto.sha384_digest(&toc_content)
     }
 }
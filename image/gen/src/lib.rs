@@ -82,6 +82,8 @@ pub struct ImageGeneratorVendorConfig {
     pub not_after: [u8; 15],
 
     pub pl0_pauser: Option<u32>,
+
+    pub pauser_privileges: Vec<ImagePauserPrivileges>,
 }
 
 /// Image Generator Owner Configuration
@@ -96,6 +98,23 @@ pub struct ImageGeneratorOwnerConfig {
     pub not_after: [u8; 15],
 }
 
+/// Image Generator Auxiliary Image Configuration
+#[derive(Default, Clone)]
+pub struct ImageGeneratorAuxImage {
+    /// SoC-defined identifier, starting at `ImageTocEntryId::AuxFirst`
+    pub id: u32,
+
+    pub version: u32,
+
+    pub svn: u32,
+
+    pub min_svn: u32,
+
+    pub rev: ImageRevision,
+
+    pub content: Vec<u8>,
+}
+
 /// Image Generator Configuration
 #[derive(Default)]
 pub struct ImageGeneratorConfig<T>
@@ -109,4 +128,7 @@ where
     pub fmc: T,
 
     pub runtime: T,
+
+    /// Auxiliary images verified and measured, but not loaded, by Caliptra
+    pub aux: Vec<ImageGeneratorAuxImage>,
 }
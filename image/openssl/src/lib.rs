@@ -12,6 +12,8 @@ Abstract:
 
 --*/
 
+mod lms_state;
+
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context};
@@ -28,6 +30,8 @@ use openssl::sha::{Sha256, Sha384};
 
 use zerocopy::{AsBytes, FromBytes};
 
+pub use lms_state::{LmsKeyState, OsslStatefulCrypto};
+
 #[derive(Default)]
 pub struct OsslCrypto {}
 
@@ -90,7 +94,8 @@ impl ImageGeneratorCrypto for OsslCrypto {
     // This function is here as a convenience for creating test images.
     // This function uses the same Q value for each signature which is
     // insecure, a Q value must be used at most one time.  In practice
-    // the digest should be passed to a FIPS approved HSM for signature.
+    // the digest should be passed to a FIPS approved HSM for signature,
+    // or signed with OsslStatefulCrypto, which tracks the used Q values.
     fn lms_sign(
         &self,
         digest: &ImageDigest,
@@ -0,0 +1,512 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+   lms_state.rs
+
+Abstract:
+
+    File contains the stateful LMS signer. The index of the next unused
+    one-time signature key (q) of each LMS private key is kept in a state
+    file, and is persisted before the signature is generated.
+
+--*/
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use caliptra_image_gen::ImageGeneratorCrypto;
+use caliptra_image_types::*;
+use openssl::rand::rand_bytes;
+use zerocopy::{AsBytes, FromBytes};
+
+use crate::{from_hw_format, sign_with_lms_key, OsslCrypto, IMAGE_LMS_TREE_TYPE_HT_5};
+
+/// "LMSQ"
+const LMS_STATE_MARKER: u32 = 0x514d_534c;
+
+/// Contents of the LMS state file
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Default)]
+struct LmsStateRecord {
+    marker: u32,
+
+    /// Identifier of the LMS key the state belongs to
+    id: [u8; 16],
+
+    /// Next unused q value
+    next_q: u32,
+}
+
+/// State of an LMS private key, persisted in a file
+#[derive(Clone)]
+pub struct LmsKeyState {
+    path: PathBuf,
+
+    id: [u8; 16],
+
+    /// Number of leaves of the LMS tree
+    leaf_count: u32,
+}
+
+impl LmsKeyState {
+    /// Create the state file of an LMS private key. `first_q` is the first
+    /// q value that was never used to sign with the key: 0 for a new key, or
+    /// the next q value of the signer that used the key before. Fails if the
+    /// state file exists.
+    pub fn init(path: &Path, priv_key: &ImageLmsPrivKey, first_q: u32) -> anyhow::Result<Self> {
+        let state = Self::new(path, priv_key)?;
+        if first_q > state.leaf_count {
+            return Err(anyhow!(
+                "Invalid first q value {}: LMS key {} has {} signatures",
+                first_q,
+                hex::encode(state.id),
+                state.leaf_count
+            ));
+        }
+
+        let _lock = state.lock()?;
+        if path.exists() {
+            return Err(anyhow!("LMS state file {} already exists", path.display()));
+        }
+        state.write(first_q)?;
+
+        Ok(state)
+    }
+
+    /// Open the state file of an LMS private key created with
+    /// [`LmsKeyState::init`]. Fails if the state file does not exist: the
+    /// signer cannot tell which q values of the key were used, and reusing a
+    /// q value breaks the security of the key.
+    pub fn open(path: &Path, priv_key: &ImageLmsPrivKey) -> anyhow::Result<Self> {
+        let state = Self::new(path, priv_key)?;
+        if !path.exists() {
+            return Err(anyhow!(
+                "LMS state file {} does not exist; initialize it with the first unused q value of the key",
+                path.display()
+            ));
+        }
+        state.read()?;
+
+        Ok(state)
+    }
+
+    fn new(path: &Path, priv_key: &ImageLmsPrivKey) -> anyhow::Result<Self> {
+        let height = match priv_key.tree_type {
+            IMAGE_LMS_TREE_TYPE => 15,
+            IMAGE_LMS_TREE_TYPE_HT_5 => 5,
+            _ => return Err(anyhow!("Error looking up lms tree type")),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            id: priv_key.id,
+            leaf_count: 1 << height,
+        })
+    }
+
+    /// Path of the state file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Next q value that will be used to sign
+    pub fn next_q(&self) -> anyhow::Result<u32> {
+        self.read()
+    }
+
+    /// Number of signatures that can still be generated with the key
+    pub fn remaining(&self) -> anyhow::Result<u32> {
+        Ok(self.leaf_count - self.read()?)
+    }
+
+    /// Reserve the next q value. The state file is updated before the q
+    /// value is returned, so a q value is never reused, even if the signer
+    /// is interrupted before the signature is generated.
+    fn reserve(&self) -> anyhow::Result<u32> {
+        let _lock = self.lock()?;
+        let q = self.read()?;
+        if q >= self.leaf_count {
+            return Err(anyhow!(
+                "LMS key {} is exhausted: all {} signatures have been used",
+                hex::encode(self.id),
+                self.leaf_count
+            ));
+        }
+        self.write(q + 1)?;
+        Ok(q)
+    }
+
+    fn read(&self) -> anyhow::Result<u32> {
+        let bytes = std::fs::read(&self.path)
+            .with_context(|| format!("Failed to read LMS state file {}", self.path.display()))?;
+        let record = LmsStateRecord::read_from(bytes.as_slice())
+            .filter(|record| record.marker == LMS_STATE_MARKER)
+            .with_context(|| format!("Invalid LMS state file {}", self.path.display()))?;
+        if record.id != self.id {
+            return Err(anyhow!(
+                "LMS state file {} belongs to key {}, not {}",
+                self.path.display(),
+                hex::encode(record.id),
+                hex::encode(self.id)
+            ));
+        }
+        if record.next_q > self.leaf_count {
+            return Err(anyhow!(
+                "Invalid q value {} in LMS state file {}",
+                record.next_q,
+                self.path.display()
+            ));
+        }
+        Ok(record.next_q)
+    }
+
+    /// Replace the state file. The new state is written to a temporary file
+    /// that is renamed over the state file, so the state file always holds
+    /// either the old or the new state.
+    fn write(&self, next_q: u32) -> anyhow::Result<()> {
+        let record = LmsStateRecord {
+            marker: LMS_STATE_MARKER,
+            id: self.id,
+            next_q,
+        };
+        let tmp_path = self.sibling("tmp");
+        let mut file = File::create(&tmp_path)
+            .with_context(|| format!("Failed to create file {}", tmp_path.display()))?;
+        file.write_all(record.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to update LMS state file {}", self.path.display()))?;
+
+        // Persist the rename
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Lock the state file to keep concurrent signers from reserving the
+    /// same q value. The lock is an advisory lock on a lock file, which the
+    /// OS releases when the signer exits, so a lock file left behind by a
+    /// signer that crashed does not block the next one.
+    #[cfg(unix)]
+    fn lock(&self) -> anyhow::Result<LmsStateLock> {
+        use std::os::unix::io::AsRawFd;
+
+        let path = self.sibling("lock");
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+        // SAFETY: the file descriptor is open for the lifetime of `file`
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            return Err(std::io::Error::last_os_error()).with_context(|| {
+                format!(
+                    "Failed to lock LMS state file {}; another signer is running",
+                    self.path.display()
+                )
+            });
+        }
+        Ok(LmsStateLock { _file: file })
+    }
+
+    /// Take a lock file to keep concurrent signers from reserving the same q
+    /// value
+    #[cfg(not(unix))]
+    fn lock(&self) -> anyhow::Result<LmsStateLock> {
+        let path = self.sibling("lock");
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| {
+                format!(
+                    "Failed to lock LMS state file; if no other signer is running, remove {}",
+                    path.display()
+                )
+            })?;
+        Ok(LmsStateLock { _file: file, path })
+    }
+
+    fn sibling(&self, extension: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".");
+        path.push(extension);
+        path.into()
+    }
+}
+
+/// Lock of a state file, released when dropped. On unix, the lock file is
+/// left in place: removing it would let a signer lock a new file while
+/// another one holds the lock on the removed one.
+struct LmsStateLock {
+    _file: File,
+
+    #[cfg(not(unix))]
+    path: PathBuf,
+}
+
+#[cfg(not(unix))]
+impl Drop for LmsStateLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Image generator crypto that signs with LMS keys whose state is persisted
+#[derive(Default)]
+pub struct OsslStatefulCrypto {
+    crypto: OsslCrypto,
+
+    lms_states: Vec<LmsKeyState>,
+}
+
+impl OsslStatefulCrypto {
+    /// Track the state of an LMS private key. Signing with an LMS key that
+    /// is not tracked fails.
+    pub fn add_lms_key_state(&mut self, state: LmsKeyState) {
+        self.lms_states.push(state);
+    }
+}
+
+impl ImageGeneratorCrypto for OsslStatefulCrypto {
+    fn sha384_digest(&self, data: &[u8]) -> anyhow::Result<ImageDigest> {
+        self.crypto.sha384_digest(data)
+    }
+
+    fn ecdsa384_sign(
+        &self,
+        digest: &ImageDigest,
+        priv_key: &ImageEccPrivKey,
+        pub_key: &ImageEccPubKey,
+    ) -> anyhow::Result<ImageEccSignature> {
+        self.crypto.ecdsa384_sign(digest, priv_key, pub_key)
+    }
+
+    fn lms_sign(
+        &self,
+        digest: &ImageDigest,
+        priv_key: &ImageLmsPrivKey,
+    ) -> anyhow::Result<ImageLmsSignature> {
+        let state = self
+            .lms_states
+            .iter()
+            .find(|state| state.id == priv_key.id)
+            .with_context(|| format!("No state for LMS key {}", hex::encode(priv_key.id)))?;
+        let q = state.reserve()?;
+
+        let message: [u8; ECC384_SCALAR_BYTE_SIZE] = from_hw_format(digest);
+        let mut nonce = [0u8; SHA192_DIGEST_BYTE_SIZE];
+        rand_bytes(&mut nonce)?;
+        sign_with_lms_key(priv_key, &message, &nonce, q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IMAGE_LMS_OTS_TYPE_8;
+    use caliptra_lms_types::bytes_to_words_6;
+
+    fn priv_key() -> ImageLmsPrivKey {
+        ImageLmsPrivKey {
+            tree_type: IMAGE_LMS_TREE_TYPE_HT_5,
+            otstype: IMAGE_LMS_OTS_TYPE_8,
+            id: [
+                0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d,
+                0x2e, 0x2f,
+            ],
+            seed: bytes_to_words_6([
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+            ]),
+        }
+    }
+
+    fn state_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "caliptra-lms-state-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_lms_state() {
+        let dir = state_dir("sign");
+        let path = dir.join("lms-key.state");
+
+        let priv_key = priv_key();
+        assert!(LmsKeyState::open(&path, &priv_key).is_err());
+        let state = LmsKeyState::init(&path, &priv_key, 0).unwrap();
+        assert_eq!(state.next_q().unwrap(), 0);
+        assert_eq!(state.remaining().unwrap(), 32);
+
+        let mut crypto = OsslStatefulCrypto::default();
+        let digest = ImageDigest::default();
+        assert!(crypto.lms_sign(&digest, &priv_key).is_err());
+
+        crypto.add_lms_key_state(state.clone());
+        for q in 0..32 {
+            let sig = crypto.lms_sign(&digest, &priv_key).unwrap();
+            assert_eq!(u32::from(sig.q), q);
+        }
+        assert_eq!(state.remaining().unwrap(), 0);
+        assert!(crypto.lms_sign(&digest, &priv_key).is_err());
+
+        // The state survives reopening, and is tied to the key
+        assert_eq!(
+            LmsKeyState::open(&path, &priv_key)
+                .unwrap()
+                .next_q()
+                .unwrap(),
+            32
+        );
+        let other_key = ImageLmsPrivKey {
+            id: [0x30; 16],
+            ..priv_key
+        };
+        assert!(LmsKeyState::open(&path, &other_key).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lms_state_init() {
+        let dir = state_dir("init");
+        let path = dir.join("lms-key.state");
+        let priv_key = priv_key();
+
+        assert!(LmsKeyState::init(&path, &priv_key, 33).is_err());
+        assert!(!path.exists());
+
+        let state = LmsKeyState::init(&path, &priv_key, 7).unwrap();
+        assert_eq!(state.next_q().unwrap(), 7);
+        assert_eq!(state.remaining().unwrap(), 25);
+
+        // An existing state is never reset
+        assert!(LmsKeyState::init(&path, &priv_key, 0).is_err());
+        assert_eq!(state.next_q().unwrap(), 7);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lms_state_exhausted_after_reopen() {
+        let dir = state_dir("exhausted");
+        let path = dir.join("lms-key.state");
+        let priv_key = priv_key();
+        let digest = ImageDigest::default();
+
+        let mut crypto = OsslStatefulCrypto::default();
+        crypto.add_lms_key_state(LmsKeyState::init(&path, &priv_key, 30).unwrap());
+        assert_eq!(
+            u32::from(crypto.lms_sign(&digest, &priv_key).unwrap().q),
+            30
+        );
+        drop(crypto);
+
+        let mut crypto = OsslStatefulCrypto::default();
+        crypto.add_lms_key_state(LmsKeyState::open(&path, &priv_key).unwrap());
+        assert_eq!(
+            u32::from(crypto.lms_sign(&digest, &priv_key).unwrap().q),
+            31
+        );
+        drop(crypto);
+
+        let state = LmsKeyState::open(&path, &priv_key).unwrap();
+        assert_eq!(state.remaining().unwrap(), 0);
+        let mut crypto = OsslStatefulCrypto::default();
+        crypto.add_lms_key_state(state.clone());
+        assert!(crypto.lms_sign(&digest, &priv_key).is_err());
+        assert_eq!(state.next_q().unwrap(), 32);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lms_state_corrupt() {
+        let dir = state_dir("corrupt");
+        let path = dir.join("lms-key.state");
+        let priv_key = priv_key();
+        let digest = ImageDigest::default();
+
+        let state = LmsKeyState::init(&path, &priv_key, 3).unwrap();
+        let record = std::fs::read(&path).unwrap();
+
+        let mut bad_marker = record.clone();
+        bad_marker[0] ^= 1;
+        let mut bad_q = record.clone();
+        bad_q[20..24].copy_from_slice(&33u32.to_le_bytes());
+        for contents in [
+            &record[..record.len() - 1],
+            &[][..],
+            &bad_marker[..],
+            &bad_q[..],
+        ] {
+            std::fs::write(&path, contents).unwrap();
+            assert!(LmsKeyState::open(&path, &priv_key).is_err());
+
+            // Signing fails, and leaves the state file as it is
+            let mut crypto = OsslStatefulCrypto::default();
+            crypto.add_lms_key_state(state.clone());
+            assert!(crypto.lms_sign(&digest, &priv_key).is_err());
+            assert_eq!(std::fs::read(&path).unwrap(), contents);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lms_state_leftovers() {
+        let dir = state_dir("leftovers");
+        let path = dir.join("lms-key.state");
+        let priv_key = priv_key();
+        let digest = ImageDigest::default();
+
+        let state = LmsKeyState::init(&path, &priv_key, 0).unwrap();
+        let mut crypto = OsslStatefulCrypto::default();
+        crypto.add_lms_key_state(state.clone());
+
+        // Files left behind by a signer that crashed
+        std::fs::write(state.sibling("tmp"), [0xaa; 7]).unwrap();
+        #[cfg(unix)]
+        std::fs::write(state.sibling("lock"), []).unwrap();
+
+        assert_eq!(u32::from(crypto.lms_sign(&digest, &priv_key).unwrap().q), 0);
+        assert!(!state.sibling("tmp").exists());
+        assert_eq!(state.next_q().unwrap(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lms_state_locked() {
+        let dir = state_dir("locked");
+        let path = dir.join("lms-key.state");
+        let priv_key = priv_key();
+        let digest = ImageDigest::default();
+
+        let state = LmsKeyState::init(&path, &priv_key, 0).unwrap();
+        let mut crypto = OsslStatefulCrypto::default();
+        crypto.add_lms_key_state(LmsKeyState::open(&path, &priv_key).unwrap());
+
+        // Another signer holds the lock
+        let lock = state.lock().unwrap();
+        assert!(crypto.lms_sign(&digest, &priv_key).is_err());
+        assert_eq!(state.next_q().unwrap(), 0);
+
+        drop(lock);
+        assert_eq!(u32::from(crypto.lms_sign(&digest, &priv_key).unwrap().q), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
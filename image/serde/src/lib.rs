@@ -31,6 +31,9 @@ impl<W: Write> ImageBundleWriter<W> {
         self.writer.write_all(image.manifest.as_bytes())?;
         self.writer.write_all(&image.fmc)?;
         self.writer.write_all(&image.runtime)?;
+        for aux in &image.aux {
+            self.writer.write_all(aux)?;
+        }
         Ok(())
     }
 }
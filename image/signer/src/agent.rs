@@ -0,0 +1,185 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    agent.rs
+
+Abstract:
+
+    File contains a signing agent that holds the keys in memory. It stands in
+    for an HSM proxy when testing the external signer.
+
+--*/
+
+use std::io::{BufRead, Write};
+
+use caliptra_image_gen::ImageGeneratorCrypto;
+use caliptra_image_types::*;
+
+use crate::protocol::{hex_to_words, SignRequest, SignResponse};
+
+/// Signing agent holding its keys in memory
+pub struct LocalSigningAgent<Crypto: ImageGeneratorCrypto> {
+    crypto: Crypto,
+
+    ecc_keys: Vec<(ImageEccPubKey, ImageEccPrivKey)>,
+
+    lms_keys: Vec<ImageLmsPrivKey>,
+}
+
+impl<Crypto: ImageGeneratorCrypto> LocalSigningAgent<Crypto> {
+    /// Create an agent signing with `crypto`
+    pub fn new(crypto: Crypto) -> Self {
+        Self {
+            crypto,
+            ecc_keys: vec![],
+            lms_keys: vec![],
+        }
+    }
+
+    pub fn add_ecc_key(&mut self, pub_key: ImageEccPubKey, priv_key: ImageEccPrivKey) {
+        self.ecc_keys.push((pub_key, priv_key));
+    }
+
+    pub fn add_lms_key(&mut self, priv_key: ImageLmsPrivKey) {
+        self.lms_keys.push(priv_key);
+    }
+
+    /// Handle a single request
+    pub fn handle(&self, request: &SignRequest) -> SignResponse {
+        self.sign(request)
+            .unwrap_or_else(|err| SignResponse::Error {
+                message: format!("{err:#}"),
+            })
+    }
+
+    /// Serve the requests read from `reader` until it is closed
+    pub fn serve(&self, reader: impl BufRead, mut writer: impl Write) -> anyhow::Result<()> {
+        for line in reader.lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => self.handle(&request),
+                Err(err) => SignResponse::Error {
+                    message: format!("Invalid request: {err}"),
+                },
+            };
+            let mut line = serde_json::to_string(&response)?;
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn sign(&self, request: &SignRequest) -> anyhow::Result<SignResponse> {
+        match request {
+            SignRequest::Ecc384Sign {
+                digest,
+                pub_key_x,
+                pub_key_y,
+            } => {
+                let pub_key = ImageEccPubKey {
+                    x: hex_to_words(pub_key_x)?,
+                    y: hex_to_words(pub_key_y)?,
+                };
+                let (pub_key, priv_key) = self
+                    .ecc_keys
+                    .iter()
+                    .find(|(key, _)| *key == pub_key)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown ECC key {pub_key_x}{pub_key_y}"))?;
+                let sig = self
+                    .crypto
+                    .ecdsa384_sign(&hex_to_words(digest)?, priv_key, pub_key)?;
+                Ok(SignResponse::ecc384_sig(&sig))
+            }
+            SignRequest::LmsSign { digest, key_id } => {
+                let priv_key = self
+                    .lms_keys
+                    .iter()
+                    .find(|key| hex::encode(key.id) == *key_id)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown LMS key {key_id}"))?;
+                let sig = self.crypto.lms_sign(&hex_to_words(digest)?, priv_key)?;
+                Ok(SignResponse::lms_sig(&sig))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use caliptra_image_fake_keys::VENDOR_CONFIG_KEY_0;
+    use caliptra_image_openssl::OsslCrypto;
+
+    /// Serve the request lines, and return the responses
+    fn serve(agent: &LocalSigningAgent<OsslCrypto>, requests: &[String]) -> Vec<SignResponse> {
+        let mut input = requests.join("\n");
+        input.push('\n');
+        let mut output = vec![];
+        agent.serve(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn error_message(response: &SignResponse) -> &str {
+        match response {
+            SignResponse::Error { message } => message,
+            other => panic!("Unexpected response {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_serve() {
+        let pub_keys = VENDOR_CONFIG_KEY_0.pub_keys;
+        let priv_keys = VENDOR_CONFIG_KEY_0.priv_keys.unwrap();
+        let mut agent = LocalSigningAgent::new(OsslCrypto::default());
+        agent.add_ecc_key(pub_keys.ecc_pub_keys[0], priv_keys.ecc_priv_keys[0]);
+        agent.add_lms_key(priv_keys.lms_priv_keys[0]);
+
+        let digest = OsslCrypto::default().sha384_digest(b"caliptra").unwrap();
+        let request = |request: SignRequest| serde_json::to_string(&request).unwrap();
+        let responses = serve(
+            &agent,
+            &[
+                request(SignRequest::ecc384_sign(&digest, &pub_keys.ecc_pub_keys[0])),
+                "{\"op\":\"ecc384_sign\"".into(),
+                request(SignRequest::lms_sign(
+                    &digest,
+                    &priv_keys.lms_priv_keys[0].id,
+                )),
+                request(SignRequest::ecc384_sign(&digest, &pub_keys.ecc_pub_keys[1])),
+                request(SignRequest::lms_sign(&digest, &[0xff; 16])),
+                r#"{"op":"lms_sign","digest":"00","key_id":"00"}"#.into(),
+            ],
+        );
+        assert_eq!(responses.len(), 6);
+
+        assert!(responses[0].clone().into_ecc384_sig().is_ok());
+        assert!(error_message(&responses[1]).starts_with("Invalid request"));
+        // The agent keeps serving after an invalid request
+        assert!(responses[2].clone().into_lms_sig().is_ok());
+        assert!(error_message(&responses[3]).starts_with("Unknown ECC key"));
+        assert_eq!(
+            error_message(&responses[4]),
+            format!("Unknown LMS key {}", "ff".repeat(16))
+        );
+        assert_eq!(error_message(&responses[5]), "Unknown LMS key 00");
+    }
+
+    #[test]
+    fn test_serve_eof() {
+        let agent = LocalSigningAgent::new(OsslCrypto::default());
+        let mut output = vec![];
+        agent.serve(&b""[..], &mut output).unwrap();
+        assert!(output.is_empty());
+
+        // A request cut short by the end of the stream is still answered
+        agent.serve(&b"{\"op\":"[..], &mut output).unwrap();
+        let response: SignResponse = serde_json::from_slice(&output).unwrap();
+        assert!(error_message(&response).starts_with("Invalid request"));
+    }
+}
@@ -0,0 +1,244 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    client.rs
+
+Abstract:
+
+    File contains the image generator crypto that forwards signing requests
+    to an external signing agent.
+
+--*/
+
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{anyhow, Context};
+use caliptra_image_gen::ImageGeneratorCrypto;
+use caliptra_image_openssl::OsslCrypto;
+use caliptra_image_types::*;
+
+use crate::protocol::{SignRequest, SignResponse};
+
+struct Connection {
+    reader: Box<dyn BufRead + Send>,
+
+    writer: Box<dyn Write + Send>,
+
+    /// Agent process started by the signer, if any
+    child: Option<Child>,
+}
+
+/// Image generator crypto backed by an external signing agent.
+///
+/// The digests are calculated locally; only the digests to sign are sent to
+/// the agent. Keys are identified by their public part: ECC keys by the
+/// public key, LMS keys by their identifier. As the image generator only
+/// signs with the keys it is given, use [`vendor_key_handles`] and
+/// [`owner_key_handles`] in place of the private keys.
+///
+/// [`vendor_key_handles`]: crate::vendor_key_handles
+/// [`owner_key_handles`]: crate::owner_key_handles
+pub struct ExternalSigner {
+    crypto: OsslCrypto,
+
+    connection: Mutex<Connection>,
+}
+
+impl ExternalSigner {
+    /// Connect to an agent listening on a Unix socket
+    #[cfg(unix)]
+    pub fn connect(path: &Path) -> anyhow::Result<Self> {
+        let stream = UnixStream::connect(path)
+            .with_context(|| format!("Failed to connect to signing agent {}", path.display()))?;
+        Ok(Self::from_streams(
+            BufReader::new(stream.try_clone()?),
+            stream,
+        ))
+    }
+
+    /// Connect to an agent listening on a Unix socket
+    #[cfg(not(unix))]
+    pub fn connect(path: &Path) -> anyhow::Result<Self> {
+        Err(anyhow!(
+            "Failed to connect to signing agent {}: Unix sockets are not supported on this platform",
+            path.display()
+        ))
+    }
+
+    /// Start an agent process that serves requests on its stdin and stdout
+    pub fn spawn(command: &mut Command) -> anyhow::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start signing agent {command:?}"))?;
+        let reader = child
+            .stdout
+            .take()
+            .with_context(|| "No signing agent stdout")?;
+        let writer = child
+            .stdin
+            .take()
+            .with_context(|| "No signing agent stdin")?;
+        Ok(Self::new(Connection {
+            reader: Box::new(BufReader::new(reader)),
+            writer: Box::new(writer),
+            child: Some(child),
+        }))
+    }
+
+    /// Talk to an agent over an existing pair of streams
+    pub fn from_streams(
+        reader: impl BufRead + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> Self {
+        Self::new(Connection {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            child: None,
+        })
+    }
+
+    fn new(connection: Connection) -> Self {
+        Self {
+            crypto: OsslCrypto::default(),
+            connection: Mutex::new(connection),
+        }
+    }
+
+    fn request(&self, request: &SignRequest) -> anyhow::Result<SignResponse> {
+        // A request that panicked may have left a partial message on the
+        // connection
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow!("Signing agent connection is unusable after a failed request"))?;
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        connection.writer.write_all(line.as_bytes())?;
+        connection.writer.flush()?;
+
+        line.clear();
+        if connection.reader.read_line(&mut line)? == 0 {
+            return Err(anyhow!("Signing agent closed the connection"));
+        }
+        serde_json::from_str(&line).with_context(|| "Invalid response from signing agent")
+    }
+}
+
+impl Drop for ExternalSigner {
+    fn drop(&mut self) {
+        let connection = self
+            .connection
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(mut child) = connection.child.take() {
+            // Closing stdin tells the agent to exit
+            connection.writer = Box::new(std::io::sink());
+            let _ = child.wait();
+        }
+    }
+}
+
+impl ImageGeneratorCrypto for ExternalSigner {
+    fn sha384_digest(&self, data: &[u8]) -> anyhow::Result<ImageDigest> {
+        self.crypto.sha384_digest(data)
+    }
+
+    fn ecdsa384_sign(
+        &self,
+        digest: &ImageDigest,
+        _priv_key: &ImageEccPrivKey,
+        pub_key: &ImageEccPubKey,
+    ) -> anyhow::Result<ImageEccSignature> {
+        self.request(&SignRequest::ecc384_sign(digest, pub_key))?
+            .into_ecc384_sig()
+    }
+
+    fn lms_sign(
+        &self,
+        digest: &ImageDigest,
+        priv_key: &ImageLmsPrivKey,
+    ) -> anyhow::Result<ImageLmsSignature> {
+        self.request(&SignRequest::lms_sign(digest, &priv_key.id))?
+            .into_lms_sig()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use caliptra_image_fake_keys::VENDOR_CONFIG_KEY_0;
+    use std::io::Cursor;
+
+    /// Signer reading canned responses
+    fn signer(responses: &str) -> ExternalSigner {
+        ExternalSigner::from_streams(Cursor::new(responses.to_string()), std::io::sink())
+    }
+
+    fn ecdsa384_sign(signer: &ExternalSigner) -> anyhow::Result<ImageEccSignature> {
+        let pub_key = VENDOR_CONFIG_KEY_0.pub_keys.ecc_pub_keys[0];
+        signer.ecdsa384_sign(
+            &ImageDigest::default(),
+            &ImageEccPrivKey::default(),
+            &pub_key,
+        )
+    }
+
+    fn error_message(result: anyhow::Result<ImageEccSignature>) -> String {
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn test_responses() {
+        let sig = ImageEccSignature {
+            r: [1; ECC384_SCALAR_WORD_SIZE],
+            s: [2; ECC384_SCALAR_WORD_SIZE],
+        };
+        let mut responses = serde_json::to_string(&SignResponse::ecc384_sig(&sig)).unwrap();
+        responses.push('\n');
+        responses.push_str(r#"{"op":"error","message":"Key is disabled"}"#);
+        responses.push_str("\nnot json\n");
+        responses.push_str(r#"{"op":"lms_sig","sig":""}"#);
+        responses.push('\n');
+
+        let signer = signer(&responses);
+        assert_eq!(ecdsa384_sign(&signer).unwrap(), sig);
+        assert_eq!(
+            error_message(ecdsa384_sign(&signer)),
+            "Signing agent error: Key is disabled"
+        );
+        assert!(error_message(ecdsa384_sign(&signer))
+            .starts_with("Invalid response from signing agent"));
+        assert!(error_message(ecdsa384_sign(&signer))
+            .starts_with("Unexpected response from signing agent"));
+        assert_eq!(
+            error_message(ecdsa384_sign(&signer)),
+            "Signing agent closed the connection"
+        );
+    }
+
+    #[test]
+    fn test_spawn() {
+        let err = ExternalSigner::spawn(&mut Command::new("/nonexistent/caliptra-signing-agent"))
+            .err()
+            .unwrap();
+        assert!(format!("{err:#}").starts_with("Failed to start signing agent"));
+
+        // An agent that exits without answering
+        #[cfg(unix)]
+        {
+            let signer = ExternalSigner::spawn(&mut Command::new("true")).unwrap();
+            assert!(ecdsa384_sign(&signer).is_err());
+        }
+    }
+}
@@ -0,0 +1,133 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    lib.rs
+
+Abstract:
+
+    File contains the image generator crypto that signs images with an
+    external signing agent, such as an HSM proxy, and a local stand-in agent.
+
+--*/
+
+mod agent;
+mod client;
+pub mod protocol;
+
+pub use agent::LocalSigningAgent;
+pub use client::ExternalSigner;
+
+use caliptra_image_types::*;
+
+/// Placeholder for an LMS private key held by the signing agent. It only
+/// carries the public parameters of the key.
+pub fn lms_key_handle(pub_key: &ImageLmsPublicKey) -> ImageLmsPrivKey {
+    ImageLmsPrivKey {
+        tree_type: pub_key.tree_type,
+        otstype: pub_key.otstype,
+        id: pub_key.id,
+        ..Default::default()
+    }
+}
+
+/// Placeholders for the vendor private keys held by the signing agent
+pub fn vendor_key_handles(pub_keys: &ImageVendorPubKeys) -> ImageVendorPrivKeys {
+    let mut priv_keys = ImageVendorPrivKeys::default();
+    for (priv_key, pub_key) in priv_keys
+        .lms_priv_keys
+        .iter_mut()
+        .zip(pub_keys.lms_pub_keys.iter())
+    {
+        *priv_key = lms_key_handle(pub_key);
+    }
+    priv_keys
+}
+
+/// Placeholders for the owner private keys held by the signing agent
+pub fn owner_key_handles(pub_keys: &ImageOwnerPubKeys) -> ImageOwnerPrivKeys {
+    ImageOwnerPrivKeys {
+        lms_priv_key: lms_key_handle(&pub_keys.lms_pub_key),
+        ..Default::default()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use caliptra_image_fake_keys::{OWNER_CONFIG, VENDOR_CONFIG_KEY_0};
+    use caliptra_image_gen::ImageGeneratorCrypto;
+    use caliptra_image_openssl::OsslCrypto;
+    use std::io::BufReader;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn test_external_signer() {
+        let vendor_pub_keys = VENDOR_CONFIG_KEY_0.pub_keys;
+        let vendor_priv_keys = VENDOR_CONFIG_KEY_0.priv_keys.unwrap();
+        let owner_pub_keys = OWNER_CONFIG.pub_keys;
+        let owner_priv_keys = OWNER_CONFIG.priv_keys.unwrap();
+
+        let mut agent = LocalSigningAgent::new(OsslCrypto::default());
+        agent.add_ecc_key(
+            vendor_pub_keys.ecc_pub_keys[0],
+            vendor_priv_keys.ecc_priv_keys[0],
+        );
+        agent.add_lms_key(vendor_priv_keys.lms_priv_keys[0]);
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let agent_thread = std::thread::spawn(move || {
+            agent
+                .serve(BufReader::new(server.try_clone().unwrap()), server)
+                .unwrap()
+        });
+
+        let signer =
+            ExternalSigner::from_streams(BufReader::new(client.try_clone().unwrap()), client);
+        let crypto = OsslCrypto::default();
+        let digest = crypto.sha384_digest(b"caliptra").unwrap();
+        assert_eq!(signer.sha384_digest(b"caliptra").unwrap(), digest);
+
+        let vendor_handles = vendor_key_handles(&vendor_pub_keys);
+        let sig = signer
+            .ecdsa384_sign(
+                &digest,
+                &vendor_handles.ecc_priv_keys[0],
+                &vendor_pub_keys.ecc_pub_keys[0],
+            )
+            .unwrap();
+        assert_ne!(sig, ImageEccSignature::default());
+
+        // LMS signatures are deterministic apart from the random nonce
+        let sig = signer
+            .lms_sign(&digest, &vendor_handles.lms_priv_keys[0])
+            .unwrap();
+        let expected = crypto
+            .lms_sign(&digest, &vendor_priv_keys.lms_priv_keys[0])
+            .unwrap();
+        assert_eq!(sig.q, expected.q);
+        assert_eq!(sig.tree_path, expected.tree_path);
+
+        // Keys the agent does not hold are rejected
+        let owner_handles = owner_key_handles(&owner_pub_keys);
+        assert!(signer
+            .ecdsa384_sign(
+                &digest,
+                &owner_handles.ecc_priv_key,
+                &owner_pub_keys.ecc_pub_key
+            )
+            .is_err());
+        assert!(signer
+            .lms_sign(&digest, &owner_handles.lms_priv_key)
+            .is_err());
+        assert_ne!(
+            owner_priv_keys.lms_priv_key.id,
+            vendor_priv_keys.lms_priv_keys[0].id
+        );
+
+        drop(signer);
+        agent_thread.join().unwrap();
+    }
+}
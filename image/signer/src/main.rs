@@ -0,0 +1,144 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    main.rs
+
+Abstract:
+
+    Main entry point for the reference signing agent. It holds the keys in
+    memory and serves signing requests on a Unix socket or on stdin/stdout.
+
+--*/
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use caliptra_image_openssl::{
+    ecc_priv_key_from_pem, lms_priv_key_from_pem, LmsKeyState, OsslStatefulCrypto,
+};
+use caliptra_image_signer::protocol::bytes_to_words;
+use caliptra_image_signer::LocalSigningAgent;
+use caliptra_image_types::*;
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+
+fn main() {
+    let args = Command::new("caliptra-image-signer")
+        .about("Reference signing agent for caliptra-image-app")
+        .arg(
+            arg!(--"ecc-key" <FILE> "ECC-384 private key PEM file (may be repeated)")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"lms-key" <FILE> "LMS private key file (may be repeated)")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"lms-first-q" <U32> "Create the state files of the LMS keys, starting at this q value")
+                .required(false)
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"socket" <FILE> "Unix socket to listen on [default: serve stdin/stdout]")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .get_matches();
+
+    run(&args).unwrap();
+}
+
+fn run(args: &ArgMatches) -> anyhow::Result<()> {
+    let mut crypto = OsslStatefulCrypto::default();
+    let mut lms_keys = vec![];
+    let first_q = args.get_one::<u32>("lms-first-q").copied();
+    for path in args.get_many::<PathBuf>("lms-key").into_iter().flatten() {
+        let priv_key = lms_priv_key_from_pem(path)?;
+        // The q values used are tracked next to the key, as
+        // `caliptra-image-app create` does
+        let mut state_path = path.clone().into_os_string();
+        state_path.push(".state");
+        let state_path = Path::new(&state_path);
+        crypto.add_lms_key_state(match first_q {
+            Some(first_q) => LmsKeyState::init(state_path, &priv_key, first_q)?,
+            None => LmsKeyState::open(state_path, &priv_key)?,
+        });
+        lms_keys.push(priv_key);
+    }
+
+    let mut agent = LocalSigningAgent::new(crypto);
+    for path in args.get_many::<PathBuf>("ecc-key").into_iter().flatten() {
+        agent.add_ecc_key(
+            ecc_pub_key_from_priv_pem(path)?,
+            ecc_priv_key_from_pem(path)?,
+        );
+    }
+    for priv_key in lms_keys {
+        agent.add_lms_key(priv_key);
+    }
+
+    let Some(socket_path) = args.get_one::<PathBuf>("socket") else {
+        return agent.serve(std::io::stdin().lock(), std::io::stdout().lock());
+    };
+
+    serve_socket(&agent, socket_path)
+}
+
+/// Serve the connections to a Unix socket, one at a time
+#[cfg(unix)]
+fn serve_socket(
+    agent: &LocalSigningAgent<OsslStatefulCrypto>,
+    socket_path: &Path,
+) -> anyhow::Result<()> {
+    use std::io::BufReader;
+    use std::os::unix::net::UnixListener;
+
+    let listener = UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+    eprintln!("Listening on {}", socket_path.display());
+    for stream in listener.incoming() {
+        let stream = stream?;
+        if let Err(err) = agent.serve(BufReader::new(stream.try_clone()?), stream) {
+            eprintln!("Connection failed: {err:#}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(
+    _agent: &LocalSigningAgent<OsslStatefulCrypto>,
+    socket_path: &Path,
+) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "Failed to listen on {}: Unix sockets are not supported on this platform",
+        socket_path.display()
+    ))
+}
+
+/// Read the public key of an ECC-384 private key PEM file
+fn ecc_pub_key_from_priv_pem(path: &PathBuf) -> anyhow::Result<ImageEccPubKey> {
+    let key_bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read private key PEM file {}", path.display()))?;
+    let key = EcKey::private_key_from_pem(&key_bytes)?;
+    let group = EcGroup::from_curve_name(Nid::SECP384R1)?;
+    let mut ctx = BigNumContext::new()?;
+    let mut x = BigNum::new()?;
+    let mut y = BigNum::new()?;
+    key.public_key()
+        .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)?;
+
+    Ok(ImageEccPubKey {
+        x: bytes_to_words(&x.to_vec_padded(ECC384_SCALAR_BYTE_SIZE as i32)?)?,
+        y: bytes_to_words(&y.to_vec_padded(ECC384_SCALAR_BYTE_SIZE as i32)?)?,
+    })
+}
@@ -0,0 +1,234 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    protocol.rs
+
+Abstract:
+
+    File contains the messages exchanged with an external signing agent.
+    Each message is a JSON object on a single line, whose `op` field tells
+    the kind of message. Digests, keys and signatures are hex strings of
+    their big-endian byte representation.
+
+--*/
+
+use anyhow::{anyhow, Context};
+use caliptra_image_types::*;
+use serde_derive::{Deserialize, Serialize};
+use zerocopy::{AsBytes, FromBytes};
+
+/// Request sent to the signing agent
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SignRequest {
+    /// Sign a SHA-384 digest with the ECC-384 key of the given public key
+    Ecc384Sign {
+        digest: String,
+        pub_key_x: String,
+        pub_key_y: String,
+    },
+
+    /// Sign a SHA-384 digest with the LMS key of the given identifier
+    LmsSign { digest: String, key_id: String },
+}
+
+/// Response of the signing agent
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SignResponse {
+    Ecc384Sig {
+        r: String,
+        s: String,
+    },
+
+    /// LMS signature, in the format of RFC 8554
+    LmsSig {
+        sig: String,
+    },
+
+    Error {
+        message: String,
+    },
+}
+
+impl SignRequest {
+    pub fn ecc384_sign(digest: &ImageDigest, pub_key: &ImageEccPubKey) -> Self {
+        Self::Ecc384Sign {
+            digest: words_to_hex(digest),
+            pub_key_x: words_to_hex(&pub_key.x),
+            pub_key_y: words_to_hex(&pub_key.y),
+        }
+    }
+
+    pub fn lms_sign(digest: &ImageDigest, key_id: &[u8; 16]) -> Self {
+        Self::LmsSign {
+            digest: words_to_hex(digest),
+            key_id: hex::encode(key_id),
+        }
+    }
+}
+
+impl SignResponse {
+    pub fn ecc384_sig(sig: &ImageEccSignature) -> Self {
+        Self::Ecc384Sig {
+            r: words_to_hex(&sig.r),
+            s: words_to_hex(&sig.s),
+        }
+    }
+
+    pub fn lms_sig(sig: &ImageLmsSignature) -> Self {
+        Self::LmsSig {
+            sig: hex::encode(sig.as_bytes()),
+        }
+    }
+
+    /// Extract the ECC signature from the response
+    pub fn into_ecc384_sig(self) -> anyhow::Result<ImageEccSignature> {
+        match self {
+            Self::Ecc384Sig { r, s } => Ok(ImageEccSignature {
+                r: hex_to_words(&r)?,
+                s: hex_to_words(&s)?,
+            }),
+            other => Err(other.unexpected()),
+        }
+    }
+
+    /// Extract the LMS signature from the response
+    pub fn into_lms_sig(self) -> anyhow::Result<ImageLmsSignature> {
+        match self {
+            Self::LmsSig { sig } => ImageLmsSignature::read_from(hex::decode(sig)?.as_slice())
+                .with_context(|| "Invalid LMS signature length"),
+            other => Err(other.unexpected()),
+        }
+    }
+
+    fn unexpected(self) -> anyhow::Error {
+        match self {
+            Self::Error { message } => anyhow!("Signing agent error: {message}"),
+            other => anyhow!("Unexpected response from signing agent: {other:?}"),
+        }
+    }
+}
+
+/// Hex string of a value in the hardware format (big-endian words)
+pub fn words_to_hex(words: &[u32; ECC384_SCALAR_WORD_SIZE]) -> String {
+    hex::encode(
+        words
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Parse a value in the hardware format (big-endian words) from a hex string
+pub fn hex_to_words(value: &str) -> anyhow::Result<[u32; ECC384_SCALAR_WORD_SIZE]> {
+    bytes_to_words(&hex::decode(value)?)
+}
+
+/// Convert big-endian bytes to the hardware format
+pub fn bytes_to_words(bytes: &[u8]) -> anyhow::Result<[u32; ECC384_SCALAR_WORD_SIZE]> {
+    if bytes.len() != ECC384_SCALAR_BYTE_SIZE {
+        return Err(anyhow!("Expected {ECC384_SCALAR_BYTE_SIZE} bytes"));
+    }
+    let mut words = [0u32; ECC384_SCALAR_WORD_SIZE];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: ImageDigest = [0x0102_0304; ECC384_SCALAR_WORD_SIZE];
+
+    #[test]
+    fn test_request() {
+        let request = SignRequest::lms_sign(&DIGEST, &[0xab; 16]);
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"op":"lms_sign","digest":"{}","key_id":"{}"}}"#,
+                "01020304".repeat(ECC384_SCALAR_WORD_SIZE),
+                "ab".repeat(16)
+            )
+        );
+        assert_eq!(serde_json::from_str::<SignRequest>(&json).unwrap(), request);
+    }
+
+    #[test]
+    fn test_response() {
+        let sig = ImageEccSignature {
+            r: DIGEST,
+            s: [0xa0b0_c0d0; 12],
+        };
+        let response = SignResponse::ecc384_sig(&sig);
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"op":"ecc384_sig","r":"{}","s":"{}"}}"#,
+                "01020304".repeat(ECC384_SCALAR_WORD_SIZE),
+                "a0b0c0d0".repeat(ECC384_SCALAR_WORD_SIZE)
+            )
+        );
+        let response = serde_json::from_str::<SignResponse>(&json).unwrap();
+        assert_eq!(response.into_ecc384_sig().unwrap(), sig);
+
+        let sig = ImageLmsSignature::default();
+        let response = SignResponse::lms_sig(&sig);
+        assert!(response.clone().into_ecc384_sig().is_err());
+        assert_eq!(response.into_lms_sig().unwrap().as_bytes(), sig.as_bytes());
+
+        let json = r#"{"op":"error","message":"Key not found"}"#;
+        let response = serde_json::from_str::<SignResponse>(json).unwrap();
+        assert_eq!(
+            format!("{:#}", response.clone().into_ecc384_sig().unwrap_err()),
+            "Signing agent error: Key not found"
+        );
+        assert!(response.into_lms_sig().is_err());
+    }
+
+    #[test]
+    fn test_malformed() {
+        for json in [
+            "",
+            "{",
+            r#"{"digest":"00"}"#,
+            r#"{"op":"rsa_sign","digest":"00"}"#,
+            r#"{"op":"lms_sign","digest":"00"}"#,
+            r#"{"lms_sign":{"digest":"00","key_id":"00"}}"#,
+        ] {
+            assert!(serde_json::from_str::<SignRequest>(json).is_err(), "{json}");
+        }
+        for json in [
+            r#"{"error":{"message":"x"}}"#,
+            r#"{"op":"ecc384_sig","r":"00"}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<SignResponse>(json).is_err(),
+                "{json}"
+            );
+        }
+
+        let bad_hex = SignResponse::Ecc384Sig {
+            r: "zz".repeat(ECC384_SCALAR_BYTE_SIZE),
+            s: "00".repeat(ECC384_SCALAR_BYTE_SIZE),
+        };
+        assert!(bad_hex.into_ecc384_sig().is_err());
+        let short = SignResponse::Ecc384Sig {
+            r: "00".repeat(ECC384_SCALAR_BYTE_SIZE - 1),
+            s: "00".repeat(ECC384_SCALAR_BYTE_SIZE),
+        };
+        assert!(short.into_ecc384_sig().is_err());
+        let short = SignResponse::LmsSig {
+            sig: "00".repeat(16),
+        };
+        assert!(short.into_lms_sig().is_err());
+    }
+}
@@ -28,7 +28,10 @@ use zerocopy::{AsBytes, FromBytes};
 pub const MANIFEST_MARKER: u32 = 0x4E414D43;
 pub const VENDOR_ECC_KEY_COUNT: u32 = 4;
 pub const VENDOR_LMS_KEY_COUNT: u32 = 32;
-pub const MAX_TOC_ENTRY_COUNT: u32 = 2;
+pub const MAX_AUX_TOC_ENTRY_COUNT: usize = 4;
+pub const MIN_TOC_ENTRY_COUNT: u32 = 2;
+pub const MAX_TOC_ENTRY_COUNT: u32 = MIN_TOC_ENTRY_COUNT + MAX_AUX_TOC_ENTRY_COUNT as u32;
+pub const MAX_PAUSER_PRIVILEGES_COUNT: usize = 8;
 pub const IMAGE_REVISION_BYTE_SIZE: usize = 20;
 pub const ECC384_SCALAR_WORD_SIZE: usize = 12;
 pub const ECC384_SCALAR_BYTE_SIZE: usize = 48;
@@ -91,6 +94,9 @@ pub struct ImageBundle {
 
     /// Runtime
     pub runtime: Vec<u8>,
+
+    /// Auxiliary images
+    pub aux: Vec<Vec<u8>>,
 }
 
 #[cfg(feature = "std")]
@@ -125,6 +131,24 @@ impl ImageBundle {
             ));
         }
         result.extend_from_slice(&self.runtime);
+        if self.aux.len() > MAX_AUX_TOC_ENTRY_COUNT {
+            return Err(std::io::Error::new(ErrorKind::Other, "too many aux images"));
+        }
+        for (toc, aux) in self.manifest.aux.iter().zip(self.aux.iter()) {
+            if toc.offset as usize != result.len() {
+                return Err(std::io::Error::new(
+                    ErrorKind::Other,
+                    "actual aux offset does not match manifest",
+                ));
+            }
+            if toc.size as usize != aux.len() {
+                return Err(std::io::Error::new(
+                    ErrorKind::Other,
+                    "actual aux size does not match manifest",
+                ));
+            }
+            result.extend_from_slice(aux);
+        }
         Ok(result)
     }
 }
@@ -151,6 +175,10 @@ pub struct ImageManifest {
 
     /// Runtime TOC Entry
     pub runtime: ImageTocEntry,
+
+    /// Auxiliary Image TOC Entries. Only the first `header.toc_len - 2`
+    /// entries are used.
+    pub aux: [ImageTocEntry; MAX_AUX_TOC_ENTRY_COUNT],
 }
 
 impl Default for ImageManifest {
@@ -162,6 +190,7 @@ impl Default for ImageManifest {
             header: ImageHeader::default(),
             fmc: ImageTocEntry::default(),
             runtime: ImageTocEntry::default(),
+            aux: [ImageTocEntry::default(); MAX_AUX_TOC_ENTRY_COUNT],
         }
     }
 }
@@ -186,10 +215,17 @@ impl ImageManifest {
         span.start as u32..span.end as u32
     }
 
-    /// Returns `Range<u32>` containing the table of contents
-    pub fn toc_range() -> Range<u32> {
-        let span = span_of!(ImageManifest, fmc..=runtime);
-        span.start as u32..span.end as u32
+    /// Returns `Range<u32>` containing the first `toc_len` entries of the
+    /// table of contents
+    pub fn toc_range(toc_len: u32) -> Range<u32> {
+        let start = offset_of!(ImageManifest, fmc) as u32;
+        start..start + toc_len * size_of::<ImageTocEntry>() as u32
+    }
+
+    /// Returns the number of auxiliary image TOC entries in use
+    pub fn aux_count(&self) -> usize {
+        (self.header.toc_len.saturating_sub(MIN_TOC_ENTRY_COUNT) as usize)
+            .min(MAX_AUX_TOC_ENTRY_COUNT)
     }
 }
 
@@ -304,6 +340,7 @@ pub struct ImageHeader {
 
     /// Flags
     /// Bit 0: Interpret the pl0_pauser field. If not set, all PAUSERs are PL1.
+    /// Bit 1: The runtime image ends with an `ImagePauserPrivilegesTable`.
     pub flags: u32,
 
     /// TOC Entry Count
@@ -323,10 +360,62 @@ pub struct ImageHeader {
     pub owner_data: OwnerSignedData,
 }
 
+/// PAUSER privilege table entry
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, FromBytes, Default, Debug, Eq, PartialEq, Zeroize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ImagePauserPrivileges {
+    /// PAUSER of the SoC agent
+    pub pauser: u32,
+
+    /// DPE locality of contexts created by the SoC agent
+    pub locality: u32,
+
+    /// Flags
+    /// Bit 0: The SoC agent has PL0 privileges. If not set, it is PL1.
+    pub flags: u32,
+}
+
+impl ImagePauserPrivileges {
+    pub const PL0_FLAG: u32 = 1 << 0;
+
+    pub fn is_pl0(&self) -> bool {
+        self.flags & Self::PL0_FLAG != 0
+    }
+}
+
+/// PAUSER privilege table
+///
+/// Appended to the runtime image, so it is covered by the runtime image
+/// digest but is not interpreted by the ROM.
+#[repr(C)]
+#[derive(AsBytes, Clone, Copy, FromBytes, Default, Debug, Zeroize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ImagePauserPrivilegesTable {
+    /// Number of valid entries in `entries`. If zero, the DPE locality of
+    /// a caller is its PAUSER and the privilege level is determined by
+    /// `pl0_pauser`.
+    pub len: u32,
+
+    /// DPE locality and privilege level of each SoC agent that is allowed
+    /// to send DPE commands
+    pub entries: [ImagePauserPrivileges; MAX_PAUSER_PRIVILEGES_COUNT],
+}
+
+impl ImagePauserPrivilegesTable {
+    /// Valid entries, or None if `len` is out of range
+    pub fn entries(&self) -> Option<&[ImagePauserPrivileges]> {
+        self.entries.get(..self.len as usize)
+    }
+}
+
 /// Caliptra table contents entry id
 pub enum ImageTocEntryType {
     /// First mutable code
     Executable = 1,
+
+    /// Auxiliary SoC image, verified and measured but not loaded by Caliptra
+    Auxiliary = 2,
 }
 
 impl From<ImageTocEntryType> for u32 {
@@ -343,6 +432,10 @@ pub enum ImageTocEntryId {
 
     /// Runtime
     Runtime = 2,
+
+    /// First identifier available to auxiliary images. Auxiliary images
+    /// carry SoC-defined identifiers from this value on.
+    AuxFirst = 0x100,
 }
 
 impl From<ImageTocEntryId> for u32 {
@@ -428,6 +521,20 @@ mod tests {
         assert_eq!(std::mem::size_of::<ImageManifest>() % 4, 0);
     }
 
+    #[test]
+    fn test_toc_range() {
+        let span = span_of!(ImageManifest, fmc..=runtime);
+        assert_eq!(
+            ImageManifest::toc_range(MIN_TOC_ENTRY_COUNT),
+            span.start as u32..span.end as u32
+        );
+        let span = span_of!(ImageManifest, fmc..=aux);
+        assert_eq!(
+            ImageManifest::toc_range(MAX_TOC_ENTRY_COUNT),
+            span.start as u32..span.end as u32
+        );
+    }
+
     #[test]
     fn test_image_overlap() {
         let mut image1 = ImageTocEntry::default();
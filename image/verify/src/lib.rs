@@ -53,6 +53,22 @@ pub struct ImageVerificationExeInfo {
     pub digest: ImageDigest,
 }
 
+/// Image Verification Auxiliary Image Info
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ImageVerificationAuxInfo {
+    /// TOC entry identifier
+    pub id: u32,
+
+    /// Length
+    pub size: u32,
+
+    /// Security version number
+    pub svn: u32,
+
+    /// Digest of the image
+    pub digest: ImageDigest,
+}
+
 /// Information To Be Logged For The Verified Image
 #[derive(Default, Debug)]
 pub struct ImageVerificationLogInfo {
@@ -96,10 +112,23 @@ pub struct ImageVerificationInfo {
     /// Runtime
     pub runtime: ImageVerificationExeInfo,
 
+    /// Number of auxiliary images
+    pub aux_count: u32,
+
+    /// Auxiliary images. Only the first `aux_count` entries are valid.
+    pub aux: [ImageVerificationAuxInfo; MAX_AUX_TOC_ENTRY_COUNT],
+
     /// Information Returned To Be Logged
     pub log_info: ImageVerificationLogInfo,
 }
 
+impl ImageVerificationInfo {
+    /// Auxiliary images, in table of contents order
+    pub fn aux_images(&self) -> &[ImageVerificationAuxInfo] {
+        self.aux.get(..self.aux_count as usize).unwrap_or(&[])
+    }
+}
+
 /// Image Verification Environment
 pub trait ImageVerificationEnv {
     /// Calculate SHA-384 Digest
@@ -46,6 +46,7 @@ struct TocInfo<'a> {
 struct ImageInfo<'a> {
     fmc: &'a ImageTocEntry,
     runtime: &'a ImageTocEntry,
+    aux: &'a [ImageTocEntry],
 }
 
 /// Image Verifier
@@ -111,6 +112,12 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
         // Verify Runtime
         let (runtime_info, rt_log_info) = self.verify_runtime(image_info.runtime)?;
 
+        // Verify Auxiliary Images
+        let mut aux = [ImageVerificationAuxInfo::default(); MAX_AUX_TOC_ENTRY_COUNT];
+        for (aux_info, verify_info) in aux.iter_mut().zip(image_info.aux) {
+            *aux_info = self.verify_aux(verify_info)?;
+        }
+
         let info = ImageVerificationInfo {
             vendor_ecc_pub_key_idx: header_info.vendor_ecc_pub_key_idx,
             vendor_lms_pub_key_idx: header_info.vendor_lms_pub_key_idx,
@@ -118,6 +125,8 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
             owner_pub_keys_digest_in_fuses: header_info.owner_pub_keys_digest_in_fuses,
             fmc: fmc_info,
             runtime: runtime_info,
+            aux_count: image_info.aux.len() as u32,
+            aux,
             log_info: ImageVerificationLogInfo {
                 vendor_ecc_pub_key_idx: header_info.vendor_ecc_pub_key_idx,
                 fuse_vendor_ecc_pub_key_revocation: header_info.vendor_ecc_pub_key_revocation,
@@ -506,11 +515,16 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
         verify_info: &TocInfo,
         img_bundle_sz: u32,
     ) -> CaliptraResult<ImageInfo<'a>> {
-        if verify_info.len != MAX_TOC_ENTRY_COUNT {
+        if verify_info.len < MIN_TOC_ENTRY_COUNT || verify_info.len > MAX_TOC_ENTRY_COUNT {
             Err(CaliptraError::IMAGE_VERIFIER_ERR_TOC_ENTRY_COUNT_INVALID)?;
         }
 
-        let range = ImageManifest::toc_range();
+        let aux = manifest
+            .aux
+            .get(..(verify_info.len - MIN_TOC_ENTRY_COUNT) as usize)
+            .ok_or(CaliptraError::IMAGE_VERIFIER_ERR_TOC_ENTRY_COUNT_INVALID)?;
+
+        let range = ImageManifest::toc_range(verify_info.len);
 
         let actual = self
             .env
@@ -537,7 +551,11 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
         // Image length does not exceed the Image Bundle size
         let img_len: u64 = manifest.size as u64
             + manifest.fmc.image_size() as u64
-            + manifest.runtime.image_size() as u64;
+            + manifest.runtime.image_size() as u64
+            + aux
+                .iter()
+                .map(|entry| entry.image_size() as u64)
+                .sum::<u64>();
 
         if img_len > img_bundle_sz.into() {
             Err(CaliptraError::IMAGE_VERIFIER_ERR_IMAGE_LEN_MORE_THAN_BUNDLE_SIZE)?;
@@ -555,6 +573,31 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
             Err(CaliptraError::IMAGE_VERIFIER_ERR_FMC_RUNTIME_INCORRECT_ORDER)?;
         }
 
+        // Auxiliary images follow the runtime image in the image, in the
+        // order of the table of contents.
+        let mut prev_end = runtime_range.end;
+        for (i, entry) in aux.iter().enumerate() {
+            if entry.image_size() == 0 {
+                Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_SIZE_ZERO)?;
+            }
+
+            if entry.r#type != ImageTocEntryType::Auxiliary.into() {
+                Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_TYPE_INVALID)?;
+            }
+
+            if entry.id < ImageTocEntryId::AuxFirst.into()
+                || aux.iter().take(i).any(|other| other.id == entry.id)
+            {
+                Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_ID_INVALID)?;
+            }
+
+            let range = entry.image_range()?;
+            if range.start < prev_end {
+                Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_INCORRECT_ORDER)?;
+            }
+            prev_end = range.end;
+        }
+
         // Check if fmc and runtime images don't overlap on loading in the ICCM.
         let fmc_load_addr_start = manifest.fmc.load_addr;
         let (fmc_load_addr_end, overflow) =
@@ -579,6 +622,7 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
         let info = ImageInfo {
             fmc: &manifest.fmc,
             runtime: &manifest.runtime,
+            aux,
         };
 
         Ok(info)
@@ -755,6 +799,41 @@ impl<Env: ImageVerificationEnv> ImageVerifier<Env> {
         Ok((info, log_info))
     }
 
+    /// Verify Auxiliary Image
+    fn verify_aux(
+        &mut self,
+        verify_info: &ImageTocEntry,
+    ) -> CaliptraResult<ImageVerificationAuxInfo> {
+        let range = verify_info.image_range()?;
+
+        let actual = self
+            .env
+            .sha384_digest(range.start, range.len() as u32)
+            .map_err(|err| {
+                self.env.set_fw_extended_error(err.into());
+                CaliptraError::IMAGE_VERIFIER_ERR_AUX_DIGEST_FAILURE
+            })?;
+
+        if verify_info.digest != actual {
+            Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_DIGEST_MISMATCH)?;
+        }
+
+        // There are no SVN fuses for auxiliary images. The SoC enforces its
+        // own anti-rollback policy with the SVN reported for the image.
+        if self.svn_check_required() && verify_info.svn < verify_info.min_svn {
+            Err(CaliptraError::IMAGE_VERIFIER_ERR_AUX_SVN_LESS_THAN_MIN_SUPPORTED)?;
+        }
+
+        let info = ImageVerificationAuxInfo {
+            id: verify_info.id,
+            size: verify_info.size,
+            svn: verify_info.svn,
+            digest: verify_info.digest,
+        };
+
+        Ok(info)
+    }
+
     /// Calculates the effective fuse SVN.
     ///
     /// If anti-rollback is disabled, the effective fuse-SVN is zero.
@@ -1303,7 +1382,17 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT / 2,
+            len: MIN_TOC_ENTRY_COUNT - 1,
+            digest: &ImageDigest::default(),
+        };
+        let result = verifier.verify_toc(&manifest, &toc_info, manifest.size);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_TOC_ENTRY_COUNT_INVALID)
+        );
+
+        let toc_info = TocInfo {
+            len: MAX_TOC_ENTRY_COUNT + 1,
             digest: &ImageDigest::default(),
         };
         let result = verifier.verify_toc(&manifest, &toc_info, manifest.size);
@@ -1319,7 +1408,7 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &DUMMY_DATA,
         };
         let result = verifier.verify_toc(&manifest, &toc_info, manifest.size);
@@ -1335,7 +1424,7 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &ImageDigest::default(),
         };
 
@@ -1465,7 +1554,7 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &ImageDigest::default(),
         };
 
@@ -1510,7 +1599,7 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &ImageDigest::default(),
         };
 
@@ -1537,7 +1626,7 @@ mod tests {
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &ImageDigest::default(),
         };
 
@@ -1558,13 +1647,100 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_toc_aux() {
+        let mut manifest = ImageManifest::default();
+        let test_env = TestEnv::default();
+        let mut verifier = ImageVerifier::new(test_env);
+        let toc_info = TocInfo {
+            len: MIN_TOC_ENTRY_COUNT + 2,
+            digest: &ImageDigest::default(),
+        };
+
+        // [-FMC--][--RT--][-AUX0-][-AUX1-]
+        manifest.fmc.offset = 0;
+        manifest.fmc.size = 100;
+        manifest.runtime.offset = 100;
+        manifest.runtime.size = 200;
+        manifest.fmc.load_addr = 0x1000;
+        manifest.runtime.load_addr = 0x2000;
+        for (i, aux) in manifest.aux.iter_mut().take(2).enumerate() {
+            aux.id = u32::from(ImageTocEntryId::AuxFirst) + i as u32;
+            aux.r#type = ImageTocEntryType::Auxiliary.into();
+            aux.offset = 300 + 50 * i as u32;
+            aux.size = 50;
+        }
+        let img_bundle_sz = manifest.size + 400;
+
+        let result = verifier.verify_toc(&manifest, &toc_info, img_bundle_sz);
+        assert_eq!(result.unwrap().aux.len(), 2);
+
+        let result = verifier.verify_toc(&manifest, &toc_info, img_bundle_sz - 1);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_IMAGE_LEN_MORE_THAN_BUNDLE_SIZE)
+        );
+
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[1].size = 0;
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_SIZE_ZERO)
+        );
+
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[1].r#type = ImageTocEntryType::Executable.into();
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_TYPE_INVALID)
+        );
+
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[1].id = ImageTocEntryId::Runtime.into();
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_ID_INVALID)
+        );
+
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[1].id = bad_manifest.aux[0].id;
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_ID_INVALID)
+        );
+
+        // [-FMC--][--RT--]
+        //               [-AUX0-]
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[0].offset = 299;
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_INCORRECT_ORDER)
+        );
+
+        // [-FMC--][--RT--][-AUX1-][-AUX0-]
+        let mut bad_manifest = manifest;
+        bad_manifest.aux[0].offset = 350;
+        bad_manifest.aux[1].offset = 300;
+        let result = verifier.verify_toc(&bad_manifest, &toc_info, img_bundle_sz);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_INCORRECT_ORDER)
+        );
+    }
+
     #[test]
     fn test_fmc_rt_load_address_range_overlap() {
         let mut manifest = ImageManifest::default();
         let test_env = TestEnv::default();
         let mut verifier = ImageVerifier::new(test_env);
         let toc_info = TocInfo {
-            len: MAX_TOC_ENTRY_COUNT,
+            len: MIN_TOC_ENTRY_COUNT,
             digest: &ImageDigest::default(),
         };
 
@@ -1736,6 +1912,51 @@ mod tests {
         assert_eq!(info.size, 100);
     }
 
+    #[test]
+    fn test_aux_digest_mismatch() {
+        let test_env = TestEnv::default();
+        let mut verifier = ImageVerifier::new(test_env);
+        let verify_info = ImageTocEntry {
+            digest: DUMMY_DATA,
+            ..Default::default()
+        };
+        let result = verifier.verify_aux(&verify_info);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_DIGEST_MISMATCH)
+        );
+    }
+
+    #[test]
+    fn test_aux_svn() {
+        let test_env = TestEnv {
+            lifecycle: Lifecycle::Production,
+            ..Default::default()
+        };
+        let mut verifier = ImageVerifier::new(test_env);
+        let verify_info = ImageTocEntry {
+            id: ImageTocEntryId::AuxFirst.into(),
+            svn: 1,
+            min_svn: 2,
+            size: 100,
+            ..Default::default()
+        };
+        let result = verifier.verify_aux(&verify_info);
+        assert_eq!(
+            result.err(),
+            Some(CaliptraError::IMAGE_VERIFIER_ERR_AUX_SVN_LESS_THAN_MIN_SUPPORTED)
+        );
+
+        let verify_info = ImageTocEntry {
+            svn: 2,
+            ..verify_info
+        };
+        let info = verifier.verify_aux(&verify_info).unwrap();
+        assert_eq!(info.id, u32::from(ImageTocEntryId::AuxFirst));
+        assert_eq!(info.svn, 2);
+        assert_eq!(info.size, 100);
+    }
+
     struct TestEnv {
         digest: ImageDigest,
         fmc_digest: ImageDigest,
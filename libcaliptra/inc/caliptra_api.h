@@ -111,12 +111,21 @@ int caliptra_get_idev_info(struct caliptra_get_idev_info_resp *resp, bool async)
 // Get LDEV cert
 int caliptra_get_ldev_cert(struct caliptra_get_ldev_cert_resp *resp, bool async);
 
+// Get DICE cert chain
+int caliptra_get_cert_chain(struct caliptra_get_cert_chain_req *req, struct caliptra_get_cert_chain_resp *resp, bool async);
+
 // ECDSA384 Verify
 int caliptra_ecdsa384_verify(struct caliptra_ecdsa_verify_req *req, bool async);
 
 // Stash measurement
 int caliptra_stash_measurement(struct caliptra_stash_measurement_req *req, struct caliptra_stash_measurement_resp *resp, bool async);
 
+// Get measurement log
+int caliptra_get_measurement_log(struct caliptra_get_measurement_log_req *req, struct caliptra_get_measurement_log_resp *resp, bool async);
+
+// Quote PCRs
+int caliptra_quote(struct caliptra_quote_req *req, struct caliptra_quote_resp *resp, bool async);
+
 // Disable attestation
 int caliptra_disable_attestation(bool async);
 
@@ -133,7 +142,7 @@ int caliptra_fips_version(struct caliptra_fips_version_resp *resp, bool async);
 int caliptra_self_test_start(bool async);
 
 // Self test get results
-int caliptra_self_test_get_results(struct caliptra_test_get_fmc_alias_cert_resp *resp, bool async);
+int caliptra_self_test_get_results(struct caliptra_self_test_get_results_resp *resp, bool async);
 
 // Shutdown
 int caliptra_shutdown(bool async);
@@ -141,4 +150,7 @@ int caliptra_shutdown(bool async);
 // Capabilities
 int caliptra_capabilities(struct caliptra_capabilities_resp *resp, bool async);
 
+// Capabilities (Runtime Firmware)
+int caliptra_rt_capabilities(struct caliptra_rt_capabilities_resp *resp, bool async);
+
 
@@ -83,6 +83,19 @@ struct caliptra_get_ldev_cert_resp {
     uint8_t data[1024];
 };
 
+struct caliptra_get_cert_chain_req {
+    struct caliptra_req_header hdr;
+    uint32_t offset;
+    uint32_t size;
+};
+
+struct caliptra_get_cert_chain_resp {
+    struct caliptra_resp_header hdr;
+    uint32_t total_size;
+    uint32_t data_size;
+    uint8_t data[1024];
+};
+
 struct caliptra_ecdsa_verify_req {
     struct caliptra_req_header hdr;
     uint8_t pub_key_x[48];
@@ -112,6 +125,48 @@ struct caliptra_stash_measurement_resp {
     uint32_t dpe_result;
 };
 
+#define CALIPTRA_MEASUREMENT_LOG_MAX_COUNT 40
+
+struct caliptra_measurement_log_entry {
+    uint32_t counter;
+    uint32_t pauser;
+    uint8_t metadata[4];
+    uint32_t svn;
+    uint8_t measurement[48];
+    uint8_t context[48];
+};
+
+struct caliptra_get_measurement_log_req {
+    struct caliptra_req_header hdr;
+    uint8_t nonce[48];
+};
+
+struct caliptra_get_measurement_log_resp {
+    struct caliptra_resp_header hdr;
+    uint8_t signature_r[48];
+    uint8_t signature_s[48];
+    uint8_t nonce[48];
+    uint32_t data_size;
+    struct caliptra_measurement_log_entry entries[CALIPTRA_MEASUREMENT_LOG_MAX_COUNT];
+};
+
+struct caliptra_quote_req {
+    struct caliptra_req_header hdr;
+    uint8_t nonce[48];
+    uint32_t pcr_selection;
+};
+
+#define CALIPTRA_PCR_COUNT 32
+
+struct caliptra_quote_resp {
+    struct caliptra_resp_header hdr;
+    uint8_t nonce[48];
+    uint32_t pcr_selection;
+    uint8_t pcrs[CALIPTRA_PCR_COUNT][48];
+    uint8_t signature_r[48];
+    uint8_t signature_s[48];
+};
+
 struct caliptra_test_get_fmc_alias_cert_resp {
     struct caliptra_resp_header hdr;
     uint32_t data_size;
@@ -139,6 +194,22 @@ struct caliptra_capabilities_resp {
     uint8_t capabilities[16];
 };
 
+struct caliptra_rt_capabilities_resp {
+    struct caliptra_resp_header hdr;
+    uint8_t capabilities[16];
+    uint32_t dpe_profile;
+    uint32_t dpe_support;
+    uint32_t fmc_version;
+    uint32_t app_version;
+};
+
+#define CALIPTRA_SELF_TEST_COUNT 9
+
+struct caliptra_self_test_get_results_resp {
+    struct caliptra_resp_header hdr;
+    uint32_t results[CALIPTRA_SELF_TEST_COUNT];
+};
+
 // The below fields are placeholders to set up the baseline
 // required for communication of DPE commands to Caliptra
 // firmware.
@@ -789,6 +789,25 @@ int caliptra_get_ldev_cert(struct caliptra_get_ldev_cert_resp *resp, bool async)
     return pack_and_execute_command(&p, async);
 }
 
+// Get DICE cert chain
+int caliptra_get_cert_chain(struct caliptra_get_cert_chain_req *req, struct caliptra_get_cert_chain_resp *resp, bool async)
+{
+    if (!req || !resp)
+    {
+        return INVALID_PARAMS;
+    }
+
+    struct parcel p = {
+        .command   = OP_GET_CERT_CHAIN,
+        .tx_buffer = (uint8_t*)req,
+        .tx_bytes  = sizeof(*req),
+        .rx_buffer = (uint8_t*)resp,
+        .rx_bytes  = sizeof(*resp),
+    };
+
+    return pack_and_execute_command(&p, async);
+}
+
 // ECDSA384 Verify
 int caliptra_ecdsa384_verify(struct caliptra_ecdsa_verify_req *req, bool async)
 {
@@ -829,6 +848,44 @@ int caliptra_stash_measurement(struct caliptra_stash_measurement_req *req, struc
     return pack_and_execute_command(&p, async);
 }
 
+// Get measurement log
+int caliptra_get_measurement_log(struct caliptra_get_measurement_log_req *req, struct caliptra_get_measurement_log_resp *resp, bool async)
+{
+    if (!req || !resp)
+    {
+        return INVALID_PARAMS;
+    }
+
+    struct parcel p = {
+        .command   = OP_GET_MEASUREMENT_LOG,
+        .tx_buffer = (uint8_t*)req,
+        .tx_bytes  = sizeof(*req),
+        .rx_buffer = (uint8_t*)resp,
+        .rx_bytes  = sizeof(*resp),
+    };
+
+    return pack_and_execute_command(&p, async);
+}
+
+// Quote PCRs
+int caliptra_quote(struct caliptra_quote_req *req, struct caliptra_quote_resp *resp, bool async)
+{
+    if (!req || !resp)
+    {
+        return INVALID_PARAMS;
+    }
+
+    struct parcel p = {
+        .command   = OP_QUOTE,
+        .tx_buffer = (uint8_t*)req,
+        .tx_bytes  = sizeof(*req),
+        .rx_buffer = (uint8_t*)resp,
+        .rx_bytes  = sizeof(*resp),
+    };
+
+    return pack_and_execute_command(&p, async);
+}
+
 // Disable attestation
 int caliptra_disable_attestation(bool async)
 {
@@ -930,7 +987,7 @@ int caliptra_self_test_start(bool async)
 }
 
 // Self test get results
-int caliptra_self_test_get_results(struct caliptra_test_get_fmc_alias_cert_resp *resp, bool async)
+int caliptra_self_test_get_results(struct caliptra_self_test_get_results_resp *resp, bool async)
 {
     if (!resp)
     {
@@ -985,5 +1042,26 @@ int caliptra_capabilities(struct caliptra_capabilities_resp *resp, bool async)
         .rx_bytes  = sizeof(*resp),
     };
 
+    return pack_and_execute_command(&p, async);
+}
+
+// Capabilities (Runtime Firmware)
+int caliptra_rt_capabilities(struct caliptra_rt_capabilities_resp *resp, bool async)
+{
+    if (!resp)
+    {
+        return INVALID_PARAMS;
+    }
+
+    caliptra_checksum checksum = 0;
+
+    struct parcel p = {
+        .command   = OP_CAPABILITIES,
+        .tx_buffer = (uint8_t*)&checksum,
+        .tx_bytes  = sizeof(checksum),
+        .rx_buffer = (uint8_t*)resp,
+        .rx_bytes  = sizeof(*resp),
+    };
+
     return pack_and_execute_command(&p, async);
 }
\ No newline at end of file
@@ -30,6 +30,9 @@ enum mailbox_command {
     OP_DISABLE_ATTESTATION       = 0x4453424C, // "DSBL"
     OP_INVOKE_DPE_COMMAND        = 0x44504543, // "DPEC"
     OP_FW_INFO                   = 0x494E464F, // "INFO"
+    OP_GET_CERT_CHAIN            = 0x43484E43, // "CHNC"
+    OP_GET_MEASUREMENT_LOG       = 0x4D4C4F47, // "MLOG"
+    OP_QUOTE                     = 0x51554F54, // "QUOT"
     OP_FIPS_VERSION              = 0x46505652, // "FPVR"
     OP_SELF_TEST_START           = 0x46504C54, // "FPST"
     OP_SELF_TEST_GET_RESULTS     = 0x46504C67, // "FPGR"
@@ -446,6 +446,9 @@ impl FirmwareProcessor {
 
     /// Load the image to ICCM & DCCM
     ///
+    /// Auxiliary images are verified and measured from the mailbox but never
+    /// loaded.
+    ///
     /// # Arguments
     ///
     /// * `env`      - ROM Environment
@@ -32,9 +32,6 @@ type InitDevIdCsr<'a> = Certificate<'a, { MAX_CSR_SIZE }>;
 /// Initialization Vector used by Deobfuscation Engine during UDS / field entropy decryption.
 const DOE_IV: Array4x4 = Array4xN::<4, 16>([0xfb10365b, 0xa1179741, 0xfba193a1, 0x0f406d7e]);
 
-/// Maximum Certificate Signing Request Size
-const MAX_CSR_SIZE: usize = 512;
-
 /// Dice Initial Device Identity (IDEVID) Layer
 pub enum InitDevIdLayer {}
 
@@ -210,6 +207,8 @@ impl InitDevIdLayer {
         //
         // A flag is asserted via JTAG interface to enable the generation of CSR
         if !env.soc_ifc.mfg_flag_gen_idev_id_csr() {
+            // Make sure a stale CSR is never handed to runtime.
+            env.persistent_data.get_mut().idevid_csr.zeroize();
             return Ok(());
         }
 
@@ -281,6 +280,10 @@ impl InitDevIdLayer {
         cprintln!("[idev] CSR = {}", HexBytes(&csr[..csr_len]));
         report_boot_status(IDevIdMakeCsrComplete.into());
 
+        // Stash the CSR in persistent data so runtime can serve GET_IDEV_CSR
+        env.persistent_data.get_mut().idevid_csr =
+            IdevIdCsr::new(&csr[..csr_len]).ok_or(CaliptraError::ROM_IDEVID_CSR_OVERFLOW)?;
+
         // Execute Send CSR Flow
         let result = Self::send_csr(env, InitDevIdCsr::new(&csr, csr_len));
         csr.zeroize();
@@ -28,7 +28,8 @@ use caliptra_common::{
     PcrLogEntry, PcrLogEntryId,
 };
 use caliptra_drivers::{
-    CaliptraError, CaliptraResult, PcrBank, PersistentData, PersistentDataAccessor, Sha384,
+    Array4x12, CaliptraError, CaliptraResult, PcrBank, PersistentData, PersistentDataAccessor,
+    Sha384,
 };
 use caliptra_image_verify::ImageVerificationInfo;
 
@@ -102,6 +103,20 @@ pub(crate) fn extend_pcrs(
         PcrLogEntryId::FmcTci,
     )?;
 
+    // Auxiliary images are not loaded by Caliptra, only measured. The
+    // identifier and SVN of each image are logged right before its digest,
+    // as a log entry only holds 48 bytes.
+    for aux in info.aux_images() {
+        let mut aux_info = [0u8; 8];
+        aux_info[..4].copy_from_slice(&aux.id.to_le_bytes());
+        aux_info[4..].copy_from_slice(&aux.svn.to_le_bytes());
+        pcr.extend(&aux_info, PcrLogEntryId::AuxImageInfo)?;
+        pcr.extend(
+            &<[u8; 48]>::from(&Array4x12::from(aux.digest)),
+            PcrLogEntryId::AuxImageDigest,
+        )?;
+    }
+
     Ok(())
 }
 
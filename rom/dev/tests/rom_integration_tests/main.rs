@@ -3,6 +3,7 @@
 mod helpers;
 
 mod rv32_unit_tests;
+mod test_boot_timing;
 mod test_capabilities;
 mod test_cfi;
 mod test_dice_derivations;
@@ -0,0 +1,68 @@
+// Licensed under the Apache-2.0 license
+
+use caliptra_builder::{firmware, ImageOptions};
+use caliptra_common::RomBootStatus::ColdResetComplete;
+use caliptra_hw_model::{BootParams, CryptoLatency, HwModel, InitParams, ModelEmulated};
+
+/// Upper bounds, in cycles, of the ROM boot phases with the RTL latencies of
+/// the crypto peripherals. Each allows for the ECC operations of the phase
+/// plus a few million cycles of firmware.
+const ROM_PHASE_BUDGETS: [(&str, u64); 6] = [
+    // KATs, including the software LMS verification
+    ("ROM", 12_000_000),
+    ("ROM cold reset", 1_000_000),
+    // DOE and the IDevID key pair generation
+    ("ROM IDevID", 4_000_000),
+    // Key pair generation, certificate signing and signature verification
+    ("ROM LDevID", 8_000_000),
+    // Vendor and owner signature verification of the manifest
+    ("ROM firmware processor", 10_000_000),
+    ("ROM FMC alias", 8_000_000),
+];
+
+#[test]
+fn test_rom_boot_phase_budgets() {
+    let rom = caliptra_builder::build_firmware_rom(&firmware::ROM_WITH_UART).unwrap();
+    let image = caliptra_builder::build_and_sign_image(
+        &firmware::FMC_WITH_UART,
+        &firmware::APP_WITH_UART,
+        ImageOptions::default(),
+    )
+    .unwrap();
+    let mut hw: ModelEmulated = HwModel::new(BootParams {
+        init_params: InitParams {
+            rom: &rom,
+            crypto_latency: Some(CryptoLatency::RTL),
+            // The budgets are checked below; don't let the watchdog fire first
+            wdt_timeout_cycles: 100_000_000,
+            ..Default::default()
+        },
+        fw_image: Some(&image.to_bytes().unwrap()),
+        ..Default::default()
+    })
+    .unwrap();
+    hw.step_until_boot_status(ColdResetComplete.into(), true);
+
+    let timing = hw.boot_timing();
+    let phases = timing.phases();
+    for (name, _) in ROM_PHASE_BUDGETS {
+        assert!(
+            phases.iter().any(|phase| phase.name == name),
+            "missing phase {name}\n{timing}"
+        );
+    }
+    for phase in phases {
+        let Some(&(_, budget)) = ROM_PHASE_BUDGETS
+            .iter()
+            .find(|(name, _)| *name == phase.name)
+        else {
+            panic!("unexpected phase {}\n{timing}", phase.name);
+        };
+        assert!(
+            phase.cycles() <= budget,
+            "{} took {} cycles, over its budget of {budget}\n{timing}",
+            phase.name,
+            phase.cycles()
+        );
+    }
+}
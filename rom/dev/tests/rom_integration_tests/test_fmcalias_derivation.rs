@@ -18,9 +18,9 @@ use caliptra_drivers::{ColdResetEntry4, PcrId, RomVerifyConfig};
 use caliptra_error::CaliptraError;
 use caliptra_hw_model::{BootParams, Fuses, HwModel, InitParams, ModelError, SecurityState};
 use caliptra_image_fake_keys::{OWNER_CONFIG, VENDOR_CONFIG_KEY_1};
-use caliptra_image_gen::ImageGenerator;
+use caliptra_image_gen::{ImageGenerator, ImageGeneratorAuxImage};
 use caliptra_image_openssl::OsslCrypto;
-use caliptra_image_types::IMAGE_BYTE_SIZE;
+use caliptra_image_types::{ImageTocEntryId, IMAGE_BYTE_SIZE};
 use caliptra_test::swap_word_bytes;
 use openssl::hash::{Hasher, MessageDigest};
 use zerocopy::{AsBytes, FromBytes};
@@ -395,6 +395,125 @@ fn test_pcr_log_fmc_fuse_svn() {
     );
 }
 
+#[test]
+fn test_pcr_log_aux_images() {
+    let aux = vec![
+        ImageGeneratorAuxImage {
+            id: ImageTocEntryId::AuxFirst.into(),
+            svn: 3,
+            min_svn: 1,
+            content: vec![0x5a; 0x80],
+            ..Default::default()
+        },
+        ImageGeneratorAuxImage {
+            id: u32::from(ImageTocEntryId::AuxFirst) + 7,
+            svn: 9,
+            content: vec![0xa5; 0x44],
+            ..Default::default()
+        },
+    ];
+    let image_options = ImageOptions {
+        vendor_config: VENDOR_CONFIG_KEY_1,
+        aux: aux.clone(),
+        ..Default::default()
+    };
+    let image_bundle = caliptra_builder::build_and_sign_image(
+        &TEST_FMC_INTERACTIVE,
+        &APP_WITH_UART,
+        image_options,
+    )
+    .unwrap();
+
+    let gen = ImageGenerator::new(OsslCrypto::default());
+    let fuses = Fuses {
+        key_manifest_pk_hash: gen
+            .vendor_pubkey_digest(&image_bundle.manifest.preamble)
+            .unwrap(),
+        owner_pk_hash: gen
+            .owner_pubkey_digest(&image_bundle.manifest.preamble)
+            .unwrap(),
+        ..Default::default()
+    };
+    let rom = caliptra_builder::build_firmware_rom(&ROM_WITH_UART).unwrap();
+    let mut hw = caliptra_hw_model::new(BootParams {
+        init_params: InitParams {
+            rom: &rom,
+            security_state: SecurityState::from(fuses.life_cycle as u32),
+            ..Default::default()
+        },
+        fuses,
+        ..Default::default()
+    })
+    .unwrap();
+
+    hw.upload_firmware(&image_bundle.to_bytes().unwrap())
+        .unwrap();
+
+    hw.step_until_boot_status(ColdResetComplete.into(), true);
+
+    let pcr_entry_arr = hw.mailbox_execute(0x1000_0000, &[]).unwrap().unwrap();
+
+    // The identifier and SVN of each auxiliary image are logged right before
+    // its digest, after the FMC TCI
+    const FIRST_AUX_ENTRY: usize = 4;
+    assert_eq!(
+        pcr_entry_arr.len(),
+        (FIRST_AUX_ENTRY + 2 * aux.len()) * PCR_ENTRY_SIZE
+    );
+    check_pcr_log_entry(
+        &pcr_entry_arr,
+        FIRST_AUX_ENTRY - 1,
+        PcrLogEntryId::FmcTci,
+        PCR0_AND_PCR1_EXTENDED_ID,
+        swap_word_bytes(&image_bundle.manifest.fmc.digest).as_bytes(),
+    );
+    for (i, aux) in aux.iter().enumerate() {
+        let mut aux_info = aux.id.to_le_bytes().to_vec();
+        aux_info.extend_from_slice(&aux.svn.to_le_bytes());
+        check_pcr_log_entry(
+            &pcr_entry_arr,
+            FIRST_AUX_ENTRY + 2 * i,
+            PcrLogEntryId::AuxImageInfo,
+            PCR0_AND_PCR1_EXTENDED_ID,
+            &aux_info,
+        );
+        check_pcr_log_entry(
+            &pcr_entry_arr,
+            FIRST_AUX_ENTRY + 2 * i + 1,
+            PcrLogEntryId::AuxImageDigest,
+            PCR0_AND_PCR1_EXTENDED_ID,
+            &openssl::sha::sha384(&aux.content),
+        );
+    }
+
+    // The PCRs match the log, including the auxiliary images
+    let pcrs = hw.mailbox_execute(0x1000_0006, &[]).unwrap().unwrap();
+    assert_eq!(pcrs.len(), PCR_COUNT * 48);
+
+    let mut pcr0_from_hw: [u8; 48] = pcrs[0..48].try_into().unwrap();
+    let mut pcr1_from_hw: [u8; 48] = pcrs[48..96].try_into().unwrap();
+
+    helpers::change_dword_endianess(&mut pcr0_from_hw);
+    helpers::change_dword_endianess(&mut pcr1_from_hw);
+
+    assert_eq!(
+        pcr0_from_hw,
+        hash_pcr_log_entries(&[0; 48], &pcr_entry_arr, PcrId::PcrId0)
+    );
+    assert_eq!(
+        pcr1_from_hw,
+        hash_pcr_log_entries(&[0; 48], &pcr_entry_arr, PcrId::PcrId1)
+    );
+    assert_ne!(
+        pcr0_from_hw,
+        hash_pcr_log_entries(
+            &[0; 48],
+            &pcr_entry_arr[..FIRST_AUX_ENTRY * PCR_ENTRY_SIZE],
+            PcrId::PcrId0
+        )
+    );
+}
+
 fn hash_pcr_log_entry(entry: &PcrLogEntry, pcr: &mut [u8; 48]) {
     let mut hasher = Hasher::new(MessageDigest::sha384()).unwrap();
     hasher.update(pcr).unwrap();
@@ -595,6 +714,7 @@ fn test_fuse_log() {
         app_svn: FMC_SVN,
         app_min_svn: FMC_MIN_SVN,
         app_version: 0,
+        aux: vec![],
     };
     let image_bundle =
         caliptra_builder::build_and_sign_image(&TEST_FMC_WITH_UART, &APP_WITH_UART, image_options)
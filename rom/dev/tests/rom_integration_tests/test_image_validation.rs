@@ -2059,6 +2059,7 @@ fn update_header(image_bundle: &mut ImageBundle) {
         runtime: ElfExecutable::default(),
         vendor_config: opts.vendor_config,
         owner_config: opts.owner_config,
+        aux: vec![],
     };
 
     let gen = ImageGenerator::new(OsslCrypto::default());
@@ -2096,7 +2097,11 @@ fn update_fmc_runtime_ranges(
 
     // Update TOC digest.
     image_bundle.manifest.header.toc_digest = gen
-        .toc_digest(&image_bundle.manifest.fmc, &image_bundle.manifest.runtime)
+        .toc_digest(
+            &image_bundle.manifest.fmc,
+            &image_bundle.manifest.runtime,
+            &[],
+        )
         .unwrap();
 
     // Update Header.
@@ -2117,7 +2122,11 @@ fn update_load_addr(image_bundle: &mut ImageBundle, is_fmc: bool, new_load_addr:
 
     // Update TOC digest.
     image_bundle.manifest.header.toc_digest = gen
-        .toc_digest(&image_bundle.manifest.fmc, &image_bundle.manifest.runtime)
+        .toc_digest(
+            &image_bundle.manifest.fmc,
+            &image_bundle.manifest.runtime,
+            &[],
+        )
         .unwrap();
 
     // Update Header.
@@ -2142,7 +2151,11 @@ fn update_entry_point(
 
     // Update TOC digest.
     image_bundle.manifest.header.toc_digest = gen
-        .toc_digest(&image_bundle.manifest.fmc, &image_bundle.manifest.runtime)
+        .toc_digest(
+            &image_bundle.manifest.fmc,
+            &image_bundle.manifest.runtime,
+            &[],
+        )
         .unwrap();
 
     // Update Header.
@@ -1,11 +1,11 @@
 // Licensed under the Apache-2.0 license
 
 #[cfg(feature = "test_only_commands")]
+use caliptra_common::mailbox_api::TestGetFmcAliasCertResp;
 use caliptra_common::mailbox_api::{
-    GetLdevCertResp, MailboxResp, MailboxRespHeader, TestGetFmcAliasCertResp,
+    GetCertChainReq, GetCertChainResp, GetLdevCertResp, MailboxResp, MailboxRespHeader,
 };
 
-#[cfg(feature = "test_only_commands")]
 use crate::Drivers;
 
 use caliptra_drivers::{
@@ -13,10 +13,10 @@ use caliptra_drivers::{
     PersistentData,
 };
 use caliptra_x509::{Ecdsa384CertBuilder, Ecdsa384Signature};
+use zerocopy::FromBytes;
 
 pub struct GetLdevCertCmd;
 impl GetLdevCertCmd {
-    #[cfg(feature = "test_only_commands")]
     pub(crate) fn execute(drivers: &mut Drivers) -> CaliptraResult<MailboxResp> {
         let mut resp = GetLdevCertResp {
             hdr: MailboxRespHeader::default(),
@@ -34,6 +34,42 @@ impl GetLdevCertCmd {
     }
 }
 
+pub struct GetCertChainCmd;
+impl GetCertChainCmd {
+    /// Returns a chunk of the LDevID -> FMC Alias -> RT Alias certificate
+    /// chain, starting at `offset`, along with the total size of the chain.
+    pub(crate) fn execute(drivers: &mut Drivers, cmd_args: &[u8]) -> CaliptraResult<MailboxResp> {
+        if let Some(cmd) = GetCertChainReq::read_from(cmd_args) {
+            let cert_chain = drivers.cert_chain.as_slice();
+            let offset = cmd.offset as usize;
+            let size = cmd.size as usize;
+            if offset > cert_chain.len() || size > GetCertChainResp::DATA_MAX_SIZE {
+                return Err(CaliptraError::RUNTIME_MAILBOX_INVALID_PARAMS);
+            }
+
+            let end = offset + size.min(cert_chain.len() - offset);
+            let chunk = cert_chain
+                .get(offset..end)
+                .ok_or(CaliptraError::RUNTIME_MAILBOX_INVALID_PARAMS)?;
+
+            let mut resp = GetCertChainResp {
+                hdr: MailboxRespHeader::default(),
+                total_size: cert_chain.len() as u32,
+                data_size: chunk.len() as u32,
+                data: [0u8; GetCertChainResp::DATA_MAX_SIZE],
+            };
+            resp.data
+                .get_mut(..chunk.len())
+                .ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?
+                .copy_from_slice(chunk);
+
+            Ok(MailboxResp::GetCertChain(resp))
+        } else {
+            Err(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)
+        }
+    }
+}
+
 pub struct TestGetFmcAliasCertCmd;
 impl TestGetFmcAliasCertCmd {
     #[cfg(feature = "test_only_commands")]
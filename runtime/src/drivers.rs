@@ -6,8 +6,8 @@
 pub use crate::fips::{fips_self_test_cmd, fips_self_test_cmd::SelfTestStatus};
 
 use crate::{
-    dice, CptraDpeTypes, DisableAttestationCmd, DpeCrypto, DpePlatform, Mailbox, DPE_SUPPORT,
-    MAX_CERT_CHAIN_SIZE,
+    dice, CptraDpeTypes, DisableAttestationCmd, DpeCrypto, DpePlatform, Mailbox,
+    PauserPrivilegesTable, DPE_SUPPORT, MAX_CERT_CHAIN_SIZE,
 };
 
 use arrayvec::ArrayVec;
@@ -90,6 +90,7 @@ impl Drivers {
         match reset_reason {
             ResetReason::ColdReset => {
                 Self::initialize_dpe(&mut drivers)?;
+                Self::initialize_rt_measurement_log(&mut drivers);
             }
             ResetReason::UpdateReset => {
                 Self::validate_dpe_structure(&mut drivers)?;
@@ -239,8 +240,16 @@ impl Drivers {
         Ok(token)
     }
 
+    /// Start an empty measurement log. Runtime entries are numbered after
+    /// the measurements ROM stashed during this boot.
+    fn initialize_rt_measurement_log(drivers: &mut Drivers) {
+        let pdata = drivers.persistent_data.get_mut();
+        pdata.rt_measurement_log.reset(pdata.fht.meas_log_index);
+    }
+
     fn initialize_dpe(drivers: &mut Drivers) -> CaliptraResult<()> {
-        let locality = drivers.persistent_data.get().manifest1.header.pl0_pauser;
+        let locality =
+            PauserPrivilegesTable::new(&drivers.persistent_data.get().manifest1)?.pl0_locality();
         let hashed_rt_pub_key = drivers.compute_rt_alias_sn()?;
         let mut crypto = DpeCrypto::new(
             &mut drivers.sha384,
@@ -43,6 +43,7 @@ impl FipsModule {
 pub mod fips_self_test_cmd {
     use super::*;
     use crate::RtBootStatus::{RtFipSelfTestComplete, RtFipSelfTestStarted};
+    use caliptra_common::mailbox_api::{SelfTestGetResultsResp, SelfTestId};
     use caliptra_common::HexBytes;
     use caliptra_common::{
         verifier::FirmwareImageVerificationEnv, FMC_ORG, FMC_SIZE, RUNTIME_ORG, RUNTIME_SIZE,
@@ -50,6 +51,9 @@ pub mod fips_self_test_cmd {
     use caliptra_drivers::{ResetReason, ShaAccLockState};
     use caliptra_image_types::RomInfo;
     use caliptra_image_verify::ImageVerifier;
+    use caliptra_kat::{
+        Ecc384Kat, Hmac384Kat, LmsKat, Sha1Kat, Sha256Kat, Sha384AccKat, Sha384Kat,
+    };
     use zerocopy::AsBytes;
 
     // Helper function to create a slice from a memory region
@@ -59,8 +63,27 @@ pub mod fips_self_test_cmd {
     }
     pub enum SelfTestStatus {
         Idle,
-        InProgress(fn(&mut Drivers) -> CaliptraResult<()>),
-        Done,
+        InProgress(SelfTestProgress),
+        Done([u32; SelfTestId::COUNT]),
+    }
+
+    /// Progress of a self test started by SELF_TEST_START
+    #[derive(Clone, Copy)]
+    pub struct SelfTestProgress {
+        /// Next test to run
+        next: SelfTestId,
+
+        /// Result of each completed test; 0 on success, otherwise the error code
+        results: [u32; SelfTestId::COUNT],
+    }
+
+    impl Default for SelfTestProgress {
+        fn default() -> Self {
+            Self {
+                next: SelfTestId::ROM_INTEGRITY,
+                results: [0; SelfTestId::COUNT],
+            }
+        }
     }
 
     fn copy_and_verify_image(env: &mut Drivers) -> CaliptraResult<()> {
@@ -105,54 +128,111 @@ pub mod fips_self_test_cmd {
                 + env.persistent_data.get().manifest1.runtime.size,
             ResetReason::UpdateReset,
         )?;
-        env.mbox.unlock();
         cprintln!("[rt] Verify complete");
         Ok(())
     }
 
-    pub(crate) fn execute(env: &mut Drivers) -> CaliptraResult<()> {
+    /// Start the self test. The individual tests are run one at a time by
+    /// [`execute_next`] while the runtime is otherwise idle.
+    pub(crate) fn start(env: &mut Drivers) {
         caliptra_drivers::report_boot_status(RtFipSelfTestStarted.into());
         cprintln!("[rt] FIPS self test");
-        rom_integrity_test(env)?;
-        execute_kats(env)?;
-        copy_and_verify_image(env)?;
-        caliptra_drivers::report_boot_status(RtFipSelfTestComplete.into());
-        Ok(())
+        env.self_test_status = SelfTestStatus::InProgress(SelfTestProgress::default());
     }
 
-    /// Execute KAT for cryptographic algorithms implemented in H/W.
-    fn execute_kats(env: &mut Drivers) -> CaliptraResult<()> {
-        let mut kats_env = caliptra_kat::KatsEnv {
-            // SHA1 Engine
-            sha1: &mut env.sha1,
-
-            // sha256
-            sha256: &mut env.sha256,
-
-            // SHA2-384 Engine
-            sha384: &mut env.sha384,
-
-            // SHA2-384 Accelerator
-            sha384_acc: &mut env.sha384_acc,
-
-            // Hmac384 Engine
-            hmac384: &mut env.hmac384,
-
-            /// Cryptographically Secure Random Number Generator
-            trng: &mut env.trng,
-
-            // LMS Engine
-            lms: &mut env.lms,
+    /// Run the next pending self test, if any.
+    ///
+    /// Only one test runs per call so that mailbox commands arriving
+    /// mid-test are serviced between tests. A failing test does not stop the
+    /// remaining tests from running.
+    pub(crate) fn execute_next(env: &mut Drivers) {
+        let SelfTestStatus::InProgress(mut progress) = env.self_test_status else {
+            return;
+        };
 
-            /// Ecc384 Engine
-            ecc384: &mut env.ecc384,
+        let result = match execute_test(env, progress.next) {
+            Ok(true) => 0,
+            // The test could not run yet; retry on the next call.
+            Ok(false) => return,
+            Err(e) => {
+                caliptra_drivers::report_fw_error_non_fatal(e.into());
+                e.into()
+            }
+        };
+        progress.results[progress.next.0 as usize] = result;
+        progress.next = SelfTestId(progress.next.0 + 1);
 
-            /// SHA Acc Lock State
-            sha_acc_lock_state: ShaAccLockState::NotAcquired,
+        env.self_test_status = if progress.next.0 as usize == SelfTestId::COUNT {
+            caliptra_drivers::report_boot_status(RtFipSelfTestComplete.into());
+            SelfTestStatus::Done(progress.results)
+        } else {
+            SelfTestStatus::InProgress(progress)
         };
+    }
 
-        caliptra_kat::execute_kat(&mut kats_env)?;
-        Ok(())
+    /// Execute a single self test.
+    ///
+    /// Returns `Ok(false)` if the test cannot run yet.
+    fn execute_test(env: &mut Drivers, id: SelfTestId) -> CaliptraResult<bool> {
+        match id {
+            SelfTestId::ROM_INTEGRITY => rom_integrity_test(env)?,
+            SelfTestId::SHA1 => {
+                cprintln!("[kat] sha1");
+                Sha1Kat::default().execute(&mut env.sha1)?;
+            }
+            SelfTestId::SHA256 => {
+                cprintln!("[kat] SHA2-256");
+                Sha256Kat::default().execute(&mut env.sha256)?;
+            }
+            SelfTestId::SHA384 => {
+                cprintln!("[kat] SHA2-384");
+                Sha384Kat::default().execute(&mut env.sha384)?;
+            }
+            SelfTestId::SHA384_ACC => {
+                cprintln!("[kat] SHA2-384-ACC");
+                Sha384AccKat::default()
+                    .execute(&mut env.sha384_acc, ShaAccLockState::NotAcquired)?;
+            }
+            SelfTestId::ECC384 => {
+                cprintln!("[kat] ECC-384");
+                Ecc384Kat::default().execute(&mut env.ecc384, &mut env.trng)?;
+            }
+            SelfTestId::HMAC384 => {
+                cprintln!("[kat] HMAC-384");
+                Hmac384Kat::default().execute(&mut env.hmac384, &mut env.trng)?;
+            }
+            SelfTestId::LMS => {
+                cprintln!("[kat] LMS");
+                LmsKat::default().execute(&mut env.sha256, &env.lms)?;
+            }
+            SelfTestId::IMAGE_VERIFY => {
+                // The image is staged through the mailbox, so wait until the
+                // SoC is not using it.
+                if env.mbox.lock() {
+                    return Ok(false);
+                }
+                let result = copy_and_verify_image(env);
+                env.mbox.unlock();
+                result?;
+            }
+            _ => return Err(CaliptraError::RUNTIME_SELF_TEST_NOT_STARTED),
+        }
+        Ok(true)
+    }
+
+    /// Handle SELF_TEST_GET_RESULTS
+    pub(crate) fn get_results(env: &mut Drivers) -> CaliptraResult<MailboxResp> {
+        match env.self_test_status {
+            SelfTestStatus::Done(results) => {
+                env.self_test_status = SelfTestStatus::Idle;
+                Ok(MailboxResp::SelfTestGetResults(SelfTestGetResultsResp {
+                    hdr: MailboxRespHeader::default(),
+                    results,
+                }))
+            }
+            SelfTestStatus::InProgress(_) => Err(CaliptraError::RUNTIME_SELF_TEST_IN_PROGRESS),
+            SelfTestStatus::Idle => Err(CaliptraError::RUNTIME_SELF_TEST_NOT_STARTED),
+        }
     }
 
     fn rom_integrity_test(env: &mut Drivers) -> CaliptraResult<()> {
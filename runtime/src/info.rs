@@ -1,11 +1,16 @@
 // Licensed under the Apache-2.0 license
 
-use crate::{handoff::RtHandoff, Drivers};
-use caliptra_common::mailbox_api::{
-    FwInfoResp, GetIdevCertReq, GetIdevCertResp, GetIdevInfoResp, MailboxResp, MailboxRespHeader,
+use crate::{handoff::RtHandoff, Drivers, DPE_SUPPORT};
+use caliptra_common::{
+    capabilities::Capabilities,
+    mailbox_api::{
+        FwInfoResp, GetIdevCertReq, GetIdevCertResp, GetIdevCsrResp, GetIdevInfoResp, MailboxResp,
+        MailboxRespHeader, RtCapabilitiesResp,
+    },
 };
 use caliptra_drivers::{CaliptraError, CaliptraResult};
 use caliptra_x509::{Ecdsa384CertBuilder, Ecdsa384Signature};
+use dpe::DPE_PROFILE;
 use zerocopy::FromBytes;
 
 pub struct FwInfoCmd;
@@ -33,6 +38,51 @@ impl FwInfoCmd {
     }
 }
 
+pub struct CapabilitiesCmd;
+impl CapabilitiesCmd {
+    /// Mailbox commands compiled into this Runtime Firmware
+    pub const RT_CAPABILITIES: Capabilities = {
+        let caps = Capabilities::RT_FIRMWARE_LOAD
+            .union(Capabilities::RT_GET_IDEV_CERT)
+            .union(Capabilities::RT_GET_IDEV_CSR)
+            .union(Capabilities::RT_GET_IDEV_INFO)
+            .union(Capabilities::RT_GET_LDEV_CERT)
+            .union(Capabilities::RT_GET_CERT_CHAIN)
+            .union(Capabilities::RT_INVOKE_DPE)
+            .union(Capabilities::RT_ECDSA384_VERIFY)
+            .union(Capabilities::RT_STASH_MEASUREMENT)
+            .union(Capabilities::RT_GET_MEASUREMENT_LOG)
+            .union(Capabilities::RT_QUOTE)
+            .union(Capabilities::RT_DISABLE_ATTESTATION)
+            .union(Capabilities::RT_FW_INFO)
+            .union(Capabilities::RT_CAPABILITIES)
+            .union(Capabilities::RT_VERSION)
+            .union(Capabilities::RT_SHUTDOWN);
+        #[cfg(feature = "fips_self_test")]
+        let caps = caps
+            .union(Capabilities::RT_SELF_TEST_START)
+            .union(Capabilities::RT_SELF_TEST_GET_RESULTS);
+        #[cfg(feature = "test_only_commands")]
+        let caps = caps
+            .union(Capabilities::RT_TEST_ONLY_GET_FMC_ALIAS_CERT)
+            .union(Capabilities::RT_TEST_ONLY_HMAC384_VERIFY);
+        caps
+    };
+
+    pub(crate) fn execute(drivers: &Drivers) -> CaliptraResult<MailboxResp> {
+        let manifest = &drivers.persistent_data.get().manifest1;
+
+        Ok(MailboxResp::RtCapabilities(RtCapabilitiesResp {
+            hdr: MailboxRespHeader::default(),
+            capabilities: Self::RT_CAPABILITIES.to_bytes(),
+            dpe_profile: DPE_PROFILE as u32,
+            dpe_support: DPE_SUPPORT.bits(),
+            fmc_version: manifest.fmc.version,
+            app_version: manifest.runtime.version,
+        }))
+    }
+}
+
 pub struct IDevIdInfoCmd;
 impl IDevIdInfoCmd {
     pub(crate) fn execute(drivers: &Drivers) -> CaliptraResult<MailboxResp> {
@@ -86,3 +136,27 @@ impl IDevIdCertCmd {
         }
     }
 }
+
+pub struct IDevIdCsrCmd;
+impl IDevIdCsrCmd {
+    pub(crate) fn execute(drivers: &Drivers) -> CaliptraResult<MailboxResp> {
+        // ROM only generates the CSR when requested through the manufacturing
+        // service register.
+        let Some(csr) = drivers.persistent_data.get().idevid_csr.get() else {
+            return Err(CaliptraError::RUNTIME_GET_IDEV_CSR_UNPROVISIONED);
+        };
+
+        let mut resp = GetIdevCsrResp {
+            hdr: MailboxRespHeader::default(),
+            data_size: 0,
+            data: [0; GetIdevCsrResp::DATA_MAX_SIZE],
+        };
+        resp.data
+            .get_mut(..csr.len())
+            .ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?
+            .copy_from_slice(csr);
+        resp.data_size = csr.len() as u32;
+
+        Ok(MailboxResp::GetIdevCsr(resp))
+    }
+}
@@ -1,6 +1,8 @@
 // Licensed under the Apache-2.0 license
 
-use crate::{CptraDpeTypes, DpeCrypto, DpeEnv, DpePlatform, Drivers};
+use crate::{
+    CptraDpeTypes, DpeCrypto, DpeEnv, DpePlatform, Drivers, PauserPrivileges, PauserPrivilegesTable,
+};
 use caliptra_common::mailbox_api::{InvokeDpeReq, InvokeDpeResp, MailboxResp, MailboxRespHeader};
 use caliptra_drivers::{CaliptraError, CaliptraResult};
 use crypto::{AlgLen, Crypto};
@@ -16,7 +18,6 @@ use zerocopy::FromBytes;
 
 pub struct InvokeDpeCmd;
 impl InvokeDpeCmd {
-    const PL0_PAUSER_FLAG: u32 = 1;
     pub const PL0_DPE_ACTIVE_CONTEXT_THRESHOLD: usize = 8;
     pub const PL1_DPE_ACTIVE_CONTEXT_THRESHOLD: usize = 16;
 
@@ -38,17 +39,20 @@ impl InvokeDpeCmd {
                 &mut drivers.key_vault,
                 rt_pub_key,
             );
-            let image_header = &pdata.manifest1.header;
-            let pl0_pauser = pdata.manifest1.header.pl0_pauser;
+            let pausers = PauserPrivilegesTable::new(&pdata.manifest1)?;
+            let caller = pausers.privileges(drivers.mbox.user())?;
             let mut env = DpeEnv::<CptraDpeTypes> {
                 crypto,
-                platform: DpePlatform::new(pl0_pauser, hashed_rt_pub_key, &mut drivers.cert_chain),
+                platform: DpePlatform::new(
+                    pausers.pl0_locality(),
+                    hashed_rt_pub_key,
+                    &mut drivers.cert_chain,
+                ),
             };
 
-            let locality = drivers.mbox.user();
+            let locality = caller.locality;
             let command = Command::deserialize(&cmd.data[..cmd.data_size as usize])
                 .map_err(|_| CaliptraError::RUNTIME_INVOKE_DPE_FAILED)?;
-            let flags = pdata.manifest1.header.flags;
 
             let mut dpe = &mut drivers.persistent_data.get_mut().dpe;
             let resp = match command {
@@ -59,18 +63,18 @@ impl InvokeDpeCmd {
                 Command::InitCtx(cmd) => {
                     // InitCtx can only create new contexts if they are simulation contexts.
                     if InitCtxCmd::flag_is_simulation(&cmd) {
-                        Self::pl_context_threshold_exceeded(pl0_pauser, flags, locality, dpe)?;
+                        Self::pl_context_threshold_exceeded(&pausers, caller, dpe)?;
                     }
                     cmd.execute(dpe, &mut env, locality)
                 }
                 Command::DeriveChild(cmd) => {
                     // If retain parent is not set for the DeriveChildCmd, the change in number of contexts is 0.
                     if DeriveChildCmd::retains_parent(&cmd) {
-                        Self::pl_context_threshold_exceeded(pl0_pauser, flags, locality, dpe)?;
+                        Self::pl_context_threshold_exceeded(&pausers, caller, dpe)?;
                     }
                     if DeriveChildCmd::changes_locality(&cmd)
-                        && cmd.target_locality == pl0_pauser
-                        && Self::is_caller_pl1(pl0_pauser, flags, locality)
+                        && pausers.is_pl0_locality(cmd.target_locality)
+                        && caller.is_pl1()
                     {
                         return Err(CaliptraError::RUNTIME_INCORRECT_PAUSER_PRIVILEGE_LEVEL);
                     }
@@ -78,9 +82,7 @@ impl InvokeDpeCmd {
                 }
                 Command::CertifyKey(cmd) => {
                     // PL1 cannot request X509
-                    if cmd.format == CertifyKeyCmd::FORMAT_X509
-                        && Self::is_caller_pl1(pl0_pauser, flags, locality)
-                    {
+                    if cmd.format == CertifyKeyCmd::FORMAT_X509 && caller.is_pl1() {
                         return Err(CaliptraError::RUNTIME_INCORRECT_PAUSER_PRIVILEGE_LEVEL);
                     }
                     cmd.execute(dpe, &mut env, locality)
@@ -117,14 +119,13 @@ impl InvokeDpeCmd {
     }
 
     fn pl_context_threshold_exceeded(
-        pl0_pauser: u32,
-        flags: u32,
-        locality: u32,
+        pausers: &PauserPrivilegesTable,
+        caller: PauserPrivileges,
         dpe: &DpeInstance,
     ) -> CaliptraResult<()> {
         let used_pl0_dpe_context_count = dpe
             .count_contexts(|c: &Context| {
-                c.state != ContextState::Inactive && c.locality == pl0_pauser
+                c.state != ContextState::Inactive && pausers.is_pl0_locality(c.locality)
             })
             .map_err(|_| CaliptraError::RUNTIME_INTERNAL)?;
         // the number of used pl1 dpe contexts is the total number of used contexts
@@ -134,19 +135,13 @@ impl InvokeDpeCmd {
             .count_contexts(|c: &Context| c.state != ContextState::Inactive)
             .map_err(|_| CaliptraError::RUNTIME_INTERNAL)?
             - used_pl0_dpe_context_count;
-        if Self::is_caller_pl1(pl0_pauser, flags, locality)
-            && used_pl1_dpe_context_count == Self::PL1_DPE_ACTIVE_CONTEXT_THRESHOLD
-        {
+        if caller.is_pl1() && used_pl1_dpe_context_count == Self::PL1_DPE_ACTIVE_CONTEXT_THRESHOLD {
             return Err(CaliptraError::RUNTIME_PL1_USED_DPE_CONTEXT_THRESHOLD_EXCEEDED);
-        } else if !Self::is_caller_pl1(pl0_pauser, flags, locality)
+        } else if !caller.is_pl1()
             && used_pl0_dpe_context_count == Self::PL0_DPE_ACTIVE_CONTEXT_THRESHOLD
         {
             return Err(CaliptraError::RUNTIME_PL0_USED_DPE_CONTEXT_THRESHOLD_EXCEEDED);
         }
         Ok(())
     }
-
-    fn is_caller_pl1(pl0_pauser: u32, flags: u32, locality: u32) -> bool {
-        flags & Self::PL0_PAUSER_FLAG == 0 && locality != pl0_pauser
-    }
 }
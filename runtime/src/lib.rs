@@ -10,6 +10,8 @@ pub mod fips;
 pub mod handoff;
 pub mod info;
 mod invoke_dpe;
+mod pauser_privileges;
+mod quote;
 mod stash_measurement;
 mod update;
 mod verify;
@@ -21,7 +23,8 @@ use mailbox::Mailbox;
 
 pub use caliptra_common::fips::FipsVersionCmd;
 #[cfg(feature = "test_only_commands")]
-pub use dice::{GetLdevCertCmd, TestGetFmcAliasCertCmd};
+pub use dice::TestGetFmcAliasCertCmd;
+pub use dice::{GetCertChainCmd, GetLdevCertCmd};
 pub use disable::DisableAttestationCmd;
 use dpe_crypto::DpeCrypto;
 pub use dpe_platform::{DpePlatform, VENDOR_ID, VENDOR_SKU};
@@ -29,9 +32,11 @@ pub use fips::FipsShutdownCmd;
 #[cfg(feature = "fips_self_test")]
 pub use fips::{fips_self_test_cmd, fips_self_test_cmd::SelfTestStatus};
 
-pub use info::{FwInfoCmd, IDevIdCertCmd, IDevIdInfoCmd};
+pub use info::{CapabilitiesCmd, FwInfoCmd, IDevIdCertCmd, IDevIdCsrCmd, IDevIdInfoCmd};
 pub use invoke_dpe::InvokeDpeCmd;
-pub use stash_measurement::StashMeasurementCmd;
+pub use pauser_privileges::{PauserPrivilegeLevel, PauserPrivileges, PauserPrivilegesTable};
+pub use quote::QuoteCmd;
+pub use stash_measurement::{GetMeasurementLogCmd, StashMeasurementCmd};
 pub use verify::EcdsaVerifyCmd;
 pub mod packet;
 use caliptra_common::mailbox_api::CommandId;
@@ -41,6 +46,7 @@ use caliptra_common::cprintln;
 #[cfg(feature = "fips_self_test")]
 use caliptra_common::mailbox_api::MailboxResp;
 
+use caliptra_cpu::{IntSource, Pic};
 use caliptra_drivers::{CaliptraError, CaliptraResult, ResetReason};
 use caliptra_registers::mbox::enums::MboxStatusE;
 use dpe::{
@@ -86,20 +92,20 @@ impl DpeTypes for CptraDpeTypes {
 fn enter_idle(drivers: &mut Drivers) {
     // Run pending jobs before entering low power mode.
     #[cfg(feature = "fips_self_test")]
-    if let SelfTestStatus::InProgress(execute) = drivers.self_test_status {
-        if drivers.mbox.lock() == false {
-            match execute(drivers) {
-                Ok(_) => drivers.self_test_status = SelfTestStatus::Done,
-                Err(e) => caliptra_drivers::report_fw_error_non_fatal(e.into()),
-            }
-        }
+    if let SelfTestStatus::InProgress(_) = drivers.self_test_status {
+        // Run one test at a time so that incoming mailbox commands are
+        // serviced between tests. Don't sleep while tests are outstanding.
+        fips_self_test_cmd::execute_next(drivers);
+        return;
     }
 
-    // TODO: Enable interrupts?
-    //#[cfg(feature = "riscv")]
-    //unsafe {
-    //core::arch::asm!("wfi");
-    //}
+    // Sleep until the SoC sends a mailbox command. The notification is
+    // sticky, so a command that arrived while we were busy leaves it pending
+    // and wfi returns immediately.
+    if !drivers.mbox.is_cmd_ready() && !drivers.is_shutdown {
+        caliptra_cpu::wait_for_interrupt();
+    }
+    drivers.soc_ifc.clear_mbox_notif_status();
 }
 
 /// Handles the pending mailbox command and writes the repsonse back to the mailbox
@@ -129,16 +135,18 @@ fn handle_command(drivers: &mut Drivers) -> CaliptraResult<MboxStatusE> {
     let mut resp = match CommandId::from(req_packet.cmd) {
         CommandId::FIRMWARE_LOAD => Err(CaliptraError::RUNTIME_UNIMPLEMENTED_COMMAND),
         CommandId::GET_IDEV_CERT => IDevIdCertCmd::execute(cmd_bytes),
-        CommandId::GET_IDEV_CSR => Err(CaliptraError::RUNTIME_UNIMPLEMENTED_COMMAND),
+        CommandId::GET_IDEV_CSR => IDevIdCsrCmd::execute(drivers),
         CommandId::GET_IDEV_INFO => IDevIdInfoCmd::execute(drivers),
-        CommandId::GET_LDEV_CERT => Err(CaliptraError::RUNTIME_UNIMPLEMENTED_COMMAND),
+        CommandId::GET_LDEV_CERT => GetLdevCertCmd::execute(drivers),
+        CommandId::GET_CERT_CHAIN => GetCertChainCmd::execute(drivers, cmd_bytes),
         CommandId::INVOKE_DPE => InvokeDpeCmd::execute(drivers, cmd_bytes),
         CommandId::ECDSA384_VERIFY => EcdsaVerifyCmd::execute(drivers, cmd_bytes),
         CommandId::STASH_MEASUREMENT => StashMeasurementCmd::execute(drivers, cmd_bytes),
+        CommandId::GET_MEASUREMENT_LOG => GetMeasurementLogCmd::execute(drivers, cmd_bytes),
+        CommandId::QUOTE => QuoteCmd::execute(drivers, cmd_bytes),
         CommandId::DISABLE_ATTESTATION => DisableAttestationCmd::execute(drivers),
         CommandId::FW_INFO => FwInfoCmd::execute(drivers),
-        #[cfg(feature = "test_only_commands")]
-        CommandId::TEST_ONLY_GET_LDEV_CERT => GetLdevCertCmd::execute(drivers),
+        CommandId::CAPABILITIES => CapabilitiesCmd::execute(drivers),
         #[cfg(feature = "test_only_commands")]
         CommandId::TEST_ONLY_GET_FMC_ALIAS_CERT => TestGetFmcAliasCertCmd::execute(drivers),
         #[cfg(feature = "test_only_commands")]
@@ -147,19 +155,13 @@ fn handle_command(drivers: &mut Drivers) -> CaliptraResult<MboxStatusE> {
         #[cfg(feature = "fips_self_test")]
         CommandId::SELF_TEST_START => match drivers.self_test_status {
             SelfTestStatus::Idle => {
-                drivers.self_test_status = SelfTestStatus::InProgress(fips_self_test_cmd::execute);
+                fips_self_test_cmd::start(drivers);
                 Ok(MailboxResp::default())
             }
             _ => Err(CaliptraError::RUNTIME_SELF_TEST_IN_PROGRESS),
         },
         #[cfg(feature = "fips_self_test")]
-        CommandId::SELF_TEST_GET_RESULTS => match drivers.self_test_status {
-            SelfTestStatus::Done => {
-                drivers.self_test_status = SelfTestStatus::Idle;
-                Ok(MailboxResp::default())
-            }
-            _ => Err(CaliptraError::RUNTIME_SELF_TEST_NOT_STARTED),
-        },
+        CommandId::SELF_TEST_GET_RESULTS => fips_self_test_cmd::get_results(drivers),
         CommandId::SHUTDOWN => FipsShutdownCmd::execute(drivers),
         _ => Err(CaliptraError::RUNTIME_UNIMPLEMENTED_COMMAND),
     }?;
@@ -188,6 +190,11 @@ pub fn handle_mailbox_commands(drivers: &mut Drivers) -> CaliptraResult<()> {
             }
         }
     }
+
+    // Wake from low power mode when the SoC sends a mailbox command.
+    drivers.soc_ifc.enable_mbox_notif_interrupts();
+    Pic::enable_interrupt(IntSource::SocIfcNotif, 1);
+
     loop {
         enter_idle(drivers);
         if drivers.is_shutdown {
@@ -0,0 +1,126 @@
+// Licensed under the Apache-2.0 license
+
+use caliptra_drivers::{CaliptraError, CaliptraResult};
+use caliptra_image_types::{ImageManifest, ImagePauserPrivileges, ImagePauserPrivilegesTable};
+
+/// Privilege level of a mailbox caller
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauserPrivilegeLevel {
+    Pl0,
+    Pl1,
+}
+
+/// DPE locality and privilege level of a mailbox caller, as defined by the
+/// image manifest.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PauserPrivileges {
+    pub locality: u32,
+    pub level: PauserPrivilegeLevel,
+}
+
+impl PauserPrivileges {
+    pub fn is_pl1(&self) -> bool {
+        self.level == PauserPrivilegeLevel::Pl1
+    }
+
+    fn from_entry(entry: &ImagePauserPrivileges) -> Self {
+        Self {
+            locality: entry.locality,
+            level: if entry.is_pl0() {
+                PauserPrivilegeLevel::Pl0
+            } else {
+                PauserPrivilegeLevel::Pl1
+            },
+        }
+    }
+}
+
+/// PAUSER privileges granted by the image manifest and the PAUSER privilege
+/// table at the end of the runtime image.
+#[derive(Clone, Copy)]
+pub struct PauserPrivilegesTable {
+    flags: u32,
+    pl0_pauser: u32,
+    entries: &'static [ImagePauserPrivileges],
+}
+
+impl PauserPrivilegesTable {
+    const PL0_PAUSER_FLAG: u32 = 1 << 0;
+    const PAUSER_PRIVILEGES_FLAG: u32 = 1 << 1;
+
+    /// Read the PAUSER privileges of the runtime image loaded from `manifest`.
+    pub fn new(manifest: &ImageManifest) -> CaliptraResult<Self> {
+        let header = &manifest.header;
+        let mut table = Self {
+            flags: header.flags,
+            pl0_pauser: header.pl0_pauser,
+            entries: &[],
+        };
+        if header.flags & Self::PAUSER_PRIVILEGES_FLAG == 0 {
+            return Ok(table);
+        }
+
+        let runtime = &manifest.runtime;
+        let table_size = core::mem::size_of::<ImagePauserPrivilegesTable>() as u32;
+        if runtime.size < table_size || runtime.size % 4 != 0 {
+            return Err(CaliptraError::RUNTIME_PAUSER_PRIVILEGES_TABLE_INVALID);
+        }
+        // SAFETY: ROM loaded the verified runtime image to ICCM at
+        // `load_addr`, and ICCM is not written again until the next reset.
+        // Both `load_addr` and `size` are word aligned.
+        let image_table = unsafe {
+            &*((runtime.load_addr + runtime.size - table_size) as *const ImagePauserPrivilegesTable)
+        };
+        table.entries = image_table
+            .entries()
+            .ok_or(CaliptraError::RUNTIME_PAUSER_PRIVILEGES_TABLE_INVALID)?;
+        Ok(table)
+    }
+
+    /// Look up the privileges of the caller with `pauser`.
+    ///
+    /// If the image has a PAUSER privilege table, callers that are not in
+    /// the table are rejected. Otherwise the locality is the PAUSER itself and
+    /// only the manifest's `pl0_pauser` is PL0.
+    pub fn privileges(&self, pauser: u32) -> CaliptraResult<PauserPrivileges> {
+        if self.entries.is_empty() {
+            return Ok(PauserPrivileges {
+                locality: pauser,
+                level: if self.is_legacy_pl0(pauser) {
+                    PauserPrivilegeLevel::Pl0
+                } else {
+                    PauserPrivilegeLevel::Pl1
+                },
+            });
+        }
+
+        self.entries
+            .iter()
+            .find(|entry| entry.pauser == pauser)
+            .map(PauserPrivileges::from_entry)
+            .ok_or(CaliptraError::RUNTIME_UNAUTHORIZED_PAUSER)
+    }
+
+    /// Locality of the PL0 caller. The DPE default context is created in
+    /// this locality.
+    pub fn pl0_locality(&self) -> u32 {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_pl0())
+            .map_or(self.pl0_pauser, |entry| entry.locality)
+    }
+
+    /// Returns true if contexts in `locality` belong to PL0
+    pub fn is_pl0_locality(&self, locality: u32) -> bool {
+        if self.entries.is_empty() {
+            return self.is_legacy_pl0(locality);
+        }
+        self.entries
+            .iter()
+            .any(|entry| entry.is_pl0() && entry.locality == locality)
+    }
+
+    fn is_legacy_pl0(&self, pauser: u32) -> bool {
+        self.flags & Self::PL0_PAUSER_FLAG != 0 && pauser == self.pl0_pauser
+    }
+}
@@ -0,0 +1,40 @@
+// Licensed under the Apache-2.0 license
+
+use crate::Drivers;
+use caliptra_common::keyids::KEY_ID_RT_PRIV_KEY;
+use caliptra_common::mailbox_api::{MailboxResp, QuoteReq, QuoteResp};
+use caliptra_drivers::{CaliptraError, CaliptraResult, Ecc384PrivKeyIn, KeyReadArgs, PcrId};
+use zerocopy::FromBytes;
+
+pub struct QuoteCmd;
+impl QuoteCmd {
+    pub(crate) fn execute(drivers: &mut Drivers, cmd_args: &[u8]) -> CaliptraResult<MailboxResp> {
+        let cmd =
+            QuoteReq::read_from(cmd_args).ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?;
+
+        let mut resp = QuoteResp {
+            nonce: cmd.nonce,
+            pcr_selection: cmd.pcr_selection,
+            ..Default::default()
+        };
+        for (i, pcr) in resp.pcrs.iter_mut().enumerate() {
+            if cmd.pcr_selection & (1 << i) != 0 {
+                let id = PcrId::try_from(i as u8)
+                    .map_err(|_| CaliptraError::RUNTIME_MAILBOX_INVALID_PARAMS)?;
+                *pcr = drivers.pcr_bank.read_pcr(id).into();
+            }
+        }
+
+        let digest = drivers.sha384.digest(resp.signed_data())?;
+        let sig = drivers.ecc384.sign(
+            &Ecc384PrivKeyIn::Key(KeyReadArgs::new(KEY_ID_RT_PRIV_KEY)),
+            &drivers.persistent_data.get().fht.rt_dice_pub_key,
+            &digest,
+            &mut drivers.trng,
+        )?;
+        resp.signature_r = sig.r.into();
+        resp.signature_s = sig.s.into();
+
+        Ok(MailboxResp::Quote(resp))
+    }
+}
@@ -1,10 +1,16 @@
 // Licensed under the Apache-2.0 license
 
-use crate::{dpe_crypto::DpeCrypto, CptraDpeTypes, DpePlatform, Drivers};
+use crate::{dpe_crypto::DpeCrypto, CptraDpeTypes, DpePlatform, Drivers, PauserPrivilegesTable};
+use caliptra_common::keyids::KEY_ID_RT_PRIV_KEY;
 use caliptra_common::mailbox_api::{
-    MailboxResp, MailboxRespHeader, StashMeasurementReq, StashMeasurementResp,
+    GetMeasurementLogReq, GetMeasurementLogResp, MailboxResp, MailboxRespHeader,
+    RtMeasurementLogEntry, StashMeasurementReq, StashMeasurementResp,
 };
-use caliptra_drivers::{CaliptraError, CaliptraResult};
+use caliptra_drivers::{
+    pcr_log, CaliptraError, CaliptraResult, Ecc384PrivKeyIn, KeyReadArgs, MEASUREMENT_MAX_COUNT,
+    RT_MEASUREMENT_MAX_COUNT,
+};
+use core::mem::size_of;
 use crypto::{AlgLen, Crypto};
 use dpe::{
     commands::{CommandExecution, DeriveChildCmd, DeriveChildFlags},
@@ -12,12 +18,25 @@ use dpe::{
     dpe_instance::DpeEnv,
     response::DpeErrorCode,
 };
-use zerocopy::FromBytes;
+use zerocopy::{AsBytes, FromBytes};
 
 pub struct StashMeasurementCmd;
 impl StashMeasurementCmd {
     pub(crate) fn execute(drivers: &mut Drivers, cmd_args: &[u8]) -> CaliptraResult<MailboxResp> {
         if let Some(cmd) = StashMeasurementReq::read_from(cmd_args) {
+            // Refuse measurements that could not be logged, so that the log
+            // always accounts for every measurement folded into DPE.
+            if drivers
+                .persistent_data
+                .get()
+                .rt_measurement_log
+                .entries()
+                .len()
+                >= RT_MEASUREMENT_MAX_COUNT
+            {
+                return Err(CaliptraError::RUNTIME_MEASUREMENT_LOG_FULL);
+            }
+
             let hashed_rt_pub_key = drivers.compute_rt_alias_sn()?;
             let pdata = drivers.persistent_data.get();
             let rt_pub_key = pdata.fht.rt_dice_pub_key;
@@ -29,16 +48,18 @@ impl StashMeasurementCmd {
                 &mut drivers.key_vault,
                 rt_pub_key,
             );
+            let pausers = PauserPrivilegesTable::new(&pdata.manifest1)?;
+            let pauser = drivers.mbox.user();
+            let locality = pausers.privileges(pauser)?.locality;
             let mut env = DpeEnv::<CptraDpeTypes> {
                 crypto,
                 platform: DpePlatform::new(
-                    pdata.manifest1.header.pl0_pauser,
+                    pausers.pl0_locality(),
                     hashed_rt_pub_key,
                     &mut drivers.cert_chain,
                 ),
             };
 
-            let locality = drivers.mbox.user();
             let derive_child_resp = DeriveChildCmd {
                 handle: ContextHandle::default(),
                 data: cmd.measurement,
@@ -56,7 +77,21 @@ impl StashMeasurementCmd {
             );
 
             let dpe_result = match derive_child_resp {
-                Ok(_) => DpeErrorCode::NoError,
+                Ok(_) => {
+                    let mut entry = pcr_log::RtMeasurementLogEntry::new_zeroed();
+                    entry.pauser = pauser;
+                    entry.metadata = cmd.metadata;
+                    entry.svn = cmd.svn;
+                    entry.measurement = cmd.measurement;
+                    entry.context = cmd.context;
+                    drivers
+                        .persistent_data
+                        .get_mut()
+                        .rt_measurement_log
+                        .push(entry)
+                        .ok_or(CaliptraError::RUNTIME_MEASUREMENT_LOG_FULL)?;
+                    DpeErrorCode::NoError
+                }
                 Err(e) => e,
             } as u32;
 
@@ -69,3 +104,81 @@ impl StashMeasurementCmd {
         }
     }
 }
+
+// GET_MEASUREMENT_LOG must be able to return both measurement logs in full.
+const _: () = assert!(
+    GetMeasurementLogResp::MAX_ENTRY_COUNT == MEASUREMENT_MAX_COUNT + RT_MEASUREMENT_MAX_COUNT
+);
+
+pub struct GetMeasurementLogCmd;
+impl GetMeasurementLogCmd {
+    pub(crate) fn execute(drivers: &mut Drivers, cmd_args: &[u8]) -> CaliptraResult<MailboxResp> {
+        let cmd = GetMeasurementLogReq::read_from(cmd_args)
+            .ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?;
+        let pdata = drivers.persistent_data.get();
+
+        // Measurements stashed with ROM come first, followed by the
+        // measurements stashed with runtime.
+        let rom_log = pdata
+            .measurement_log
+            .get(..pdata.fht.meas_log_index as usize)
+            .ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?;
+        let rt_log = pdata.rt_measurement_log.entries();
+
+        let entry_count = rom_log.len() + rt_log.len();
+        if entry_count > GetMeasurementLogResp::MAX_ENTRY_COUNT {
+            return Err(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY);
+        }
+
+        let rom_entries = rom_log
+            .iter()
+            .enumerate()
+            .map(|(i, rom_entry)| RtMeasurementLogEntry {
+                counter: i as u32,
+                pauser: 0,
+                metadata: rom_entry.metadata,
+                svn: rom_entry.svn,
+                measurement: zerocopy::transmute!(rom_entry.pcr_entry.pcr_data),
+                context: zerocopy::transmute!(rom_entry.context),
+            });
+
+        let mut resp = GetMeasurementLogResp {
+            nonce: cmd.nonce,
+            data_size: (entry_count * size_of::<RtMeasurementLogEntry>()) as u32,
+            ..Default::default()
+        };
+        for (dst, entry) in resp
+            .data
+            .chunks_exact_mut(size_of::<RtMeasurementLogEntry>())
+            .zip(rom_entries.chain(rt_log.iter().map(Self::log_entry)))
+        {
+            dst.copy_from_slice(entry.as_bytes());
+        }
+
+        let signed_data = resp
+            .signed_data()
+            .ok_or(CaliptraError::RUNTIME_INSUFFICIENT_MEMORY)?;
+        let digest = drivers.sha384.digest(signed_data)?;
+        let sig = drivers.ecc384.sign(
+            &Ecc384PrivKeyIn::Key(KeyReadArgs::new(KEY_ID_RT_PRIV_KEY)),
+            &pdata.fht.rt_dice_pub_key,
+            &digest,
+            &mut drivers.trng,
+        )?;
+        resp.signature_r = sig.r.into();
+        resp.signature_s = sig.s.into();
+
+        Ok(MailboxResp::GetMeasurementLog(resp))
+    }
+
+    fn log_entry(entry: &pcr_log::RtMeasurementLogEntry) -> RtMeasurementLogEntry {
+        RtMeasurementLogEntry {
+            counter: entry.counter,
+            pauser: entry.pauser,
+            metadata: entry.metadata,
+            svn: entry.svn,
+            measurement: entry.measurement,
+            context: entry.context,
+        }
+    }
+}
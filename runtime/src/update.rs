@@ -1,9 +1,51 @@
 // Licensed under the Apache-2.0 license
 
 use crate::Drivers;
-use caliptra_drivers::{CaliptraError, CaliptraResult};
+use caliptra_common::{cprintln, verifier::FirmwareImageVerificationEnv};
+use caliptra_drivers::{CaliptraError, CaliptraResult, ResetReason};
+use caliptra_image_types::ImageManifest;
+use caliptra_image_verify::ImageVerifier;
+use zerocopy::LayoutVerified;
+
+/// Verify the image staged in the mailbox before committing to an update reset
+///
+/// Performs the same checks ROM performs during the update reset flow, so a
+/// bad image is rejected while the current firmware is still running.
+fn verify_staged_image(drivers: &mut Drivers) -> CaliptraResult<()> {
+    let img_bundle_sz = drivers.mbox.dlen();
+    let image = drivers
+        .mbox
+        .raw_mailbox_contents()
+        .get(..img_bundle_sz as usize)
+        .ok_or(CaliptraError::RUNTIME_UPDATE_INVALID_IMAGE_SIZE)?;
+
+    let (manifest, _) = LayoutVerified::<_, ImageManifest>::new_from_prefix(image)
+        .ok_or(CaliptraError::RUNTIME_UPDATE_MANIFEST_READ_FAILURE)?;
+
+    let mut venv = FirmwareImageVerificationEnv {
+        sha256: &mut drivers.sha256,
+        sha384: &mut drivers.sha384,
+        soc_ifc: &mut drivers.soc_ifc,
+        ecc384: &mut drivers.ecc384,
+        data_vault: &mut drivers.data_vault,
+        pcr_bank: &mut drivers.pcr_bank,
+        image,
+    };
+
+    let mut verifier = ImageVerifier::new(&mut venv);
+    let info = verifier.verify(&manifest, img_bundle_sz, ResetReason::UpdateReset)?;
+
+    cprintln!(
+        "[rt] Update image verified using Vendor ECC Key Index {}",
+        info.vendor_ecc_pub_key_idx
+    );
+
+    Ok(())
+}
 
 pub(crate) fn handle_impactless_update(drivers: &mut Drivers) -> CaliptraResult<()> {
+    verify_staged_image(drivers)?;
+
     let cycles = drivers.soc_ifc.internal_fw_update_reset_wait_cycles();
     for _ in 0..cycles {
         drivers.soc_ifc.assert_fw_update_reset();
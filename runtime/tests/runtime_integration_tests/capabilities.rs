@@ -0,0 +1,136 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::run_rt_test;
+use caliptra_builder::{
+    firmware::{
+        APP_WITH_UART, APP_WITH_UART_FIPS_SELF_TEST, APP_WITH_UART_NO_OPTIONAL_CMDS,
+        APP_WITH_UART_TEST_ONLY_CMDS,
+    },
+    FwId,
+};
+use caliptra_common::{
+    capabilities::Capabilities,
+    mailbox_api::{CommandId, MailboxReqHeader, MailboxRespHeader, RtCapabilitiesResp},
+};
+use caliptra_drivers::CaliptraError;
+use caliptra_hw_model::{DefaultHwModel, HwModel, ModelError};
+use caliptra_runtime::{RtBootStatus, DPE_SUPPORT};
+use dpe::DPE_PROFILE;
+use zerocopy::{AsBytes, FromBytes};
+
+const BASE_CAPABILITIES: Capabilities = Capabilities::RT_FIRMWARE_LOAD
+    .union(Capabilities::RT_GET_IDEV_CERT)
+    .union(Capabilities::RT_GET_IDEV_CSR)
+    .union(Capabilities::RT_GET_IDEV_INFO)
+    .union(Capabilities::RT_GET_LDEV_CERT)
+    .union(Capabilities::RT_GET_CERT_CHAIN)
+    .union(Capabilities::RT_INVOKE_DPE)
+    .union(Capabilities::RT_ECDSA384_VERIFY)
+    .union(Capabilities::RT_STASH_MEASUREMENT)
+    .union(Capabilities::RT_GET_MEASUREMENT_LOG)
+    .union(Capabilities::RT_QUOTE)
+    .union(Capabilities::RT_DISABLE_ATTESTATION)
+    .union(Capabilities::RT_FW_INFO)
+    .union(Capabilities::RT_CAPABILITIES)
+    .union(Capabilities::RT_VERSION)
+    .union(Capabilities::RT_SHUTDOWN);
+
+const FIPS_SELF_TEST_CAPABILITIES: Capabilities =
+    Capabilities::RT_SELF_TEST_START.union(Capabilities::RT_SELF_TEST_GET_RESULTS);
+
+const TEST_ONLY_CAPABILITIES: Capabilities =
+    Capabilities::RT_TEST_ONLY_GET_FMC_ALIAS_CERT.union(Capabilities::RT_TEST_ONLY_HMAC384_VERIFY);
+
+fn get_capabilities(model: &mut DefaultHwModel) -> RtCapabilitiesResp {
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::CAPABILITIES), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::CAPABILITIES), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let caps_resp = RtCapabilitiesResp::read_from(resp.as_slice()).unwrap();
+
+    // Verify checksum and FIPS status
+    assert!(caliptra_common::checksum::verify_checksum(
+        caps_resp.hdr.chksum,
+        0x0,
+        &caps_resp.as_bytes()[core::mem::size_of_val(&caps_resp.hdr.chksum)..],
+    ));
+    assert_eq!(
+        caps_resp.hdr.fips_status,
+        MailboxRespHeader::FIPS_STATUS_APPROVED
+    );
+    caps_resp
+}
+
+fn check_capabilities(fwid: &'static FwId, expected: Capabilities) {
+    let mut model = run_rt_test(Some(fwid), None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    let caps_resp = get_capabilities(&mut model);
+    let caps = Capabilities::try_from(caps_resp.capabilities.as_bytes()).unwrap();
+    assert_eq!(caps, expected);
+    assert!(!caps.contains(Capabilities::ROM_BASE));
+
+    assert_eq!(caps_resp.dpe_profile, DPE_PROFILE as u32);
+    assert_eq!(caps_resp.dpe_support, DPE_SUPPORT.bits());
+    assert_eq!(caps_resp.fmc_version, 0xaaaaaaaa);
+    assert_eq!(caps_resp.app_version, 0xbbbbbbbb);
+
+    // Commands that are not advertised must not be implemented.
+    for cmd in [
+        CommandId::SELF_TEST_START,
+        CommandId::SELF_TEST_GET_RESULTS,
+        CommandId::TEST_ONLY_GET_FMC_ALIAS_CERT,
+        CommandId::TEST_ONLY_HMAC384_VERIFY,
+    ] {
+        let cmd = u32::from(cmd);
+        if caps.supports_rt_command(CommandId::from(cmd)) {
+            continue;
+        }
+        let payload = MailboxReqHeader {
+            chksum: caliptra_common::checksum::calc_checksum(cmd, &[]),
+        };
+        assert_eq!(
+            model.mailbox_execute(cmd, payload.as_bytes()),
+            Err(ModelError::MailboxCmdFailed(
+                CaliptraError::RUNTIME_UNIMPLEMENTED_COMMAND.into()
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_capabilities_no_optional_cmds() {
+    check_capabilities(&APP_WITH_UART_NO_OPTIONAL_CMDS, BASE_CAPABILITIES);
+}
+
+#[test]
+fn test_capabilities_test_only_cmds() {
+    check_capabilities(
+        &APP_WITH_UART_TEST_ONLY_CMDS,
+        BASE_CAPABILITIES.union(TEST_ONLY_CAPABILITIES),
+    );
+}
+
+#[test]
+fn test_capabilities_fips_self_test() {
+    check_capabilities(
+        &APP_WITH_UART_FIPS_SELF_TEST,
+        BASE_CAPABILITIES.union(FIPS_SELF_TEST_CAPABILITIES),
+    );
+}
+
+#[test]
+fn test_capabilities_all_cmds() {
+    check_capabilities(
+        &APP_WITH_UART,
+        BASE_CAPABILITIES
+            .union(FIPS_SELF_TEST_CAPABILITIES)
+            .union(TEST_ONLY_CAPABILITIES),
+    );
+}
@@ -4,7 +4,12 @@ use caliptra_builder::{
     firmware::{APP_WITH_UART, FMC_WITH_UART, ROM_WITH_UART},
     FwId, ImageOptions,
 };
+use caliptra_common::mailbox_api::{
+    CommandId, GetCertChainReq, GetCertChainResp, MailboxReqHeader,
+};
 use caliptra_hw_model::{BootParams, DefaultHwModel, HwModel, InitParams};
+use openssl::x509::X509;
+use zerocopy::{AsBytes, FromBytes};
 
 // Run a test which boots ROM -> FMC -> test_bin. If test_bin_name is None,
 // run the production runtime image.
@@ -46,3 +51,38 @@ pub fn run_rt_test(
 
     model
 }
+
+// Fetch the RT Alias certificate, the last certificate in the chain returned
+// by GET_CERT_CHAIN.
+pub fn get_rt_alias_cert(model: &mut DefaultHwModel) -> X509 {
+    let mut cert_chain = vec![];
+    loop {
+        let mut cmd = GetCertChainReq {
+            hdr: MailboxReqHeader { chksum: 0 },
+            offset: cert_chain.len() as u32,
+            size: GetCertChainResp::DATA_MAX_SIZE as u32,
+        };
+        cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+            u32::from(CommandId::GET_CERT_CHAIN),
+            &cmd.as_bytes()[4..],
+        );
+        let resp = model
+            .mailbox_execute(u32::from(CommandId::GET_CERT_CHAIN), cmd.as_bytes())
+            .unwrap()
+            .unwrap();
+        let resp = GetCertChainResp::read_from(resp.as_slice()).unwrap();
+        cert_chain.extend_from_slice(&resp.data[..resp.data_size as usize]);
+        if cert_chain.len() == resp.total_size as usize {
+            break;
+        }
+    }
+
+    let mut remaining = &cert_chain[..];
+    loop {
+        let cert = X509::from_der(remaining).unwrap();
+        remaining = &remaining[cert.to_der().unwrap().len()..];
+        if remaining.is_empty() {
+            return cert;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::run_rt_test;
+use caliptra_builder::firmware::APP_WITH_UART_FIPS_SELF_TEST;
+use caliptra_common::mailbox_api::{
+    CommandId, FwInfoResp, MailboxReqHeader, MailboxRespHeader, SelfTestGetResultsResp, SelfTestId,
+};
+use caliptra_drivers::CaliptraError;
+use caliptra_hw_model::{DefaultHwModel, HwModel, ModelError};
+use caliptra_runtime::RtBootStatus;
+use zerocopy::{AsBytes, FromBytes};
+
+fn execute_no_args(model: &mut DefaultHwModel, cmd: CommandId) -> Result<Vec<u8>, ModelError> {
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(cmd), &[]),
+    };
+    model
+        .mailbox_execute(u32::from(cmd), payload.as_bytes())
+        .map(|resp| resp.unwrap_or_default())
+}
+
+#[test]
+fn test_self_test_yields_to_mailbox() {
+    let mut model = run_rt_test(Some(&APP_WITH_UART_FIPS_SELF_TEST), None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    assert_eq!(
+        execute_no_args(&mut model, CommandId::SELF_TEST_GET_RESULTS),
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_SELF_TEST_NOT_STARTED.into()
+        ))
+    );
+
+    let resp = execute_no_args(&mut model, CommandId::SELF_TEST_START).unwrap();
+    let resp = MailboxRespHeader::read_from(resp.as_slice()).unwrap();
+    assert_eq!(resp.fips_status, MailboxRespHeader::FIPS_STATUS_APPROVED);
+
+    // Other commands are serviced while the self test is running.
+    let resp = execute_no_args(&mut model, CommandId::FW_INFO).unwrap();
+    FwInfoResp::read_from(resp.as_slice()).unwrap();
+    assert_eq!(
+        execute_no_args(&mut model, CommandId::SELF_TEST_GET_RESULTS),
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_SELF_TEST_IN_PROGRESS.into()
+        ))
+    );
+    assert_eq!(
+        execute_no_args(&mut model, CommandId::SELF_TEST_START),
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_SELF_TEST_IN_PROGRESS.into()
+        ))
+    );
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtFipSelfTestComplete)
+    });
+
+    let resp = execute_no_args(&mut model, CommandId::SELF_TEST_GET_RESULTS).unwrap();
+    let resp = SelfTestGetResultsResp::read_from(resp.as_slice()).unwrap();
+    assert!(caliptra_common::checksum::verify_checksum(
+        resp.hdr.chksum,
+        0x0,
+        &resp.as_bytes()[core::mem::size_of_val(&resp.hdr.chksum)..],
+    ));
+    assert_eq!(
+        resp.hdr.fips_status,
+        MailboxRespHeader::FIPS_STATUS_APPROVED
+    );
+    for id in [
+        SelfTestId::ROM_INTEGRITY,
+        SelfTestId::SHA1,
+        SelfTestId::SHA256,
+        SelfTestId::SHA384,
+        SelfTestId::SHA384_ACC,
+        SelfTestId::ECC384,
+        SelfTestId::HMAC384,
+        SelfTestId::LMS,
+        SelfTestId::IMAGE_VERIFY,
+    ] {
+        assert_eq!(resp.result(id), 0, "{id:?}");
+    }
+
+    // Results are only reported once.
+    assert_eq!(
+        execute_no_args(&mut model, CommandId::SELF_TEST_GET_RESULTS),
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_SELF_TEST_NOT_STARTED.into()
+        ))
+    );
+}
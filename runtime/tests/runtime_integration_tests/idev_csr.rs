@@ -0,0 +1,116 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::run_rt_test;
+use caliptra_builder::{
+    firmware::{APP_WITH_UART, FMC_WITH_UART, ROM_WITH_UART},
+    ImageOptions,
+};
+use caliptra_common::mailbox_api::{
+    CommandId, GetIdevCsrResp, GetIdevInfoResp, MailboxReqHeader, MailboxRespHeader,
+};
+use caliptra_drivers::{CaliptraError, MfgFlags};
+use caliptra_hw_model::{BootParams, HwModel, InitParams, ModelError};
+use caliptra_runtime::RtBootStatus;
+use openssl::{bn::BigNumContext, ec::PointConversionForm, x509::X509Req};
+use zerocopy::{AsBytes, FromBytes};
+
+#[test]
+fn test_get_idev_csr() {
+    let rom = caliptra_builder::build_firmware_rom(&ROM_WITH_UART).unwrap();
+    let mut model = caliptra_hw_model::new(BootParams {
+        init_params: InitParams {
+            rom: &rom,
+            ..Default::default()
+        },
+        initial_dbg_manuf_service_reg: MfgFlags::GENERATE_IDEVID_CSR.bits(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    // Download the CSR that ROM uploads to the mailbox in the manufacturing flow.
+    model.step_until(|m| m.soc_ifc().cptra_flow_status().read().idevid_csr_ready());
+    let mut txn = model.wait_for_mailbox_receive().unwrap();
+    let rom_csr = std::mem::take(&mut txn.req.data);
+    txn.respond_success();
+    model.soc_ifc().cptra_dbg_manuf_service_reg().write(|_| 0);
+
+    let mut opts = ImageOptions::default();
+    opts.vendor_config.pl0_pauser = Some(0x1);
+    let image = caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, opts)
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+    model.step_until(|m| m.soc_ifc().cptra_flow_status().read().ready_for_fw());
+    model.upload_firmware(&image).unwrap();
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_IDEV_CSR), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::GET_IDEV_CSR), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let csr_resp = GetIdevCsrResp::read_from(resp.as_slice()).unwrap();
+    assert!(caliptra_common::checksum::verify_checksum(
+        csr_resp.hdr.chksum,
+        0x0,
+        &csr_resp.as_bytes()[core::mem::size_of_val(&csr_resp.hdr.chksum)..],
+    ));
+    assert_eq!(
+        csr_resp.hdr.fips_status,
+        MailboxRespHeader::FIPS_STATUS_APPROVED
+    );
+
+    // The runtime must serve the exact CSR ROM generated.
+    let csr = &csr_resp.data[..csr_resp.data_size as usize];
+    assert_eq!(csr, &rom_csr[..]);
+
+    // Check the CSR is self-signed by the IDevID key.
+    let req = X509Req::from_der(csr).unwrap();
+    let csr_pub_key = req.public_key().unwrap();
+    assert!(req.verify(&csr_pub_key).unwrap());
+
+    // Check the CSR public key matches the one reported by GET_IDEV_INFO.
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_IDEV_INFO), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::GET_IDEV_INFO), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let info = GetIdevInfoResp::read_from(resp.as_slice()).unwrap();
+
+    let ec_key = csr_pub_key.ec_key().unwrap();
+    let mut ctx = BigNumContext::new().unwrap();
+    let point = ec_key
+        .public_key()
+        .to_bytes(ec_key.group(), PointConversionForm::UNCOMPRESSED, &mut ctx)
+        .unwrap();
+    assert_eq!(point[0], 0x04);
+    assert_eq!(&point[1..49], &info.idev_pub_x);
+    assert_eq!(&point[49..], &info.idev_pub_y);
+}
+
+#[test]
+fn test_get_idev_csr_unprovisioned() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    // ROM was not asked to generate a CSR, so there is nothing to serve.
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_IDEV_CSR), &[]),
+    };
+    let resp = model.mailbox_execute(u32::from(CommandId::GET_IDEV_CSR), payload.as_bytes());
+    assert_eq!(
+        resp,
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_GET_IDEV_CSR_UNPROVISIONED.into()
+        ))
+    );
+}
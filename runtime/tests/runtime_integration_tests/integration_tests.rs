@@ -6,12 +6,17 @@ use caliptra_builder::{
     ImageOptions,
 };
 use caliptra_common::mailbox_api::{
-    CommandId, EcdsaVerifyReq, FipsVersionResp, FwInfoResp, GetIdevCertReq, GetIdevCertResp,
-    GetIdevInfoResp, InvokeDpeReq, InvokeDpeResp, MailboxReqHeader, MailboxRespHeader,
-    StashMeasurementReq, StashMeasurementResp,
+    CommandId, EcdsaVerifyReq, FipsVersionResp, FwInfoResp, GetCertChainReq, GetCertChainResp,
+    GetIdevCertReq, GetIdevCertResp, GetIdevInfoResp, GetLdevCertResp, InvokeDpeReq, InvokeDpeResp,
+    MailboxReqHeader, MailboxRespHeader, StashMeasurementReq, StashMeasurementResp,
 };
 use caliptra_drivers::{CaliptraError, Ecc384PubKey};
-use caliptra_hw_model::{DefaultHwModel, HwModel, ModelError, ShaAccMode};
+use caliptra_hw_model::{
+    BootParams, DefaultHwModel, DeviceLifecycle, Fuses, HwModel, InitParams, ModelError,
+    SecurityState, ShaAccMode,
+};
+use caliptra_image_gen::ImageGenerator;
+use caliptra_image_openssl::OsslCrypto;
 use caliptra_runtime::{
     FipsVersionCmd, InvokeDpeCmd, RtBootStatus, DPE_SUPPORT, VENDOR_ID, VENDOR_SKU,
 };
@@ -88,6 +93,122 @@ fn test_update() {
     assert_eq!(fw_rev[1], 0xaabbccdd);
 }
 
+#[test]
+fn test_update_invalid_image() {
+    let image_options = ImageOptions {
+        app_version: 0xaabbccdd,
+        ..Default::default()
+    };
+    let mut image =
+        caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, image_options)
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+
+    // Corrupt the runtime firmware, which is the last section of the bundle.
+    let last = image.len() - 1;
+    image[last] ^= 0xff;
+
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    // The runtime must reject the image before triggering the update reset.
+    let resp = model.mailbox_execute(u32::from(CommandId::FIRMWARE_LOAD), &image);
+    assert_eq!(
+        resp,
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::IMAGE_VERIFIER_ERR_RUNTIME_DIGEST_MISMATCH.into()
+        ))
+    );
+
+    // The current firmware keeps running and serving commands.
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::FW_INFO), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::FW_INFO), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    FwInfoResp::read_from(resp.as_slice()).unwrap();
+
+    let fw_rev = model.soc_ifc().cptra_fw_rev_id().read();
+    assert_eq!(fw_rev[0], 0xaaaaaaaa);
+    assert_eq!(fw_rev[1], 0xbbbbbbbb);
+}
+
+#[test]
+fn test_update_rollback_image() {
+    let fuse_svn: [u32; 4] = [0xf, 0, 0, 0]; // fuse svn = 4
+    let image_options = ImageOptions {
+        app_svn: 4,
+        ..Default::default()
+    };
+    let image =
+        caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, image_options)
+            .unwrap();
+    let vendor_pubkey_digest = ImageGenerator::new(OsslCrypto::default())
+        .vendor_pubkey_digest(&image.manifest.preamble)
+        .unwrap();
+
+    let fuses = Fuses {
+        life_cycle: DeviceLifecycle::Manufacturing,
+        anti_rollback_disable: false,
+        key_manifest_pk_hash: vendor_pubkey_digest,
+        runtime_svn: fuse_svn,
+        ..Default::default()
+    };
+    let rom = caliptra_builder::build_firmware_rom(&firmware::ROM_WITH_UART).unwrap();
+    let mut model = caliptra_hw_model::new(BootParams {
+        init_params: InitParams {
+            rom: &rom,
+            security_state: SecurityState::from(fuses.life_cycle as u32),
+            ..Default::default()
+        },
+        fuses,
+        fw_image: Some(&image.to_bytes().unwrap()),
+        ..Default::default()
+    })
+    .unwrap();
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    // Roll the runtime firmware back below the SVN fuses.
+    let rollback_options = ImageOptions {
+        app_svn: 3,
+        ..Default::default()
+    };
+    let rollback_image =
+        caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, rollback_options)
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+
+    // The runtime must reject the image before triggering the update reset.
+    let resp = model.mailbox_execute(u32::from(CommandId::FIRMWARE_LOAD), &rollback_image);
+    assert_eq!(
+        resp,
+        Err(ModelError::MailboxCmdFailed(
+            CaliptraError::IMAGE_VERIFIER_ERR_RUNTIME_SVN_LESS_THAN_FUSE.into()
+        ))
+    );
+
+    // The current firmware keeps running and serving commands.
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::FW_INFO), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::FW_INFO), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let info = FwInfoResp::read_from(resp.as_slice()).unwrap();
+    assert_eq!(info.runtime_svn, 4);
+}
+
 #[test]
 fn test_boot() {
     let mut model = run_rt_test(Some(&firmware::runtime_tests::BOOT), None, None);
@@ -211,6 +332,136 @@ fn test_certs() {
         .unwrap();
 }
 
+#[test]
+fn test_get_cert_chain() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| m.soc_mbox().status().read().mbox_fsm_ps().mbox_idle());
+
+    let mut get_chunk = |offset: u32, size: u32| {
+        let mut cmd = GetCertChainReq {
+            hdr: MailboxReqHeader { chksum: 0 },
+            offset,
+            size,
+        };
+        cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+            u32::from(CommandId::GET_CERT_CHAIN),
+            &cmd.as_bytes()[4..],
+        );
+        model
+            .mailbox_execute(u32::from(CommandId::GET_CERT_CHAIN), cmd.as_bytes())
+            .map(|resp| GetCertChainResp::read_from(resp.unwrap().as_slice()).unwrap())
+    };
+
+    // Reassemble the chain using chunks that don't line up with cert boundaries.
+    const CHUNK_SIZE: u32 = 500;
+    let mut cert_chain = vec![];
+    loop {
+        let resp = get_chunk(cert_chain.len() as u32, CHUNK_SIZE).unwrap();
+        assert!(caliptra_common::checksum::verify_checksum(
+            resp.hdr.chksum,
+            0x0,
+            &resp.as_bytes()[core::mem::size_of_val(&resp.hdr.chksum)..],
+        ));
+        assert!(resp.data_size <= CHUNK_SIZE);
+        cert_chain.extend_from_slice(&resp.data[..resp.data_size as usize]);
+        if cert_chain.len() == resp.total_size as usize {
+            break;
+        }
+        assert_eq!(resp.data_size, CHUNK_SIZE);
+    }
+
+    // Offsets past the end of the chain and oversized chunks are rejected.
+    assert_eq!(
+        get_chunk(cert_chain.len() as u32 + 1, CHUNK_SIZE).unwrap_err(),
+        ModelError::MailboxCmdFailed(CaliptraError::RUNTIME_MAILBOX_INVALID_PARAMS.into())
+    );
+    assert_eq!(
+        get_chunk(0, GetCertChainResp::DATA_MAX_SIZE as u32 + 1).unwrap_err(),
+        ModelError::MailboxCmdFailed(CaliptraError::RUNTIME_MAILBOX_INVALID_PARAMS.into())
+    );
+
+    // Split the DER-encoded chain into LDevID, FMC Alias and RT Alias certs.
+    let mut certs = vec![];
+    let mut remaining = &cert_chain[..];
+    while !remaining.is_empty() {
+        let cert = X509::from_der(remaining).unwrap();
+        remaining = &remaining[cert.to_der().unwrap().len()..];
+        certs.push(cert);
+    }
+    assert_eq!(certs.len(), 3);
+    let rt_cert = certs.pop().unwrap();
+    let fmc_cert = certs.pop().unwrap();
+    let ldev_cert = certs.pop().unwrap();
+
+    // Verify full cert chain
+    let mut roots_bldr = X509StoreBuilder::new().unwrap();
+    roots_bldr.add_cert(ldev_cert).unwrap();
+    roots_bldr
+        .set_flags(X509VerifyFlags::X509_STRICT | X509VerifyFlags::PARTIAL_CHAIN)
+        .unwrap();
+    let roots = roots_bldr.build();
+    let mut cert_store = X509StoreContext::new().unwrap();
+    let mut chain = Stack::new().unwrap();
+    chain.push(fmc_cert).unwrap();
+    cert_store
+        .init(&roots, &rt_cert, &chain, |c| {
+            let success = c.verify_cert().unwrap();
+            assert_eq!(c.error(), X509VerifyResult::OK);
+            assert!(success);
+
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_get_ldev_cert() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| m.soc_mbox().status().read().mbox_fsm_ps().mbox_idle());
+
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_LDEV_CERT), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::GET_LDEV_CERT), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let ldev_resp = GetLdevCertResp::read_from(resp.as_slice()).unwrap();
+
+    // Verify checksum and FIPS status
+    assert!(caliptra_common::checksum::verify_checksum(
+        ldev_resp.hdr.chksum,
+        0x0,
+        &ldev_resp.as_bytes()[core::mem::size_of_val(&ldev_resp.hdr.chksum)..],
+    ));
+    assert_eq!(
+        ldev_resp.hdr.fips_status,
+        MailboxRespHeader::FIPS_STATUS_APPROVED
+    );
+
+    let ldev_cert = X509::from_der(&ldev_resp.data[..ldev_resp.data_size as usize]).unwrap();
+
+    // Check the LDevID is signed by IDevID
+    let payload = MailboxReqHeader {
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_IDEV_INFO), &[]),
+    };
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::GET_IDEV_INFO), payload.as_bytes())
+        .unwrap()
+        .unwrap();
+    let idev_resp = GetIdevInfoResp::read_from(resp.as_slice()).unwrap();
+
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    let idev_x = &BigNum::from_slice(&idev_resp.idev_pub_x).unwrap();
+    let idev_y = &BigNum::from_slice(&idev_resp.idev_pub_y).unwrap();
+    let idev_ec_key = EcKey::from_public_key_affine_coordinates(&group, idev_x, idev_y).unwrap();
+    assert!(ldev_cert
+        .verify(&PKey::from_ec_key(idev_ec_key).unwrap())
+        .unwrap());
+}
+
 #[test]
 fn test_fw_info() {
     let mut image_opts = ImageOptions::default();
@@ -990,24 +1241,7 @@ fn test_unimplemented_cmds() {
 
     let expected_err = Err(ModelError::MailboxCmdFailed(0xe0002));
 
-    // GET_IDEV_CSR
-    let payload = MailboxReqHeader {
-        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_IDEV_CSR), &[]),
-    };
-
-    let mut resp = model.mailbox_execute(u32::from(CommandId::GET_IDEV_CSR), payload.as_bytes());
-    assert_eq!(resp, expected_err);
-
-    // GET_LDEV_CERT
-    let payload = MailboxReqHeader {
-        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_LDEV_CERT), &[]),
-    };
-
-    resp = model.mailbox_execute(u32::from(CommandId::GET_LDEV_CERT), payload.as_bytes());
-    assert_eq!(resp, expected_err);
-
     // Send something that is not a valid RT command.
-    let expected_err = Err(ModelError::MailboxCmdFailed(0xe0002));
     const INVALID_CMD: u32 = 0xAABBCCDD;
     let payload = MailboxReqHeader {
         chksum: caliptra_common::checksum::calc_checksum(INVALID_CMD, &[]),
@@ -1,7 +1,13 @@
 // Licensed under the Apache-2.0 license
 
+mod capabilities;
 mod common;
 mod ecdsa;
+mod fips_self_test;
 mod hmac;
+mod idev_csr;
 mod integration_tests;
+mod measurement_log;
+mod pauser_privileges;
+mod quote;
 mod test_panic_missing;
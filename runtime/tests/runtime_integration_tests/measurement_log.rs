@@ -0,0 +1,225 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::{get_rt_alias_cert, run_rt_test};
+use caliptra_builder::{
+    firmware::{APP_WITH_UART, FMC_WITH_UART, ROM_WITH_UART},
+    ImageOptions,
+};
+use caliptra_common::mailbox_api::{
+    CommandId, GetMeasurementLogReq, GetMeasurementLogResp, StashMeasurementReq,
+    StashMeasurementResp,
+};
+use caliptra_hw_model::{BootParams, DefaultHwModel, HwModel, InitParams};
+use caliptra_runtime::RtBootStatus;
+use openssl::{bn::BigNum, ecdsa::EcdsaSig};
+use zerocopy::{AsBytes, FromBytes};
+
+fn stash_measurement(model: &mut DefaultHwModel, metadata: [u8; 4], measurement: [u8; 48]) {
+    let mut cmd = StashMeasurementReq {
+        metadata,
+        measurement,
+        svn: 7,
+        ..Default::default()
+    };
+    cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+        u32::from(CommandId::STASH_MEASUREMENT),
+        &cmd.as_bytes()[4..],
+    );
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::STASH_MEASUREMENT), cmd.as_bytes())
+        .unwrap()
+        .unwrap();
+    let resp = StashMeasurementResp::read_from(resp.as_slice()).unwrap();
+    assert_eq!(resp.dpe_result, 0);
+}
+
+fn get_measurement_log(model: &mut DefaultHwModel, nonce: [u8; 48]) -> GetMeasurementLogResp {
+    let mut cmd = GetMeasurementLogReq {
+        nonce,
+        ..Default::default()
+    };
+    cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+        u32::from(CommandId::GET_MEASUREMENT_LOG),
+        &cmd.as_bytes()[4..],
+    );
+    let resp_buf = model
+        .mailbox_execute(u32::from(CommandId::GET_MEASUREMENT_LOG), cmd.as_bytes())
+        .unwrap()
+        .unwrap();
+    assert!(resp_buf.len() <= std::mem::size_of::<GetMeasurementLogResp>());
+    let mut resp = GetMeasurementLogResp::default();
+    resp.as_bytes_mut()[..resp_buf.len()].copy_from_slice(&resp_buf);
+    assert!(caliptra_common::checksum::verify_checksum(
+        resp.hdr.chksum,
+        0x0,
+        &resp_buf[core::mem::size_of_val(&resp.hdr.chksum)..],
+    ));
+    assert_eq!(resp.nonce, nonce);
+    resp
+}
+
+fn verify_measurement_log(model: &mut DefaultHwModel, resp: &GetMeasurementLogResp) {
+    let digest = openssl::sha::sha384(resp.signed_data().unwrap());
+
+    let sig = EcdsaSig::from_private_components(
+        BigNum::from_slice(&resp.signature_r).unwrap(),
+        BigNum::from_slice(&resp.signature_s).unwrap(),
+    )
+    .unwrap();
+    let rt_pub_key = get_rt_alias_cert(model).public_key().unwrap();
+    assert!(sig.verify(&digest, &rt_pub_key.ec_key().unwrap()).unwrap());
+}
+
+#[test]
+fn test_measurement_log() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    let resp = get_measurement_log(&mut model, [0x5a; 48]);
+    assert_eq!(resp.entries().unwrap().len(), 0);
+    verify_measurement_log(&mut model, &resp);
+
+    stash_measurement(&mut model, *b"MSR1", [0x11; 48]);
+    stash_measurement(&mut model, *b"MSR2", [0x22; 48]);
+
+    let resp = get_measurement_log(&mut model, [0x5a; 48]);
+    verify_measurement_log(&mut model, &resp);
+    let entries = resp.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    for (i, (entry, (metadata, measurement))) in entries
+        .iter()
+        .zip([(*b"MSR1", [0x11; 48]), (*b"MSR2", [0x22; 48])])
+        .enumerate()
+    {
+        // ROM did not stash any measurements, so runtime entries start at 0.
+        assert_eq!(entry.counter, i as u32);
+        assert_eq!(entry.pauser, 0x1);
+        assert_eq!(entry.metadata, metadata);
+        assert_eq!(entry.svn, 7);
+        assert_eq!(entry.measurement, measurement);
+        assert_eq!(entry.context, [0; 48]);
+    }
+}
+
+#[test]
+fn test_measurement_log_preserved_across_update_reset() {
+    // Update to the same image that run_rt_test boots.
+    let mut image_options = ImageOptions::default();
+    image_options.vendor_config.pl0_pauser = Some(0x1);
+    image_options.fmc_version = 0xaaaaaaaa;
+    image_options.app_version = 0xbbbbbbbb;
+    let image =
+        caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, image_options)
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    stash_measurement(&mut model, *b"MSR1", [0x11; 48]);
+    let before = get_measurement_log(&mut model, [0x5a; 48]);
+
+    model
+        .mailbox_execute(u32::from(CommandId::FIRMWARE_LOAD), &image)
+        .unwrap();
+    model
+        .step_until_output_contains("Caliptra RT listening for mailbox commands...")
+        .unwrap();
+
+    // Entries logged before the update are kept and new entries continue
+    // the counter.
+    stash_measurement(&mut model, *b"MSR2", [0x22; 48]);
+    let after = get_measurement_log(&mut model, [0x5a; 48]);
+    verify_measurement_log(&mut model, &after);
+    let entries = after.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0], before.entries().unwrap()[0]);
+    assert_eq!(entries[1].counter, entries[0].counter + 1);
+    assert_eq!(entries[1].metadata, *b"MSR2");
+}
+
+#[test]
+fn test_measurement_log_includes_rom_measurements() {
+    let rom = caliptra_builder::build_firmware_rom(&ROM_WITH_UART).unwrap();
+    let mut model = caliptra_hw_model::new(BootParams {
+        init_params: InitParams {
+            rom: &rom,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut cmd = StashMeasurementReq {
+        metadata: *b"ROM1",
+        measurement: [0x33; 48],
+        context: [0x44; 48],
+        svn: 3,
+        ..Default::default()
+    };
+    cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+        u32::from(CommandId::STASH_MEASUREMENT),
+        &cmd.as_bytes()[4..],
+    );
+    model.upload_measurement(cmd.as_bytes()).unwrap();
+
+    let mut image_options = ImageOptions::default();
+    image_options.vendor_config.pl0_pauser = Some(0x1);
+    let image =
+        caliptra_builder::build_and_sign_image(&FMC_WITH_UART, &APP_WITH_UART, image_options)
+            .unwrap();
+    model.upload_firmware(&image.to_bytes().unwrap()).unwrap();
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    stash_measurement(&mut model, *b"MSR1", [0x11; 48]);
+
+    let resp = get_measurement_log(&mut model, [0xa5; 48]);
+    verify_measurement_log(&mut model, &resp);
+    let entries = resp.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    // ROM does not record the caller of STASH_MEASUREMENT.
+    assert_eq!(entries[0].counter, 0);
+    assert_eq!(entries[0].pauser, 0);
+    assert_eq!(entries[0].metadata, *b"ROM1");
+    assert_eq!(entries[0].svn, 3);
+    assert_eq!(entries[0].measurement, [0x33; 48]);
+    assert_eq!(entries[0].context, [0x44; 48]);
+
+    assert_eq!(entries[1].counter, 1);
+    assert_eq!(entries[1].pauser, 0x1);
+    assert_eq!(entries[1].metadata, *b"MSR1");
+}
+
+#[test]
+fn test_measurement_log_signature_covers_nonce() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    let mut resp = get_measurement_log(&mut model, [0x11; 48]);
+    verify_measurement_log(&mut model, &resp);
+
+    // A response replayed with another nonce does not verify.
+    resp.nonce = [0x22; 48];
+    let digest = openssl::sha::sha384(resp.signed_data().unwrap());
+    let sig = EcdsaSig::from_private_components(
+        BigNum::from_slice(&resp.signature_r).unwrap(),
+        BigNum::from_slice(&resp.signature_s).unwrap(),
+    )
+    .unwrap();
+    let rt_pub_key = get_rt_alias_cert(&mut model).public_key().unwrap();
+    assert!(!sig.verify(&digest, &rt_pub_key.ec_key().unwrap()).unwrap());
+}
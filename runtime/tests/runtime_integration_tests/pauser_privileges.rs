@@ -0,0 +1,236 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::run_rt_test;
+use caliptra_builder::ImageOptions;
+use caliptra_common::mailbox_api::{
+    CommandId, InvokeDpeReq, InvokeDpeResp, MailboxReqHeader, StashMeasurementReq,
+    StashMeasurementResp,
+};
+use caliptra_drivers::CaliptraError;
+use caliptra_hw_model::{DefaultHwModel, HwModel, ModelError};
+use caliptra_image_types::ImagePauserPrivileges;
+use caliptra_runtime::RtBootStatus;
+use dpe::{
+    commands::{
+        CertifyKeyCmd, CertifyKeyFlags, Command, CommandHdr, DeriveChildCmd, DeriveChildFlags,
+        SignCmd, SignFlags,
+    },
+    context::ContextHandle,
+    response::{CertifyKeyResp, GetProfileResp, SignResp},
+    DPE_PROFILE,
+};
+use zerocopy::{AsBytes, FromBytes};
+
+const BMC_PAUSER: u32 = 0x1;
+const HOST_PAUSER: u32 = 0x2;
+const ME_PAUSER: u32 = 0x3;
+const UNKNOWN_PAUSER: u32 = 0x4;
+
+fn boot_with_pauser_privileges(pauser_privileges: Vec<ImagePauserPrivileges>) -> DefaultHwModel {
+    let mut opts = ImageOptions::default();
+    opts.vendor_config.pl0_pauser = Some(BMC_PAUSER);
+    opts.vendor_config.pauser_privileges = pauser_privileges;
+    let mut model = run_rt_test(None, Some(opts), None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    // Let the other SoC agents use the mailbox. Index 0 is the boot PAUSER.
+    for (i, pauser) in [HOST_PAUSER, ME_PAUSER, UNKNOWN_PAUSER]
+        .into_iter()
+        .enumerate()
+    {
+        model
+            .soc_ifc()
+            .cptra_mbox_valid_pauser()
+            .at(i + 1)
+            .write(|_| pauser);
+        model
+            .soc_ifc()
+            .cptra_mbox_pauser_lock()
+            .at(i + 1)
+            .write(|w| w.lock(true));
+    }
+    model
+}
+
+fn execute_dpe_cmd(
+    model: &mut DefaultHwModel,
+    pauser: u32,
+    cmd_hdr: CommandHdr,
+    cmd: &[u8],
+) -> Result<InvokeDpeResp, ModelError> {
+    let mut data = [0u8; InvokeDpeReq::DATA_MAX_SIZE];
+    let cmd_hdr_buf = cmd_hdr.as_bytes();
+    data[..cmd_hdr_buf.len()].copy_from_slice(cmd_hdr_buf);
+    data[cmd_hdr_buf.len()..cmd_hdr_buf.len() + cmd.len()].copy_from_slice(cmd);
+    let mut mbox_cmd = InvokeDpeReq {
+        hdr: MailboxReqHeader { chksum: 0 },
+        data,
+        data_size: (cmd_hdr_buf.len() + cmd.len()) as u32,
+    };
+    mbox_cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+        u32::from(CommandId::INVOKE_DPE),
+        &mbox_cmd.as_bytes()[4..],
+    );
+
+    model.set_apb_pauser(pauser);
+    let result = model.mailbox_execute(u32::from(CommandId::INVOKE_DPE), mbox_cmd.as_bytes());
+    model.set_apb_pauser(BMC_PAUSER);
+
+    let resp_buf = result?.expect("We should have received a response");
+    assert!(resp_buf.len() <= std::mem::size_of::<InvokeDpeResp>());
+    let mut resp = InvokeDpeResp::default();
+    resp.as_bytes_mut()[..resp_buf.len()].copy_from_slice(&resp_buf);
+    assert!(caliptra_common::checksum::verify_checksum(
+        resp.hdr.chksum,
+        0x0,
+        &resp_buf[core::mem::size_of_val(&resp.hdr.chksum)..],
+    ));
+    Ok(resp)
+}
+
+fn certify_key_x509(model: &mut DefaultHwModel, pauser: u32) -> Result<InvokeDpeResp, ModelError> {
+    let certify_key_cmd = CertifyKeyCmd {
+        handle: ContextHandle::default(),
+        label: [0u8; DPE_PROFILE.get_hash_size()],
+        flags: CertifyKeyFlags::empty(),
+        format: CertifyKeyCmd::FORMAT_X509,
+    };
+    execute_dpe_cmd(
+        model,
+        pauser,
+        CommandHdr::new_for_test(Command::CERTIFY_KEY),
+        certify_key_cmd.as_bytes(),
+    )
+}
+
+#[test]
+fn test_pauser_privilege_table() {
+    let mut model = boot_with_pauser_privileges(vec![
+        ImagePauserPrivileges {
+            pauser: BMC_PAUSER,
+            locality: BMC_PAUSER,
+            flags: ImagePauserPrivileges::PL0_FLAG,
+        },
+        ImagePauserPrivileges {
+            pauser: HOST_PAUSER,
+            locality: 0x20,
+            flags: 0,
+        },
+        ImagePauserPrivileges {
+            pauser: ME_PAUSER,
+            locality: 0x30,
+            flags: 0,
+        },
+    ]);
+
+    // PL0 may request X.509 certificates.
+    let resp = certify_key_x509(&mut model, BMC_PAUSER).unwrap();
+    CertifyKeyResp::read_from(&resp.data[..resp.data_size as usize]).unwrap();
+
+    // PL1 may not.
+    assert_eq!(
+        certify_key_x509(&mut model, HOST_PAUSER).unwrap_err(),
+        ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_INCORRECT_PAUSER_PRIVILEGE_LEVEL.into()
+        )
+    );
+
+    // PL1 may not create contexts in the PL0 locality.
+    let derive_child_cmd = DeriveChildCmd {
+        handle: ContextHandle::default(),
+        data: [0u8; DPE_PROFILE.get_hash_size()],
+        flags: DeriveChildFlags::CHANGE_LOCALITY,
+        tci_type: 0,
+        target_locality: BMC_PAUSER,
+    };
+    assert_eq!(
+        execute_dpe_cmd(
+            &mut model,
+            ME_PAUSER,
+            CommandHdr::new_for_test(Command::DERIVE_CHILD),
+            derive_child_cmd.as_bytes(),
+        )
+        .unwrap_err(),
+        ModelError::MailboxCmdFailed(
+            CaliptraError::RUNTIME_INCORRECT_PAUSER_PRIVILEGE_LEVEL.into()
+        )
+    );
+
+    // Every agent in the table can use DPE.
+    for pauser in [BMC_PAUSER, HOST_PAUSER, ME_PAUSER] {
+        let resp = execute_dpe_cmd(
+            &mut model,
+            pauser,
+            CommandHdr::new_for_test(Command::GET_PROFILE),
+            &[],
+        )
+        .unwrap();
+        let profile = GetProfileResp::read_from(&resp.data[..resp.data_size as usize]).unwrap();
+        assert_eq!(profile.resp_hdr.profile, DPE_PROFILE as u32);
+    }
+
+    // Agents that are not in the table cannot.
+    assert_eq!(
+        execute_dpe_cmd(
+            &mut model,
+            UNKNOWN_PAUSER,
+            CommandHdr::new_for_test(Command::GET_PROFILE),
+            &[],
+        )
+        .unwrap_err(),
+        ModelError::MailboxCmdFailed(CaliptraError::RUNTIME_UNAUTHORIZED_PAUSER.into())
+    );
+}
+
+#[test]
+fn test_pauser_privilege_table_locality() {
+    // The PL0 agent's contexts live in a locality that differs from its PAUSER.
+    let mut model = boot_with_pauser_privileges(vec![
+        ImagePauserPrivileges {
+            pauser: HOST_PAUSER,
+            locality: 0x20,
+            flags: 0,
+        },
+        ImagePauserPrivileges {
+            pauser: BMC_PAUSER,
+            locality: 0x10,
+            flags: ImagePauserPrivileges::PL0_FLAG,
+        },
+    ]);
+
+    // The default context was created in the PL0 locality, so the PL0 agent
+    // can use it.
+    let sign_cmd = SignCmd {
+        handle: ContextHandle::default(),
+        label: [0u8; DPE_PROFILE.get_hash_size()],
+        flags: SignFlags::empty(),
+        digest: [0u8; DPE_PROFILE.get_hash_size()],
+    };
+    let resp = execute_dpe_cmd(
+        &mut model,
+        BMC_PAUSER,
+        CommandHdr::new_for_test(Command::SIGN),
+        sign_cmd.as_bytes(),
+    )
+    .unwrap();
+    assert!(SignResp::read_from(&resp.data[..resp.data_size as usize]).is_some());
+
+    // STASH_MEASUREMENT uses the mapped locality as well.
+    let mut cmd = StashMeasurementReq {
+        measurement: [0xab; 48],
+        ..Default::default()
+    };
+    cmd.hdr.chksum = caliptra_common::checksum::calc_checksum(
+        u32::from(CommandId::STASH_MEASUREMENT),
+        &cmd.as_bytes()[4..],
+    );
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::STASH_MEASUREMENT), cmd.as_bytes())
+        .unwrap()
+        .unwrap();
+    let resp = StashMeasurementResp::read_from(resp.as_slice()).unwrap();
+    assert_eq!(resp.dpe_result, 0);
+}
@@ -0,0 +1,93 @@
+// Licensed under the Apache-2.0 license.
+
+use crate::common::{get_rt_alias_cert, run_rt_test};
+use caliptra_api::quote::QuoteVerifyError;
+use caliptra_common::mailbox_api::{CommandId, QuoteReq, QuoteResp};
+use caliptra_hw_model::{DefaultHwModel, HwModel};
+use caliptra_runtime::RtBootStatus;
+use openssl::{
+    bn::{BigNum, BigNumContext},
+    ec::EcGroup,
+    nid::Nid,
+};
+use zerocopy::{AsBytes, FromBytes};
+
+fn quote(model: &mut DefaultHwModel, nonce: [u8; 48], pcr_selection: u32) -> QuoteResp {
+    let mut cmd = QuoteReq {
+        nonce,
+        pcr_selection,
+        ..Default::default()
+    };
+    cmd.hdr.chksum =
+        caliptra_common::checksum::calc_checksum(u32::from(CommandId::QUOTE), &cmd.as_bytes()[4..]);
+    let resp = model
+        .mailbox_execute(u32::from(CommandId::QUOTE), cmd.as_bytes())
+        .unwrap()
+        .unwrap();
+    let resp = QuoteResp::read_from(resp.as_slice()).unwrap();
+    assert!(caliptra_common::checksum::verify_checksum(
+        resp.hdr.chksum,
+        0x0,
+        &resp.as_bytes()[core::mem::size_of_val(&resp.hdr.chksum)..],
+    ));
+    resp
+}
+
+fn rt_alias_pub_key(model: &mut DefaultHwModel) -> ([u8; 48], [u8; 48]) {
+    let rt_cert = get_rt_alias_cert(model);
+    let ec_key = rt_cert.public_key().unwrap().ec_key().unwrap();
+    let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+    let mut ctx = BigNumContext::new().unwrap();
+    let mut x = BigNum::new().unwrap();
+    let mut y = BigNum::new().unwrap();
+    ec_key
+        .public_key()
+        .affine_coordinates(&group, &mut x, &mut y, &mut ctx)
+        .unwrap();
+    (
+        x.to_vec_padded(48).unwrap().try_into().unwrap(),
+        y.to_vec_padded(48).unwrap().try_into().unwrap(),
+    )
+}
+
+#[test]
+fn test_quote() {
+    let mut model = run_rt_test(None, None, None);
+
+    model.step_until(|m| {
+        m.soc_ifc().cptra_boot_status().read() == u32::from(RtBootStatus::RtReadyForCommands)
+    });
+
+    let (x, y) = rt_alias_pub_key(&mut model);
+
+    // Quote the ROM, FMC and runtime PCRs.
+    let nonce = [0x5a; 48];
+    let pcr_selection = 0b1111;
+    let resp = quote(&mut model, nonce, pcr_selection);
+    assert_eq!(resp.verify(&nonce, &x, &y), Ok(()));
+    assert_eq!(resp.pcr_selection, pcr_selection);
+    for id in 0..QuoteResp::PCR_COUNT {
+        match resp.pcr(id) {
+            Some(pcr) => assert_ne!(pcr, &[0; 48], "PCR{id}"),
+            None => assert_eq!(resp.pcrs[id], [0; 48], "PCR{id}"),
+        }
+    }
+
+    // A quote does not answer a different nonce.
+    assert_eq!(
+        resp.verify(&[0xa5; 48], &x, &y),
+        Err(QuoteVerifyError::NonceMismatch)
+    );
+
+    // Tampering with a PCR value breaks the signature.
+    let mut tampered = quote(&mut model, nonce, pcr_selection);
+    tampered.pcrs[3][0] ^= 1;
+    assert_eq!(
+        tampered.verify(&nonce, &x, &y),
+        Err(QuoteVerifyError::SignatureMismatch)
+    );
+
+    // All PCRs can be quoted at once.
+    let resp = quote(&mut model, [0x11; 48], u32::MAX);
+    assert_eq!(resp.verify(&[0x11; 48], &x, &y), Ok(()));
+}
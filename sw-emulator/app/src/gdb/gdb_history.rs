@@ -0,0 +1,296 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    gdb_history.rs
+
+Abstract:
+
+    File contains the execution history used for reverse debugging.
+
+--*/
+
+use caliptra_emu_cpu::Cpu;
+use caliptra_emu_periph::CaliptraRootBus;
+use std::cell::Cell;
+use std::collections::VecDeque;
+
+/// Shared by the GDB target and the host callbacks of the emulator
+#[derive(Default)]
+pub struct ReplayState {
+    replaying: Cell<bool>,
+    host_io: Cell<bool>,
+}
+
+impl ReplayState {
+    /// Returns true while the execution history is replayed, during which
+    /// output to the host is muted
+    pub fn replaying(&self) -> bool {
+        self.replaying.get()
+    }
+
+    /// Called by a host callback before it exchanges data with the target.
+    ///
+    /// # Return
+    ///
+    /// * `bool` - false while the history is replayed; the callback must do
+    ///   nothing. Otherwise the history is dropped before the next step, as
+    ///   it cannot reproduce the exchange.
+    pub fn begin_host_io(&self) -> bool {
+        if self.replaying.get() {
+            return false;
+        }
+        self.host_io.set(true);
+        true
+    }
+
+    pub(crate) fn set_replaying(&self, replaying: bool) {
+        self.replaying.set(replaying);
+    }
+
+    pub(crate) fn take_host_io(&self) -> bool {
+        self.host_io.take()
+    }
+}
+
+struct Checkpoint {
+    /// Clock value the snapshot was taken at
+    time: u64,
+    snapshot: Vec<u8>,
+}
+
+/// Ring buffer of emulator snapshots taken while the target runs forward.
+///
+/// The emulator is deterministic, so any earlier cycle can be reached by
+/// restoring the closest preceding checkpoint and stepping forward from
+/// there. Once the buffer is full the oldest checkpoint is dropped.
+pub struct ExecHistory {
+    checkpoints: VecDeque<Checkpoint>,
+    capacity: usize,
+    interval: u64,
+}
+
+impl ExecHistory {
+    /// Number of checkpoints kept
+    pub const DEFAULT_CAPACITY: usize = 64;
+
+    /// Cycles between two checkpoints
+    pub const DEFAULT_INTERVAL: u64 = 10_000;
+
+    /// Create a new execution history
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Number of checkpoints kept
+    /// * `interval` - Cycles between two checkpoints
+    pub fn new(capacity: usize, interval: u64) -> Self {
+        Self {
+            checkpoints: VecDeque::with_capacity(capacity),
+            capacity,
+            interval,
+        }
+    }
+
+    /// Take a checkpoint if the newest one is at least `interval` cycles old.
+    /// Must be called before every forward step.
+    pub fn record(&mut self, cpu: &Cpu<CaliptraRootBus>) {
+        let now = cpu.clock.now();
+        if let Some(last) = self.checkpoints.back() {
+            if now < last.time + self.interval {
+                return;
+            }
+        }
+        // Snapshots are refused while the firmware upload is pending; try
+        // again on the next step.
+        if let Ok(snapshot) = cpu.save_snapshot() {
+            if self.checkpoints.len() == self.capacity {
+                self.checkpoints.pop_front();
+            }
+            self.checkpoints.push_back(Checkpoint {
+                time: now,
+                snapshot,
+            });
+        }
+    }
+
+    /// Forget all checkpoints. Called whenever the debugger modifies the
+    /// target, as replaying would no longer reproduce the same execution.
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+    }
+
+    /// Returns the oldest cycle that can be rewound to
+    pub fn begin(&self) -> Option<u64> {
+        self.checkpoints.front().map(|c| c.time)
+    }
+
+    /// Rewind the CPU to the given cycle
+    ///
+    /// # Return
+    ///
+    /// * `bool` - false if `time` predates the history; the CPU is untouched
+    pub fn rewind_to(&self, cpu: &mut Cpu<CaliptraRootBus>, time: u64) -> bool {
+        let Some(checkpoint) = self.checkpoints.iter().rev().find(|c| c.time <= time) else {
+            return false;
+        };
+        Self::restore(cpu, checkpoint);
+        while cpu.clock.now() < time {
+            cpu.step(None);
+        }
+        true
+    }
+
+    /// Returns the last cycle before `before` at which `pred` holds, replaying
+    /// the history from the newest checkpoint backwards. The CPU is left in
+    /// an unspecified state; the caller is expected to rewind it.
+    pub fn find_last(
+        &self,
+        cpu: &mut Cpu<CaliptraRootBus>,
+        before: u64,
+        mut pred: impl FnMut(&Cpu<CaliptraRootBus>) -> bool,
+    ) -> Option<u64> {
+        let mut end = before;
+        for checkpoint in self.checkpoints.iter().rev().filter(|c| c.time < before) {
+            Self::restore(cpu, checkpoint);
+            let mut hit = None;
+            while cpu.clock.now() < end {
+                if pred(cpu) {
+                    hit = Some(cpu.clock.now());
+                }
+                cpu.step(None);
+            }
+            if hit.is_some() {
+                return hit;
+            }
+            end = checkpoint.time;
+        }
+        None
+    }
+
+    fn restore(cpu: &mut Cpu<CaliptraRootBus>, checkpoint: &Checkpoint) {
+        cpu.restore_snapshot(&checkpoint.snapshot)
+            .expect("execution history checkpoint failed to restore");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use caliptra_emu_bus::Clock;
+    use caliptra_emu_cpu::xreg_file::XReg;
+    use caliptra_emu_periph::CaliptraRootBusArgs;
+    use std::collections::HashMap;
+
+    /// A CPU running `loop: addi ra, ra, 1; j loop`
+    fn counter_cpu() -> Cpu<CaliptraRootBus> {
+        let rom = [0x0010_8093u32, 0xffdf_f06f]
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect();
+        let clock = Clock::new();
+        let bus = CaliptraRootBus::new(
+            &clock,
+            CaliptraRootBusArgs {
+                rom,
+                ..Default::default()
+            },
+        );
+        Cpu::new(bus, clock)
+    }
+
+    fn ra(cpu: &Cpu<CaliptraRootBus>) -> u32 {
+        cpu.read_xreg(XReg::X1).unwrap()
+    }
+
+    /// Run `cpu` forward for `steps` steps, returning the value of `ra` at
+    /// each cycle
+    fn run(
+        history: &mut ExecHistory,
+        cpu: &mut Cpu<CaliptraRootBus>,
+        steps: usize,
+    ) -> HashMap<u64, u32> {
+        let mut ra_at = HashMap::new();
+        for _ in 0..steps {
+            history.record(cpu);
+            ra_at.insert(cpu.clock.now(), ra(cpu));
+            cpu.step(None);
+        }
+        ra_at
+    }
+
+    #[test]
+    fn test_rewind_to() {
+        let mut cpu = counter_cpu();
+        let mut history = ExecHistory::new(4, 10);
+        assert_eq!(history.begin(), None);
+        let ra_at = run(&mut history, &mut cpu, 100);
+        let end = cpu.clock.now();
+
+        // Only the newest checkpoints are kept
+        let begin = history.begin().unwrap();
+        assert!(begin > 0 && begin + 40 >= end);
+        assert!(!history.rewind_to(&mut cpu, begin - 1));
+        assert_eq!(cpu.clock.now(), end);
+
+        for &time in ra_at.keys().filter(|&&t| t >= begin) {
+            assert!(history.rewind_to(&mut cpu, time));
+            assert_eq!(cpu.clock.now(), time);
+            assert_eq!(ra(&cpu), ra_at[&time]);
+        }
+
+        history.clear();
+        assert_eq!(history.begin(), None);
+        assert!(!history.rewind_to(&mut cpu, end));
+    }
+
+    #[test]
+    fn test_find_last() {
+        let mut cpu = counter_cpu();
+        let mut history = ExecHistory::new(4, 10);
+        let ra_at = run(&mut history, &mut cpu, 100);
+        let end = cpu.clock.now();
+        let begin = history.begin().unwrap();
+
+        // The latest cycle a value was seen at, searching back from `end`
+        let last_seen = |value: u32| {
+            ra_at
+                .iter()
+                .filter(|&(&t, &v)| t >= begin && v == value)
+                .map(|(&t, _)| t)
+                .max()
+        };
+        let newest = ra_at[&begin] + 2;
+        assert_eq!(
+            history.find_last(&mut cpu, end, |cpu| ra(cpu) == newest),
+            last_seen(newest)
+        );
+
+        // Cycles at or after `before` are not searched
+        let before = last_seen(newest).unwrap();
+        let hit = history.find_last(&mut cpu, before, |cpu| ra(cpu) == newest);
+        assert!(hit < Some(before));
+
+        // Values only seen before the history began are not found
+        assert_eq!(
+            history.find_last(&mut cpu, end, |cpu| ra(cpu) < ra_at[&begin]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_replay_state() {
+        let replay = ReplayState::default();
+        assert!(!replay.take_host_io());
+        assert!(replay.begin_host_io());
+        assert!(replay.take_host_io());
+        assert!(!replay.take_host_io());
+
+        // Host callbacks do nothing while the history is replayed
+        replay.set_replaying(true);
+        assert!(replay.replaying());
+        assert!(!replay.begin_host_io());
+        assert!(!replay.take_host_io());
+    }
+}
@@ -12,47 +12,150 @@ Abstract:
 
 --*/
 
+use super::gdb_history::{ExecHistory, ReplayState};
 use caliptra_emu_cpu::xreg_file::XReg;
 use caliptra_emu_cpu::StepAction;
 use caliptra_emu_cpu::{Cpu, WatchPtrKind};
-use caliptra_emu_periph::CaliptraRootBus;
+use caliptra_emu_periph::{CaliptraRootBus, KeyVault};
 use caliptra_emu_types::RvSize;
 use gdbstub::arch::SingleStepGdbBehavior;
 use gdbstub::common::Signal;
+use gdbstub::outputln;
 use gdbstub::stub::SingleThreadStopReason;
 use gdbstub::target;
+use gdbstub::target::ext::base::reverse_exec::ReplayLogPosition;
 use gdbstub::target::ext::base::singlethread::{SingleThreadBase, SingleThreadResume};
 use gdbstub::target::ext::base::BaseOps;
 use gdbstub::target::ext::breakpoints::WatchKind;
+use gdbstub::target::ext::monitor_cmd::ConsoleOutput;
 use gdbstub::target::Target;
-use gdbstub::target::TargetResult;
+use gdbstub::target::{TargetError, TargetResult};
 use gdbstub_arch;
+use gdbstub_arch::riscv::reg::id::RiscvRegId;
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// ABI names of the general purpose registers, in register number order
+const XREG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Control and status registers exposed to GDB through the target XML
+const CSRS: [(&str, u16); 50] = [
+    ("mstatus", 0x300),
+    ("misa", 0x301),
+    ("mie", 0x304),
+    ("mtvec", 0x305),
+    ("mcountinhibit", 0x320),
+    ("mscratch", 0x340),
+    ("mepc", 0x341),
+    ("mcause", 0x342),
+    ("mtval", 0x343),
+    ("mip", 0x344),
+    ("pmpcfg0", 0x3a0),
+    ("pmpcfg1", 0x3a1),
+    ("pmpcfg2", 0x3a2),
+    ("pmpcfg3", 0x3a3),
+    ("pmpaddr0", 0x3b0),
+    ("pmpaddr1", 0x3b1),
+    ("pmpaddr2", 0x3b2),
+    ("pmpaddr3", 0x3b3),
+    ("pmpaddr4", 0x3b4),
+    ("pmpaddr5", 0x3b5),
+    ("pmpaddr6", 0x3b6),
+    ("pmpaddr7", 0x3b7),
+    ("pmpaddr8", 0x3b8),
+    ("pmpaddr9", 0x3b9),
+    ("pmpaddr10", 0x3ba),
+    ("pmpaddr11", 0x3bb),
+    ("pmpaddr12", 0x3bc),
+    ("pmpaddr13", 0x3bd),
+    ("pmpaddr14", 0x3be),
+    ("pmpaddr15", 0x3bf),
+    ("mitcnt0", 0x7d2),
+    ("mitb0", 0x7d3),
+    ("mitctl0", 0x7d4),
+    ("mitcnt1", 0x7d5),
+    ("mitb1", 0x7d6),
+    ("mitctl1", 0x7d7),
+    ("mcycle", 0xb00),
+    ("minstret", 0xb02),
+    ("mcycleh", 0xb80),
+    ("minstreth", 0xb82),
+    ("meivt", 0xbc8),
+    ("meipt", 0xbc9),
+    ("meicpct", 0xbca),
+    ("meicidpl", 0xbcb),
+    ("meicurpl", 0xbcc),
+    ("mvendorid", 0xf11),
+    ("marchid", 0xf12),
+    ("mimpid", 0xf13),
+    ("mhartid", 0xf14),
+    ("meihap", 0xfc8),
+];
+
+/// GDB register number of the first CSR (x0-x31, pc and f0-f31 come first)
+const CSR_REGNUM_BASE: u32 = 65;
+
+const MONITOR_HELP: &str = "\
+Caliptra emulator monitor commands:
+  kv                         dump the key vault entries and their controls
+  pcr                        dump the PCRs
+  mbox                       show the mailbox FSM state
+  reset <warm|cold|update>   reset the emulator";
 
 pub enum ExecMode {
     Step,
     Continue,
+    ReverseStep,
+    ReverseContinue,
 }
 
 pub struct GdbTarget {
     cpu: Cpu<CaliptraRootBus>,
     exec_mode: ExecMode,
     breakpoints: Vec<u32>,
+    history: ExecHistory,
+    /// Snapshot taken at power-on, restored on cold reset
+    power_on: Option<Vec<u8>>,
+    /// Mutes the host callbacks while the history is replayed, so side
+    /// effects (e.g. UART output) are not repeated
+    replay: Rc<ReplayState>,
 }
 
 impl GdbTarget {
     // Create new instance of GdbTarget
-    pub fn new(cpu: Cpu<CaliptraRootBus>) -> Self {
+    pub fn new(cpu: Cpu<CaliptraRootBus>, replay: Rc<ReplayState>) -> Self {
         Self {
+            power_on: cpu.save_snapshot().ok(),
             cpu,
             exec_mode: ExecMode::Continue,
             breakpoints: Vec::new(),
+            history: ExecHistory::new(ExecHistory::DEFAULT_CAPACITY, ExecHistory::DEFAULT_INTERVAL),
+            replay,
         }
     }
 
+    // Return the CPU once the debug session is over
+    pub fn into_cpu(self) -> Cpu<CaliptraRootBus> {
+        self.cpu
+    }
+
+    // Step forward, recording the execution history (Private function)
+    fn step_forward(&mut self) -> StepAction {
+        if self.replay.take_host_io() {
+            self.history.clear();
+        }
+        self.history.record(&self.cpu);
+        self.cpu.step(None)
+    }
+
     // Conditional Run (Private function)
     fn cond_run(&mut self) -> SingleThreadStopReason<u32> {
         loop {
-            match self.cpu.step(None) {
+            match self.step_forward() {
                 StepAction::Continue => {
                     if self.breakpoints.contains(&self.cpu.read_pc()) {
                         return SingleThreadStopReason::SwBreak(());
@@ -76,18 +179,209 @@ impl GdbTarget {
         SingleThreadStopReason::Exited(0)
     }
 
+    // Step back one cycle (Private function)
+    fn reverse_step_run(&mut self) -> SingleThreadStopReason<u32> {
+        let now = self.cpu.clock.now();
+        self.replay.set_replaying(true);
+        let rewound = now > 0 && self.history.rewind_to(&mut self.cpu, now - 1);
+        self.replay.set_replaying(false);
+        if rewound {
+            SingleThreadStopReason::DoneStep
+        } else {
+            SingleThreadStopReason::ReplayLog {
+                tid: None,
+                pos: ReplayLogPosition::Begin,
+            }
+        }
+    }
+
+    // Run backwards to the previous software breakpoint (Private function)
+    fn reverse_cond_run(&mut self) -> SingleThreadStopReason<u32> {
+        let now = self.cpu.clock.now();
+        let breakpoints = &self.breakpoints;
+        self.replay.set_replaying(true);
+        let hit = self.history.find_last(&mut self.cpu, now, |cpu| {
+            breakpoints.contains(&cpu.read_pc())
+        });
+        let reason = match hit {
+            Some(time) => {
+                self.history.rewind_to(&mut self.cpu, time);
+                SingleThreadStopReason::SwBreak(())
+            }
+            None => {
+                // Stop at the oldest point in the history (or stay put if
+                // there is none).
+                let begin = self.history.begin().unwrap_or(now);
+                self.history.rewind_to(&mut self.cpu, begin);
+                SingleThreadStopReason::ReplayLog {
+                    tid: None,
+                    pos: ReplayLogPosition::Begin,
+                }
+            }
+        };
+        self.replay.set_replaying(false);
+        reason
+    }
+
     // run the gdb target
     pub fn run(&mut self) -> SingleThreadStopReason<u32> {
         match self.exec_mode {
             ExecMode::Step => {
-                self.cpu.step(None);
+                self.step_forward();
                 SingleThreadStopReason::DoneStep
             }
             ExecMode::Continue => self.cond_run(),
+            ExecMode::ReverseStep => self.reverse_step_run(),
+            ExecMode::ReverseContinue => self.reverse_cond_run(),
+        }
+    }
+
+    // Reset the emulator (Private function)
+    fn reset(&mut self, kind: &str) -> Result<(), &'static str> {
+        match kind {
+            "warm" => self.cpu.warm_reset(),
+            "update" => self.cpu.update_reset(),
+            "cold" => {
+                let snapshot = self
+                    .power_on
+                    .as_ref()
+                    .ok_or("power-on state was not captured")?;
+                self.cpu
+                    .restore_snapshot(snapshot)
+                    .map_err(|_| "failed to restore the power-on state")?;
+            }
+            _ => return Err("expected one of: warm, cold, update"),
         }
+        self.history.clear();
+        Ok(())
+    }
+
+    // Run a monitor command (Private function)
+    fn monitor(&mut self, cmd: &str, out: &mut impl Write) {
+        let args: Vec<&str> = cmd.split_whitespace().collect();
+        match args.as_slice() {
+            ["kv"] => {
+                for key_id in 0..KeyVault::KEY_COUNT {
+                    let (control, key) = self.cpu.bus.key_vault.peek_key(key_id);
+                    outputln!(out, "KEY[{key_id:2}] ctrl={control:08x} {}", hex(&key));
+                }
+            }
+            ["pcr"] => {
+                for pcr_id in 0..KeyVault::PCR_COUNT {
+                    let pcr = self.cpu.bus.key_vault.read_pcr(pcr_id);
+                    outputln!(out, "PCR[{pcr_id:2}] {}", hex(&pcr));
+                }
+            }
+            ["mbox"] => {
+                let state = self.cpu.bus.mailbox.fsm_state();
+                outputln!(out, "state:   {}", state.state);
+                outputln!(out, "locked:  {}", state.locked);
+                outputln!(out, "user:    {:?}", state.user);
+                outputln!(out, "cmd:     {:#010x}", state.cmd);
+                outputln!(out, "dlen:    {}", state.dlen);
+                outputln!(out, "execute: {}", state.execute);
+                outputln!(out, "status:  {}", state.status);
+            }
+            ["reset", kind] => match self.reset(kind) {
+                Ok(()) => {
+                    outputln!(out, "{kind} reset done; pc={:#010x}", self.cpu.read_pc());
+                    outputln!(out, "run `maintenance flush register-cache` to refresh GDB");
+                }
+                Err(e) => outputln!(out, "reset failed: {e}"),
+            },
+            _ => outputln!(out, "{MONITOR_HELP}"),
+        }
+    }
+
+    // Build the target description XML (Private function)
+    fn target_xml() -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?>\
+             <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+             <target version=\"1.0\">\
+             <architecture>riscv:rv32</architecture>\
+             <feature name=\"org.gnu.gdb.riscv.cpu\">",
+        );
+        for (regnum, name) in XREG_NAMES.iter().enumerate() {
+            let ty = match *name {
+                "sp" | "fp" => "data_ptr",
+                "ra" => "code_ptr",
+                _ => "int",
+            };
+            write!(
+                xml,
+                "<reg name=\"{name}\" bitsize=\"32\" type=\"{ty}\" regnum=\"{regnum}\"/>"
+            )
+            .unwrap();
+        }
+        xml.push_str("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"32\"/>");
+        xml.push_str("</feature><feature name=\"org.gnu.gdb.riscv.csr\">");
+        for (name, addr) in CSRS {
+            write!(
+                xml,
+                "<reg name=\"{name}\" bitsize=\"32\" type=\"int\" regnum=\"{}\" group=\"csr\"/>",
+                CSR_REGNUM_BASE + u32::from(addr)
+            )
+            .unwrap();
+        }
+        xml.push_str("</feature></target>");
+        xml
+    }
+
+    // Build the memory map XML (Private function)
+    fn memory_map() -> String {
+        let regions = [
+            ("rom", 0x0000_0000, CaliptraRootBus::ROM_SIZE),
+            // Crypto peripherals
+            ("ram", 0x1000_0000, 0x3_0000),
+            // UART, CSRNG and emulator control
+            ("ram", 0x2000_1000, 0xf000),
+            // Mailbox SRAM, mailbox, SHA512 accelerator and SoC interface
+            ("ram", 0x3000_0000, 0x4_0000),
+            ("ram", 0x4000_0000, CaliptraRootBus::ICCM_SIZE),
+            ("ram", 0x5000_0000, CaliptraRootBus::DCCM_SIZE),
+            // PIC
+            ("ram", 0x6000_0000, 0x8000),
+        ];
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?>\
+             <!DOCTYPE memory-map PUBLIC \"+//IDN gnu.org//DTD GDB Memory Map V1.0//EN\" \
+             \"http://sourceware.org/gdb/gdb-memory-map.dtd\">\
+             <memory-map>",
+        );
+        for (ty, start, len) in regions {
+            write!(
+                xml,
+                "<memory type=\"{ty}\" start=\"{start:#x}\" length=\"{len:#x}\"/>"
+            )
+            .unwrap();
+        }
+        xml.push_str("</memory-map>");
+        xml
     }
 }
 
+// Copy the requested window of an XML document into buf
+fn copy_xml(xml: &str, offset: u64, length: usize, buf: &mut [u8]) -> usize {
+    let data = xml.as_bytes();
+    let Ok(offset) = usize::try_from(offset) else {
+        return 0;
+    };
+    if offset >= data.len() {
+        return 0;
+    }
+    let len = length.min(buf.len()).min(data.len() - offset);
+    buf[..len].copy_from_slice(&data[offset..offset + len]);
+    len
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().fold(String::new(), |mut s, b| {
+        write!(s, "{b:02x}").unwrap();
+        s
+    })
+}
+
 impl Target for GdbTarget {
     type Arch = gdbstub_arch::riscv::Riscv32;
     type Error = &'static str;
@@ -109,6 +403,22 @@ impl Target for GdbTarget {
     ) -> Option<target::ext::breakpoints::BreakpointsOps<'_, Self>> {
         Some(self)
     }
+
+    fn support_monitor_cmd(&mut self) -> Option<target::ext::monitor_cmd::MonitorCmdOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_target_description_xml_override(
+        &mut self,
+    ) -> Option<
+        target::ext::target_description_xml_override::TargetDescriptionXmlOverrideOps<'_, Self>,
+    > {
+        Some(self)
+    }
+
+    fn support_memory_map(&mut self) -> Option<target::ext::memory_map::MemoryMapOps<'_, Self>> {
+        Some(self)
+    }
 }
 
 impl SingleThreadBase for GdbTarget {
@@ -131,6 +441,8 @@ impl SingleThreadBase for GdbTarget {
         &mut self,
         regs: &gdbstub_arch::riscv::reg::RiscvCoreRegs<u32>,
     ) -> TargetResult<(), Self> {
+        self.history.clear();
+
         // Write PC
         self.cpu.write_pc(regs.pc);
 
@@ -152,6 +464,7 @@ impl SingleThreadBase for GdbTarget {
     }
 
     fn write_addrs(&mut self, start_addr: u32, data: &[u8]) -> TargetResult<(), Self> {
+        self.history.clear();
         for (addr, val) in (start_addr..).zip(data.iter().copied()) {
             self.cpu.write_bus(RvSize::Byte, addr, val as u32).unwrap();
         }
@@ -163,6 +476,63 @@ impl SingleThreadBase for GdbTarget {
     ) -> Option<target::ext::base::singlethread::SingleThreadResumeOps<'_, Self>> {
         Some(self)
     }
+
+    fn support_single_register_access(
+        &mut self,
+    ) -> Option<target::ext::base::single_register_access::SingleRegisterAccessOps<'_, (), Self>>
+    {
+        Some(self)
+    }
+}
+
+impl target::ext::base::single_register_access::SingleRegisterAccess<()> for GdbTarget {
+    fn read_register(
+        &mut self,
+        _tid: (),
+        reg_id: RiscvRegId<u32>,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let val = match reg_id {
+            RiscvRegId::Gpr(idx) => self
+                .cpu
+                .read_xreg(XReg::from(u16::from(idx)))
+                .map_err(|_| TargetError::NonFatal)?,
+            RiscvRegId::Pc => self.cpu.read_pc(),
+            RiscvRegId::Csr(csr) => self
+                .cpu
+                .read_csr(u32::from(csr))
+                .map_err(|_| TargetError::NonFatal)?,
+            _ => return Err(TargetError::NonFatal),
+        };
+        let bytes = val.to_le_bytes();
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+
+    fn write_register(
+        &mut self,
+        _tid: (),
+        reg_id: RiscvRegId<u32>,
+        val: &[u8],
+    ) -> TargetResult<(), Self> {
+        let val = u32::from_le_bytes(val.try_into().map_err(|_| TargetError::NonFatal)?);
+        self.history.clear();
+        match reg_id {
+            RiscvRegId::Gpr(idx) => self
+                .cpu
+                .write_xreg(XReg::from(u16::from(idx)), val)
+                .map_err(|_| TargetError::NonFatal),
+            RiscvRegId::Pc => {
+                self.cpu.write_pc(val);
+                Ok(())
+            }
+            RiscvRegId::Csr(csr) => self
+                .cpu
+                .write_csr(u32::from(csr), val)
+                .map_err(|_| TargetError::NonFatal),
+            _ => Err(TargetError::NonFatal),
+        }
+    }
 }
 
 impl target::ext::base::singlethread::SingleThreadSingleStep for GdbTarget {
@@ -194,6 +564,34 @@ impl SingleThreadResume for GdbTarget {
     ) -> Option<target::ext::base::singlethread::SingleThreadSingleStepOps<'_, Self>> {
         Some(self)
     }
+
+    #[inline(always)]
+    fn support_reverse_step(
+        &mut self,
+    ) -> Option<target::ext::base::reverse_exec::ReverseStepOps<'_, (), Self>> {
+        Some(self)
+    }
+
+    #[inline(always)]
+    fn support_reverse_cont(
+        &mut self,
+    ) -> Option<target::ext::base::reverse_exec::ReverseContOps<'_, (), Self>> {
+        Some(self)
+    }
+}
+
+impl target::ext::base::reverse_exec::ReverseStep<()> for GdbTarget {
+    fn reverse_step(&mut self, _tid: ()) -> Result<(), Self::Error> {
+        self.exec_mode = ExecMode::ReverseStep;
+        Ok(())
+    }
+}
+
+impl target::ext::base::reverse_exec::ReverseCont<()> for GdbTarget {
+    fn reverse_cont(&mut self) -> Result<(), Self::Error> {
+        self.exec_mode = ExecMode::ReverseContinue;
+        Ok(())
+    }
 }
 
 impl target::ext::breakpoints::Breakpoints for GdbTarget {
@@ -267,3 +665,180 @@ impl target::ext::breakpoints::HwWatchpoint for GdbTarget {
         Ok(true)
     }
 }
+
+impl target::ext::monitor_cmd::MonitorCmd for GdbTarget {
+    fn handle_monitor_cmd(
+        &mut self,
+        cmd: &[u8],
+        mut out: ConsoleOutput<'_>,
+    ) -> Result<(), Self::Error> {
+        self.monitor(&String::from_utf8_lossy(cmd), &mut out);
+        Ok(())
+    }
+}
+
+impl target::ext::target_description_xml_override::TargetDescriptionXmlOverride for GdbTarget {
+    fn target_description_xml(
+        &self,
+        annex: &[u8],
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        if annex != b"target.xml" {
+            return Err(TargetError::NonFatal);
+        }
+        Ok(copy_xml(&Self::target_xml(), offset, length, buf))
+    }
+}
+
+impl target::ext::memory_map::MemoryMap for GdbTarget {
+    fn memory_map_xml(
+        &self,
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        Ok(copy_xml(&Self::memory_map(), offset, length, buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use caliptra_emu_bus::Clock;
+    use caliptra_emu_periph::CaliptraRootBusArgs;
+
+    fn new_target() -> (GdbTarget, Rc<ReplayState>) {
+        let clock = Clock::new();
+        let bus = CaliptraRootBus::new(&clock, CaliptraRootBusArgs::default());
+        let replay = Rc::new(ReplayState::default());
+        (GdbTarget::new(Cpu::new(bus, clock), replay.clone()), replay)
+    }
+
+    fn monitor(target: &mut GdbTarget, cmd: &str) -> String {
+        let mut out = String::new();
+        target.monitor(cmd, &mut out);
+        out
+    }
+
+    fn step(target: &mut GdbTarget, count: usize) {
+        target.exec_mode = ExecMode::Step;
+        for _ in 0..count {
+            target.run();
+        }
+    }
+
+    #[test]
+    fn test_monitor() {
+        let (mut target, _) = new_target();
+        assert_eq!(monitor(&mut target, ""), format!("{MONITOR_HELP}\n"));
+        assert_eq!(
+            monitor(&mut target, "kv extra"),
+            format!("{MONITOR_HELP}\n")
+        );
+
+        let kv = monitor(&mut target, "kv");
+        assert_eq!(kv.lines().count(), KeyVault::KEY_COUNT as usize);
+        assert!(kv.starts_with("KEY[ 0] ctrl=00000000 "));
+
+        let pcr = monitor(&mut target, " pcr ");
+        assert_eq!(pcr.lines().count(), KeyVault::PCR_COUNT as usize);
+        assert!(pcr.starts_with(&format!("PCR[ 0] {}\n", "00".repeat(48))));
+
+        let mbox = monitor(&mut target, "mbox");
+        assert!(mbox.contains("locked:  false\n"));
+        assert!(mbox.contains("execute: false\n"));
+
+        assert_eq!(
+            monitor(&mut target, "reset hard"),
+            "reset failed: expected one of: warm, cold, update\n"
+        );
+    }
+
+    #[test]
+    fn test_monitor_cold_reset() {
+        let (mut target, _) = new_target();
+        step(&mut target, 100);
+        assert!(target.history.begin().is_some());
+
+        let out = monitor(&mut target, "reset cold");
+        assert!(out.starts_with("cold reset done; pc=0x00000000\n"));
+        assert_eq!(target.cpu.clock.now(), 0);
+        assert_eq!(target.history.begin(), None);
+    }
+
+    #[test]
+    fn test_host_io_clears_history() {
+        let (mut target, replay) = new_target();
+        step(&mut target, 100);
+        assert_eq!(target.history.begin(), Some(0));
+
+        // The history restarts after the host exchanged data with the target
+        assert!(replay.begin_host_io());
+        let now = target.cpu.clock.now();
+        step(&mut target, 1);
+        assert_eq!(target.history.begin(), Some(now));
+
+        // The host callbacks are only muted during the replay
+        target.exec_mode = ExecMode::ReverseStep;
+        assert!(matches!(target.run(), SingleThreadStopReason::DoneStep));
+        assert!(!replay.replaying());
+        assert!(!replay.take_host_io());
+    }
+
+    #[test]
+    fn test_target_xml() {
+        let xml = GdbTarget::target_xml();
+        assert!(xml.starts_with("<?xml version=\"1.0\"?>"));
+        assert!(xml.ends_with("</feature></target>"));
+        assert_eq!(
+            xml.matches("<reg ").count(),
+            XREG_NAMES.len() + 1 + CSRS.len()
+        );
+        assert!(xml.contains("<reg name=\"zero\" bitsize=\"32\" type=\"int\" regnum=\"0\"/>"));
+        assert!(xml.contains("<reg name=\"sp\" bitsize=\"32\" type=\"data_ptr\" regnum=\"2\"/>"));
+        assert!(xml.contains("<reg name=\"t6\" bitsize=\"32\" type=\"int\" regnum=\"31\"/>"));
+        assert!(xml.contains("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"32\"/>"));
+        // GDB numbers CSRs from 65, after x0-x31, pc and f0-f31
+        assert!(xml.contains(
+            "<reg name=\"mstatus\" bitsize=\"32\" type=\"int\" regnum=\"833\" group=\"csr\"/>"
+        ));
+        assert!(xml.contains(
+            "<reg name=\"meihap\" bitsize=\"32\" type=\"int\" regnum=\"4105\" group=\"csr\"/>"
+        ));
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let xml = GdbTarget::memory_map();
+        assert!(xml.ends_with("</memory-map>"));
+        assert!(xml.contains("<memory type=\"rom\" start=\"0x0\" length=\"0xc000\"/>"));
+        assert!(xml.contains("<memory type=\"ram\" start=\"0x40000000\" length=\"0x20000\"/>"));
+        assert!(xml.contains("<memory type=\"ram\" start=\"0x50000000\" length=\"0x20000\"/>"));
+        assert_eq!(xml.matches("<memory ").count(), 7);
+    }
+
+    #[test]
+    fn test_copy_xml() {
+        let xml = GdbTarget::memory_map();
+
+        // GDB reads the document in windows
+        let mut data = vec![];
+        let mut buf = [0; 64];
+        loop {
+            let len = copy_xml(&xml, data.len() as u64, 100, &mut buf);
+            if len == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(data, xml.as_bytes());
+
+        assert_eq!(copy_xml(&xml, 0, 3, &mut buf), 3);
+        assert_eq!(&buf[..3], b"<?x");
+        assert_eq!(copy_xml(&xml, xml.len() as u64 - 1, 100, &mut buf), 1);
+        assert_eq!(copy_xml(&xml, xml.len() as u64, 100, &mut buf), 0);
+        assert_eq!(copy_xml(&xml, u64::MAX, 100, &mut buf), 0);
+    }
+}
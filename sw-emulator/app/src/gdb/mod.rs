@@ -11,5 +11,6 @@ Abstract:
     File contains gdb module for Caliptra Emulator.
 
 --*/
+pub mod gdb_history;
 pub mod gdb_state;
 pub mod gdb_target;
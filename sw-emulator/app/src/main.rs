@@ -16,21 +16,23 @@ use caliptra_emu_bus::Clock;
 use caliptra_emu_cpu::{Cpu, RvInstr, StepAction};
 use caliptra_emu_periph::soc_reg::DebugManufService;
 use caliptra_emu_periph::{
-    CaliptraRootBus, CaliptraRootBusArgs, DownloadIdevidCsrCb, MailboxInternal, ReadyForFwCb,
-    TbServicesCb, UploadUpdateFwCb,
+    CaliptraRootBus, CaliptraRootBusArgs, CryptoLatency, DownloadIdevidCsrCb, MailboxInternal,
+    ReadyForFwCb, TbServicesCb, UploadUpdateFwCb,
 };
 use caliptra_hw_model::BusMmio;
 use caliptra_hw_model_types::{DeviceLifecycle, SecurityState};
 use clap::{arg, value_parser, ArgAction};
+use std::cell::Cell;
 use std::fs::File;
 use std::io;
-use std::io::{Read, Write};
+use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::rc::Rc;
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::registers::InMemoryRegister;
 mod gdb;
+use crate::gdb::gdb_history::ReplayState;
 use crate::gdb::gdb_target::GdbTarget;
 use gdb::gdb_state;
 
@@ -45,7 +47,11 @@ const FW_WRITE_TICKS: u64 = 1000;
 const EXPECTED_CALIPTRA_BOOT_TIME_IN_CYCLES: u64 = 20_000_000; // 20 million cycles
 
 // CPU Main Loop (free_run no GDB)
-fn free_run(mut cpu: Cpu<CaliptraRootBus>, trace_path: Option<PathBuf>) {
+fn free_run(
+    cpu: &mut Cpu<CaliptraRootBus>,
+    trace_path: Option<PathBuf>,
+    exit_request: &Cell<Option<i32>>,
+) {
     if let Some(path) = trace_path {
         let mut f = File::create(path).unwrap();
         let trace_fn: &mut dyn FnMut(u32, RvInstr) = &mut |pc, instr| {
@@ -61,9 +67,17 @@ fn free_run(mut cpu: Cpu<CaliptraRootBus>, trace_path: Option<PathBuf>) {
         };
 
         // Need to have the loop in the same scope as trace_fn to prevent borrowing rules violation
-        while let StepAction::Continue = cpu.step(Some(trace_fn)) {}
+        while exit_request.get().is_none() {
+            if cpu.step(Some(trace_fn)) != StepAction::Continue {
+                break;
+            }
+        }
     } else {
-        while let StepAction::Continue = cpu.step(None) {}
+        while exit_request.get().is_none() {
+            if cpu.step(None) != StepAction::Continue {
+                break;
+            }
+        }
     };
 }
 
@@ -101,6 +115,27 @@ fn main() -> io::Result<()> {
                 .required(false)
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            arg!(--"trace" ... "Write a structured execution and bus trace to caliptra_trace.jsonl in log-dir")
+                .required(false)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--"coverage" <FILE> "Write the executed instruction address ranges to a file on exit")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"crypto-latency" <SPEC> "Crypto peripheral latencies: legacy, rtl, or a preset followed by overrides such as rtl,ecc384_sign=1300000")
+                .required(false)
+                .value_parser(value_parser!(String))
+                .default_value("legacy"),
+        )
+        .arg(
+            arg!(--"boot-timing" ... "Print the cycles spent in each boot phase on exit")
+                .required(false)
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             arg!(--"ueid" <U128> "128-bit Unique Endpoint Id")
                 .required(false)
@@ -207,6 +242,15 @@ fn main() -> io::Result<()> {
         exit(-1);
     }
 
+    let crypto_latency: CryptoLatency =
+        match args.get_one::<String>("crypto-latency").unwrap().parse() {
+            Ok(latency) => latency,
+            Err(e) => {
+                println!("{e}");
+                exit(-1);
+            }
+        };
+
     let mut current_fw_buf = Vec::new();
     if let Some(path) = args_current_fw {
         if !Path::new(&path).exists() {
@@ -232,6 +276,14 @@ fn main() -> io::Result<()> {
     let log_dir = Rc::new(args_log_dir.to_path_buf());
 
     let clock = Clock::new();
+    if args.get_flag("trace") {
+        let mut path = args_log_dir.clone();
+        path.push("caliptra_trace.jsonl");
+        let file = File::create(path)?;
+        clock
+            .tracer()
+            .set_output(Some(Box::new(BufWriter::new(file))));
+    }
 
     let req_idevid_csr = args.get_flag("req-idevid-csr");
     let req_ldevid_cert = args.get_flag("req-ldevid-cert");
@@ -249,15 +301,42 @@ fn main() -> io::Result<()> {
         },
     );
 
+    // Mutes the host callbacks while the GDB target replays the execution
+    // history
+    let replay = Rc::new(ReplayState::default());
+    let replay_uart = replay.clone();
+    let replay_fw = replay.clone();
+    let replay_update_fw = replay.clone();
+    let replay_csr = replay.clone();
+
+    // In free run, an exit requested by the firmware is deferred until the
+    // current instruction completes, so the coverage can be written. Under
+    // GDB the process exits from the callback, so flush the trace first.
+    let exit_request = Rc::new(Cell::new(None));
+    let exit_request_cloned = exit_request.clone();
+    let defer_exit = args.get_one::<String>("gdb-port").is_none();
+    let tracer = clock.tracer();
+
     let bus_args = CaliptraRootBusArgs {
         rom: rom_buffer,
         log_dir: args_log_dir.clone(),
         tb_services_cb: TbServicesCb::new(move |val| match val {
-            0x01 => exit(0xFF),
-            0xFF => exit(0x00),
+            0x01 | 0xFF => {
+                let code = if val == 0x01 { 0xFF } else { 0x00 };
+                if defer_exit {
+                    exit_request_cloned.set(Some(code));
+                } else {
+                    tracer.flush();
+                    exit(code)
+                }
+            }
+            _ if replay_uart.replaying() => {}
             _ => print!("{}", val as char),
         }),
         ready_for_fw_cb: ReadyForFwCb::new(move |args| {
+            if !replay_fw.begin_host_io() {
+                return;
+            }
             let firmware_buffer = current_fw_buf.clone();
             args.schedule_later(FW_WRITE_TICKS, move |mailbox: &mut MailboxInternal| {
                 upload_fw_to_mailbox(mailbox, firmware_buffer);
@@ -265,6 +344,9 @@ fn main() -> io::Result<()> {
         }),
         security_state,
         upload_update_fw: UploadUpdateFwCb::new(move |mailbox: &mut MailboxInternal| {
+            if !replay_update_fw.begin_host_io() {
+                return;
+            }
             upload_fw_to_mailbox(mailbox, update_fw_buf.clone());
         }),
         download_idevid_csr_cb: DownloadIdevidCsrCb::new(
@@ -273,9 +355,13 @@ fn main() -> io::Result<()> {
                 u32,
                 DebugManufService::Register,
             >| {
+                if !replay_csr.begin_host_io() {
+                    return;
+                }
                 download_idev_id_csr(mailbox, log_dir.clone(), cptra_dbg_manuf_service_reg);
             },
         ),
+        crypto_latency,
         ..Default::default()
     };
 
@@ -369,16 +455,20 @@ fn main() -> io::Result<()> {
             .write(|_| (*wdt_timeout >> 32) as u32);
     }
 
-    let cpu = Cpu::new(root_bus, clock);
+    let mut cpu = Cpu::new(root_bus, clock);
+    // FMC and runtime execute from ICCM
+    cpu.code_coverage
+        .add_region(CaliptraRootBus::ICCM_ORG, CaliptraRootBus::ICCM_SIZE);
 
     // Check if Optional GDB Port is passed
-    match args.get_one::<String>("gdb-port") {
+    let cpu = match args.get_one::<String>("gdb-port") {
         Some(port) => {
             // Create GDB Target Instance
-            let mut gdb_target = GdbTarget::new(cpu);
+            let mut gdb_target = GdbTarget::new(cpu, replay);
 
             // Execute CPU through GDB State Machine
             gdb_state::wait_for_gdb_run(&mut gdb_target, port.parse().unwrap());
+            gdb_target.into_cpu()
         }
         _ => {
             let instr_trace = if args.get_flag("trace-instr") {
@@ -390,8 +480,22 @@ fn main() -> io::Result<()> {
             };
 
             // If no GDB Port is passed, Free Run
-            free_run(cpu, instr_trace);
+            free_run(&mut cpu, instr_trace, &exit_request);
+            cpu
         }
+    };
+
+    if let Some(path) = args.get_one::<PathBuf>("coverage") {
+        let mut w = BufWriter::new(File::create(path)?);
+        cpu.code_coverage.save(&mut w)?;
+        w.flush()?;
+    }
+    if args.get_flag("boot-timing") {
+        print!("{}", cpu.bus.soc_reg.boot_timing());
+    }
+    cpu.clock.tracer().flush();
+    if let Some(code) = exit_request.get() {
+        exit(code);
     }
 
     Ok(())
@@ -104,6 +104,8 @@ static TESTS_TO_RUN: &[TestInfo] = &[
     TestInfo {extension: "C", name: "csw-01"},
     TestInfo {extension: "C", name: "cswsp-01"},
     TestInfo {extension: "C", name: "cxor-01"},
+    TestInfo {extension: "PMP", name: "pmp-tor-01"},
+    TestInfo {extension: "PMP", name: "pmp-napot-01"},
 ];
 
 fn into_io_error(err: impl Into<Box<dyn Error + Send + Sync>>) -> std::io::Error {
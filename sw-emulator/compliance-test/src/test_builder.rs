@@ -14,7 +14,7 @@ Abstract:
 use crate::exec::exec;
 use crate::fs::{self, TempDir, TempFile};
 use crate::{into_io_error, TestInfo};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[derive(Clone)]
@@ -74,10 +74,7 @@ impl TestBuilder {
                 .arg("-T")
                 .arg(self.linker_script.path())
                 .arg(
-                    self.config
-                        .test_root_path
-                        .join("riscv-test-suite/rv32i_m")
-                        .join(test.extension)
+                    self.suite_path(test)
                         .join("src")
                         .join(format!("{}.S", test.name)),
                 )
@@ -96,13 +93,21 @@ impl TestBuilder {
     }
     pub fn get_reference_data(&self, test: &TestInfo) -> std::io::Result<String> {
         String::from_utf8(fs::read(
-            self.config
-                .test_root_path
-                .join("riscv-test-suite/rv32i_m")
-                .join(test.extension)
+            self.suite_path(test)
                 .join("references")
                 .join(format!("{}.reference_output", test.name)),
         )?)
         .map_err(into_io_error)
     }
+    fn suite_path(&self, test: &TestInfo) -> PathBuf {
+        match test.extension {
+            // Tests of VeeR features that are not covered by riscv-arch-test
+            "PMP" => Path::new(env!("CARGO_MANIFEST_DIR")).join("target-files/pmp"),
+            _ => self
+                .config
+                .test_root_path
+                .join("riscv-test-suite/rv32i_m")
+                .join(test.extension),
+        }
+    }
 }
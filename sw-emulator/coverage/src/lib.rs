@@ -0,0 +1,149 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    lib.rs
+
+Abstract:
+
+    File contains the reader for code coverage files written by the emulator.
+
+--*/
+
+mod lines;
+mod report;
+
+pub use lines::{LineEntry, LineTable};
+pub use report::{FileCoverage, Report};
+
+use anyhow::Context;
+use std::ops::Range;
+use std::path::Path;
+
+/// Executed address ranges, as written by `CodeCoverage::save()` in
+/// caliptra-emu-cpu (one `start-end` pair of hex addresses per line, end
+/// exclusive). Coverage from any number of emulator runs can be merged.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CoverageData {
+    /// Sorted, non-overlapping and non-adjacent
+    ranges: Vec<Range<u32>>,
+}
+
+impl CoverageData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the contents of a coverage file
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut result = Self::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parse = || -> Option<Range<u32>> {
+                let (start, end) = line.split_once('-')?;
+                let start = u32::from_str_radix(start, 16).ok()?;
+                let end = u32::from_str_radix(end, 16).ok()?;
+                Some(start..end)
+            };
+            let Some(range) = parse() else {
+                anyhow::bail!("Invalid coverage range on line {}: {line:?}", line_no + 1);
+            };
+            result.add_range(range);
+        }
+        Ok(result)
+    }
+
+    /// Read and merge a coverage file. If `path` is a directory, all the
+    /// `*.cov` files in it are merged.
+    pub fn add_path(&mut self, path: &Path) -> anyhow::Result<()> {
+        if path.is_dir() {
+            let entries = std::fs::read_dir(path)
+                .with_context(|| format!("Failed to read directory {}", path.display()))?;
+            for entry in entries {
+                let entry_path = entry?.path();
+                if entry_path.extension().is_some_and(|ext| ext == "cov") {
+                    self.add_path(&entry_path)?;
+                }
+            }
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let data = Self::parse(&text).with_context(|| format!("In {}", path.display()))?;
+        self.merge(&data);
+        Ok(())
+    }
+
+    /// Mark `range` as executed
+    pub fn add_range(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+        // Ranges that overlap or touch the new range are merged into it
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Merge the coverage of another run
+    pub fn merge(&mut self, other: &CoverageData) {
+        for range in other.ranges.iter() {
+            self.add_range(range.clone());
+        }
+    }
+
+    /// Returns true if any byte of `range` was executed
+    pub fn any_executed(&self, range: Range<u32>) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= range.start);
+        self.ranges
+            .get(idx)
+            .is_some_and(|r| r.start < range.end && !range.is_empty())
+    }
+
+    pub fn ranges(&self) -> &[Range<u32>] {
+        &self.ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_merge() {
+        let mut data = CoverageData::parse("00000000-00000006\n0000000e-00000010\n").unwrap();
+        assert_eq!(data.ranges(), &[0..6, 14..16]);
+
+        let other = CoverageData::parse("00000004-0000000a\n\n40000000-40000004\n").unwrap();
+        data.merge(&other);
+        assert_eq!(data.ranges(), &[0..10, 14..16, 0x4000_0000..0x4000_0004]);
+
+        data.add_range(10..14);
+        assert_eq!(data.ranges(), &[0..16, 0x4000_0000..0x4000_0004]);
+
+        assert!(CoverageData::parse("00000000:00000004").is_err());
+        assert!(CoverageData::parse("0000000g-00000004").is_err());
+    }
+
+    #[test]
+    fn test_any_executed() {
+        let data = CoverageData::parse("00000004-00000008\n00000010-00000012").unwrap();
+        assert!(!data.any_executed(0..4));
+        assert!(data.any_executed(0..5));
+        assert!(data.any_executed(6..12));
+        assert!(!data.any_executed(8..16));
+        assert!(data.any_executed(8..17));
+        assert!(!data.any_executed(0x12..0x20));
+        assert!(!data.any_executed(4..4));
+    }
+}
@@ -0,0 +1,198 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    lines.rs
+
+Abstract:
+
+    File contains the address to source line mapping read from the DWARF
+    line tables of firmware ELF files.
+
+--*/
+
+use anyhow::Context;
+use elf::endian::AnyEndian;
+use elf::ElfBytes;
+use gimli::{EndianSlice, LittleEndian};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Instruction bytes generated for a source line
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineEntry {
+    pub addrs: Range<u32>,
+
+    /// Index into `LineTable::files()`
+    pub file: usize,
+
+    pub line: u32,
+}
+
+/// Address to source line mapping of the ROM, FMC and runtime
+#[derive(Clone, Debug, Default)]
+pub struct LineTable {
+    files: Vec<String>,
+    file_indices: HashMap<String, usize>,
+    entries: Vec<LineEntry>,
+}
+
+impl LineTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the line table of the ELF file at `path`
+    pub fn add_elf_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        self.add_elf(&bytes)
+            .with_context(|| format!("Failed to load line table from {}", path.display()))
+    }
+
+    /// Add the line table of an ELF file. The firmware must have been built
+    /// with debug info (the `firmware` profile includes line tables).
+    pub fn add_elf(&mut self, elf_bytes: &[u8]) -> anyhow::Result<()> {
+        let elf_file = ElfBytes::<AnyEndian>::minimal_parse(elf_bytes)
+            .with_context(|| "Failed to parse elf file")?;
+        let debug_line = elf_file.section_header_by_name(".debug_line")?;
+        if !matches!(debug_line, Some(sh) if sh.sh_size != 0) {
+            anyhow::bail!("ELF file has no .debug_line section");
+        }
+        let load_section = |id: gimli::SectionId| -> Result<_, gimli::Error> {
+            let data = elf_file
+                .section_header_by_name(id.name())
+                .ok()
+                .flatten()
+                .and_then(|sh| elf_file.section_data(&sh).ok())
+                .map_or(&[][..], |(data, _)| data);
+            Ok(EndianSlice::new(data, LittleEndian))
+        };
+        let dwarf = gimli::Dwarf::load(load_section)?;
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            // Maps the DWARF file index of this unit to the table file index
+            let mut unit_files = HashMap::new();
+            let mut prev: Option<(u64, u64, u32)> = None;
+            let mut rows = program.rows();
+            while let Some((header, row)) = rows.next_row()? {
+                // Each row covers the addresses up to the next row
+                if let Some((addr, file_index, line)) = prev.take() {
+                    if row.address() > addr && line != 0 {
+                        let file = match unit_files.get(&file_index) {
+                            Some(&file) => file,
+                            None => {
+                                let path = match header.file(file_index) {
+                                    Some(entry) => file_path(&dwarf, &unit, header, entry)?,
+                                    None => "<unknown>".into(),
+                                };
+                                let file = self.file_index(path);
+                                unit_files.insert(file_index, file);
+                                file
+                            }
+                        };
+                        if let (Ok(start), Ok(end)) =
+                            (u32::try_from(addr), u32::try_from(row.address()))
+                        {
+                            self.entries.push(LineEntry {
+                                addrs: start..end,
+                                file,
+                                line,
+                            });
+                        }
+                    }
+                }
+                if !row.end_sequence() {
+                    let line = row.line().map_or(0, |l| l.get() as u32);
+                    prev = Some((row.address(), row.file_index(), line));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a line entry for a file; used to build tables by hand
+    pub fn add_line(&mut self, addrs: Range<u32>, path: &str, line: u32) {
+        let file = self.file_index(path.into());
+        self.entries.push(LineEntry { addrs, file, line });
+    }
+
+    fn file_index(&mut self, path: String) -> usize {
+        if let Some(&index) = self.file_indices.get(&path) {
+            return index;
+        }
+        self.files.push(path.clone());
+        self.file_indices.insert(path, self.files.len() - 1);
+        self.files.len() - 1
+    }
+
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    pub fn entries(&self) -> &[LineEntry] {
+        &self.entries
+    }
+}
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+
+fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    header: &gimli::LineProgramHeader<Reader>,
+    entry: &gimli::FileEntry<Reader>,
+) -> anyhow::Result<String> {
+    let mut path = PathBuf::new();
+    if let Some(comp_dir) = &unit.comp_dir {
+        path.push(comp_dir.to_string_lossy().as_ref());
+    }
+    if let Some(dir) = entry.directory(header) {
+        // Absolute directories replace the compilation directory
+        path.push(dwarf.attr_string(unit, dir)?.to_string_lossy().as_ref());
+    }
+    path.push(
+        dwarf
+            .attr_string(unit, entry.path_name())?
+            .to_string_lossy()
+            .as_ref(),
+    );
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_add_elf() {
+        // Unit tests are built with debug info, so the test executable
+        // contains the line table of this file.
+        let mut table = LineTable::new();
+        table
+            .add_elf_file(&std::env::current_exe().unwrap())
+            .unwrap();
+        assert!(table
+            .files()
+            .iter()
+            .any(|f| f.ends_with("coverage/src/lines.rs")));
+        assert!(table
+            .entries()
+            .iter()
+            .all(|e| !e.addrs.is_empty() && e.line != 0));
+    }
+
+    #[test]
+    fn test_add_elf_without_debug_info() {
+        assert!(LineTable::new().add_elf(b"not an elf").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    main.rs
+
+Abstract:
+
+    Main entry point for the emulator code coverage report tool.
+
+--*/
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use caliptra_emu_coverage::{CoverageData, LineTable, Report};
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+
+fn main() {
+    let args = Command::new("caliptra-emu-coverage")
+        .about("Map emulator code coverage to the source lines of the firmware")
+        .arg(
+            arg!(--"elf" <FILE> "ROM, FMC or runtime ELF file (may be repeated)")
+                .required(true)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"include" <SUBSTR> "Only report source files whose path contains SUBSTR (may be repeated)")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            arg!(--"lcov" <FILE> "Write an lcov tracefile")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"html" <DIR> "Write an HTML report")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(<COVERAGE> ... "Coverage files, or directories of *.cov files, to merge")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .get_matches();
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &ArgMatches) -> anyhow::Result<()> {
+    let mut table = LineTable::new();
+    for path in args.get_many::<PathBuf>("elf").into_iter().flatten() {
+        table.add_elf_file(path)?;
+    }
+    let mut data = CoverageData::new();
+    for path in args.get_many::<PathBuf>("COVERAGE").into_iter().flatten() {
+        data.add_path(path)?;
+    }
+    let include: Vec<String> = args
+        .get_many::<String>("include")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+
+    let report = Report::new(&table, &data, &include);
+    for file in report.files.iter() {
+        println!(
+            "{:5}/{:<5} {}",
+            file.lines_hit(),
+            file.lines_found(),
+            file.path
+        );
+    }
+    println!("{:5}/{:<5} total", report.lines_hit(), report.lines_found());
+
+    if let Some(path) = args.get_one::<PathBuf>("lcov") {
+        let mut out = BufWriter::new(File::create(path)?);
+        report.write_lcov(&mut out)?;
+        out.flush()?;
+    }
+    if let Some(dir) = args.get_one::<PathBuf>("html") {
+        report.write_html(dir)?;
+    }
+    Ok(())
+}
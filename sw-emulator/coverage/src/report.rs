@@ -0,0 +1,256 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    report.rs
+
+Abstract:
+
+    File contains the per-line coverage report and its lcov and HTML output.
+
+--*/
+
+use crate::{CoverageData, LineTable};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+
+/// Line coverage of a single source file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileCoverage {
+    pub path: String,
+
+    /// Lines with instructions, and whether any of them were executed
+    pub lines: BTreeMap<u32, bool>,
+}
+
+impl FileCoverage {
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&hit| hit).count()
+    }
+}
+
+/// Line coverage of all source files of the firmware
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    /// Sorted by path
+    pub files: Vec<FileCoverage>,
+}
+
+impl Report {
+    /// Map the executed addresses in `data` to source lines. If `include` is
+    /// not empty, only files whose path contains one of its strings are
+    /// reported.
+    pub fn new(table: &LineTable, data: &CoverageData, include: &[String]) -> Self {
+        let mut files: BTreeMap<usize, BTreeMap<u32, bool>> = BTreeMap::new();
+        for entry in table.entries() {
+            let path = &table.files()[entry.file];
+            if !include.is_empty() && !include.iter().any(|s| path.contains(s.as_str())) {
+                continue;
+            }
+            let hit = files
+                .entry(entry.file)
+                .or_default()
+                .entry(entry.line)
+                .or_default();
+            *hit |= data.any_executed(entry.addrs.clone());
+        }
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(|(file, lines)| FileCoverage {
+                path: table.files()[file].clone(),
+                lines,
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self { files }
+    }
+
+    pub fn lines_found(&self) -> usize {
+        self.files.iter().map(FileCoverage::lines_found).sum()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files.iter().map(FileCoverage::lines_hit).sum()
+    }
+
+    /// Write the report as an lcov tracefile, for use with `genhtml` or
+    /// coverage services
+    pub fn write_lcov(&self, w: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(w, "TN:")?;
+        for file in self.files.iter() {
+            writeln!(w, "SF:{}", file.path)?;
+            for (line, hit) in file.lines.iter() {
+                writeln!(w, "DA:{},{}", line, u32::from(*hit))?;
+            }
+            writeln!(w, "LF:{}", file.lines_found())?;
+            writeln!(w, "LH:{}", file.lines_hit())?;
+            writeln!(w, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Write an HTML report to `dir`: `index.html` with the coverage of each
+    /// file, and one page per file with the source annotated (if the source
+    /// is available on this machine).
+    pub fn write_html(&self, dir: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut index = String::new();
+        html_header(&mut index, "Code coverage");
+        writeln!(
+            index,
+            "<p>Total: {}</p>\n<table>\n<tr><th>File</th><th>Lines</th><th>Coverage</th></tr>",
+            summary(self.lines_hit(), self.lines_found())
+        )?;
+        for (i, file) in self.files.iter().enumerate() {
+            let page = format!("file{i}.html");
+            writeln!(
+                index,
+                "<tr><td><a href=\"{page}\">{}</a></td><td>{}/{}</td><td>{:.1}%</td></tr>",
+                escape(&file.path),
+                file.lines_hit(),
+                file.lines_found(),
+                percent(file.lines_hit(), file.lines_found())
+            )?;
+            std::fs::write(dir.join(&page), file_page(file))?;
+        }
+        index.push_str("</table>\n</body>\n</html>\n");
+        std::fs::write(dir.join("index.html"), index)?;
+        Ok(())
+    }
+}
+
+fn file_page(file: &FileCoverage) -> String {
+    let mut page = String::new();
+    html_header(&mut page, &file.path);
+    let _ = writeln!(
+        page,
+        "<p>{}</p>\n<pre>",
+        summary(file.lines_hit(), file.lines_found())
+    );
+    let line_class = |line: u32| match file.lines.get(&line) {
+        Some(true) => "hit",
+        Some(false) => "miss",
+        None => "none",
+    };
+    match std::fs::read_to_string(&file.path) {
+        Ok(source) => {
+            for (i, text) in source.lines().enumerate() {
+                let line = i as u32 + 1;
+                let _ = writeln!(
+                    page,
+                    "<span class=\"{}\">{line:5} {}</span>",
+                    line_class(line),
+                    escape(text)
+                );
+            }
+        }
+        Err(_) => {
+            for &line in file.lines.keys() {
+                let _ = writeln!(page, "<span class=\"{}\">{line:5}</span>", line_class(line));
+            }
+        }
+    }
+    page.push_str("</pre>\n</body>\n</html>\n");
+    page
+}
+
+fn html_header(out: &mut String, title: &str) {
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n.hit {{ background-color: #c0ffc0; }}\n.miss {{ background-color: #ffc0c0; }}\n\
+         td, th {{ padding: 0 1em; text-align: left; }}\n</style>\n</head>\n<body>\n<h1>{}</h1>",
+        escape(title),
+        escape(title)
+    );
+}
+
+fn summary(hit: usize, found: usize) -> String {
+    format!(
+        "{hit} of {found} lines executed ({:.1}%)",
+        percent(hit, found)
+    )
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        return 100.0;
+    }
+    hit as f64 * 100.0 / found as f64
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(include: &[String]) -> Report {
+        let mut table = LineTable::new();
+        table.add_line(0x0..0x4, "rom/dev/src/flow/cold_reset.rs", 10);
+        table.add_line(0x4..0x8, "rom/dev/src/flow/cold_reset.rs", 11);
+        // Inlined code can map the same line to several ranges
+        table.add_line(0x100..0x104, "rom/dev/src/flow/cold_reset.rs", 11);
+        table.add_line(0x8..0xc, "drivers/src/sha256.rs", 3);
+        table.add_line(0x4000_0000..0x4000_0002, "fmc/src/main.rs", 7);
+        let data = CoverageData::parse("00000000-00000004\n00000100-00000102\n").unwrap();
+        Report::new(&table, &data, include)
+    }
+
+    #[test]
+    fn test_report() {
+        let all = report(&[]);
+        let paths: Vec<_> = all.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "drivers/src/sha256.rs",
+                "fmc/src/main.rs",
+                "rom/dev/src/flow/cold_reset.rs"
+            ]
+        );
+        assert_eq!(all.files[2].lines, BTreeMap::from([(10, true), (11, true)]));
+        assert_eq!(all.lines_found(), 4);
+        assert_eq!(all.lines_hit(), 2);
+
+        let flow = report(&["rom/dev/src/flow".into()]);
+        assert_eq!(flow.files.len(), 1);
+    }
+
+    #[test]
+    fn test_write_lcov() {
+        let mut out = vec![];
+        report(&[]).write_lcov(&mut out).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            "TN:\n\
+             SF:drivers/src/sha256.rs\nDA:3,0\nLF:1\nLH:0\nend_of_record\n\
+             SF:fmc/src/main.rs\nDA:7,0\nLF:1\nLH:0\nend_of_record\n\
+             SF:rom/dev/src/flow/cold_reset.rs\nDA:10,1\nDA:11,1\nLF:2\nLH:2\nend_of_record\n"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("Result<(), \"&\">"),
+            "Result&lt;(), &quot;&amp;&quot;&gt;"
+        );
+    }
+}
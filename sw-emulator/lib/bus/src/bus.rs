@@ -74,4 +74,11 @@ pub trait Bus {
     fn update_reset(&mut self) {
         // By default, do nothing
     }
+
+    /// Returns the name of the peripheral that decodes `addr`, used to
+    /// annotate traces. `#[derive(Bus)]` implements this for buses with
+    /// `#[peripheral]` fields.
+    fn peripheral_name(&self, _addr: RvAddr) -> Option<&'static str> {
+        None
+    }
 }
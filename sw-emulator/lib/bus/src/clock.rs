@@ -19,7 +19,8 @@ use std::{
     rc::Rc,
 };
 
-use crate::Bus;
+use crate::{Bus, Tracer};
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 /// Peripherals that want to use timer-based deferred execution will typically
 /// store a clone of Timer inside themselves, and use it to schedule future
@@ -123,10 +124,32 @@ impl Timer {
     pub fn cancel(&self, handle: ActionHandle) {
         self.clock.cancel(handle)
     }
+
+    /// Appends a (possibly empty) action handle to a snapshot. The action
+    /// itself is saved with the [`Clock`].
+    pub fn save_action(&self, w: &mut SnapshotWriter, action: &Option<ActionHandle>) {
+        w.write_bool(action.is_some());
+        if let Some(action) = action {
+            action.0.save_state(w);
+        }
+    }
+
+    /// Restores an action handle saved with [`Timer::save_action`], bound to
+    /// the clock of this timer.
+    pub fn restore_action(
+        &self,
+        r: &mut SnapshotReader,
+    ) -> Result<Option<ActionHandle>, SnapshotError> {
+        if !r.read_bool()? {
+            return Ok(None);
+        }
+        Ok(Some(self.clock.restore_action_handle(r)?.into()))
+    }
 }
 
 pub struct Clock {
     clock: Rc<ClockImpl>,
+    tracer: Tracer,
 }
 impl Default for Clock {
     fn default() -> Self {
@@ -136,8 +159,12 @@ impl Default for Clock {
 impl Clock {
     /// Constructs a new Clock with the cycle counter set to 0.
     pub fn new() -> Clock {
+        let clock = ClockImpl::new();
         Self {
-            clock: ClockImpl::new(),
+            tracer: Tracer::new(Timer {
+                clock: Rc::clone(&clock),
+            }),
+            clock,
         }
     }
 
@@ -146,6 +173,11 @@ impl Clock {
         Timer::new(self)
     }
 
+    /// Returns the tracer shared by everything attached to this clock.
+    pub fn tracer(&self) -> Tracer {
+        self.tracer.clone()
+    }
+
     /// Returns the number of simulated clock cycles that have elapsed since
     /// simulation start.
     #[inline]
@@ -182,12 +214,41 @@ impl Clock {
                 }
                 TimerAction::Nmi { .. } => {}
                 TimerAction::SetNmiVec { .. } => {}
+                TimerAction::SetExtIntr { .. } => {}
+                TimerAction::SetTimerIntr { .. } => {}
             }
         }
         fired_actions
     }
 }
 
+/// Saves the current time and all pending timer actions. Restoring replaces
+/// the pending actions of this clock; the handles held by peripherals are
+/// restored with [`Timer::restore_action`].
+impl Snapshot for Clock {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        let clock = &self.clock;
+        w.write_u64(clock.now());
+        w.write_u64(clock.next_action_id.get());
+        let actions = clock.action_handles.borrow();
+        w.write_usize(actions.len());
+        actions.iter().for_each(|action| action.save_state(w));
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let clock = &self.clock;
+        clock.now.set(r.read_u64()?);
+        clock.next_action_id.set(r.read_u64()?);
+        let mut actions = BTreeSet::new();
+        for _ in 0..r.read_usize()? {
+            actions.insert(clock.restore_action_handle(r)?);
+        }
+        clock.recompute_next_action_time(&actions);
+        *clock.action_handles.borrow_mut() = actions;
+        Ok(())
+    }
+}
+
 /// Represents an action scheduled with a `Timer`. Returned by
 /// [`Timer::schedule_poll_at`] and passed to [`Timer::has_fired()`] or
 /// [`Timer::cancel`].
@@ -214,6 +275,13 @@ impl From<ActionHandle> for ActionHandleImpl {
         val.0
     }
 }
+impl ActionHandleImpl {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u64(self.time);
+        w.write_u64(self.id.id);
+        w.write(&self.action);
+    }
+}
 
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 struct TimerActionId {
@@ -239,8 +307,80 @@ pub enum TimerAction {
     Poll,
     WarmReset,
     UpdateReset,
-    Nmi { mcause: u32 },
-    SetNmiVec { addr: u32 },
+    Nmi {
+        mcause: u32,
+    },
+    SetNmiVec {
+        addr: u32,
+    },
+    /// The highest-priority enabled interrupt pending in the PIC has changed.
+    /// `id` is 0 when no interrupt is pending; `priority` is the raw meipl
+    /// value of the source, interpreted in reverse order if `reverse` is set.
+    SetExtIntr {
+        id: u8,
+        priority: u8,
+        reverse: bool,
+    },
+    /// The machine timer interrupt line (mtime >= mtimecmp) has changed.
+    SetTimerIntr {
+        pending: bool,
+    },
+}
+
+impl Snapshot for TimerAction {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        match *self {
+            TimerAction::Poll => w.write_u8(0),
+            TimerAction::WarmReset => w.write_u8(1),
+            TimerAction::UpdateReset => w.write_u8(2),
+            TimerAction::Nmi { mcause } => {
+                w.write_u8(3);
+                w.write_u32(mcause);
+            }
+            TimerAction::SetNmiVec { addr } => {
+                w.write_u8(4);
+                w.write_u32(addr);
+            }
+            TimerAction::SetExtIntr {
+                id,
+                priority,
+                reverse,
+            } => {
+                w.write_u8(5);
+                w.write_u8(id);
+                w.write_u8(priority);
+                w.write_bool(reverse);
+            }
+            TimerAction::SetTimerIntr { pending } => {
+                w.write_u8(6);
+                w.write_bool(pending);
+            }
+        }
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = match r.read_u8()? {
+            0 => TimerAction::Poll,
+            1 => TimerAction::WarmReset,
+            2 => TimerAction::UpdateReset,
+            3 => TimerAction::Nmi {
+                mcause: r.read_u32()?,
+            },
+            4 => TimerAction::SetNmiVec {
+                addr: r.read_u32()?,
+            },
+            5 => TimerAction::SetExtIntr {
+                id: r.read_u8()?,
+                priority: r.read_u8()?,
+                reverse: r.read_bool()?,
+            },
+            6 => TimerAction::SetTimerIntr {
+                pending: r.read_bool()?,
+            },
+            _ => Err(SnapshotError::InvalidValue("TimerAction"))?,
+        };
+        Ok(())
+    }
 }
 
 struct ClockImpl {
@@ -318,6 +458,19 @@ impl ClockImpl {
             .set(self.next_action_id.get().wrapping_add(1));
         result
     }
+    fn restore_action_handle(
+        self: &Rc<Self>,
+        r: &mut SnapshotReader,
+    ) -> Result<ActionHandleImpl, SnapshotError> {
+        let time = r.read_u64()?;
+        let id = TimerActionId {
+            timer_ptr: Rc::as_ptr(self),
+            id: r.read_u64()?,
+        };
+        let mut action = TimerAction::Poll;
+        r.read(&mut action)?;
+        Ok(ActionHandleImpl { time, id, action })
+    }
     fn has_fired(&self, action_time: u64) -> bool {
         self.now().wrapping_sub(action_time) < (u64::MAX >> 1)
     }
@@ -523,4 +676,38 @@ mod tests {
 
         clock1.timer().cancel(clock0_action0);
     }
+
+    #[test]
+    fn test_snapshot() {
+        let clock = Clock::new();
+        let timer = clock.timer();
+        clock.increment(100);
+        let action0 = Some(timer.schedule_poll_in(10));
+        let action1 = Some(timer.schedule_action_in(20, TimerAction::Nmi { mcause: 5 }));
+        let mut w = SnapshotWriter::new();
+        w.write(&clock);
+        timer.save_action(&mut w, &action0);
+        timer.save_action(&mut w, &action1);
+        timer.save_action(&mut w, &None);
+        let snapshot = w.finish().unwrap();
+
+        let mut restored = Clock::new();
+        let restored_timer = restored.timer();
+        restored_timer.schedule_poll_in(5);
+        let mut r = SnapshotReader::new(&snapshot);
+        r.read(&mut restored).unwrap();
+        let mut action0 = restored_timer.restore_action(&mut r).unwrap();
+        let action1 = restored_timer.restore_action(&mut r).unwrap();
+        assert!(restored_timer.restore_action(&mut r).unwrap().is_none());
+        r.finish().unwrap();
+
+        // Handles are bound to the restored clock.
+        assert_eq!(restored.now(), 100);
+        restored_timer.cancel(action1.unwrap());
+        assert!(restored.increment(9).is_empty());
+        assert!(!restored_timer.fired(&mut action0));
+        assert_eq!(restored.increment(1), HashSet::from([TimerAction::Poll]));
+        assert!(restored_timer.fired(&mut action0));
+        assert!(restored.increment(100).is_empty());
+    }
 }
@@ -21,6 +21,7 @@ mod register;
 mod register_array;
 mod rom;
 pub mod testing;
+mod trace;
 
 pub use crate::bus::{Bus, BusError};
 pub use crate::clock::{ActionHandle, Clock, Timer, TimerAction};
@@ -33,3 +34,4 @@ pub use crate::register::{
 };
 pub use crate::register_array::{ReadWriteRegisterArray, RegisterArray};
 pub use crate::rom::Rom;
+pub use crate::trace::{BusMaster, TraceEvent, Tracer};
@@ -14,7 +14,9 @@ Abstract:
 --*/
 
 use crate::BusError;
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 /// Memory Exception
 #[allow(dead_code)]
@@ -386,6 +388,16 @@ impl Mem {
     }
 }
 
+impl Snapshot for Mem {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_bytes(&self.data);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read_bytes_into(&mut self.data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
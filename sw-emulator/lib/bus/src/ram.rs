@@ -13,7 +13,9 @@ Abstract:
 --*/
 
 use crate::{mem::Mem, Bus, BusError};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 /// Read Only Memory Device
 pub struct Ram {
@@ -98,6 +100,16 @@ impl Bus for Ram {
     }
 }
 
+impl Snapshot for Ram {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.data);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,4 +154,26 @@ mod tests {
             Some(BusError::StoreAccessFault),
         )
     }
+
+    #[test]
+    fn test_snapshot() {
+        let mut ram = Ram::new(vec![1, 2, 3, 4]);
+        let mut w = SnapshotWriter::new();
+        w.write(&ram);
+        let snapshot = w.finish().unwrap();
+
+        ram.write(RvSize::Word, 0, 0xdead_beef).unwrap();
+        let mut r = SnapshotReader::new(&snapshot);
+        r.read(&mut ram).unwrap();
+        assert_eq!(ram.data(), &[1, 2, 3, 4]);
+
+        let mut small = Ram::new(vec![0; 2]);
+        assert_eq!(
+            SnapshotReader::new(&snapshot).read(&mut small),
+            Err(SnapshotError::SizeMismatch {
+                expected: 2,
+                actual: 4
+            })
+        );
+    }
 }
@@ -14,7 +14,9 @@ Abstract:
 
 use crate::mem::Mem;
 use crate::{Bus, BusError};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::registers::InMemoryRegister;
 use tock_registers::{LocalRegisterCopy, RegisterLongName, UIntLike};
@@ -475,6 +477,45 @@ impl<const N: usize> Default for WriteOnlyMemory<N> {
     }
 }
 
+macro_rules! impl_snapshot_for_register {
+    ($name:ident) => {
+        impl<T: UIntLike + Snapshot, R: RegisterLongName> Snapshot for $name<T, R> {
+            fn save_state(&self, w: &mut SnapshotWriter) {
+                w.write(&self.reg.get());
+            }
+
+            fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+                let mut val = T::zero();
+                r.read(&mut val)?;
+                self.reg.set(val);
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_snapshot_for_register!(ReadWriteRegister);
+impl_snapshot_for_register!(ReadOnlyRegister);
+impl_snapshot_for_register!(WriteOnlyRegister);
+
+macro_rules! impl_snapshot_for_memory {
+    ($name:ident) => {
+        impl<const N: usize> Snapshot for $name<N> {
+            fn save_state(&self, w: &mut SnapshotWriter) {
+                w.write(&self.data);
+            }
+
+            fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+                r.read(&mut self.data)
+            }
+        }
+    };
+}
+
+impl_snapshot_for_memory!(ReadWriteMemory);
+impl_snapshot_for_memory!(ReadOnlyMemory);
+impl_snapshot_for_memory!(WriteOnlyMemory);
+
 #[cfg(test)]
 mod tests {
     use super::*;
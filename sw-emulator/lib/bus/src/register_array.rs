@@ -5,7 +5,9 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use tock_registers::{LocalRegisterCopy, RegisterLongName, UIntLike};
 
 use crate::{Bus, BusError, Register};
@@ -101,6 +103,26 @@ impl<T: UIntLike + Into<RvData> + TryFrom<RvData>, const SIZE: usize, R: Registe
     }
 }
 
+impl<
+        T: UIntLike + Into<RvData> + TryFrom<RvData> + Snapshot,
+        const SIZE: usize,
+        R: RegisterLongName,
+    > Snapshot for ReadWriteRegisterArray<T, SIZE, R>
+{
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.iter().for_each(|reg| w.write(&reg.get()));
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for reg in self.regs.iter_mut() {
+            let mut val = T::zero();
+            r.read(&mut val)?;
+            reg.set(val);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use tock_registers::register_bitfields;
@@ -14,7 +14,9 @@ Abstract:
 
 use crate::mem::Mem;
 use crate::{Bus, BusError};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 /// Read Only Memory Device
 pub struct Rom {
@@ -89,6 +91,16 @@ impl Bus for Rom {
     }
 }
 
+impl Snapshot for Rom {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.data);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
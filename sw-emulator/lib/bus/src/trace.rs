@@ -0,0 +1,314 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    trace.rs
+
+Abstract:
+
+    File contains the structured execution trace emitted by the emulator.
+
+--*/
+
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use crate::{BusError, Timer};
+use caliptra_emu_types::{RvAddr, RvData, RvSize};
+
+/// Which side of the SoC initiated a bus access
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BusMaster {
+    /// The Caliptra microcontroller
+    Uc,
+    /// The SoC, through the APB interface
+    Soc,
+}
+
+impl BusMaster {
+    fn name(self) -> &'static str {
+        match self {
+            BusMaster::Uc => "uc",
+            BusMaster::Soc => "soc",
+        }
+    }
+}
+
+/// A single structured trace event
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TraceEvent {
+    /// Instruction about to be executed. `size` is 2 for compressed
+    /// instructions; `asm` is the disassembly of the expanded instruction.
+    Instr {
+        pc: RvAddr,
+        instr: u32,
+        size: u8,
+        asm: String,
+    },
+
+    /// General purpose register write
+    RegWrite { reg: u8, val: RvData },
+
+    /// Control and status register write
+    CsrWrite { csr: RvAddr, val: RvData },
+
+    /// Bus read. `periph` is the field name from the `#[peripheral]` map.
+    BusRead {
+        master: BusMaster,
+        periph: Option<&'static str>,
+        size: RvSize,
+        addr: RvAddr,
+        result: Result<RvData, BusError>,
+    },
+
+    /// Bus write. `periph` is the field name from the `#[peripheral]` map.
+    BusWrite {
+        master: BusMaster,
+        periph: Option<&'static str>,
+        size: RvSize,
+        addr: RvAddr,
+        val: RvData,
+        result: Result<(), BusError>,
+    },
+
+    /// Key vault slot read by a crypto engine. `allowed` is false if the key
+    /// was locked or not enabled for `usage`.
+    KeyRead {
+        key_id: u32,
+        usage: u32,
+        allowed: bool,
+    },
+
+    /// Key vault slot written by a crypto engine
+    KeyWrite {
+        key_id: u32,
+        usage: u32,
+        allowed: bool,
+    },
+
+    /// Mailbox FSM state change (state names as in MBOX_STATUS.MBOX_FSM_PS)
+    MboxState {
+        from: &'static str,
+        to: &'static str,
+    },
+}
+
+impl TraceEvent {
+    /// Write the event as a single line JSON object, tagged with `cycle`.
+    pub fn write_json(&self, cycle: u64, w: &mut dyn Write) -> std::io::Result<()> {
+        write!(w, "{{\"cycle\":{cycle},")?;
+        match self {
+            TraceEvent::Instr {
+                pc,
+                instr,
+                size,
+                asm,
+            } => write!(
+                w,
+                "\"type\":\"instr\",\"pc\":{pc},\"instr\":{instr},\"size\":{size},\"asm\":\"{}\"",
+                JsonEscaped(asm)
+            )?,
+            TraceEvent::RegWrite { reg, val } => {
+                write!(w, "\"type\":\"reg_write\",\"reg\":{reg},\"val\":{val}")?
+            }
+            TraceEvent::CsrWrite { csr, val } => {
+                write!(w, "\"type\":\"csr_write\",\"csr\":{csr},\"val\":{val}")?
+            }
+            TraceEvent::BusRead {
+                master,
+                periph,
+                size,
+                addr,
+                result,
+            } => {
+                write!(w, "\"type\":\"bus_read\",")?;
+                write_bus_fields(w, *master, *periph, *size, *addr)?;
+                match result {
+                    Ok(val) => write!(w, ",\"val\":{val}")?,
+                    Err(e) => write!(w, ",\"fault\":\"{e:?}\"")?,
+                }
+            }
+            TraceEvent::BusWrite {
+                master,
+                periph,
+                size,
+                addr,
+                val,
+                result,
+            } => {
+                write!(w, "\"type\":\"bus_write\",")?;
+                write_bus_fields(w, *master, *periph, *size, *addr)?;
+                write!(w, ",\"val\":{val}")?;
+                if let Err(e) = result {
+                    write!(w, ",\"fault\":\"{e:?}\"")?;
+                }
+            }
+            TraceEvent::KeyRead {
+                key_id,
+                usage,
+                allowed,
+            } => write!(
+                w,
+                "\"type\":\"key_read\",\"key_id\":{key_id},\"usage\":{usage},\"allowed\":{allowed}"
+            )?,
+            TraceEvent::KeyWrite {
+                key_id,
+                usage,
+                allowed,
+            } => write!(
+                w,
+                "\"type\":\"key_write\",\"key_id\":{key_id},\"usage\":{usage},\"allowed\":{allowed}"
+            )?,
+            TraceEvent::MboxState { from, to } => write!(
+                w,
+                "\"type\":\"mbox_state\",\"from\":\"{from}\",\"to\":\"{to}\""
+            )?,
+        }
+        writeln!(w, "}}")
+    }
+}
+
+fn write_bus_fields(
+    w: &mut dyn Write,
+    master: BusMaster,
+    periph: Option<&'static str>,
+    size: RvSize,
+    addr: RvAddr,
+) -> std::io::Result<()> {
+    write!(w, "\"master\":\"{}\",", master.name())?;
+    match periph {
+        Some(periph) => write!(w, "\"periph\":\"{periph}\",")?,
+        None => write!(w, "\"periph\":null,")?,
+    }
+    write!(w, "\"size\":{},\"addr\":{addr}", usize::from(size))
+}
+
+struct JsonEscaped<'a>(&'a str);
+
+impl std::fmt::Display for JsonEscaped<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write;
+        for ch in self.0.chars() {
+            match ch {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Handle used by the CPU and peripherals to emit [`TraceEvent`]s. All
+/// tracers obtained from the same [`crate::Clock`] share one output, and tag
+/// events with the current cycle.
+///
+/// Tracing is disabled until an output is set with [`Tracer::set_output`].
+/// Callers building expensive events should check [`Tracer::enabled`] first.
+#[derive(Clone, Default)]
+pub struct Tracer {
+    timer: Option<Timer>,
+    out: Rc<RefCell<Option<Box<dyn Write>>>>,
+}
+
+impl Tracer {
+    pub(crate) fn new(timer: Timer) -> Self {
+        Self {
+            timer: Some(timer),
+            out: Default::default(),
+        }
+    }
+
+    /// Start (or, with `None`, stop) writing events to `out`. The previous
+    /// output is flushed.
+    pub fn set_output(&self, out: Option<Box<dyn Write>>) {
+        self.flush();
+        *self.out.borrow_mut() = out;
+    }
+
+    /// Returns true if events are being written
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.out.borrow().is_some()
+    }
+
+    /// Write `event` to the trace output, if any. Tracing is turned off if
+    /// the output fails.
+    pub fn emit(&self, event: TraceEvent) {
+        let mut out = self.out.borrow_mut();
+        if let Some(w) = out.as_mut() {
+            let cycle = self.timer.as_ref().map_or(0, |t| t.now());
+            if let Err(e) = event.write_json(cycle, w) {
+                eprintln!("Disabling trace output: {e}");
+                *out = None;
+            }
+        }
+    }
+
+    /// Flush the trace output
+    pub fn flush(&self) {
+        if let Some(w) = self.out.borrow_mut().as_mut() {
+            let _ = w.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Clock;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_emit() {
+        let clock = Clock::new();
+        let tracer = clock.tracer();
+        assert!(!tracer.enabled());
+        tracer.emit(TraceEvent::RegWrite { reg: 1, val: 2 });
+
+        let buf = SharedBuf::default();
+        clock.tracer().set_output(Some(Box::new(buf.clone())));
+        assert!(tracer.enabled());
+        clock.increment(5);
+        tracer.emit(TraceEvent::Instr {
+            pc: 0x10,
+            instr: 0x0010_8093,
+            size: 4,
+            asm: "addi ra, ra, 1".into(),
+        });
+        tracer.emit(TraceEvent::BusRead {
+            master: BusMaster::Uc,
+            periph: Some("key_vault"),
+            size: RvSize::Word,
+            addr: 0x1001_8000,
+            result: Err(BusError::LoadAccessFault),
+        });
+        tracer.emit(TraceEvent::MboxState {
+            from: "MBOX_IDLE",
+            to: "MBOX_RDY_FOR_CMD",
+        });
+        assert_eq!(
+            std::str::from_utf8(&buf.0.borrow()).unwrap(),
+            "{\"cycle\":5,\"type\":\"instr\",\"pc\":16,\"instr\":1081491,\"size\":4,\"asm\":\"addi ra, ra, 1\"}\n\
+             {\"cycle\":5,\"type\":\"bus_read\",\"master\":\"uc\",\"periph\":\"key_vault\",\"size\":4,\"addr\":268533760,\"fault\":\"LoadAccessFault\"}\n\
+             {\"cycle\":5,\"type\":\"mbox_state\",\"from\":\"MBOX_IDLE\",\"to\":\"MBOX_RDY_FOR_CMD\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_json_escape() {
+        assert_eq!(JsonEscaped("a\"b\\c\n").to_string(), "a\\\"b\\\\c\\u000a");
+    }
+}
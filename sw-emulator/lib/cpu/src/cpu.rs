@@ -13,46 +13,107 @@ Abstract:
 --*/
 
 use crate::csr_file::{Csr, CsrFile};
+use crate::disasm::disassemble;
+use crate::fault::{Fault, FaultAction, FaultInjector, FaultKind, FiredFault};
 use crate::instr::Instr;
+use crate::pmp::PmpAccess;
 use crate::types::{RvInstr, RvMStatus};
 use crate::xreg_file::{XReg, XRegFile};
 use bit_vec::BitVec;
-use caliptra_emu_bus::{Bus, BusError, Clock, TimerAction};
-use caliptra_emu_types::{RvAddr, RvData, RvException, RvSize};
+use caliptra_emu_bus::{Bus, BusError, BusMaster, Clock, TimerAction, TraceEvent, Tracer};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvException, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
+use std::io::Write;
+use std::ops::Range;
 
 pub type InstrTracer<'a> = dyn FnMut(u32, RvInstr) + 'a;
 
 #[derive(Clone)]
-pub struct CodeCoverage {
+struct CoverageRegion {
+    base: RvAddr,
     bit_vec: BitVec,
 }
 
+/// Bitmap of the instruction bytes executed, for one or more address regions
+#[derive(Clone)]
+pub struct CodeCoverage {
+    regions: Vec<CoverageRegion>,
+}
+
 impl CodeCoverage {
+    /// Create a coverage map for the region starting at address 0
     pub fn new(capacity_in_bytes: usize) -> Self {
         Self {
-            bit_vec: BitVec::from_elem(capacity_in_bytes, false),
+            regions: vec![CoverageRegion {
+                base: 0,
+                bit_vec: BitVec::from_elem(capacity_in_bytes, false),
+            }],
         }
     }
 
+    /// Also track execution in the region starting at `base`
+    pub fn add_region(&mut self, base: RvAddr, capacity_in_bytes: usize) {
+        self.regions.push(CoverageRegion {
+            base,
+            bit_vec: BitVec::from_elem(capacity_in_bytes, false),
+        });
+    }
+
     pub fn log_execution(&mut self, pc: RvData, instr: &Instr) {
-        if (pc as usize) < self.bit_vec.len() {
-            let num_bytes = match instr {
-                Instr::Compressed(_) => 2,
-                Instr::General(_) => 4,
-            };
-
-            // Mark the bytes corresponding to the executed instruction as true.
-            for i in 0..num_bytes {
-                let byte_index = (pc as usize) + i;
-                if byte_index < self.bit_vec.len() {
-                    self.bit_vec.set(byte_index, true);
+        let Some(region) = self
+            .regions
+            .iter_mut()
+            .find(|r| pc >= r.base && ((pc - r.base) as usize) < r.bit_vec.len())
+        else {
+            return;
+        };
+        let num_bytes = match instr {
+            Instr::Compressed(_) => 2,
+            Instr::General(_) => 4,
+        };
+
+        // Mark the bytes corresponding to the executed instruction as true.
+        let offset = (pc - region.base) as usize;
+        for byte_index in offset..usize::min(offset + num_bytes, region.bit_vec.len()) {
+            region.bit_vec.set(byte_index, true);
+        }
+    }
+
+    /// Bitmap of the region starting at address 0
+    pub fn code_coverage_bitmap(&self) -> &BitVec {
+        &self.regions[0].bit_vec
+    }
+
+    /// Returns the executed address ranges, in ascending order
+    pub fn executed_ranges(&self) -> Vec<Range<RvAddr>> {
+        let mut result = vec![];
+        for region in self.regions.iter() {
+            let mut start = None;
+            for (i, executed) in region.bit_vec.iter().chain([false]).enumerate() {
+                let addr = region.base + i as RvAddr;
+                match (start, executed) {
+                    (None, true) => start = Some(addr),
+                    (Some(s), false) => {
+                        result.push(s..addr);
+                        start = None;
+                    }
+                    _ => {}
                 }
             }
         }
+        result.sort_by_key(|r| r.start);
+        result
     }
 
-    pub fn code_coverage_bitmap(&self) -> &BitVec {
-        &self.bit_vec
+    /// Write the executed address ranges, one `start-end` pair of hex
+    /// addresses per line (end exclusive). Files from several runs can be
+    /// merged by taking the union of their ranges.
+    pub fn save(&self, w: &mut dyn Write) -> std::io::Result<()> {
+        for range in self.executed_ranges() {
+            writeln!(w, "{:08x}-{:08x}", range.start, range.end)?;
+        }
+        Ok(())
     }
 }
 
@@ -88,6 +149,31 @@ impl Default for WatchPtrCfg {
         Self::new()
     }
 }
+
+/// Highest-priority external interrupt presented by the PIC
+#[derive(Clone, Copy, Default)]
+struct ExtIntr {
+    /// Interrupt source ID; 0 if no interrupt is pending
+    id: u8,
+
+    /// Priority level of the source
+    priority: u8,
+
+    /// Priority levels are in reverse order (mpiccfg.priord)
+    reverse: bool,
+}
+
+impl ExtIntr {
+    /// Rank a priority level (or threshold) so that a larger value always
+    /// has higher priority
+    fn rank(&self, level: RvData) -> RvData {
+        if self.reverse {
+            0xF - (level & 0xF)
+        } else {
+            level & 0xF
+        }
+    }
+}
 /// RISCV CPU
 pub struct Cpu<TBus: Bus> {
     /// General Purpose register file
@@ -105,11 +191,20 @@ pub struct Cpu<TBus: Bus> {
     /// The NMI vector. In the real CPU, this is hardwired from the outside.
     nmivec: u32,
 
+    /// Set by `wfi`; the core is stalled until an enabled interrupt is pending.
+    waiting_for_interrupt: bool,
+
+    /// External interrupt presented by the PIC
+    ext_intr: ExtIntr,
+
     // The bus the CPU uses to talk to memory and peripherals.
     pub bus: TBus,
 
     pub clock: Clock,
 
+    /// Structured trace output, shared with the peripherals through the clock
+    tracer: Tracer,
+
     // Track if Execution is in progress
     pub(crate) is_execute_instr: bool,
 
@@ -117,6 +212,9 @@ pub struct Cpu<TBus: Bus> {
     pub(crate) watch_ptr_cfg: WatchPtrCfg,
 
     pub code_coverage: CodeCoverage,
+
+    /// Faults to inject into the execution
+    fault_injector: FaultInjector,
 }
 
 /// Cpu instruction step action
@@ -136,6 +234,33 @@ impl<TBus: Bus> Cpu<TBus> {
     /// Default Program counter reset value
     const PC_RESET_VAL: RvData = 0;
 
+    /// Interrupt bit in MCAUSE
+    const MCAUSE_INTERRUPT: RvData = 0x8000_0000;
+
+    /// Machine external interrupt cause
+    const MEI_CAUSE: RvData = 11;
+
+    /// Internal timer 0 interrupt cause (VeeR)
+    const MITI0_CAUSE: RvData = 29;
+
+    /// Internal timer 1 interrupt cause (VeeR)
+    const MITI1_CAUSE: RvData = 28;
+
+    /// NMI cause when the fast interrupt redirect fails to load the handler
+    /// address from the external interrupt vector table (VeeR)
+    const NMI_FAST_INTR_ACCESS_ERROR: u32 = 0xF000_1001;
+
+    /// Interrupts in the order they are taken when several are pending
+    /// (VeeR: external, correctable error, software, timer, internal timers)
+    const INTERRUPT_PRIORITY: [(RvData, RvData); 6] = [
+        (Csr::MIP_MEIP, Self::MEI_CAUSE),
+        (Csr::MIP_MCEIP, 30),
+        (Csr::MIP_MSIP, 3),
+        (Csr::MIP_MTIP, 7),
+        (Csr::MIP_MITIP0, Self::MITI0_CAUSE),
+        (Csr::MIP_MITIP1, Self::MITI1_CAUSE),
+    ];
+
     /// Create a new RISCV CPU
     pub fn new(bus: TBus, clock: Clock) -> Self {
         Self {
@@ -144,13 +269,17 @@ impl<TBus: Bus> Cpu<TBus> {
             pc: Self::PC_RESET_VAL,
             next_pc: Self::PC_RESET_VAL,
             bus,
+            tracer: clock.tracer(),
             clock,
             is_execute_instr: false,
             watch_ptr_cfg: WatchPtrCfg::new(),
             nmivec: 0,
+            waiting_for_interrupt: false,
+            ext_intr: ExtIntr::default(),
             // TODO: Pass in code_coverage from the outside (as caliptra-emu-cpu
             // isn't supposed to know anything about the caliptra memory map)
             code_coverage: CodeCoverage::new(48 * 1024),
+            fault_injector: FaultInjector::default(),
         }
     }
 
@@ -216,7 +345,14 @@ impl<TBus: Bus> Cpu<TBus> {
     ///
     /// * `RvException` - Exception with cause `RvExceptionCause::IllegalRegister`
     pub fn write_xreg(&mut self, reg: XReg, val: RvData) -> Result<(), RvException> {
-        self.xregs.write(reg, val)
+        self.xregs.write(reg, val)?;
+        if self.tracer.enabled() && reg != XReg::X0 {
+            self.tracer.emit(TraceEvent::RegWrite {
+                reg: u16::from(reg) as u8,
+                val,
+            });
+        }
+        Ok(())
     }
 
     /// Read the specified configuration status register
@@ -247,7 +383,18 @@ impl<TBus: Bus> Cpu<TBus> {
     ///
     /// * `RvException` - Exception with cause `RvExceptionCause::IllegalRegister`
     pub fn write_csr(&mut self, csr: RvAddr, val: RvData) -> Result<(), RvException> {
-        self.csrs.write(csr, val)
+        self.csrs.write(csr, val)?;
+        if self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::CsrWrite { csr, val });
+        }
+        match csr {
+            Csr::MEICPCT => self
+                .csrs
+                .capture_ext_intr_claim(self.ext_intr.id, self.ext_intr.priority),
+            Csr::MEIPT | Csr::MEICURPL => self.update_meip(),
+            _ => {}
+        }
+        Ok(())
     }
 
     /// Read from bus
@@ -272,8 +419,31 @@ impl<TBus: Bus> Cpu<TBus> {
                 false => None,
             }
         }
+        // Accesses by the debugger are not subject to PMP
+        if self.is_execute_instr && !self.csrs.pmp().allows(addr, size, PmpAccess::Read) {
+            return Err(RvException::load_access_fault(addr));
+        }
 
-        match self.bus.read(size, addr) {
+        let mut result = self.bus.read(size, addr);
+        if self.is_execute_instr && self.fault_injector.has_armed_reads() {
+            if let Ok(val) = result {
+                let periph = self.bus.peripheral_name(addr);
+                result =
+                    Ok(self
+                        .fault_injector
+                        .on_read(addr, periph, val, self.pc, self.clock.now()));
+            }
+        }
+        if self.is_execute_instr && self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::BusRead {
+                master: BusMaster::Uc,
+                periph: self.bus.peripheral_name(addr),
+                size,
+                addr,
+                result,
+            });
+        }
+        match result {
             Ok(val) => Ok(val),
             Err(exception) => match exception {
                 BusError::InstrAccessFault => Err(RvException::instr_access_fault(addr)),
@@ -313,7 +483,22 @@ impl<TBus: Bus> Cpu<TBus> {
                 false => None,
             }
         }
-        match self.bus.write(size, addr, val) {
+        // Accesses by the debugger are not subject to PMP
+        if self.is_execute_instr && !self.csrs.pmp().allows(addr, size, PmpAccess::Write) {
+            return Err(RvException::store_access_fault(addr));
+        }
+        let result = self.bus.write(size, addr, val);
+        if self.is_execute_instr && self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::BusWrite {
+                master: BusMaster::Uc,
+                periph: self.bus.peripheral_name(addr),
+                size,
+                addr,
+                val,
+                result,
+            });
+        }
+        match result {
             Ok(val) => Ok(val),
             Err(exception) => match exception {
                 BusError::InstrAccessFault => Err(RvException::instr_access_fault(addr)),
@@ -325,6 +510,53 @@ impl<TBus: Bus> Cpu<TBus> {
         }
     }
 
+    /// Inject `fault` into the execution when its trigger is reached.
+    /// Pending faults are not part of snapshots.
+    pub fn inject_fault(&mut self, fault: Fault) {
+        self.fault_injector.add(fault);
+    }
+
+    /// Faults that have been applied to the execution so far
+    pub fn fired_faults(&self) -> &[FiredFault] {
+        self.fault_injector.fired()
+    }
+
+    /// Apply the faults triggered by the instruction about to be executed
+    pub(crate) fn apply_faults(&mut self) -> FaultAction {
+        let mut action = FaultAction::Execute;
+        for fault in self.fault_injector.on_instr(self.pc, self.clock.now()) {
+            match fault.kind {
+                FaultKind::SkipInstr => action = FaultAction::Skip,
+                FaultKind::FlipXRegBit { reg, bit } => {
+                    if let Ok(val) = self.xregs.read(reg) {
+                        // Writes to x0 are ignored
+                        let _ = self.xregs.write(reg, val ^ (1 << (bit & 31)));
+                    }
+                }
+                FaultKind::CorruptRead { .. } | FaultKind::CorruptPeriphRead { .. } => {}
+            }
+        }
+        action
+    }
+
+    /// Emit the trace event for the instruction about to be executed
+    pub(crate) fn trace_instr(&self, instr: &Instr) {
+        if !self.tracer.enabled() {
+            return;
+        }
+        let (instr, size) = match *instr {
+            Instr::Compressed(instr) => (u32::from(instr), 2),
+            Instr::General(instr) => (instr, 4),
+        };
+        let pc = self.read_pc();
+        self.tracer.emit(TraceEvent::Instr {
+            pc,
+            instr,
+            size,
+            asm: disassemble(pc, instr).unwrap_or_else(|| format!(".word {instr:#x}")),
+        });
+    }
+
     /// Read instruction
     ///
     /// # Arguments
@@ -337,6 +569,9 @@ impl<TBus: Bus> Cpu<TBus> {
     /// * `RvException` - Exception with cause `RvExceptionCause::LoadAccessFault`
     ///                   or `RvExceptionCause::LoadAddrMisaligned`
     pub fn read_instr(&mut self, size: RvSize, addr: RvAddr) -> Result<RvData, RvException> {
+        if !self.csrs.pmp().allows(addr, size, PmpAccess::Execute) {
+            return Err(RvException::instr_access_fault(addr));
+        }
         match size {
             RvSize::Byte => Err(RvException::instr_access_fault(addr)),
             _ => match self.bus.read(size, addr) {
@@ -359,19 +594,47 @@ impl<TBus: Bus> Cpu<TBus> {
             .increment_and_process_timer_actions(1, &mut self.bus);
         for action_type in fired_action_types.iter() {
             match action_type {
-                TimerAction::WarmReset => {
-                    self.reset_pc();
+                TimerAction::WarmReset | TimerAction::UpdateReset => {
+                    self.reset_core();
                     break;
                 }
-                TimerAction::UpdateReset => {
-                    self.reset_pc();
-                    break;
+                TimerAction::Nmi { mcause } => {
+                    self.waiting_for_interrupt = false;
+                    return self.handle_nmi(*mcause, 0);
                 }
-                TimerAction::Nmi { mcause } => return self.handle_nmi(*mcause, 0),
                 TimerAction::SetNmiVec { addr } => self.nmivec = *addr,
+                TimerAction::SetExtIntr {
+                    id,
+                    priority,
+                    reverse,
+                } => {
+                    self.ext_intr = ExtIntr {
+                        id: *id,
+                        priority: *priority,
+                        reverse: *reverse,
+                    };
+                    self.update_meip();
+                }
+                TimerAction::SetTimerIntr { pending } => {
+                    self.csrs.set_mip(Csr::MIP_MTIP, *pending);
+                }
                 _ => {}
             }
         }
+        self.csrs
+            .advance_internal_timers(1, self.waiting_for_interrupt);
+
+        if self.waiting_for_interrupt {
+            if !self.is_interrupt_pending() {
+                // Stalled on wfi; only the clock advances.
+                return StepAction::Continue;
+            }
+            self.waiting_for_interrupt = false;
+        }
+
+        if let Some(cause) = self.next_interrupt() {
+            return self.handle_interrupt(cause);
+        }
 
         match self.exec_instr(instr_tracer) {
             Ok(result) => result,
@@ -379,6 +642,110 @@ impl<TBus: Bus> Cpu<TBus> {
         }
     }
 
+    /// Perform a warm reset of the core and every peripheral on the bus
+    pub fn warm_reset(&mut self) {
+        self.bus.warm_reset();
+        self.reset_core();
+    }
+
+    /// Perform a firmware update reset of the core and every peripheral on
+    /// the bus
+    pub fn update_reset(&mut self) {
+        self.bus.update_reset();
+        self.reset_core();
+    }
+
+    fn reset_core(&mut self) {
+        self.reset_pc();
+        self.waiting_for_interrupt = false;
+    }
+
+    /// Stall the core until an enabled interrupt becomes pending
+    pub(crate) fn wait_for_interrupt(&mut self) {
+        self.waiting_for_interrupt = true;
+    }
+
+    /// Returns true if the core is stalled on a `wfi` instruction
+    pub fn is_waiting_for_interrupt(&self) -> bool {
+        self.waiting_for_interrupt
+    }
+
+    /// Returns true if any interrupt is both pending and enabled. As on VeeR,
+    /// this wakes the core from `wfi` regardless of mstatus.MIE.
+    fn is_interrupt_pending(&self) -> bool {
+        // Cannot panic; MIP and MIE are valid CSRs
+        let mip = self.csrs.read(Csr::MIP).unwrap();
+        let mie = self.csrs.read(Csr::MIE).unwrap();
+        (mip & mie) != 0
+    }
+
+    /// Recompute MIP.MEIP. The PIC's interrupt is only signaled if its
+    /// priority is above both the threshold (meipt) and the priority of the
+    /// interrupt currently being serviced (meicurpl).
+    fn update_meip(&mut self) {
+        let ext_intr = self.ext_intr;
+        // Cannot panic; MEIPT and MEICURPL are valid CSRs
+        let meipt = self.csrs.read(Csr::MEIPT).unwrap();
+        let meicurpl = self.csrs.read(Csr::MEICURPL).unwrap();
+        let priority = ext_intr.rank(ext_intr.priority.into());
+        let pending = ext_intr.id != 0
+            && priority > ext_intr.rank(meipt)
+            && priority > ext_intr.rank(meicurpl);
+        self.csrs.set_mip(Csr::MIP_MEIP, pending);
+    }
+
+    /// Returns the cause of the interrupt to take before the next instruction,
+    /// if any
+    fn next_interrupt(&self) -> Option<RvData> {
+        // Cannot panic; MSTATUS is a valid CSR
+        let status = RvMStatus(self.csrs.read(Csr::MSTATUS).unwrap());
+        if status.mie() == 0 {
+            return None;
+        }
+        // Cannot panic; MIP and MIE are valid CSRs
+        let pending = self.csrs.read(Csr::MIP).unwrap() & self.csrs.read(Csr::MIE).unwrap();
+        Self::INTERRUPT_PRIORITY
+            .iter()
+            .find(|(bit, _)| pending & bit != 0)
+            .map(|(_, cause)| *cause)
+    }
+
+    /// Handle asynchronous interrupt
+    fn handle_interrupt(&mut self, cause: RvData) -> StepAction {
+        let next_pc = if cause == Self::MEI_CAUSE {
+            // VeeR fast interrupt redirect: capture the claim and jump
+            // straight to the handler address in the external interrupt
+            // vector table.
+            self.csrs
+                .capture_ext_intr_claim(self.ext_intr.id, self.ext_intr.priority);
+            // Cannot panic; MEIHAP is a valid CSR
+            let meihap = self.csrs.read(Csr::MEIHAP).unwrap();
+            match self.bus.read(RvSize::Word, meihap) {
+                Ok(handler) => handler,
+                Err(_) => return self.handle_nmi(Self::NMI_FAST_INTR_ACCESS_ERROR, meihap),
+            }
+        } else {
+            // The internal timer interrupts are pulses latched until taken.
+            match cause {
+                Self::MITI0_CAUSE => self.csrs.set_mip(Csr::MIP_MITIP0, false),
+                Self::MITI1_CAUSE => self.csrs.set_mip(Csr::MIP_MITIP1, false),
+                _ => {}
+            }
+            // Cannot panic; mtvec is a valid CSR
+            let mtvec = self.read_csr(Csr::MTVEC).unwrap();
+            match mtvec & 0b11 {
+                // Vectored mode
+                1 => (mtvec & !0b11) + 4 * cause,
+                _ => mtvec & !0b11,
+            }
+        };
+        let ret = self.handle_trap(true, self.read_pc(), cause, 0, next_pc);
+        match ret {
+            Ok(_) => StepAction::Continue,
+            Err(_) => StepAction::Fatal,
+        }
+    }
+
     /// Handle synchronous exception
     fn handle_exception(&mut self, exception: RvException) -> StepAction {
         let ret = self.handle_trap(
@@ -411,16 +778,17 @@ impl<TBus: Bus> Cpu<TBus> {
     /// * `RvException` - Exception
     fn handle_trap(
         &mut self,
-        _intr: bool,
+        intr: bool,
         pc: RvAddr,
         cause: u32,
         info: u32,
         next_pc: u32,
     ) -> Result<(), RvException> {
-        // TODO: Implement Interrupt Handling
-        // 1. Support for vectored asynchronous interrupts
-        // 2. Veer fast external interrupt support
-        assert!(!_intr);
+        let cause = if intr {
+            cause | Self::MCAUSE_INTERRUPT
+        } else {
+            cause
+        };
 
         self.write_csr(Csr::MEPC, pc)?;
         self.write_csr(Csr::MCAUSE, cause)?;
@@ -464,10 +832,84 @@ impl<TBus: Bus> Cpu<TBus> {
     }
 }
 
+impl<TBus: Bus + Snapshot> Cpu<TBus> {
+    /// Identifies an emulator snapshot ("CEMS")
+    const SNAPSHOT_MAGIC: u32 = 0x534d_4543;
+
+    /// Version of the snapshot layout
+    const SNAPSHOT_VERSION: u32 = 1;
+
+    /// Save the complete emulator state: the core, the clock with its pending
+    /// timer actions, and the bus with all its memories and peripherals.
+    ///
+    /// # Error
+    ///
+    /// * `SnapshotError` - The emulator is in a state that cannot be captured
+    pub fn save_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut w = SnapshotWriter::new();
+        w.write_u32(Self::SNAPSHOT_MAGIC);
+        w.write_u32(Self::SNAPSHOT_VERSION);
+        w.write(self);
+        w.finish()
+    }
+
+    /// Restore a snapshot taken with [`Cpu::save_snapshot`]. The CPU must have
+    /// been built with the same configuration as the one that was saved;
+    /// execution then resumes exactly where the snapshot was taken.
+    ///
+    /// # Error
+    ///
+    /// * `SnapshotError` - The snapshot does not match this emulator
+    pub fn restore_snapshot(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+        let mut r = SnapshotReader::new(snapshot);
+        if r.read_u32()? != Self::SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        match r.read_u32()? {
+            Self::SNAPSHOT_VERSION => {}
+            version => return Err(SnapshotError::UnsupportedVersion(version)),
+        }
+        r.read(self)?;
+        r.finish()
+    }
+}
+
+impl<TBus: Bus + Snapshot> Snapshot for Cpu<TBus> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.xregs);
+        w.write(&self.csrs);
+        w.write_u32(self.pc);
+        w.write_u32(self.next_pc);
+        w.write_u32(self.nmivec);
+        w.write_bool(self.waiting_for_interrupt);
+        w.write_u8(self.ext_intr.id);
+        w.write_u8(self.ext_intr.priority);
+        w.write_bool(self.ext_intr.reverse);
+        w.write(&self.clock);
+        w.write(&self.bus);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.xregs)?;
+        r.read(&mut self.csrs)?;
+        self.pc = r.read_u32()?;
+        self.next_pc = r.read_u32()?;
+        self.nmivec = r.read_u32()?;
+        self.waiting_for_interrupt = r.read_bool()?;
+        self.ext_intr = ExtIntr {
+            id: r.read_u8()?,
+            priority: r.read_u8()?,
+            reverse: r.read_bool()?,
+        };
+        r.read(&mut self.clock)?;
+        r.read(&mut self.bus)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use caliptra_emu_bus::{testing::FakeBus, DynamicBus, Rom, Timer};
+    use caliptra_emu_bus::{testing::FakeBus, DynamicBus, Ram, Rom, Timer};
 
     #[test]
     fn test_new() {
@@ -529,8 +971,528 @@ mod tests {
         assert_eq!(cpu.read_pc(), 31 * 4);
     }
 
+    #[test]
+    fn test_wfi_wakes_on_ext_intr() {
+        const RV32_NO_OP: u32 = 0x00000013;
+        const RV32_WFI: u32 = 0x10500073;
+
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut bus = DynamicBus::new();
+
+        let rom = Rom::new(
+            [RV32_WFI, RV32_NO_OP, RV32_WFI, RV32_NO_OP]
+                .into_iter()
+                .flat_map(u32::to_le_bytes)
+                .collect(),
+        );
+        bus.attach_dev("ROM", 0..=0xf, Box::new(rom)).unwrap();
+
+        let mut cpu = Cpu::new(bus, clock);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MEIP).unwrap();
+
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(cpu.is_waiting_for_interrupt());
+        for _ in 0..10 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+            assert_eq!(cpu.read_pc(), 4);
+        }
+
+        // Raising the external interrupt line wakes the core; the interrupt is
+        // not taken while mstatus.MIE is clear.
+        raise_ext_intr(&timer, 1, 1);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(!cpu.is_waiting_for_interrupt());
+        assert_eq!(cpu.read_pc(), 8);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), Csr::MIP_MEIP);
+
+        // wfi falls through while the interrupt is still pending
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 16);
+
+        raise_ext_intr(&timer, 0, 0);
+        cpu.step(None);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_wfi_ignores_masked_intr() {
+        const RV32_WFI: u32 = 0x10500073;
+
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut bus = DynamicBus::new();
+        let rom = Rom::new(RV32_WFI.to_le_bytes().to_vec());
+        bus.attach_dev("ROM", 0..=0x3, Box::new(rom)).unwrap();
+
+        let mut cpu = Cpu::new(bus, clock);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(cpu.is_waiting_for_interrupt());
+
+        // MIE.MEIE is clear, so the pending interrupt does not wake the core.
+        raise_ext_intr(&timer, 1, 1);
+        for _ in 0..10 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+            assert!(cpu.is_waiting_for_interrupt());
+        }
+
+        cpu.write_csr(Csr::MIE, Csr::MIP_MEIP).unwrap();
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(!cpu.is_waiting_for_interrupt());
+    }
+
+    const RV32_NO_OP: u32 = 0x00000013;
+
+    /// Vector table location used by the external interrupt tests
+    const MEIVT: u32 = 0x5000_0000;
+
+    /// Builds a CPU running no-ops, with an external interrupt vector table
+    /// pointing source `id` at `handler`.
+    fn intr_test_cpu(clock: Clock, id: u8, handler: u32) -> Cpu<DynamicBus> {
+        let mut bus = DynamicBus::new();
+        let rom = Rom::new(
+            std::iter::repeat(RV32_NO_OP)
+                .take(256)
+                .flat_map(u32::to_le_bytes)
+                .collect(),
+        );
+        bus.attach_dev("ROM", 0..=0x3ff, Box::new(rom)).unwrap();
+        let mut vector_table = vec![0; 0x400];
+        let entry = 4 * usize::from(id);
+        vector_table[entry..entry + 4].copy_from_slice(&handler.to_le_bytes());
+        bus.attach_dev(
+            "DCCM",
+            MEIVT..=MEIVT + 0x3ff,
+            Box::new(Ram::new(vector_table)),
+        )
+        .unwrap();
+
+        let mut cpu = Cpu::new(bus, clock);
+        cpu.write_csr(Csr::MEIVT, MEIVT).unwrap();
+        cpu.write_csr(Csr::MIE, Csr::MIP_MEIP).unwrap();
+        cpu.write_csr(Csr::MSTATUS, 0x8).unwrap();
+        cpu
+    }
+
+    fn raise_ext_intr(timer: &Timer, id: u8, priority: u8) {
+        timer.schedule_action_in(
+            0,
+            TimerAction::SetExtIntr {
+                id,
+                priority,
+                reverse: false,
+            },
+        );
+    }
+
+    #[test]
+    fn test_ext_intr_fast_redirect() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 20, 0x200);
+
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 8);
+
+        raise_ext_intr(&timer, 20, 5);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x200);
+        assert_eq!(cpu.read_csr(Csr::MEPC).unwrap(), 8);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0x8000_000B);
+        assert_eq!(cpu.read_csr(Csr::MEIHAP).unwrap(), MEIVT + 4 * 20);
+        assert_eq!(cpu.read_csr(Csr::MEICIDPL).unwrap(), 5);
+        // mstatus.MIE is cleared and saved into mstatus.MPIE
+        assert_eq!(cpu.read_csr(Csr::MSTATUS).unwrap() & 0x88, 0x80);
+
+        // Interrupts stay disabled in the handler.
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x204);
+    }
+
+    #[test]
+    fn test_ext_intr_disabled_by_mstatus() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 3, 0x200);
+        cpu.write_csr(Csr::MSTATUS, 0).unwrap();
+
+        raise_ext_intr(&timer, 3, 1);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 4);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), Csr::MIP_MEIP);
+
+        cpu.write_csr(Csr::MSTATUS, 0x8).unwrap();
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x200);
+        assert_eq!(cpu.read_csr(Csr::MEPC).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_ext_intr_priority_threshold() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 7, 0x200);
+
+        // Interrupts at or below the threshold are not signaled.
+        cpu.write_csr(Csr::MEIPT, 5).unwrap();
+        raise_ext_intr(&timer, 7, 5);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 4);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), 0);
+
+        cpu.write_csr(Csr::MEIPT, 4).unwrap();
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), Csr::MIP_MEIP);
+
+        // Nor are interrupts at or below the current priority level.
+        cpu.write_csr(Csr::MEICURPL, 5).unwrap();
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), 0);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 8);
+
+        cpu.write_csr(Csr::MEICURPL, 0).unwrap();
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x200);
+    }
+
+    #[test]
+    fn test_ext_intr_claim_capture_trigger() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 9, 0x200);
+        cpu.write_csr(Csr::MSTATUS, 0).unwrap();
+
+        raise_ext_intr(&timer, 9, 3);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_csr(Csr::MEIHAP).unwrap(), 0);
+
+        // Software polling the PIC captures the claim through meicpct.
+        cpu.write_csr(Csr::MEICPCT, 0).unwrap();
+        assert_eq!(cpu.read_csr(Csr::MEIHAP).unwrap(), MEIVT + 4 * 9);
+        assert_eq!(cpu.read_csr(Csr::MEICIDPL).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_ext_intr_vector_fetch_fault() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 9, 0x200);
+        cpu.nmivec = 0x300;
+        // Vector table outside of any memory
+        cpu.write_csr(Csr::MEIVT, 0x7000_0000).unwrap();
+
+        raise_ext_intr(&timer, 9, 3);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x300);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0xF000_1001);
+        assert_eq!(cpu.read_csr(Csr::MTVAL).unwrap(), 0x7000_0000 + 4 * 9);
+    }
+
+    #[test]
+    fn test_intr_mtvec_modes() {
+        let clock = Clock::new();
+        let mut cpu = intr_test_cpu(clock, 1, 0x200);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MSIP | Csr::MIP_MTIP)
+            .unwrap();
+
+        // Direct mode
+        cpu.write_csr(Csr::MTVEC, 0x100).unwrap();
+        cpu.csrs.set_mip(Csr::MIP_MTIP, true);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0x8000_0007);
+
+        // Vectored mode; software interrupts are taken before timer interrupts.
+        cpu.write_csr(Csr::MTVEC, 0x101).unwrap();
+        cpu.write_csr(Csr::MSTATUS, 0x8).unwrap();
+        cpu.csrs.set_mip(Csr::MIP_MSIP, true);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100 + 4 * 3);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0x8000_0003);
+
+        cpu.csrs.set_mip(Csr::MIP_MSIP, false);
+        cpu.write_csr(Csr::MSTATUS, 0x8).unwrap();
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100 + 4 * 7);
+    }
+
+    #[test]
+    fn test_timer_intr() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = intr_test_cpu(clock, 1, 0x200);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MTIP).unwrap();
+        cpu.write_csr(Csr::MTVEC, 0x100).unwrap();
+
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 4);
+
+        // mtime reached mtimecmp
+        timer.schedule_action_in(0, TimerAction::SetTimerIntr { pending: true });
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MEPC).unwrap(), 4);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0x8000_0007);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), Csr::MIP_MTIP);
+
+        // The line stays high until mtimecmp is moved.
+        timer.schedule_action_in(0, TimerAction::SetTimerIntr { pending: false });
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_internal_timer_intr() {
+        let clock = Clock::new();
+        let mut cpu = intr_test_cpu(clock, 1, 0x200);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MITIP0).unwrap();
+        cpu.write_csr(Csr::MTVEC, 0x100).unwrap();
+        cpu.write_csr(Csr::MITB0, 4).unwrap();
+
+        for _ in 0..4 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+        }
+        assert_eq!(cpu.read_pc(), 16);
+        assert_eq!(cpu.read_csr(Csr::MITCNT0).unwrap(), 4);
+
+        // The counter reaches its bound and the interrupt is taken.
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MEPC).unwrap(), 16);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 0x8000_001D);
+        assert_eq!(cpu.read_csr(Csr::MITCNT0).unwrap(), 0);
+        assert_eq!(cpu.read_csr(Csr::MIP).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_internal_timer_wakes_wfi() {
+        const RV32_WFI: u32 = 0x10500073;
+
+        let clock = Clock::new();
+        let mut bus = DynamicBus::new();
+        let rom = Rom::new(RV32_WFI.to_le_bytes().to_vec());
+        bus.attach_dev("ROM", 0..=0x3, Box::new(rom)).unwrap();
+
+        let mut cpu = Cpu::new(bus, clock);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MITIP0).unwrap();
+        cpu.write_csr(Csr::MITB0, 4).unwrap();
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(cpu.is_waiting_for_interrupt());
+
+        // Timer 0 is not enabled to count while the core is halted.
+        for _ in 0..10 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+            assert!(cpu.is_waiting_for_interrupt());
+        }
+        assert_eq!(cpu.read_csr(Csr::MITCNT0).unwrap(), 1);
+
+        cpu.write_csr(Csr::MITCTL0, Csr::MITCTL_ENABLE | Csr::MITCTL_HALT_EN)
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+            assert!(cpu.is_waiting_for_interrupt());
+        }
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert!(!cpu.is_waiting_for_interrupt());
+    }
+
+    fn snapshot_test_cpu(clock: Clock) -> Cpu<Ram> {
+        const RV32_ADDI_X1_X1_1: u32 = 0x00108093;
+        const RV32_JAL_X0_BACK: u32 = 0xffdff06f;
+        const RV32_ADDI_X2_X2_1: u32 = 0x00110113;
+        const RV32_JAL_X0_SELF: u32 = 0x0000006f;
+
+        let mut ram = Ram::new(vec![0; 0x200]);
+        let words = [
+            (0x000, RV32_ADDI_X1_X1_1),
+            (0x004, RV32_JAL_X0_BACK),
+            (0x100, RV32_ADDI_X2_X2_1),
+            (0x104, RV32_JAL_X0_SELF),
+        ];
+        for (addr, instr) in words {
+            ram.write(RvSize::Word, addr, instr).unwrap();
+        }
+        Cpu::new(ram, clock)
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let clock = Clock::new();
+        let timer = Timer::new(&clock);
+        let mut cpu = snapshot_test_cpu(clock);
+        cpu.write_csr(Csr::MIE, Csr::MIP_MTIP).unwrap();
+        cpu.write_csr(Csr::MSTATUS, 0x8).unwrap();
+        cpu.write_csr(Csr::MTVEC, 0x100).unwrap();
+        timer.schedule_action_in(100, TimerAction::SetTimerIntr { pending: true });
+        for _ in 0..31 {
+            cpu.step(None);
+        }
+        let snapshot = cpu.save_snapshot().unwrap();
+
+        // The restored CPU starts from blank memory and no pending actions.
+        let mut restored = Cpu::new(Ram::new(vec![0; 0x200]), Clock::new());
+        restored.restore_snapshot(&snapshot).unwrap();
+        assert_eq!(restored.read_pc(), cpu.read_pc());
+        assert_eq!(restored.clock.now(), 31);
+
+        for _ in 0..200 {
+            cpu.step(None);
+            restored.step(None);
+            assert_eq!(restored.read_pc(), cpu.read_pc());
+        }
+        // The timer interrupt scheduled before the snapshot was delivered.
+        assert_eq!(restored.read_csr(Csr::MCAUSE).unwrap(), 0x8000_0007);
+        assert_eq!(restored.read_xreg(XReg::X2).unwrap(), 1);
+        assert_eq!(
+            restored.read_xreg(XReg::X1).unwrap(),
+            cpu.read_xreg(XReg::X1).unwrap()
+        );
+        assert_eq!(restored.bus.data(), cpu.bus.data());
+    }
+
+    #[test]
+    fn test_snapshot_errors() {
+        let cpu = snapshot_test_cpu(Clock::new());
+        let mut snapshot = cpu.save_snapshot().unwrap();
+
+        let mut small = Cpu::new(Ram::new(vec![0; 0x100]), Clock::new());
+        assert!(matches!(
+            small.restore_snapshot(&snapshot),
+            Err(SnapshotError::SizeMismatch { .. })
+        ));
+
+        let mut restored = snapshot_test_cpu(Clock::new());
+        snapshot[4] = 2;
+        assert_eq!(
+            restored.restore_snapshot(&snapshot),
+            Err(SnapshotError::UnsupportedVersion(2))
+        );
+        snapshot[0] = 0;
+        assert_eq!(
+            restored.restore_snapshot(&snapshot),
+            Err(SnapshotError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_pmp_access_faults() {
+        use crate::pmp::Pmp;
+
+        const RV32_LW_X1_0X40: u32 = 0x04002083;
+        const RV32_SW_X0_0X80: u32 = 0x08002023;
+        const RV32_JAL_X0_0X80: u32 = 0x0800006f;
+
+        let mut ram = Ram::new(vec![0; 0x200]);
+        let words = [
+            (0x000, RV32_LW_X1_0X40),
+            (0x004, RV32_SW_X0_0X80),
+            (0x100, RV32_JAL_X0_0X80),
+        ];
+        for (addr, instr) in words {
+            ram.write(RvSize::Word, addr, instr).unwrap();
+        }
+        let mut cpu = Cpu::new(ram, Clock::new());
+        cpu.write_csr(Csr::MTVEC, 0x100).unwrap();
+
+        // [0x0, 0x100) execute-only, [0x180, 0x200) not executable
+        cpu.write_csr(Csr::PMPADDR0, 0x100 >> 2).unwrap();
+        cpu.write_csr(Csr::PMPADDR0 + 1, (0x180 >> 2) | 0xf)
+            .unwrap();
+        let cfg0 = Pmp::CFG_L | Pmp::CFG_A_TOR | Pmp::CFG_X;
+        let cfg1 = Pmp::CFG_L | Pmp::CFG_A_NAPOT | Pmp::CFG_R | Pmp::CFG_W;
+        cpu.write_csr(Csr::PMPCFG0, u32::from(cfg0) | u32::from(cfg1) << 8)
+            .unwrap();
+
+        // The debugger is not subject to PMP
+        assert_eq!(
+            cpu.read_bus(RvSize::Word, 0x100).ok(),
+            Some(RV32_JAL_X0_0X80)
+        );
+
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 5);
+        assert_eq!(cpu.read_csr(Csr::MTVAL).unwrap(), 0x40);
+        assert_eq!(cpu.read_xreg(XReg::X1).unwrap(), 0);
+
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x180);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 1);
+        assert_eq!(cpu.read_csr(Csr::MTVAL).unwrap(), 0x180);
+
+        cpu.write_pc(0x4);
+        assert_eq!(cpu.step(None), StepAction::Continue);
+        assert_eq!(cpu.read_pc(), 0x100);
+        assert_eq!(cpu.read_csr(Csr::MCAUSE).unwrap(), 7);
+        assert_eq!(cpu.read_csr(Csr::MTVAL).unwrap(), 0x80);
+        assert_eq!(cpu.read_bus(RvSize::Word, 0x80).ok(), Some(0));
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        use crate::fault::FaultTrigger;
+
+        const RV32_ADDI_X1_X0_1: u32 = 0x00100093;
+        const RV32_ADDI_X2_X0_2: u32 = 0x00200113;
+        const RV32_LW_X3_0X100: u32 = 0x10002183;
+
+        let mut ram = Ram::new(vec![0; 0x200]);
+        let words = [
+            (0x000, RV32_ADDI_X1_X0_1),
+            (0x004, RV32_ADDI_X2_X0_2),
+            (0x008, RV32_LW_X3_0X100),
+            (0x100, 0x1234_5678),
+        ];
+        for (addr, instr) in words {
+            ram.write(RvSize::Word, addr, instr).unwrap();
+        }
+        let mut cpu = Cpu::new(ram, Clock::new());
+        let skip = Fault::new(FaultTrigger::Instr(0), FaultKind::SkipInstr);
+        let flip = Fault::new(
+            FaultTrigger::Pc { pc: 0x8, hit: 0 },
+            FaultKind::FlipXRegBit {
+                reg: XReg::X2,
+                bit: 4,
+            },
+        );
+        let corrupt = Fault::new(
+            FaultTrigger::Instr(1),
+            FaultKind::CorruptRead {
+                addrs: 0x100..=0x103,
+                xor: 0xff,
+            },
+        );
+        cpu.inject_fault(skip.clone());
+        cpu.inject_fault(flip.clone());
+        cpu.inject_fault(corrupt.clone());
+
+        for _ in 0..3 {
+            assert_eq!(cpu.step(None), StepAction::Continue);
+        }
+        assert_eq!(cpu.read_pc(), 0xc);
+        assert_eq!(cpu.read_xreg(XReg::X1).unwrap(), 0);
+        assert_eq!(cpu.read_xreg(XReg::X2).unwrap(), 0x12);
+        assert_eq!(cpu.read_xreg(XReg::X3).unwrap(), 0x1234_5687);
+        // The memory itself is not modified
+        assert_eq!(cpu.read_bus(RvSize::Word, 0x100).ok(), Some(0x1234_5678));
+        assert_eq!(
+            cpu.fired_faults()
+                .iter()
+                .map(|f| (f.fault.clone(), f.pc))
+                .collect::<Vec<_>>(),
+            vec![(skip, 0x0), (flip, 0x8), (corrupt, 0x8)]
+        );
+    }
+
     pub fn count_executed(coverage: &CodeCoverage) -> usize {
-        coverage.bit_vec.iter().filter(|&executed| executed).count()
+        coverage
+            .code_coverage_bitmap()
+            .iter()
+            .filter(|&executed| executed)
+            .count()
     }
 
     #[test]
@@ -553,4 +1515,29 @@ mod tests {
         // Check for expected values
         assert_eq!(count_executed(&coverage), 8);
     }
+
+    #[test]
+    fn test_code_coverage_regions() {
+        let mut coverage = CodeCoverage::new(16);
+        coverage.add_region(0x4000_0000, 16);
+        coverage.log_execution(0, &Instr::Compressed(0x0001));
+        coverage.log_execution(2, &Instr::General(0x0000_0013));
+        coverage.log_execution(14, &Instr::General(0x0000_0013));
+        coverage.log_execution(0x100, &Instr::General(0x0000_0013));
+        coverage.log_execution(0x4000_0004, &Instr::General(0x0000_0013));
+
+        assert_eq!(
+            coverage.executed_ranges(),
+            vec![0..6, 14..16, 0x4000_0004..0x4000_0008]
+        );
+        assert!(coverage.code_coverage_bitmap()[5]);
+        assert!(!coverage.code_coverage_bitmap()[6]);
+
+        let mut out = vec![];
+        coverage.save(&mut out).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&out).unwrap(),
+            "00000000-00000006\n0000000e-00000010\n40000004-40000008\n"
+        );
+    }
 }
@@ -12,7 +12,10 @@ Abstract:
 
 --*/
 
-use caliptra_emu_types::{RvAddr, RvData, RvException};
+use crate::pmp::Pmp;
+use caliptra_emu_types::{
+    RvAddr, RvData, RvException, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 /// Configuration & Status Register
 #[derive(Copy, Clone)]
@@ -46,6 +49,18 @@ impl Csr {
     /// Interrupt Vector Table Address CSR
     pub const MTVEC: RvAddr = 0x305;
 
+    /// PMP Configuration CSR 0 (entries 0-3); CSRs 1-3 follow
+    pub const PMPCFG0: RvAddr = 0x3A0;
+
+    /// PMP Configuration CSR 3 (entries 12-15)
+    pub const PMPCFG3: RvAddr = 0x3A3;
+
+    /// PMP Address CSR 0; CSRs 1-15 follow
+    pub const PMPADDR0: RvAddr = 0x3B0;
+
+    /// PMP Address CSR 15
+    pub const PMPADDR15: RvAddr = 0x3BF;
+
     /// Performance Counter Inhibit register CSR
     pub const MCOUNTINHIBIT: RvAddr = 0x320;
 
@@ -76,6 +91,69 @@ impl Csr {
     /// Instruction Retired High Counter CSR
     pub const MINSTRETH: RvAddr = 0xB82;
 
+    /// Internal Timer Counter 0 CSR (VeeR)
+    pub const MITCNT0: RvAddr = 0x7D2;
+
+    /// Internal Timer Bound 0 CSR (VeeR)
+    pub const MITB0: RvAddr = 0x7D3;
+
+    /// Internal Timer Control 0 CSR (VeeR)
+    pub const MITCTL0: RvAddr = 0x7D4;
+
+    /// Internal Timer Counter 1 CSR (VeeR)
+    pub const MITCNT1: RvAddr = 0x7D5;
+
+    /// Internal Timer Bound 1 CSR (VeeR)
+    pub const MITB1: RvAddr = 0x7D6;
+
+    /// Internal Timer Control 1 CSR (VeeR)
+    pub const MITCTL1: RvAddr = 0x7D7;
+
+    /// External Interrupt Vector Table CSR (VeeR)
+    pub const MEIVT: RvAddr = 0xBC8;
+
+    /// External Interrupt Priority Threshold CSR (VeeR)
+    pub const MEIPT: RvAddr = 0xBC9;
+
+    /// External Interrupt Claim ID / Priority Level Capture Trigger CSR (VeeR)
+    pub const MEICPCT: RvAddr = 0xBCA;
+
+    /// External Interrupt Claim ID's Priority Level CSR (VeeR)
+    pub const MEICIDPL: RvAddr = 0xBCB;
+
+    /// External Interrupt Current Priority Level CSR (VeeR)
+    pub const MEICURPL: RvAddr = 0xBCC;
+
+    /// External Interrupt Handler Address Pointer CSR (VeeR)
+    pub const MEIHAP: RvAddr = 0xFC8;
+
+    /// Machine Software Interrupt Pending bit in MIP (and enable bit in MIE)
+    pub const MIP_MSIP: RvData = 1 << 3;
+
+    /// Machine Timer Interrupt Pending bit in MIP (and enable bit in MIE)
+    pub const MIP_MTIP: RvData = 1 << 7;
+
+    /// Machine External Interrupt Pending bit in MIP (and enable bit in MIE)
+    pub const MIP_MEIP: RvData = 1 << 11;
+
+    /// Internal Timer 1 Interrupt Pending bit in MIP (VeeR)
+    pub const MIP_MITIP1: RvData = 1 << 28;
+
+    /// Internal Timer 0 Interrupt Pending bit in MIP (VeeR)
+    pub const MIP_MITIP0: RvData = 1 << 29;
+
+    /// Correctable Error Local Interrupt Pending bit in MIP (VeeR)
+    pub const MIP_MCEIP: RvData = 1 << 30;
+
+    /// Internal timer enable bit in MITCTL0/1
+    pub const MITCTL_ENABLE: RvData = 1 << 0;
+
+    /// Internal timer keeps counting while the core is halted (MITCTL0/1)
+    pub const MITCTL_HALT_EN: RvData = 1 << 1;
+
+    /// Internal timer 1 counts timer 0 bound matches instead of cycles (MITCTL1)
+    pub const MITCTL1_CASCADE: RvData = 1 << 3;
+
     /// Create a new Configurations and Status register
     ///
     /// # Arguments
@@ -92,6 +170,9 @@ impl Csr {
 pub struct CsrFile {
     /// CSRS
     csrs: [Csr; CsrFile::CSR_COUNT],
+
+    /// Regions decoded from the PMP CSRs
+    pmp: Pmp,
 }
 
 impl CsrFile {
@@ -102,6 +183,7 @@ impl CsrFile {
     pub fn new() -> Self {
         let mut csrs = Self {
             csrs: [Csr::new(0, 0); CsrFile::CSR_COUNT],
+            pmp: Pmp::default(),
         };
 
         csrs.reset();
@@ -123,11 +205,31 @@ impl CsrFile {
         self.csrs[Csr::MEPC as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
         self.csrs[Csr::MCAUSE as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
         self.csrs[Csr::MTVAL as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
-        self.csrs[Csr::MIP as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
+        self.csrs[Csr::MIP as usize] = Csr::new(0x0000_0000, 0x0000_0000);
         self.csrs[Csr::MCYCLE as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
         self.csrs[Csr::MCYCLEH as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
         self.csrs[Csr::MINSTRET as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
         self.csrs[Csr::MINSTRETH as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
+        self.csrs[Csr::MITCNT0 as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
+        self.csrs[Csr::MITB0 as usize] = Csr::new(0xFFFF_FFFF, 0xFFFF_FFFF);
+        self.csrs[Csr::MITCTL0 as usize] = Csr::new(0x0000_0001, 0x0000_0007);
+        self.csrs[Csr::MITCNT1 as usize] = Csr::new(0x0000_0000, 0xFFFF_FFFF);
+        self.csrs[Csr::MITB1 as usize] = Csr::new(0xFFFF_FFFF, 0xFFFF_FFFF);
+        self.csrs[Csr::MITCTL1 as usize] = Csr::new(0x0000_0001, 0x0000_000F);
+        self.csrs[Csr::MEIVT as usize] = Csr::new(0x0000_0000, 0xFFFF_FC00);
+        self.csrs[Csr::MEIPT as usize] = Csr::new(0x0000_0000, 0x0000_000F);
+        self.csrs[Csr::MEICPCT as usize] = Csr::new(0x0000_0000, 0x0000_0000);
+        self.csrs[Csr::MEICIDPL as usize] = Csr::new(0x0000_0000, 0x0000_000F);
+        self.csrs[Csr::MEICURPL as usize] = Csr::new(0x0000_0000, 0x0000_000F);
+        self.csrs[Csr::MEIHAP as usize] = Csr::new(0x0000_0000, 0x0000_0000);
+        // PMP CSRs are written by write_pmpcfg() and write_pmpaddr()
+        for addr in Csr::PMPCFG0..=Csr::PMPCFG3 {
+            self.csrs[addr as usize] = Csr::new(0x0000_0000, 0x0000_0000);
+        }
+        for addr in Csr::PMPADDR0..=Csr::PMPADDR15 {
+            self.csrs[addr as usize] = Csr::new(0x0000_0000, 0x0000_0000);
+        }
+        self.update_pmp();
     }
 
     /// Read the specified configuration status register
@@ -165,7 +267,19 @@ impl CsrFile {
     pub fn write(&mut self, addr: RvAddr, val: RvData) -> Result<(), RvException> {
         let addr = addr as usize;
         const CSR_MAX: usize = CsrFile::CSR_COUNT - 1;
+        const PMPCFG0: usize = Csr::PMPCFG0 as usize;
+        const PMPCFG3: usize = Csr::PMPCFG3 as usize;
+        const PMPADDR0: usize = Csr::PMPADDR0 as usize;
+        const PMPADDR15: usize = Csr::PMPADDR15 as usize;
         match addr {
+            PMPCFG0..=PMPCFG3 => {
+                self.write_pmpcfg(addr - PMPCFG0, val);
+                Ok(())
+            }
+            PMPADDR0..=PMPADDR15 => {
+                self.write_pmpaddr(addr - PMPADDR0, val);
+                Ok(())
+            }
             0..=CSR_MAX => {
                 let csr = &mut self.csrs[addr];
                 csr.val = (csr.val & !csr.mask) | (val & csr.mask);
@@ -174,12 +288,181 @@ impl CsrFile {
             _ => Err(RvException::illegal_register()),
         }
     }
+
+    /// PMP regions configured by the PMP CSRs
+    pub fn pmp(&self) -> &Pmp {
+        &self.pmp
+    }
+
+    /// Returns the pmpcfg byte of PMP entry `index`
+    fn pmp_cfg(&self, index: usize) -> u8 {
+        let cfg = self.csrs[Csr::PMPCFG0 as usize + index / 4].val;
+        (cfg >> ((index % 4) * 8)) as u8
+    }
+
+    /// Write a pmpcfg CSR. The configuration of locked entries is not
+    /// modified, the reserved bits are hardwired to zero and the reserved
+    /// write-only permission is not retained.
+    ///
+    /// # Arguments
+    ///
+    /// * `reg` - Index of the pmpcfg CSR
+    /// * `val` - Value to write
+    fn write_pmpcfg(&mut self, reg: usize, val: RvData) {
+        let mut result = self.csrs[Csr::PMPCFG0 as usize + reg].val;
+        for i in 0..4 {
+            if self.pmp_cfg(reg * 4 + i) & Pmp::CFG_L != 0 {
+                continue;
+            }
+            let mut cfg = (val >> (i * 8)) as u8
+                & (Pmp::CFG_L | Pmp::CFG_A | Pmp::CFG_X | Pmp::CFG_W | Pmp::CFG_R);
+            if cfg & Pmp::CFG_R == 0 {
+                cfg &= !Pmp::CFG_W;
+            }
+            result &= !(0xFF << (i * 8));
+            result |= RvData::from(cfg) << (i * 8);
+        }
+        self.csrs[Csr::PMPCFG0 as usize + reg].val = result;
+        self.update_pmp();
+    }
+
+    /// Write a pmpaddr CSR. The address of a locked entry, or of the bottom
+    /// of a locked TOR entry, is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the PMP entry
+    /// * `val` - Value to write
+    fn write_pmpaddr(&mut self, index: usize, val: RvData) {
+        if self.pmp_cfg(index) & Pmp::CFG_L != 0 {
+            return;
+        }
+        if index + 1 < Pmp::ENTRY_COUNT {
+            let next = self.pmp_cfg(index + 1);
+            if next & Pmp::CFG_L != 0 && next & Pmp::CFG_A == Pmp::CFG_A_TOR {
+                return;
+            }
+        }
+        self.csrs[Csr::PMPADDR0 as usize + index].val = val;
+        self.update_pmp();
+    }
+
+    /// Decode the PMP regions from the PMP CSRs
+    fn update_pmp(&mut self) {
+        let mut cfgs = [0u8; Pmp::ENTRY_COUNT];
+        let mut addrs = [0u32; Pmp::ENTRY_COUNT];
+        for (i, (cfg, addr)) in cfgs.iter_mut().zip(addrs.iter_mut()).enumerate() {
+            *cfg = self.pmp_cfg(i);
+            *addr = self.csrs[Csr::PMPADDR0 as usize + i].val;
+        }
+        self.pmp = Pmp::new(&cfgs, &addrs);
+    }
+
+    /// Set or clear interrupt pending bits in MIP
+    ///
+    /// MIP is read-only to software; its bits reflect the level of the
+    /// interrupt lines driven into the core.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - Interrupt pending bits to update
+    /// * `pending` - New level of the interrupt lines
+    pub fn set_mip(&mut self, bits: RvData, pending: bool) {
+        let csr = &mut self.csrs[Csr::MIP as usize];
+        if pending {
+            csr.val |= bits;
+        } else {
+            csr.val &= !bits;
+        }
+    }
+
+    /// Capture the claim ID and priority level of an external interrupt into
+    /// MEIHAP and MEICIDPL
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Interrupt source ID
+    /// * `priority` - Priority level of the interrupt source
+    pub fn capture_ext_intr_claim(&mut self, id: u8, priority: u8) {
+        let meivt = self.csrs[Csr::MEIVT as usize].val;
+        self.csrs[Csr::MEIHAP as usize].val = meivt | (RvData::from(id) << 2);
+        self.csrs[Csr::MEICIDPL as usize].val = RvData::from(priority) & 0xF;
+    }
+
+    /// Advance the VeeR internal timers, setting MIP.MITIP0/1 when a counter
+    /// reaches its bound. The pending bits are cleared when the interrupt is
+    /// taken.
+    ///
+    /// # Arguments
+    ///
+    /// * `cycles` - Number of core clock cycles elapsed
+    /// * `halted` - The core was halted (stalled on `wfi`) during these cycles
+    pub fn advance_internal_timers(&mut self, cycles: u64, halted: bool) {
+        let counting = |ctl: RvData| {
+            ctl & Csr::MITCTL_ENABLE != 0 && (!halted || ctl & Csr::MITCTL_HALT_EN != 0)
+        };
+
+        let ctl0 = self.csrs[Csr::MITCTL0 as usize].val;
+        let ticks0 = if counting(ctl0) { cycles } else { 0 };
+        let matches0 = self.advance_internal_timer(Csr::MITCNT0, Csr::MITB0, ticks0);
+
+        let ctl1 = self.csrs[Csr::MITCTL1 as usize].val;
+        let ticks1 = match (counting(ctl1), ctl1 & Csr::MITCTL1_CASCADE != 0) {
+            (false, _) => 0,
+            (true, true) => matches0,
+            (true, false) => cycles,
+        };
+        let matches1 = self.advance_internal_timer(Csr::MITCNT1, Csr::MITB1, ticks1);
+
+        if matches0 != 0 {
+            self.set_mip(Csr::MIP_MITIP0, true);
+        }
+        if matches1 != 0 {
+            self.set_mip(Csr::MIP_MITIP1, true);
+        }
+    }
+
+    /// Advance an internal timer counter by `ticks`, returning the number of
+    /// times it reached its bound. The counter wraps to zero on the tick
+    /// after reaching the bound.
+    fn advance_internal_timer(&mut self, cnt: RvAddr, bound: RvAddr, ticks: u64) -> u64 {
+        if ticks == 0 {
+            return 0;
+        }
+        let bound = u64::from(self.csrs[bound as usize].val);
+        let count = u64::from(self.csrs[cnt as usize].val).min(bound);
+        let period = bound + 1;
+        let first_match = bound - count + 1;
+        self.csrs[cnt as usize].val = ((count + ticks) % period) as RvData;
+        if ticks >= first_match {
+            1 + (ticks - first_match) / period
+        } else {
+            0
+        }
+    }
+}
+
+/// Saves the CSR values; write masks are fixed by the core and not saved.
+impl Snapshot for CsrFile {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.csrs.iter().for_each(|csr| w.write_u32(csr.val));
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        for csr in self.csrs.iter_mut() {
+            csr.val = r.read_u32()?;
+        }
+        self.update_pmp();
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::pmp::PmpAccess;
+    use caliptra_emu_types::RvSize;
 
     #[test]
     fn test_read_only_csr() {
@@ -189,6 +472,19 @@ mod tests {
         assert_eq!(csrs.read(Csr::MISA).ok(), Some(0x4000_1104));
     }
 
+    #[test]
+    fn test_mip_hw_driven() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.write(Csr::MIP, u32::MAX).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(0));
+        csrs.set_mip(Csr::MIP_MEIP, true);
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MEIP));
+        assert_eq!(csrs.write(Csr::MIP, 0).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MEIP));
+        csrs.set_mip(Csr::MIP_MEIP, false);
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(0));
+    }
+
     #[test]
     fn test_read_write_csr() {
         let mut csrs = CsrFile::new();
@@ -209,4 +505,151 @@ mod tests {
         assert_eq!(csrs.write(Csr::MCOUNTINHIBIT, u32::MAX).ok(), Some(()));
         assert_eq!(csrs.read(Csr::MCOUNTINHIBIT).ok(), Some(0x0000_007D));
     }
+
+    #[test]
+    fn test_pmp_csrs() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.read(Csr::PMPCFG0).ok(), Some(0));
+
+        // Reserved bits read as zero and W without R is not retained
+        assert_eq!(csrs.write(Csr::PMPCFG0, 0x0000_7F62).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::PMPCFG0).ok(), Some(0x0000_1F00));
+        assert_eq!(csrs.write(Csr::PMPADDR15, 0x1234_5678).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::PMPADDR15).ok(), Some(0x1234_5678));
+        assert!(csrs.pmp().allows(0x0, RvSize::Word, PmpAccess::Write));
+
+        // Entry 1 locked as TOR [pmpaddr0, pmpaddr1), read-only
+        assert_eq!(csrs.write(Csr::PMPADDR0, 0x1000 >> 2).ok(), Some(()));
+        assert_eq!(csrs.write(Csr::PMPADDR0 + 1, 0x2000 >> 2).ok(), Some(()));
+        let cfg = Pmp::CFG_L | Pmp::CFG_A_TOR | Pmp::CFG_R;
+        assert_eq!(
+            csrs.write(Csr::PMPCFG0, RvData::from(cfg) << 8).ok(),
+            Some(())
+        );
+        assert!(csrs.pmp().allows(0x1000, RvSize::Word, PmpAccess::Read));
+        assert!(!csrs.pmp().allows(0x1000, RvSize::Word, PmpAccess::Write));
+        assert!(csrs.pmp().allows(0x2000, RvSize::Word, PmpAccess::Write));
+
+        // The locked entry and the bottom of its range can't be modified
+        assert_eq!(csrs.write(Csr::PMPCFG0, 0x0000_0F07).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::PMPCFG0).ok(), Some(0x0000_8907));
+        assert_eq!(csrs.write(Csr::PMPADDR0, 0).ok(), Some(()));
+        assert_eq!(csrs.write(Csr::PMPADDR0 + 1, 0x3000 >> 2).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::PMPADDR0).ok(), Some(0x1000 >> 2));
+        assert_eq!(csrs.read(Csr::PMPADDR0 + 1).ok(), Some(0x2000 >> 2));
+        assert!(!csrs.pmp().allows(0x1000, RvSize::Word, PmpAccess::Write));
+    }
+
+    #[test]
+    fn test_ext_intr_claim_capture() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.write(Csr::MEIVT, 0x5000_0ABC).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MEIVT).ok(), Some(0x5000_0800));
+
+        // MEIHAP and MEICIDPL are only updated by a claim capture.
+        assert_eq!(csrs.write(Csr::MEIHAP, u32::MAX).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MEIHAP).ok(), Some(0));
+
+        csrs.capture_ext_intr_claim(20, 7);
+        assert_eq!(csrs.read(Csr::MEIHAP).ok(), Some(0x5000_0850));
+        assert_eq!(csrs.read(Csr::MEICIDPL).ok(), Some(7));
+    }
+
+    #[test]
+    fn test_internal_timer_csrs() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MITB0).ok(), Some(0xFFFF_FFFF));
+        assert_eq!(csrs.read(Csr::MITCTL0).ok(), Some(Csr::MITCTL_ENABLE));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MITB1).ok(), Some(0xFFFF_FFFF));
+        assert_eq!(csrs.read(Csr::MITCTL1).ok(), Some(Csr::MITCTL_ENABLE));
+
+        assert_eq!(csrs.write(Csr::MITCTL0, u32::MAX).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MITCTL0).ok(), Some(0x0000_0007));
+        assert_eq!(csrs.write(Csr::MITCTL1, u32::MAX).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MITCTL1).ok(), Some(0x0000_000F));
+        assert_eq!(csrs.write(Csr::MITCNT1, 0x1234_5678).ok(), Some(()));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(0x1234_5678));
+    }
+
+    #[test]
+    fn test_internal_timer_count() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.write(Csr::MITB0, 9).ok(), Some(()));
+        assert_eq!(csrs.write(Csr::MITCTL1, 0).ok(), Some(()));
+
+        csrs.advance_internal_timers(9, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(9));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(0));
+
+        // The counter wraps on the tick after reaching the bound.
+        csrs.advance_internal_timers(1, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MITIP0));
+
+        // Several periods at once
+        csrs.set_mip(Csr::MIP_MITIP0, false);
+        csrs.advance_internal_timers(25, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(5));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MITIP0));
+
+        // Lowering the bound below the count matches on the next tick.
+        csrs.set_mip(Csr::MIP_MITIP0, false);
+        assert_eq!(csrs.write(Csr::MITB0, 2).ok(), Some(()));
+        csrs.advance_internal_timers(1, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MITIP0));
+    }
+
+    #[test]
+    fn test_internal_timer_halt() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(
+            csrs.write(Csr::MITCTL1, Csr::MITCTL_ENABLE | Csr::MITCTL_HALT_EN)
+                .ok(),
+            Some(())
+        );
+
+        csrs.advance_internal_timers(10, true);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(10));
+
+        csrs.advance_internal_timers(10, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(10));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(20));
+    }
+
+    #[test]
+    fn test_internal_timer_cascade() {
+        let mut csrs = CsrFile::new();
+        assert_eq!(csrs.write(Csr::MITB0, 3).ok(), Some(()));
+        assert_eq!(csrs.write(Csr::MITB1, 2).ok(), Some(()));
+        assert_eq!(
+            csrs.write(Csr::MITCTL1, Csr::MITCTL_ENABLE | Csr::MITCTL1_CASCADE)
+                .ok(),
+            Some(())
+        );
+
+        // Timer 1 counts the matches of timer 0.
+        csrs.advance_internal_timers(8, false);
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(2));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(Csr::MIP_MITIP0));
+
+        csrs.advance_internal_timers(4, false);
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(0));
+        assert_eq!(
+            csrs.read(Csr::MIP).ok(),
+            Some(Csr::MIP_MITIP0 | Csr::MIP_MITIP1)
+        );
+
+        // A disabled timer does not count.
+        csrs.set_mip(Csr::MIP_MITIP0 | Csr::MIP_MITIP1, false);
+        assert_eq!(csrs.write(Csr::MITCTL0, 0).ok(), Some(()));
+        csrs.advance_internal_timers(100, false);
+        assert_eq!(csrs.read(Csr::MITCNT0).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MITCNT1).ok(), Some(0));
+        assert_eq!(csrs.read(Csr::MIP).ok(), Some(0));
+    }
 }
@@ -0,0 +1,334 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    disasm.rs
+
+Abstract:
+
+    File contains the instruction disassembler used for execution traces.
+
+--*/
+
+use crate::csr_file::Csr;
+use crate::instr::compression::decompress_instr;
+use crate::types::{
+    RvInstr32B, RvInstr32BranchFunct3, RvInstr32FenceFunct3, RvInstr32I, RvInstr32J,
+    RvInstr32LoadFunct3, RvInstr32OpFunct3, RvInstr32OpFunct7, RvInstr32OpImmFunct3,
+    RvInstr32OpImmFunct7, RvInstr32Opcode, RvInstr32R, RvInstr32S, RvInstr32StoreFunct3,
+    RvInstr32SystemFunct3, RvInstr32SystemImm, RvInstr32U,
+};
+use crate::xreg_file::XReg;
+use caliptra_emu_types::RvAddr;
+
+/// ABI names of the general purpose registers
+const XREG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+fn reg(reg: XReg) -> &'static str {
+    XREG_NAMES[usize::from(u16::from(reg)) & 0x1f]
+}
+
+fn csr_name(csr: RvAddr) -> String {
+    let name = match csr {
+        Csr::MISA => "misa",
+        Csr::MVENDORID => "mvendorid",
+        Csr::MARCHID => "marchid",
+        Csr::MIMPIID => "mimpid",
+        Csr::MHARTID => "mhartid",
+        Csr::MSTATUS => "mstatus",
+        Csr::MIE => "mie",
+        Csr::MTVEC => "mtvec",
+        Csr::MCOUNTINHIBIT => "mcountinhibit",
+        Csr::MSCRATCH => "mscratch",
+        Csr::MEPC => "mepc",
+        Csr::MCAUSE => "mcause",
+        Csr::MTVAL => "mtval",
+        Csr::MIP => "mip",
+        Csr::MCYCLE => "mcycle",
+        Csr::MINSTRET => "minstret",
+        Csr::MCYCLEH => "mcycleh",
+        Csr::MINSTRETH => "minstreth",
+        Csr::MITCNT0 => "mitcnt0",
+        Csr::MITB0 => "mitb0",
+        Csr::MITCTL0 => "mitctl0",
+        Csr::MITCNT1 => "mitcnt1",
+        Csr::MITB1 => "mitb1",
+        Csr::MITCTL1 => "mitctl1",
+        Csr::MEIVT => "meivt",
+        Csr::MEIPT => "meipt",
+        Csr::MEICPCT => "meicpct",
+        Csr::MEICIDPL => "meicidpl",
+        Csr::MEICURPL => "meicurpl",
+        Csr::MEIHAP => "meihap",
+        Csr::PMPCFG0..=Csr::PMPCFG3 => return format!("pmpcfg{}", csr - Csr::PMPCFG0),
+        Csr::PMPADDR0..=Csr::PMPADDR15 => return format!("pmpaddr{}", csr - Csr::PMPADDR0),
+        _ => return format!("{csr:#x}"),
+    };
+    name.into()
+}
+
+/// Disassemble the instruction `instr` located at `pc`. Compressed
+/// instructions are shown in their expanded form. Branch and jump targets are
+/// absolute addresses.
+///
+/// Returns `None` if the instruction is not supported by the emulator.
+pub fn disassemble(pc: RvAddr, instr: u32) -> Option<String> {
+    let instr = if instr & 0b11 != 0b11 {
+        decompress_instr(instr as u16).ok()?
+    } else {
+        instr
+    };
+    match RvInstr32I(instr).opcode() {
+        RvInstr32Opcode::Lui => {
+            let i = RvInstr32U(instr);
+            Some(format!(
+                "lui {}, {:#x}",
+                reg(i.rd()),
+                i.imm() as u32 & 0xf_ffff
+            ))
+        }
+        RvInstr32Opcode::Auipc => {
+            let i = RvInstr32U(instr);
+            Some(format!(
+                "auipc {}, {:#x}",
+                reg(i.rd()),
+                i.imm() as u32 & 0xf_ffff
+            ))
+        }
+        RvInstr32Opcode::Jal => {
+            let i = RvInstr32J(instr);
+            let target = pc.wrapping_add(i.imm());
+            Some(match i.rd() {
+                XReg::X0 => format!("j {target:#x}"),
+                rd => format!("jal {}, {target:#x}", reg(rd)),
+            })
+        }
+        RvInstr32Opcode::Jalr => {
+            let i = RvInstr32I(instr);
+            Some(match (i.rd(), i.rs(), i.imm()) {
+                (XReg::X0, XReg::X1, 0) => "ret".into(),
+                (rd, rs, imm) => format!("jalr {}, {imm}({})", reg(rd), reg(rs)),
+            })
+        }
+        RvInstr32Opcode::Branch => {
+            let i = RvInstr32B(instr);
+            let mnemonic = match i.funct3().into() {
+                RvInstr32BranchFunct3::Beq => "beq",
+                RvInstr32BranchFunct3::Bne => "bne",
+                RvInstr32BranchFunct3::Blt => "blt",
+                RvInstr32BranchFunct3::Bge => "bge",
+                RvInstr32BranchFunct3::Bltu => "bltu",
+                RvInstr32BranchFunct3::Bgeu => "bgeu",
+                _ => return None,
+            };
+            Some(format!(
+                "{mnemonic} {}, {}, {:#x}",
+                reg(i.rs1()),
+                reg(i.rs2()),
+                pc.wrapping_add(i.imm())
+            ))
+        }
+        RvInstr32Opcode::Load => {
+            let i = RvInstr32I(instr);
+            let mnemonic = match i.funct3().into() {
+                RvInstr32LoadFunct3::Lb => "lb",
+                RvInstr32LoadFunct3::Lh => "lh",
+                RvInstr32LoadFunct3::Lw => "lw",
+                RvInstr32LoadFunct3::Lbu => "lbu",
+                RvInstr32LoadFunct3::Lhu => "lhu",
+                _ => return None,
+            };
+            Some(format!(
+                "{mnemonic} {}, {}({})",
+                reg(i.rd()),
+                i.imm(),
+                reg(i.rs())
+            ))
+        }
+        RvInstr32Opcode::Store => {
+            let i = RvInstr32S(instr);
+            let mnemonic = match i.funct3().into() {
+                RvInstr32StoreFunct3::Sb => "sb",
+                RvInstr32StoreFunct3::Sh => "sh",
+                RvInstr32StoreFunct3::Sw => "sw",
+                _ => return None,
+            };
+            Some(format!(
+                "{mnemonic} {}, {}({})",
+                reg(i.rs2()),
+                i.imm(),
+                reg(i.rs1())
+            ))
+        }
+        RvInstr32Opcode::OpImm => disassemble_op_imm(RvInstr32I(instr)),
+        RvInstr32Opcode::Op => disassemble_op(RvInstr32R(instr)),
+        RvInstr32Opcode::System => disassemble_system(RvInstr32I(instr)),
+        RvInstr32Opcode::Fence => match RvInstr32I(instr).funct3().into() {
+            RvInstr32FenceFunct3::Fence => Some("fence".into()),
+            RvInstr32FenceFunct3::FenceI => Some("fence.i".into()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn disassemble_op_imm(i: RvInstr32I) -> Option<String> {
+    let (rd, rs) = (reg(i.rd()), reg(i.rs()));
+    let unary = |mnemonic: &str| Some(format!("{mnemonic} {rd}, {rs}"));
+    let shift = |mnemonic: &str| Some(format!("{mnemonic} {rd}, {rs}, {}", i.shamt()));
+    let mnemonic = match i.funct3().into() {
+        RvInstr32OpImmFunct3::Addi => {
+            return Some(match (i.rd(), i.rs(), i.imm()) {
+                (XReg::X0, XReg::X0, 0) => "nop".into(),
+                (_, XReg::X0, imm) => format!("li {rd}, {imm}"),
+                (_, _, 0) => format!("mv {rd}, {rs}"),
+                (_, _, imm) => format!("addi {rd}, {rs}, {imm}"),
+            });
+        }
+        RvInstr32OpImmFunct3::Sli => {
+            return match (i.funct7().into(), i.funct5()) {
+                (RvInstr32OpImmFunct7::Slli, _) => shift("slli"),
+                (RvInstr32OpImmFunct7::Bitmanip, 0b0_0000) => unary("clz"),
+                (RvInstr32OpImmFunct7::Bitmanip, 0b0_0001) => unary("ctz"),
+                (RvInstr32OpImmFunct7::Bitmanip, 0b0_0010) => unary("cpop"),
+                (RvInstr32OpImmFunct7::Bitmanip, 0b0_0100) => unary("sext.b"),
+                (RvInstr32OpImmFunct7::Bitmanip, 0b0_0101) => unary("sext.h"),
+                _ => None,
+            };
+        }
+        RvInstr32OpImmFunct3::Sri => {
+            return match (i.funct7().into(), i.funct5()) {
+                (RvInstr32OpImmFunct7::Srli, _) => shift("srli"),
+                (RvInstr32OpImmFunct7::Srai, _) => shift("srai"),
+                (RvInstr32OpImmFunct7::Bitmanip, _) => shift("rori"),
+                (RvInstr32OpImmFunct7::Orc, 0b0_0111) => unary("orc.b"),
+                (RvInstr32OpImmFunct7::Rev8, 0b1_1000) => unary("rev8"),
+                _ => None,
+            };
+        }
+        RvInstr32OpImmFunct3::Slti => "slti",
+        RvInstr32OpImmFunct3::Sltiu => "sltiu",
+        RvInstr32OpImmFunct3::Xori => "xori",
+        RvInstr32OpImmFunct3::Ori => "ori",
+        RvInstr32OpImmFunct3::Andi => "andi",
+        _ => return None,
+    };
+    Some(format!("{mnemonic} {rd}, {rs}, {}", i.imm()))
+}
+
+fn disassemble_op(i: RvInstr32R) -> Option<String> {
+    use RvInstr32OpFunct3 as F3;
+    use RvInstr32OpFunct7 as F7;
+    let mnemonic = match (i.funct3().into(), i.funct7().into()) {
+        (F3::Zero, F7::Add) => "add",
+        (F3::Zero, F7::Sub) => "sub",
+        (F3::One, F7::Sll) => "sll",
+        (F3::Two, F7::Slt) => "slt",
+        (F3::Three, F7::Sltu) => "sltu",
+        (F3::Four, F7::Xor) => "xor",
+        (F3::Five, F7::Srl) => "srl",
+        (F3::Five, F7::Sra) => "sra",
+        (F3::Six, F7::Or) => "or",
+        (F3::Seven, F7::And) => "and",
+        (F3::Zero, F7::Mul) => "mul",
+        (F3::One, F7::Mulh) => "mulh",
+        (F3::Two, F7::Mulhsu) => "mulhsu",
+        (F3::Three, F7::Mulhu) => "mulhu",
+        (F3::Four, F7::Div) => "div",
+        (F3::Five, F7::Divu) => "divu",
+        (F3::Six, F7::Rem) => "rem",
+        (F3::Seven, F7::Remu) => "remu",
+        (F3::Seven, F7::Andn) => "andn",
+        (F3::Six, F7::Orn) => "orn",
+        (F3::Four, F7::Xnor) => "xnor",
+        (F3::Four, F7::MinMaxClmul) => "min",
+        (F3::Five, F7::MinMaxClmul) => "minu",
+        (F3::Six, F7::MinMaxClmul) => "max",
+        (F3::Seven, F7::MinMaxClmul) => "maxu",
+        (F3::One, F7::Rotate) => "rol",
+        (F3::Five, F7::Rotate) => "ror",
+        (F3::Four, F7::Zext) if i.rs2() == XReg::X0 => {
+            return Some(format!("zext.h {}, {}", reg(i.rd()), reg(i.rs1())));
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "{mnemonic} {}, {}, {}",
+        reg(i.rd()),
+        reg(i.rs1()),
+        reg(i.rs2())
+    ))
+}
+
+fn disassemble_system(i: RvInstr32I) -> Option<String> {
+    let csr = csr_name(i.uimm());
+    let (rd, rs) = (reg(i.rd()), reg(i.rs()));
+    // For the immediate forms the rs1 field holds a 5-bit immediate
+    let uimm = u16::from(i.rs());
+    Some(match i.funct3().into() {
+        RvInstr32SystemFunct3::Priv => match i.uimm().into() {
+            RvInstr32SystemImm::Ecall => "ecall".into(),
+            RvInstr32SystemImm::Ebreak => "ebreak".into(),
+            RvInstr32SystemImm::Wfi => "wfi".into(),
+            RvInstr32SystemImm::Mret => "mret".into(),
+            _ => return None,
+        },
+        RvInstr32SystemFunct3::Csrrw => format!("csrrw {rd}, {csr}, {rs}"),
+        RvInstr32SystemFunct3::Csrrs => format!("csrrs {rd}, {csr}, {rs}"),
+        RvInstr32SystemFunct3::Csrrc => format!("csrrc {rd}, {csr}, {rs}"),
+        RvInstr32SystemFunct3::Csrrwi => format!("csrrwi {rd}, {csr}, {uimm}"),
+        RvInstr32SystemFunct3::Csrrsi => format!("csrrsi {rd}, {csr}, {uimm}"),
+        RvInstr32SystemFunct3::Csrrci => format!("csrrci {rd}, {csr}, {uimm}"),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let cases: &[(u32, u32, &str)] = &[
+            (0, 0x0000_0013, "nop"),
+            (0, 0x0010_8093, "addi ra, ra, 1"),
+            (0, 0x0050_0513, "li a0, 5"),
+            (0, 0x0005_8513, "mv a0, a1"),
+            (0, 0xfff5_0513, "addi a0, a0, -1"),
+            (0, 0x1234_5537, "lui a0, 0x12345"),
+            (0x100, 0xffdf_f06f, "j 0xfc"),
+            (0x100, 0x0080_00ef, "jal ra, 0x108"),
+            (0, 0x0000_8067, "ret"),
+            (0x200, 0x00b5_0463, "beq a0, a1, 0x208"),
+            (0, 0x0081_2503, "lw a0, 8(sp)"),
+            (0, 0xfea1_2e23, "sw a0, -4(sp)"),
+            (0, 0x02b5_0533, "mul a0, a0, a1"),
+            (0, 0x40b5_0533, "sub a0, a0, a1"),
+            (0, 0x6005_1513, "clz a0, a0"),
+            (0, 0x3005_9073, "csrrw zero, mstatus, a1"),
+            (0, 0x3004_6073, "csrrsi zero, mstatus, 8"),
+            (0, 0x3b15_9073, "csrrw zero, pmpaddr1, a1"),
+            (0, 0x3020_0073, "mret"),
+            (0, 0x1050_0073, "wfi"),
+            // c.addi a0, 1
+            (0, 0x0505, "addi a0, a0, 1"),
+            // c.ret
+            (0, 0x8082, "ret"),
+        ];
+        for (pc, instr, expected) in cases {
+            assert_eq!(
+                disassemble(*pc, *instr).as_deref(),
+                Some(*expected),
+                "instr {instr:#010x}"
+            );
+        }
+        assert_eq!(disassemble(0, 0xffff_ffff), None);
+        assert_eq!(disassemble(0, 0x0000_0000), None);
+    }
+}
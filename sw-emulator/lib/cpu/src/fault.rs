@@ -0,0 +1,343 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    fault.rs
+
+Abstract:
+
+    File contains the fault injection model of the CPU, used to check the
+    glitch resistance of the firmware.
+
+--*/
+
+use crate::xreg_file::XReg;
+use caliptra_emu_types::{RvAddr, RvData};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// When a fault is injected
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FaultTrigger {
+    /// The Nth instruction executed after the fault is injected, counting
+    /// from 0
+    Instr(u64),
+
+    /// The first instruction executed at or after the given clock cycle
+    Cycle(u64),
+
+    /// The Nth execution of the instruction at `pc`, counting from 0
+    Pc { pc: RvAddr, hit: u32 },
+}
+
+/// What a fault does to the execution
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FaultKind {
+    /// Skip the instruction without executing it
+    SkipInstr,
+
+    /// Flip bit `bit` of register `reg` before the instruction executes
+    FlipXRegBit { reg: XReg, bit: u8 },
+
+    /// XOR `xor` into the result of the next firmware read from an address
+    /// in `addrs`, such as the result registers of a crypto engine
+    CorruptRead {
+        addrs: RangeInclusive<RvAddr>,
+        xor: RvData,
+    },
+
+    /// XOR `xor` into the result of the next firmware read from the
+    /// peripheral named `periph` by `Bus::peripheral_name()`
+    CorruptPeriphRead { periph: &'static str, xor: RvData },
+}
+
+/// A fault to inject into the execution of the firmware
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fault {
+    pub trigger: FaultTrigger,
+    pub kind: FaultKind,
+}
+
+impl Fault {
+    pub fn new(trigger: FaultTrigger, kind: FaultKind) -> Self {
+        Self { trigger, kind }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FaultKind::SkipInstr => write!(f, "skip instr")?,
+            FaultKind::FlipXRegBit { reg, bit } => write!(f, "flip {reg} bit {bit}")?,
+            FaultKind::CorruptRead { addrs, xor } => write!(
+                f,
+                "read {:#x}..={:#x} ^ {xor:#x}",
+                addrs.start(),
+                addrs.end()
+            )?,
+            FaultKind::CorruptPeriphRead { periph, xor } => write!(f, "read {periph} ^ {xor:#x}")?,
+        }
+        match self.trigger {
+            FaultTrigger::Instr(n) => write!(f, " @ instr {n}"),
+            FaultTrigger::Cycle(cycle) => write!(f, " @ cycle {cycle}"),
+            FaultTrigger::Pc { pc, hit } => write!(f, " @ pc {pc:#x} hit {hit}"),
+        }
+    }
+}
+
+/// A fault that was applied to the execution
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FiredFault {
+    pub fault: Fault,
+
+    /// Clock cycle at which the fault was applied
+    pub cycle: u64,
+
+    /// Program counter of the instruction that was faulted
+    pub pc: RvAddr,
+}
+
+/// What the CPU must do with the instruction about to be executed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum FaultAction {
+    Execute,
+    Skip,
+}
+
+/// A fault whose trigger has not been reached
+struct PendingFault {
+    fault: Fault,
+
+    /// Instructions executed before the fault was injected
+    start_instr: u64,
+
+    /// Executions of the trigger pc seen so far
+    hits: u32,
+}
+
+/// Faults waiting for their trigger, and corrupted reads waiting for a
+/// matching read
+#[derive(Default)]
+pub(crate) struct FaultInjector {
+    pending: Vec<PendingFault>,
+
+    /// Triggered read corruptions that have not seen a matching read yet
+    armed_reads: Vec<Fault>,
+
+    fired: Vec<FiredFault>,
+
+    /// Instructions executed so far
+    instr_count: u64,
+}
+
+impl FaultInjector {
+    pub fn add(&mut self, fault: Fault) {
+        self.pending.push(PendingFault {
+            fault,
+            start_instr: self.instr_count,
+            hits: 0,
+        });
+    }
+
+    pub fn fired(&self) -> &[FiredFault] {
+        &self.fired
+    }
+
+    pub fn has_armed_reads(&self) -> bool {
+        !self.armed_reads.is_empty()
+    }
+
+    /// Called before each instruction. Returns the faults that trigger on
+    /// this instruction; read corruptions are armed instead of returned.
+    pub fn on_instr(&mut self, pc: RvAddr, cycle: u64) -> Vec<Fault> {
+        let instr = self.instr_count;
+        self.instr_count += 1;
+        if self.pending.is_empty() {
+            return vec![];
+        }
+        let mut result = vec![];
+        let mut i = 0;
+        while i < self.pending.len() {
+            let pending = &mut self.pending[i];
+            let triggered = match pending.fault.trigger {
+                FaultTrigger::Instr(n) => instr - pending.start_instr == n,
+                FaultTrigger::Cycle(c) => cycle >= c,
+                FaultTrigger::Pc { pc: fault_pc, hit } => {
+                    if fault_pc == pc {
+                        pending.hits += 1;
+                        pending.hits == hit + 1
+                    } else {
+                        false
+                    }
+                }
+            };
+            if !triggered {
+                i += 1;
+                continue;
+            }
+            let fault = self.pending.remove(i).fault;
+            match fault.kind {
+                FaultKind::CorruptRead { .. } | FaultKind::CorruptPeriphRead { .. } => {
+                    self.armed_reads.push(fault)
+                }
+                _ => {
+                    self.fired.push(FiredFault {
+                        fault: fault.clone(),
+                        cycle,
+                        pc,
+                    });
+                    result.push(fault);
+                }
+            }
+        }
+        result
+    }
+
+    /// Called after each successful firmware read. Returns the value to
+    /// return to the firmware.
+    pub fn on_read(
+        &mut self,
+        addr: RvAddr,
+        periph: Option<&str>,
+        val: RvData,
+        pc: RvAddr,
+        cycle: u64,
+    ) -> RvData {
+        let Some(idx) = self.armed_reads.iter().position(|fault| match &fault.kind {
+            FaultKind::CorruptRead { addrs, .. } => addrs.contains(&addr),
+            FaultKind::CorruptPeriphRead { periph: name, .. } => periph == Some(*name),
+            _ => false,
+        }) else {
+            return val;
+        };
+        let fault = self.armed_reads.remove(idx);
+        let xor = match fault.kind {
+            FaultKind::CorruptRead { xor, .. } | FaultKind::CorruptPeriphRead { xor, .. } => xor,
+            _ => 0,
+        };
+        self.fired.push(FiredFault { fault, cycle, pc });
+        val ^ xor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triggers() {
+        let mut injector = FaultInjector::default();
+        let skip = Fault::new(FaultTrigger::Instr(2), FaultKind::SkipInstr);
+        let flip = Fault::new(
+            FaultTrigger::Pc { pc: 0x10, hit: 1 },
+            FaultKind::FlipXRegBit {
+                reg: XReg::X5,
+                bit: 3,
+            },
+        );
+        let late = Fault::new(FaultTrigger::Cycle(100), FaultKind::SkipInstr);
+        injector.add(skip.clone());
+        injector.add(flip.clone());
+        injector.add(late.clone());
+
+        assert_eq!(injector.on_instr(0x10, 1), vec![]);
+        assert_eq!(injector.on_instr(0x14, 2), vec![]);
+        assert_eq!(injector.on_instr(0x18, 3), vec![skip.clone()]);
+        assert_eq!(injector.on_instr(0x10, 4), vec![flip.clone()]);
+        assert_eq!(injector.on_instr(0x10, 5), vec![]);
+        assert_eq!(injector.on_instr(0x14, 150), vec![late.clone()]);
+
+        // Instruction counts are relative to the injection
+        let relative = Fault::new(FaultTrigger::Instr(0), FaultKind::SkipInstr);
+        injector.add(relative.clone());
+        assert_eq!(injector.on_instr(0x18, 151), vec![relative]);
+        assert_eq!(
+            injector.fired()[..3],
+            [
+                FiredFault {
+                    fault: skip,
+                    cycle: 3,
+                    pc: 0x18
+                },
+                FiredFault {
+                    fault: flip,
+                    cycle: 4,
+                    pc: 0x10
+                },
+                FiredFault {
+                    fault: late,
+                    cycle: 150,
+                    pc: 0x14
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_corrupt_read() {
+        let mut injector = FaultInjector::default();
+        injector.add(Fault::new(
+            FaultTrigger::Instr(1),
+            FaultKind::CorruptRead {
+                addrs: 0x1000_8000..=0x1000_802f,
+                xor: 0x1,
+            },
+        ));
+        injector.add(Fault::new(
+            FaultTrigger::Instr(1),
+            FaultKind::CorruptPeriphRead {
+                periph: "sha512",
+                xor: 0x8000_0000,
+            },
+        ));
+
+        // Not armed yet
+        injector.on_instr(0x0, 0);
+        assert_eq!(injector.on_read(0x1000_8000, None, 0x10, 0x0, 0), 0x10);
+
+        assert_eq!(injector.on_instr(0x4, 1), vec![]);
+        assert!(injector.has_armed_reads());
+        assert_eq!(injector.on_read(0x1000_7ffc, None, 0x10, 0x4, 1), 0x10);
+        assert_eq!(injector.on_read(0x1000_8004, None, 0x10, 0x4, 1), 0x11);
+        assert_eq!(injector.on_read(0x1000_8004, None, 0x10, 0x4, 1), 0x10);
+        assert_eq!(
+            injector.on_read(0x1002_0000, Some("sha512"), 0x10, 0x8, 2),
+            0x8000_0010
+        );
+        assert!(!injector.has_armed_reads());
+        assert_eq!(injector.fired().len(), 2);
+        assert_eq!(injector.fired()[1].pc, 0x8);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Fault::new(FaultTrigger::Instr(7), FaultKind::SkipInstr).to_string(),
+            "skip instr @ instr 7"
+        );
+        assert_eq!(
+            Fault::new(
+                FaultTrigger::Pc { pc: 0x1234, hit: 0 },
+                FaultKind::FlipXRegBit {
+                    reg: XReg::X10,
+                    bit: 31
+                }
+            )
+            .to_string(),
+            "flip X10 bit 31 @ pc 0x1234 hit 0"
+        );
+        assert_eq!(
+            Fault::new(
+                FaultTrigger::Cycle(5000),
+                FaultKind::CorruptPeriphRead {
+                    periph: "ecc384",
+                    xor: 0xff
+                }
+            )
+            .to_string(),
+            "read ecc384 ^ 0xff @ cycle 5000"
+        );
+    }
+}
@@ -17,7 +17,7 @@ References:
 
 mod auipc;
 mod branch;
-mod compression;
+pub(crate) mod compression;
 mod fence;
 mod jal;
 mod jalr;
@@ -31,6 +31,7 @@ mod test_encoder;
 mod test_macros;
 
 use crate::cpu::{Cpu, InstrTracer, StepAction};
+use crate::fault::FaultAction;
 use crate::types::{RvInstr, RvInstr32, RvInstr32Opcode};
 use caliptra_emu_bus::Bus;
 use caliptra_emu_types::{RvException, RvSize};
@@ -59,9 +60,35 @@ impl<TBus: Bus> Cpu<TBus> {
         self.is_execute_instr = true;
         self.watch_ptr_cfg.hit = None;
 
+        // Leave execution mode even if the instruction faults, so that later
+        // debugger accesses are not traced or checked against the PMP
+        let result = self.fetch_and_exec_instr(instr_tracer);
+        self.is_execute_instr = false;
+        result?;
+
+        match self.get_watchptr_hit() {
+            Some(_hit) => Ok(StepAction::Break),
+            None => Ok(StepAction::Continue),
+        }
+    }
+
+    /// Fetch, execute and retire the instruction at the program counter
+    fn fetch_and_exec_instr(
+        &mut self,
+        instr_tracer: Option<&mut InstrTracer>,
+    ) -> Result<(), RvException> {
         let instr = self.fetch()?;
+        if self.apply_faults() == FaultAction::Skip {
+            let size = match instr {
+                Instr::Compressed(_) => 2,
+                Instr::General(_) => 4,
+            };
+            self.write_pc(self.read_pc().wrapping_add(size));
+            return Ok(());
+        }
         // Code coverage here.
         self.code_coverage.log_execution(self.read_pc(), &instr);
+        self.trace_instr(&instr);
 
         match instr {
             Instr::Compressed(instr) => {
@@ -74,13 +101,7 @@ impl<TBus: Bus> Cpu<TBus> {
             }
         }
         self.write_pc(self.next_pc());
-
-        self.is_execute_instr = false;
-
-        match self.get_watchptr_hit() {
-            Some(_hit) => Ok(StepAction::Break),
-            None => Ok(StepAction::Continue),
-        }
+        Ok(())
     }
 
     /// Fetch an instruction from current program counter
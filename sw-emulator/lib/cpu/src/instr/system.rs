@@ -41,6 +41,10 @@ impl<TBus: Bus> Cpu<TBus> {
             RvInstr32SystemFunct3::Priv => match imm.into() {
                 RvInstr32SystemImm::Ecall => Err(RvException::environment_call()),
                 RvInstr32SystemImm::Ebreak => Err(RvException::breakpoint(self.read_pc())),
+                RvInstr32SystemImm::Wfi => {
+                    self.wait_for_interrupt();
+                    Ok(())
+                }
                 RvInstr32SystemImm::Mret => {
                     let mut status = RvMStatus(self.read_csr(Csr::MSTATUS)?);
                     status.set_mie(status.mpie());
@@ -94,9 +98,10 @@ impl<TBus: Bus> Cpu<TBus> {
 
 #[cfg(test)]
 mod tests {
+    use crate::cpu::StepAction;
     use crate::csr_file::Csr;
     use crate::instr::test_encoder::tests::{
-        csrrc, csrrci, csrrs, csrrsi, csrrw, csrrwi, ebreak, ecall,
+        csrrc, csrrci, csrrs, csrrsi, csrrw, csrrwi, ebreak, ecall, wfi,
     };
     use crate::xreg_file::XReg;
     use crate::{isa_test, isa_test_cpu, text};
@@ -120,6 +125,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wfi() {
+        let mut cpu = isa_test_cpu!(0x0000 => text![wfi();], 0x1000 => vec![0]);
+        assert_eq!(cpu.exec_instr(None).ok(), Some(StepAction::Continue));
+        assert_eq!(cpu.read_pc(), 0x0004);
+        assert!(cpu.is_waiting_for_interrupt());
+    }
+
     #[test]
     fn test_csrrw() {
         isa_test!(
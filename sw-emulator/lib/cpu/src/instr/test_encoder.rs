@@ -230,6 +230,7 @@ pub mod tests {
 
     op_system_instr!(ecall, Priv, Ecall);
     op_system_instr!(ebreak, Priv, Ebreak);
+    op_system_instr!(wfi, Priv, Wfi);
     op_system_instr!(csrrw, Csrrw);
     op_system_instr!(csrrs, Csrrs);
     op_system_instr!(csrrc, Csrrc);
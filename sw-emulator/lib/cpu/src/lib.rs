@@ -14,7 +14,10 @@ Abstract:
 
 pub mod cpu;
 mod csr_file;
+mod disasm;
+mod fault;
 mod instr;
+mod pmp;
 mod types;
 pub mod xreg_file;
 
@@ -22,4 +25,6 @@ pub use cpu::StepAction;
 pub use cpu::WatchPtrHit;
 pub use cpu::WatchPtrKind;
 pub use cpu::{Cpu, InstrTracer};
+pub use disasm::disassemble;
+pub use fault::{Fault, FaultKind, FaultTrigger, FiredFault};
 pub use types::RvInstr;
@@ -0,0 +1,243 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    pmp.rs
+
+Abstract:
+
+    File contains implementation of RISC-V physical memory protection.
+
+--*/
+
+use caliptra_emu_types::{RvAddr, RvSize};
+
+/// Kind of memory access checked against the PMP regions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PmpAccess {
+    Read,
+    Write,
+    Execute,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct PmpRegion {
+    /// First byte of the region
+    start: u64,
+
+    /// End of the region (exclusive)
+    end: u64,
+
+    /// pmpcfg byte of the entry
+    cfg: u8,
+}
+
+/// Physical memory protection regions, decoded from the pmpcfg and pmpaddr
+/// CSRs.
+///
+/// The core only executes in machine mode, where an entry is only enforced
+/// if it is locked. Accesses that match no entry are allowed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Pmp {
+    /// Active entries, lowest-numbered (highest priority) first
+    regions: Vec<PmpRegion>,
+
+    /// At least one active entry is locked
+    enforced: bool,
+}
+
+impl Pmp {
+    /// Number of PMP entries
+    pub const ENTRY_COUNT: usize = 16;
+
+    /// Read permission
+    pub const CFG_R: u8 = 1 << 0;
+
+    /// Write permission
+    pub const CFG_W: u8 = 1 << 1;
+
+    /// Execute permission
+    pub const CFG_X: u8 = 1 << 2;
+
+    /// Address matching mode
+    pub const CFG_A: u8 = 3 << 3;
+
+    /// Address matching mode: top of range
+    pub const CFG_A_TOR: u8 = 1 << 3;
+
+    /// Address matching mode: naturally aligned four-byte region
+    pub const CFG_A_NA4: u8 = 2 << 3;
+
+    /// Address matching mode: naturally aligned power-of-two region
+    pub const CFG_A_NAPOT: u8 = 3 << 3;
+
+    /// Lock; the entry is enforced in machine mode and can't be modified
+    pub const CFG_L: u8 = 1 << 7;
+
+    /// Decode the PMP entries
+    ///
+    /// # Arguments
+    ///
+    /// * `cfgs` - pmpcfg byte of each entry
+    /// * `addrs` - pmpaddr value (address bits 33:2) of each entry
+    pub fn new(cfgs: &[u8; Self::ENTRY_COUNT], addrs: &[u32; Self::ENTRY_COUNT]) -> Self {
+        let mut regions = vec![];
+        for (i, (&cfg, &addr)) in cfgs.iter().zip(addrs.iter()).enumerate() {
+            let addr = u64::from(addr);
+            let (start, end) = match cfg & Self::CFG_A {
+                Self::CFG_A_TOR => {
+                    let prev = if i == 0 { 0 } else { u64::from(addrs[i - 1]) };
+                    (prev << 2, addr << 2)
+                }
+                Self::CFG_A_NA4 => (addr << 2, (addr << 2) + 4),
+                Self::CFG_A_NAPOT => {
+                    let ones = addr.trailing_ones();
+                    let size = 1u64 << (ones + 3);
+                    let start = (addr << 2) & !(size - 1);
+                    (start, start + size)
+                }
+                _ => continue,
+            };
+            // A TOR entry whose address is not above the previous one
+            // matches nothing
+            if start < end {
+                regions.push(PmpRegion { start, end, cfg });
+            }
+        }
+        let enforced = regions.iter().any(|r| r.cfg & Self::CFG_L != 0);
+        Self { regions, enforced }
+    }
+
+    /// Returns true if a machine mode access of `size` bytes at `addr` is
+    /// allowed.
+    pub fn allows(&self, addr: RvAddr, size: RvSize, access: PmpAccess) -> bool {
+        if !self.enforced {
+            return true;
+        }
+        let start = u64::from(addr);
+        let end = start + size as u64;
+        let Some(region) = self
+            .regions
+            .iter()
+            .find(|r| start < r.end && r.start < end)
+        else {
+            return true;
+        };
+        // The access must be fully contained in the matching entry
+        if start < region.start || end > region.end {
+            return false;
+        }
+        if region.cfg & Self::CFG_L == 0 {
+            return true;
+        }
+        let perm = match access {
+            PmpAccess::Read => Self::CFG_R,
+            PmpAccess::Write => Self::CFG_W,
+            PmpAccess::Execute => Self::CFG_X,
+        };
+        region.cfg & perm != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const R: u8 = Pmp::CFG_R;
+    const W: u8 = Pmp::CFG_W;
+    const X: u8 = Pmp::CFG_X;
+    const L: u8 = Pmp::CFG_L;
+    const TOR: u8 = Pmp::CFG_A_TOR;
+    const NA4: u8 = Pmp::CFG_A_NA4;
+    const NAPOT: u8 = Pmp::CFG_A_NAPOT;
+
+    fn decode(entries: &[(u8, u32)]) -> Pmp {
+        let mut cfgs = [0; Pmp::ENTRY_COUNT];
+        let mut addrs = [0; Pmp::ENTRY_COUNT];
+        for (i, &(cfg, addr)) in entries.iter().enumerate() {
+            cfgs[i] = cfg;
+            addrs[i] = addr;
+        }
+        Pmp::new(&cfgs, &addrs)
+    }
+
+    #[test]
+    fn test_no_locked_entries() {
+        let pmp = decode(&[(NAPOT, 0x1fff)]);
+        assert!(pmp.allows(0x0, RvSize::Word, PmpAccess::Write));
+        assert!(pmp.allows(0x1234, RvSize::Word, PmpAccess::Execute));
+    }
+
+    #[test]
+    fn test_tor() {
+        // ROM [0x0, 0xc000) execute-only
+        let pmp = decode(&[(TOR | X | L, 0xc000 >> 2)]);
+        assert!(pmp.allows(0x0, RvSize::Word, PmpAccess::Execute));
+        assert!(pmp.allows(0xbffc, RvSize::Word, PmpAccess::Execute));
+        assert!(!pmp.allows(0x100, RvSize::Word, PmpAccess::Read));
+        assert!(!pmp.allows(0x100, RvSize::Byte, PmpAccess::Write));
+        // Outside of the region
+        assert!(pmp.allows(0xc000, RvSize::Word, PmpAccess::Read));
+        // Straddling the end of the region
+        assert!(!pmp.allows(0xbffe, RvSize::Word, PmpAccess::Execute));
+
+        // Bottom of the range is the previous entry's address
+        let pmp = decode(&[(0, 0x4000_0000 >> 2), (TOR | R | X | L, 0x4002_0000 >> 2)]);
+        assert!(pmp.allows(0x3fff_fffc, RvSize::Word, PmpAccess::Write));
+        assert!(pmp.allows(0x4000_0000, RvSize::Word, PmpAccess::Read));
+        assert!(!pmp.allows(0x4000_0000, RvSize::Word, PmpAccess::Write));
+        assert!(!pmp.allows(0x4001_fffc, RvSize::Word, PmpAccess::Write));
+
+        // Empty range
+        let pmp = decode(&[(L, 0x100 >> 2), (TOR | L, 0x100 >> 2)]);
+        assert!(pmp.allows(0x0, RvSize::Word, PmpAccess::Write));
+    }
+
+    #[test]
+    fn test_na4() {
+        let pmp = decode(&[(NA4 | R | L, 0x5000_0010 >> 2)]);
+        assert!(pmp.allows(0x5000_0010, RvSize::Word, PmpAccess::Read));
+        assert!(pmp.allows(0x5000_0013, RvSize::Byte, PmpAccess::Read));
+        assert!(!pmp.allows(0x5000_0012, RvSize::HalfWord, PmpAccess::Write));
+        assert!(pmp.allows(0x5000_0014, RvSize::Word, PmpAccess::Write));
+        assert!(pmp.allows(0x5000_000c, RvSize::Word, PmpAccess::Write));
+    }
+
+    #[test]
+    fn test_napot() {
+        // DCCM [0x5000_0000, 0x5002_0000) not executable
+        let pmp = decode(&[(
+            NAPOT | R | W | L,
+            (0x5000_0000 >> 2) | ((0x2_0000 >> 3) - 1),
+        )]);
+        assert!(pmp.allows(0x5000_0000, RvSize::Word, PmpAccess::Write));
+        assert!(pmp.allows(0x5001_fffc, RvSize::Word, PmpAccess::Read));
+        assert!(!pmp.allows(0x5000_1000, RvSize::HalfWord, PmpAccess::Execute));
+        assert!(pmp.allows(0x5002_0000, RvSize::Word, PmpAccess::Execute));
+        assert!(pmp.allows(0x4fff_fffc, RvSize::Word, PmpAccess::Execute));
+
+        // All ones covers the whole address space
+        let pmp = decode(&[(NAPOT | R | L, 0xffff_ffff)]);
+        assert!(pmp.allows(0x0, RvSize::Word, PmpAccess::Read));
+        assert!(pmp.allows(0xffff_fffc, RvSize::Word, PmpAccess::Read));
+        assert!(!pmp.allows(0xffff_fffc, RvSize::Word, PmpAccess::Write));
+    }
+
+    #[test]
+    fn test_priority() {
+        // An unlocked entry grants machine mode access, even if a later
+        // locked entry would deny it
+        let pmp = decode(&[
+            (NA4 | R, 0x100 >> 2),
+            (NA4 | R | W | X, 0x104 >> 2),
+            (NAPOT | L, (0x100 >> 2) | 0x3),
+        ]);
+        assert!(pmp.allows(0x100, RvSize::Word, PmpAccess::Write));
+        assert!(pmp.allows(0x104, RvSize::Word, PmpAccess::Execute));
+        assert!(!pmp.allows(0x108, RvSize::Word, PmpAccess::Read));
+        // Matches entry 0 but isn't contained in it
+        assert!(!pmp.allows(0x102, RvSize::Word, PmpAccess::Read));
+    }
+}
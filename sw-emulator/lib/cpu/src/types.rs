@@ -387,6 +387,9 @@ emu_enum! {
         /// Break
         Ebreak = 0b0000_0000_0001,
 
+        /// Wait for interrupt
+        Wfi = 0b0001_0000_0101,
+
         /// Mret
         Mret = 0b0011_0000_0010,
     };
@@ -12,11 +12,13 @@ Abstract:
 
 --*/
 
-use caliptra_emu_types::{emu_enum, RvAddr, RvData, RvException};
+use caliptra_emu_types::{
+    emu_enum, RvAddr, RvData, RvException, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 emu_enum!(
     /// RISCV general purpose registers
-    #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
+    #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
     pub XReg;
     RvAddr;
     {
@@ -140,6 +142,16 @@ impl Default for XRegFile {
     }
 }
 
+impl Snapshot for XRegFile {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.reg);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.reg)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
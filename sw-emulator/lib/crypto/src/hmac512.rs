@@ -13,6 +13,7 @@ Abstract:
 --*/
 
 use crate::{helpers::EndianessTransform, Sha512, Sha512Mode};
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 /// HMAC-512 Mode
 #[derive(Debug, Copy, Clone)]
@@ -184,6 +185,29 @@ impl<const KEY_SIZE: usize> Hmac512<KEY_SIZE> {
     }
 }
 
+impl<const KEY_SIZE: usize> Snapshot for Hmac512<KEY_SIZE> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.hash1);
+        w.write(&self.hash2);
+        w.write_u8(self.mode as u8);
+        w.write(&self.opad);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.hash1)?;
+        r.read(&mut self.hash2)?;
+        self.mode = match r.read_u8()? {
+            0 => Hmac512Mode::Sha224,
+            1 => Hmac512Mode::Sha256,
+            2 => Hmac512Mode::Sha384,
+            3 => Hmac512Mode::Sha512,
+            _ => Err(SnapshotError::InvalidValue("Hmac512Mode"))?,
+        };
+        r.read(&mut self.opad)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,6 +13,7 @@ Abstract:
 --*/
 
 use crate::helpers::EndianessTransform;
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use sha2::digest::block_buffer::Block;
 use sha2::digest::consts::U64;
 
@@ -125,6 +126,23 @@ impl Sha256 {
     }
 }
 
+impl Snapshot for Sha256 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.hash);
+        w.write_u8(self.mode as u8);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.hash)?;
+        self.mode = match r.read_u8()? {
+            0 => Sha256Mode::Sha224,
+            1 => Sha256Mode::Sha256,
+            _ => Err(SnapshotError::InvalidValue("Sha256Mode"))?,
+        };
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,6 +13,7 @@ Abstract:
 --*/
 
 use crate::helpers::EndianessTransform;
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use sha2::digest::block_buffer::Block;
 use sha2::digest::consts::U128;
 
@@ -229,6 +230,40 @@ impl Sha512 {
     }
 }
 
+impl Snapshot for Sha512Mode {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u8(*self as u8);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = match r.read_u8()? {
+            0 => Sha512Mode::Sha224,
+            1 => Sha512Mode::Sha256,
+            2 => Sha512Mode::Sha384,
+            3 => Sha512Mode::Sha512,
+            _ => Err(SnapshotError::InvalidValue("Sha512Mode"))?,
+        };
+        Ok(())
+    }
+}
+
+impl Snapshot for Sha512 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.hash);
+        w.write(&self.mode);
+        w.write_bytes(&self.partial_block);
+        w.write_usize(self.blocks_processed);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.hash)?;
+        r.read(&mut self.mode)?;
+        self.partial_block = r.read_bytes()?.to_vec();
+        self.blocks_processed = r.read_usize()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -329,4 +364,26 @@ mod tests {
 
         assert_eq!(&hash, &expected);
     }
+
+    #[test]
+    fn test_snapshot() {
+        let mut sha = Sha512::new(Sha512Mode::Sha384);
+        sha.update_bytes(&[0x5a; 200]);
+        let mut w = SnapshotWriter::new();
+        w.write(&sha);
+        let snapshot = w.finish().unwrap();
+
+        let mut restored = Sha512::new(Sha512Mode::Sha512);
+        SnapshotReader::new(&snapshot).read(&mut restored).unwrap();
+        sha.update_bytes(&[0xa5; 100]);
+        restored.update_bytes(&[0xa5; 100]);
+        sha.finalize(300);
+        restored.finalize(300);
+
+        let (mut expected, mut actual) = ([0u8; 64], [0u8; 64]);
+        sha.copy_hash(&mut expected);
+        restored.copy_hash(&mut actual);
+        assert_eq!(restored.hash_len(), 48);
+        assert_eq!(actual, expected);
+    }
 }
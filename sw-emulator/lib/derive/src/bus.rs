@@ -48,6 +48,17 @@ pub fn derive_bus(input: TokenStream) -> TokenStream {
     } else {
         quote! {}
     };
+    let peripheral_name_tokens = if let Some(mask_matches) = &mask_matches {
+        let name_match_tokens = gen_bus_match_tokens(mask_matches, AccessType::Name);
+        quote! {
+            fn peripheral_name(&self, addr: caliptra_emu_types::RvAddr) -> Option<&'static str> {
+                #name_match_tokens
+                None
+            }
+        }
+    } else {
+        quote! {}
+    };
     let read_reg_match_tokens = gen_register_match_tokens(&register_fields, AccessType::Read);
     let write_reg_match_tokens = gen_register_match_tokens(&register_fields, AccessType::Write);
     let self_poll_tokens = if let Some(poll_fn) = &poll_fn {
@@ -98,7 +109,7 @@ pub fn derive_bus(input: TokenStream) -> TokenStream {
                 #(self.#field_idents.update_reset();)*
                 #self_update_reset_tokens
             }
-
+            #peripheral_name_tokens
         }
     }
 }
@@ -331,6 +342,8 @@ fn build_match_tree_from_fields(fields: &[PeripheralField]) -> Option<MaskMatchB
 enum AccessType {
     Read,
     Write,
+    /// Resolve the name of the peripheral field (for `Bus::peripheral_name()`)
+    Name,
 }
 
 fn lsbs_contiguous(mask: u32) -> bool {
@@ -339,7 +352,7 @@ fn lsbs_contiguous(mask: u32) -> bool {
 
 /// Serialize `mask_matches` into a stream of Rust tokens. `access_type`
 /// influences whether the generated code calls [`Bus::read()`] or [`Bus::write()`] on
-/// the matching peripheral field.
+/// the matching peripheral field, or returns the field's name.
 fn gen_bus_match_tokens(mask_matches: &MaskMatchBlock, access_type: AccessType) -> TokenStream {
     let match_mask = hex_literal_u32(mask_matches.mask);
     let addr_mask = hex_literal_u32(!mask_matches.mask);
@@ -358,6 +371,11 @@ fn gen_bus_match_tokens(mask_matches: &MaskMatchBlock, access_type: AccessType)
                     #offset => return caliptra_emu_bus::Bus::write(&mut self.#field_name, size, addr & #addr_mask, val),
                 }
             },
+            (MatchBody::Field(field_name), AccessType::Name) => {
+                quote! {
+                    #offset => return Some(#field_name),
+                }
+            },
             (MatchBody::SubMatchBlock(ref sub_mask_matches), _) => {
                 let submatch_tokens = gen_bus_match_tokens(sub_mask_matches, access_type);
                 quote! {
@@ -492,6 +510,7 @@ fn gen_register_match_tokens(registers: &[RegisterField], access_type: AccessTyp
                     unreachable!();
                 }
             }
+            AccessType::Name => unreachable!(),
         }
     }).collect();
 
@@ -795,6 +814,30 @@ mod tests {
                         self.i2c2.update_reset();
                         self.spi0.update_reset();
                     }
+                    fn peripheral_name(&self, addr: caliptra_emu_types::RvAddr) -> Option<&'static str> {
+                        match addr & 0xf000_0000 {
+                            0x0000_0000 => return Some("rom"),
+                            0x1000_0000 => return Some("sram"),
+                            0x2000_0000 => return Some("dram"),
+                            0xa000_0000 => match addr & 0xffff_0000 {
+                                0xaa00_0000 => return Some("uart0"),
+                                0xaa01_0000 => return Some("uart1"),
+                                0xaa02_0000 => match addr & 0xffff_ff00 {
+                                    0xaa02_0000 => return Some("i2c0"),
+                                    0xaa02_0400 => return Some("i2c1"),
+                                    0xaa02_0800 => return Some("i2c2"),
+                                    _ => {}
+                                },
+                                _ => {}
+                            },
+                            0xb000_0000 => match addr & 0xffff_0000 {
+                                0xbb42_0000 => return Some("spi0"),
+                                _ => {}
+                            },
+                            _ => {}
+                        }
+                        None
+                    }
                 }
             }.to_string()
         );
@@ -549,3 +549,40 @@ fn test_poll() {
     assert_eq!(bus.log.take(), "poll; ");
     assert_eq!(bus.fake.log.take(), "poll()\n")
 }
+
+#[test]
+fn test_peripheral_name() {
+    let bus = MyBus {
+        rom: Ram::new(vec![0u8; 65536]),
+        sram: Ram::new(vec![0u8; 65536]),
+        dram: Ram::new(vec![0u8; 65536]),
+        uart0: Ram::new(vec![0u8; 128]),
+        uart1: Ram::new(vec![0u8; 128]),
+        i2c0: Ram::new(vec![0u8; 128]),
+        i2c1: Ram::new(vec![0u8; 128]),
+        i2c2: Ram::new(vec![0u8; 128]),
+        spi0: Ram::new(vec![0u8; 65536]),
+        fake: FakeBus::new(),
+
+        reg_u32: 0,
+        reg_u16: 0,
+        reg_u8: 0,
+
+        reg_array: [0; 5],
+        reg_array_action0: [0; 2],
+        reg_array_action1: [0; 2],
+
+        reg_action0: 0,
+        reg_action1: 0,
+
+        _fieldless_regs: (),
+
+        log: Log::new(),
+    };
+    assert_eq!(bus.peripheral_name(0x0000_1000), Some("rom"));
+    assert_eq!(bus.peripheral_name(0x2fff_fffc), Some("dram"));
+    assert_eq!(bus.peripheral_name(0xaa02_0404), Some("i2c1"));
+    assert_eq!(bus.peripheral_name(0xaa05_0010), Some("fake"));
+    assert_eq!(bus.peripheral_name(0xaa02_0200), None);
+    assert_eq!(bus.peripheral_name(0xcafe_f0d0), None);
+}
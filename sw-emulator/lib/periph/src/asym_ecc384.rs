@@ -13,11 +13,13 @@ Abstract:
 --*/
 
 use crate::helpers::{bytes_from_words_le, words_from_bytes_le};
-use crate::{KeyUsage, KeyVault};
+use crate::intr_block::{irq, IntrBlock, NOTIF_CMD_DONE_STS};
+use crate::latency::CryptoLatency;
+use crate::{KeyUsage, KeyVault, Pic};
 use caliptra_emu_bus::{ActionHandle, BusError, Clock, ReadOnlyRegister, ReadWriteRegister, Timer};
 use caliptra_emu_crypto::{Ecc384, Ecc384PubKey, Ecc384Signature};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::register_bitfields;
 use tock_registers::registers::InMemoryRegister;
@@ -34,12 +36,6 @@ const ECC384_IV_SIZE: usize = 48;
 /// ECC384 Nonce size
 const ECC384_NONCE_SIZE: usize = 48;
 
-/// The number of CPU clock cycles it takes to perform ECC operation
-const ECC384_OP_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles read and write keys from key vault
-const KEY_RW_TICKS: u64 = 100;
-
 register_bitfields! [
     u32,
 
@@ -203,12 +199,19 @@ pub struct AsymEcc384 {
     #[register(offset = 0x0000_0614)]
     key_write_status: ReadOnlyRegister<u32, KeyWriteStatus::Register>,
 
+    /// Interrupt Block
+    #[peripheral(offset = 0x0000_0800, mask = 0x0000_00ff)]
+    intr_block: IntrBlock,
+
     /// Key Vault
     key_vault: KeyVault,
 
     /// Timer
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     /// Operation complete callback
     op_complete_action: Option<ActionHandle>,
 
@@ -236,7 +239,7 @@ impl AsymEcc384 {
     const VERSION1_VAL: RvData = 0x00000000;
 
     /// Create a new instance of ECC-384 Engine
-    pub fn new(clock: &Clock, key_vault: KeyVault) -> Self {
+    pub fn new(clock: &Clock, key_vault: KeyVault, pic: &Pic) -> Self {
         Self {
             name0: ReadOnlyRegister::new(Self::NAME0_VAL),
             name1: ReadOnlyRegister::new(Self::NAME1_VAL),
@@ -264,6 +267,8 @@ impl AsymEcc384 {
             key_write_status: ReadOnlyRegister::new(KeyWriteStatus::READY::SET.value),
             key_vault,
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
+            intr_block: IntrBlock::new(pic, irq::ECC_ERROR, irq::ECC_NOTIF),
             op_complete_action: None,
             op_key_read_complete_action: None,
             op_seed_read_complete_action: None,
@@ -271,6 +276,11 @@ impl AsymEcc384 {
         }
     }
 
+    /// Set the latency of ECC operations and key vault accesses
+    pub fn set_latency(&mut self, latency: CryptoLatency) {
+        self.latency = latency;
+    }
+
     /// On Write callback for `control` register
     ///
     /// # Arguments
@@ -290,18 +300,19 @@ impl AsymEcc384 {
         // Set the control register
         self.control.reg.set(val);
 
-        match self.control.reg.read_as_enum(Control::CTRL) {
-            Some(Control::CTRL::Value::GEN_KEY)
-            | Some(Control::CTRL::Value::SIGN)
-            | Some(Control::CTRL::Value::VERIFY) => {
-                // Reset the Ready and Valid status bits
-                self.status
-                    .reg
-                    .modify(Status::READY::CLEAR + Status::VALID::CLEAR);
-
-                self.op_complete_action = Some(self.timer.schedule_poll_in(ECC384_OP_TICKS));
-            }
-            _ => {}
+        let op_ticks = match self.control.reg.read_as_enum(Control::CTRL) {
+            Some(Control::CTRL::Value::GEN_KEY) => Some(self.latency.ecc384_keygen),
+            Some(Control::CTRL::Value::SIGN) => Some(self.latency.ecc384_sign),
+            Some(Control::CTRL::Value::VERIFY) => Some(self.latency.ecc384_verify),
+            _ => None,
+        };
+        if let Some(op_ticks) = op_ticks {
+            // Reset the Ready and Valid status bits
+            self.status
+                .reg
+                .modify(Status::READY::CLEAR + Status::VALID::CLEAR);
+
+            self.op_complete_action = Some(self.timer.schedule_poll_in(op_ticks));
         }
 
         if self.control.reg.is_set(Control::ZEROIZE) {
@@ -342,7 +353,8 @@ impl AsymEcc384 {
                     + KeyReadStatus::ERROR::CLEAR,
             );
 
-            self.op_key_read_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_key_read_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         Ok(())
@@ -383,7 +395,8 @@ impl AsymEcc384 {
                     + KeyReadStatus::ERROR::CLEAR,
             );
 
-            self.op_seed_read_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_seed_read_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         Ok(())
@@ -468,6 +481,8 @@ impl AsymEcc384 {
         self.status
             .reg
             .modify(Status::READY::SET + Status::VALID::SET);
+
+        self.intr_block.set_notif(NOTIF_CMD_DONE_STS);
     }
 
     fn key_read_complete(&mut self) {
@@ -585,7 +600,8 @@ impl AsymEcc384 {
                     + KeyWriteStatus::ERROR::CLEAR,
             );
 
-            self.op_key_write_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_key_write_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         } else {
             // Make the private key available to the uC
             self.priv_key_out = self.priv_key_in;
@@ -637,6 +653,67 @@ impl AsymEcc384 {
     }
 }
 
+impl Snapshot for AsymEcc384 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.control);
+        w.write(&self.status);
+        w.write(&self.sca_cfg);
+        w.write(&self.seed);
+        w.write(&self.hash);
+        w.write(&self.priv_key_out);
+        w.write(&self.priv_key_in);
+        w.write(&self.pub_key_x);
+        w.write(&self.pub_key_y);
+        w.write(&self.sig_r);
+        w.write(&self.sig_s);
+        w.write(&self.verify_r);
+        w.write(&self.iv);
+        w.write(&self.nonce);
+        w.write(&self.key_read_ctrl);
+        w.write(&self.key_read_status);
+        w.write(&self.seed_read_ctrl);
+        w.write(&self.seed_read_status);
+        w.write(&self.key_write_ctrl);
+        w.write(&self.key_write_status);
+        w.write(&self.intr_block);
+        self.timer.save_action(w, &self.op_complete_action);
+        self.timer.save_action(w, &self.op_key_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_seed_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_key_write_complete_action);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.control)?;
+        r.read(&mut self.status)?;
+        r.read(&mut self.sca_cfg)?;
+        r.read(&mut self.seed)?;
+        r.read(&mut self.hash)?;
+        r.read(&mut self.priv_key_out)?;
+        r.read(&mut self.priv_key_in)?;
+        r.read(&mut self.pub_key_x)?;
+        r.read(&mut self.pub_key_y)?;
+        r.read(&mut self.sig_r)?;
+        r.read(&mut self.sig_s)?;
+        r.read(&mut self.verify_r)?;
+        r.read(&mut self.iv)?;
+        r.read(&mut self.nonce)?;
+        r.read(&mut self.key_read_ctrl)?;
+        r.read(&mut self.key_read_status)?;
+        r.read(&mut self.seed_read_ctrl)?;
+        r.read(&mut self.seed_read_status)?;
+        r.read(&mut self.key_write_ctrl)?;
+        r.read(&mut self.key_write_status)?;
+        r.read(&mut self.intr_block)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        self.op_key_read_complete_action = self.timer.restore_action(r)?;
+        self.op_seed_read_complete_action = self.timer.restore_action(r)?;
+        self.op_key_write_complete_action = self.timer.restore_action(r)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -712,7 +789,8 @@ mod tests {
 
     #[test]
     fn test_name() {
-        let mut ecc = AsymEcc384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let name0 = ecc.read(RvSize::Word, OFFSET_NAME0).unwrap();
         let name0 = String::from_utf8_lossy(&name0.to_be_bytes()).to_string();
@@ -725,7 +803,8 @@ mod tests {
 
     #[test]
     fn test_version() {
-        let mut ecc = AsymEcc384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let version0 = ecc.read(RvSize::Word, OFFSET_VERSION0).unwrap();
         let version0 = String::from_utf8_lossy(&version0.to_le_bytes()).to_string();
@@ -738,20 +817,22 @@ mod tests {
 
     #[test]
     fn test_control() {
-        let mut ecc = AsymEcc384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(ecc.read(RvSize::Word, OFFSET_CONTROL).unwrap(), 0);
     }
 
     #[test]
     fn test_status() {
-        let mut ecc = AsymEcc384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(ecc.read(RvSize::Word, OFFSET_STATUS).unwrap(), 1);
     }
 
     #[test]
     fn test_gen_key() {
         let clock = Clock::new();
-        let mut ecc = AsymEcc384::new(&clock, KeyVault::new());
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let mut seed = [0u8; 48];
         seed.to_big_endian(); // Change DWORDs to big-endian.
@@ -824,7 +905,7 @@ mod tests {
             key_vault
                 .write_key(key_id, &seed, u32::from(key_usage))
                 .unwrap();
-            let mut ecc = AsymEcc384::new(&clock, key_vault);
+            let mut ecc = AsymEcc384::new(&clock, key_vault, &Pic::new(&clock));
 
             // Instruct seed to be read from key-vault.
             let seed_ctrl = InMemoryRegister::<u32, KeyReadControl::Register>::new(0);
@@ -892,7 +973,7 @@ mod tests {
             let mut seed = [0u8; 48];
             seed.to_big_endian(); // Change DWORDs to big-endian.
 
-            let mut ecc = AsymEcc384::new(&clock, KeyVault::new());
+            let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
             for i in (0..seed.len()).step_by(4) {
                 assert_eq!(
@@ -970,7 +1051,7 @@ mod tests {
     #[test]
     fn test_sign() {
         let clock = Clock::new();
-        let mut ecc = AsymEcc384::new(&clock, KeyVault::new());
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let mut hash = [0u8; KeyVault::KEY_SIZE];
         hash.to_big_endian(); // Change DWORDs to big-endian.
@@ -1041,7 +1122,7 @@ mod tests {
                 .write_key(key_id, &priv_key, u32::from(key_usage))
                 .unwrap();
 
-            let mut ecc = AsymEcc384::new(&clock, key_vault);
+            let mut ecc = AsymEcc384::new(&clock, key_vault, &Pic::new(&clock));
 
             let mut hash = [0u8; 48];
             hash.to_big_endian(); // Change DWORDs to big-endian.
@@ -1122,7 +1203,7 @@ mod tests {
                 .write_key(key_id, &priv_key, !(u32::from(key_usage)))
                 .unwrap();
 
-            let mut ecc = AsymEcc384::new(&clock, key_vault);
+            let mut ecc = AsymEcc384::new(&clock, key_vault, &Pic::new(&clock));
 
             let mut hash = [0u8; 48];
             hash.to_big_endian(); // Change DWORDs to big-endian.
@@ -1166,7 +1247,7 @@ mod tests {
     #[test]
     fn test_verify() {
         let clock = Clock::new();
-        let mut ecc = AsymEcc384::new(&clock, KeyVault::new());
+        let mut ecc = AsymEcc384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let hash = [0u8; KeyVault::KEY_SIZE];
         for i in (0..hash.len()).step_by(4) {
@@ -0,0 +1,185 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    boot_timing.rs
+
+Abstract:
+
+    File contains the boot phase cycle budgets derived from the boot status
+    reported by the firmware.
+
+--*/
+
+use std::fmt;
+
+/// A write to the CPTRA_BOOT_STATUS register
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BootStatusChange {
+    /// Clock cycle of the write
+    pub cycle: u64,
+
+    pub status: u32,
+}
+
+/// Consecutive boot statuses belonging to the same part of the boot flow
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BootPhase {
+    pub name: &'static str,
+
+    /// Cycle at which the previous phase reported its last status
+    pub start: u64,
+
+    /// Cycle at which this phase reported its last status
+    pub end: u64,
+}
+
+impl BootPhase {
+    pub fn cycles(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+/// Boot statuses reported by the ROM, FMC and runtime, with the cycles at
+/// which they were reported
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BootTiming {
+    changes: Vec<BootStatusChange>,
+}
+
+impl BootTiming {
+    pub fn new(changes: Vec<BootStatusChange>) -> Self {
+        Self { changes }
+    }
+
+    pub fn changes(&self) -> &[BootStatusChange] {
+        &self.changes
+    }
+
+    /// Returns the cycle at which `status` was first reported
+    pub fn cycle_of(&self, status: u32) -> Option<u64> {
+        self.changes
+            .iter()
+            .find(|c| c.status == status)
+            .map(|c| c.cycle)
+    }
+
+    /// Group the boot statuses into phases. A phase that is entered more than
+    /// once (such as after a warm reset) is reported each time.
+    pub fn phases(&self) -> Vec<BootPhase> {
+        let mut result: Vec<BootPhase> = vec![];
+        let mut start = 0;
+        for change in self.changes.iter() {
+            let name = phase_name(change.status);
+            match result.last_mut() {
+                Some(phase) if phase.name == name => phase.end = change.cycle,
+                _ => result.push(BootPhase {
+                    name,
+                    start,
+                    end: change.cycle,
+                }),
+            }
+            start = change.cycle;
+        }
+        result
+    }
+}
+
+impl fmt::Display for BootTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12} {:>12}",
+            "phase", "start", "end", "cycles"
+        )?;
+        for phase in self.phases() {
+            writeln!(
+                f,
+                "{:<24} {:>12} {:>12} {:>12}",
+                phase.name,
+                phase.start,
+                phase.end,
+                phase.cycles()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Boot status ranges of the ROM (common/src/boot_status.rs), FMC
+/// (fmc/src/boot_status.rs) and runtime (runtime/src/lib.rs)
+fn phase_name(status: u32) -> &'static str {
+    match status {
+        1..=64 => "ROM IDevID",
+        65..=128 => "ROM LDevID",
+        129..=192 => "ROM firmware processor",
+        193..=256 => "ROM FMC alias",
+        257..=320 => "ROM cold reset",
+        321..=384 => "ROM update reset",
+        385..=0x3ff => "ROM",
+        0x400..=0x5ff => "FMC RT alias",
+        0x600..=0x7ff => "Runtime",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(cycle: u64, status: u32) -> BootStatusChange {
+        BootStatusChange { cycle, status }
+    }
+
+    #[test]
+    fn test_phases() {
+        let timing = BootTiming::new(vec![
+            change(100, 385),
+            change(5000, 387),
+            change(6000, 1),
+            change(9000, 10),
+            change(12000, 65),
+            change(20000, 0x400),
+            change(30000, 0x600),
+        ]);
+        assert_eq!(
+            timing.phases(),
+            vec![
+                BootPhase {
+                    name: "ROM",
+                    start: 0,
+                    end: 5000
+                },
+                BootPhase {
+                    name: "ROM IDevID",
+                    start: 5000,
+                    end: 9000
+                },
+                BootPhase {
+                    name: "ROM LDevID",
+                    start: 9000,
+                    end: 12000
+                },
+                BootPhase {
+                    name: "FMC RT alias",
+                    start: 12000,
+                    end: 20000
+                },
+                BootPhase {
+                    name: "Runtime",
+                    start: 20000,
+                    end: 30000
+                },
+            ]
+        );
+        assert_eq!(timing.phases()[1].cycles(), 4000);
+        assert_eq!(timing.cycle_of(0x600), Some(30000));
+        assert_eq!(timing.cycle_of(0x601), None);
+        assert_eq!(
+            timing.to_string().lines().nth(2),
+            Some("ROM IDevID                       5000         9000         4000")
+        );
+    }
+}
@@ -2,7 +2,7 @@
 
 use caliptra_emu_bus::{BusError, ReadOnlyRegister, WriteOnlyRegister};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use caliptra_registers::entropy_src::regs::{
     AdaptpHiThresholdsReadVal, AdaptpLoThresholdsReadVal, ConfReadVal, HealthTestWindowsReadVal,
     RepcntThresholdsReadVal,
@@ -303,6 +303,55 @@ impl Csrng {
     }
 }
 
+impl Snapshot for Csrng {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.ctrl);
+        w.write(&self.cmd_req);
+        w.write(&self.sw_cmd_sts);
+        w.write(&self.genbits_vld);
+        w.write(&self.genbits);
+        w.write(&self.err_code);
+        w.write(&self.module_enable);
+        w.write(&self.conf);
+        w.write(&self.health_test_windows);
+        w.write(&self.repcnt_thresholds);
+        w.write(&self.adaptp_hi_thresholds);
+        w.write(&self.adaptp_lo_thresholds);
+        w.write(&self.alert_summary_fail_counts);
+        w.write(&self.alert_fail_counts);
+        w.write(&self.main_sm_state);
+        w.write(&self.cmd_req_state);
+        w.write(&self.seed);
+        w.write(&self.ctr_drbg);
+        w.write(&self.words);
+        w.write(&self.health_tester);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.ctrl)?;
+        r.read(&mut self.cmd_req)?;
+        r.read(&mut self.sw_cmd_sts)?;
+        r.read(&mut self.genbits_vld)?;
+        r.read(&mut self.genbits)?;
+        r.read(&mut self.err_code)?;
+        r.read(&mut self.module_enable)?;
+        r.read(&mut self.conf)?;
+        r.read(&mut self.health_test_windows)?;
+        r.read(&mut self.repcnt_thresholds)?;
+        r.read(&mut self.adaptp_hi_thresholds)?;
+        r.read(&mut self.adaptp_lo_thresholds)?;
+        r.read(&mut self.alert_summary_fail_counts)?;
+        r.read(&mut self.alert_fail_counts)?;
+        r.read(&mut self.main_sm_state)?;
+        r.read(&mut self.cmd_req_state)?;
+        r.read(&mut self.seed)?;
+        r.read(&mut self.ctr_drbg)?;
+        r.read(&mut self.words)?;
+        r.read(&mut self.health_tester)?;
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 struct Words {
     block: Block,
@@ -322,6 +371,19 @@ impl Words {
     }
 }
 
+impl Snapshot for Words {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.block);
+        w.write(&self.cursor);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.block)?;
+        r.read(&mut self.cursor)?;
+        Ok(())
+    }
+}
+
 impl Iterator for Words {
     type Item = Word;
 
@@ -355,6 +417,29 @@ enum CmdReqState {
     ExpectSeedWords { num_words: usize },
 }
 
+impl Snapshot for CmdReqState {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        match self {
+            Self::ExpectNewCommand => w.write_u8(0),
+            Self::ExpectSeedWords { num_words } => {
+                w.write_u8(1);
+                w.write_usize(*num_words);
+            }
+        }
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = match r.read_u8()? {
+            0 => Self::ExpectNewCommand,
+            1 => Self::ExpectSeedWords {
+                num_words: r.read_usize()?,
+            },
+            _ => return Err(SnapshotError::InvalidValue("csrng cmd_req_state")),
+        };
+        Ok(())
+    }
+}
+
 #[repr(u32)]
 enum MultiBitBool {
     False = 9,
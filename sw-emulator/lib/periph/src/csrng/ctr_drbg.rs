@@ -3,6 +3,7 @@
 //! Unverified implementation of CTR_DRBG AES-256
 //! Section 10.2 (page 48) of https://doi.org/10.6028/NIST.SP.800-90Ar1
 
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use std::iter;
 
 use super::WORD_SIZE_BYTES;
@@ -34,6 +35,21 @@ pub struct CtrDrbg {
     generated_bytes: Vec<Block>,
 }
 
+impl Snapshot for CtrDrbg {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.v);
+        w.write(&self.key);
+        w.write(&self.generated_bytes);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.v)?;
+        r.read(&mut self.key)?;
+        r.read(&mut self.generated_bytes)?;
+        Ok(())
+    }
+}
+
 impl CtrDrbg {
     pub fn new() -> Self {
         Self {
@@ -1,6 +1,7 @@
 // Licensed under the Apache-2.0 license
 
 use super::BITS_PER_NIBBLE;
+use caliptra_emu_types::{CountingIter, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use caliptra_registers::entropy_src::regs::{
     AdaptpHiThresholdsReadVal, AdaptpLoThresholdsReadVal, RepcntThresholdsReadVal,
 };
@@ -8,7 +9,7 @@ use caliptra_registers::entropy_src::regs::{
 const HEALTH_TEST_WINDOW_BITS: usize = 2048;
 
 pub struct HealthTester {
-    itrng_nibbles: Box<dyn Iterator<Item = u8>>,
+    itrng_nibbles: CountingIter<u8>,
     pub repcnt: RepetitionCountTester,
     pub adaptp: AdaptiveProportionTester,
     boot_time_nibbles: Vec<u8>,
@@ -17,7 +18,7 @@ pub struct HealthTester {
 impl HealthTester {
     pub fn new(itrng_nibbles: Box<dyn Iterator<Item = u8>>) -> Self {
         Self {
-            itrng_nibbles,
+            itrng_nibbles: CountingIter::new(itrng_nibbles),
             repcnt: RepetitionCountTester::new(),
             adaptp: AdaptiveProportionTester::new(),
             boot_time_nibbles: Vec::new(),
@@ -48,6 +49,25 @@ impl HealthTester {
     }
 }
 
+/// The entropy source of the instance being restored is advanced to the
+/// saved position.
+impl Snapshot for HealthTester {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.itrng_nibbles);
+        w.write(&self.repcnt);
+        w.write(&self.adaptp);
+        w.write(&self.boot_time_nibbles);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.itrng_nibbles)?;
+        r.read(&mut self.repcnt)?;
+        r.read(&mut self.adaptp)?;
+        r.read(&mut self.boot_time_nibbles)?;
+        Ok(())
+    }
+}
+
 impl Iterator for HealthTester {
     type Item = u8;
 
@@ -79,6 +99,36 @@ pub struct RepetitionCountTester {
     failures: u32,
 }
 
+impl Snapshot for RepetitionCountTester {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u32(self.threshold);
+        for bit in self.prev_nibble {
+            w.write_u8(match bit {
+                None => 0,
+                Some(Bit::Zero) => 1,
+                Some(Bit::One) => 2,
+            });
+        }
+        w.write(&self.repetition_count);
+        w.write_u32(self.failures);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.threshold = r.read_u32()?;
+        for bit in self.prev_nibble.iter_mut() {
+            *bit = match r.read_u8()? {
+                0 => None,
+                1 => Some(Bit::Zero),
+                2 => Some(Bit::One),
+                _ => return Err(SnapshotError::InvalidValue("repcnt prev_nibble")),
+            };
+        }
+        r.read(&mut self.repetition_count)?;
+        self.failures = r.read_u32()?;
+        Ok(())
+    }
+}
+
 impl RepetitionCountTester {
     pub fn new() -> Self {
         Self {
@@ -135,6 +185,27 @@ pub struct AdaptiveProportionTester {
     num_bits_seen: usize,
 }
 
+impl Snapshot for AdaptiveProportionTester {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.lo_threshold);
+        w.write(&self.hi_threshold);
+        w.write(&self.lo_failures);
+        w.write(&self.hi_failures);
+        w.write(&self.num_ones_seen);
+        w.write(&self.num_bits_seen);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.lo_threshold)?;
+        r.read(&mut self.hi_threshold)?;
+        r.read(&mut self.lo_failures)?;
+        r.read(&mut self.hi_failures)?;
+        r.read(&mut self.num_ones_seen)?;
+        r.read(&mut self.num_bits_seen)?;
+        Ok(())
+    }
+}
+
 impl AdaptiveProportionTester {
     pub fn new() -> Self {
         Self {
@@ -13,22 +13,20 @@ Abstract:
 --*/
 
 use crate::helpers::bytes_swap_word_endian;
+use crate::latency::CryptoLatency;
 use crate::{KeyVault, SocRegistersInternal};
 use caliptra_emu_bus::{
     ActionHandle, BusError, Clock, ReadOnlyRegister, ReadWriteMemory, ReadWriteRegister, Timer,
 };
 use caliptra_emu_crypto::Aes256Cbc;
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::register_bitfields;
 
 /// Initialization vector size
 const DOE_IV_SIZE: usize = 16;
 
-/// The number of CPU clock cycles it takes to perform the hash update action.
-const DOE_OP_TICKS: u64 = 1000;
-
 // hmac_key_dest_valid | hmac_block_dest_valid
 const DOE_KEY_USAGE: u32 = 0x3;
 
@@ -76,6 +74,9 @@ pub struct Doe {
     /// Timer
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     /// Key Vault
     key_vault: KeyVault,
 
@@ -104,12 +105,18 @@ impl Doe {
             control: ReadWriteRegister::new(0),
             status: ReadOnlyRegister::new(Status::READY::SET.value),
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
             key_vault,
             soc_reg,
             op_complete_action: None,
         }
     }
 
+    /// Set the latency of deobfuscation commands
+    pub fn set_latency(&mut self, latency: CryptoLatency) {
+        self.latency = latency;
+    }
+
     /// On Write callback for `control` register
     ///
     /// # Arguments
@@ -133,7 +140,7 @@ impl Doe {
             self.status
                 .reg
                 .modify(Status::READY::CLEAR + Status::VALID::CLEAR);
-            self.op_complete_action = Some(self.timer.schedule_poll_in(DOE_OP_TICKS));
+            self.op_complete_action = Some(self.timer.schedule_poll_in(self.latency.doe));
         }
 
         Ok(())
@@ -209,10 +216,27 @@ impl Doe {
     }
 }
 
+impl Snapshot for Doe {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.iv);
+        w.write(&self.control);
+        w.write(&self.status);
+        self.timer.save_action(w, &self.op_complete_action);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.iv)?;
+        r.read(&mut self.control)?;
+        r.read(&mut self.status)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{CaliptraRootBusArgs, Iccm, KeyUsage, MailboxInternal, MailboxRam};
+    use crate::{CaliptraRootBusArgs, Iccm, KeyUsage, MailboxInternal, MailboxRam, Pic};
     use caliptra_emu_bus::Bus;
     use caliptra_emu_crypto::EndianessTransform;
     use caliptra_emu_types::RvAddr;
@@ -250,8 +274,9 @@ mod tests {
         let key_vault = KeyVault::new();
         let soc_reg = SocRegistersInternal::new(
             &clock,
-            MailboxInternal::new(MailboxRam::new()),
+            MailboxInternal::new(&clock, MailboxRam::new()),
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs {
                 security_state: *SecurityState::default().set_debug_locked(true),
                 ..CaliptraRootBusArgs::default()
@@ -311,8 +336,9 @@ mod tests {
         let key_vault = KeyVault::new();
         let soc_reg = SocRegistersInternal::new(
             &clock,
-            MailboxInternal::new(MailboxRam::new()),
+            MailboxInternal::new(&clock, MailboxRam::new()),
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs {
                 security_state: *SecurityState::default().set_debug_locked(true),
                 ..CaliptraRootBusArgs::default()
@@ -372,8 +398,9 @@ mod tests {
         let key_vault = KeyVault::new();
         let soc_reg = SocRegistersInternal::new(
             &clock,
-            MailboxInternal::new(MailboxRam::new()),
+            MailboxInternal::new(&clock, MailboxRam::new()),
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs {
                 security_state: *SecurityState::default().set_debug_locked(true),
                 ..CaliptraRootBusArgs::default()
@@ -12,13 +12,16 @@ Abstract:
 
 --*/
 
+use crate::intr_block::{irq, IntrBlock, NOTIF_CMD_DONE_STS};
+use crate::latency::CryptoLatency;
+use crate::Pic;
 use caliptra_emu_bus::{
     ActionHandle, BusError, Clock, ReadOnlyMemory, ReadOnlyRegister, ReadWriteMemory,
     ReadWriteRegister, Timer,
 };
 use caliptra_emu_crypto::{Sha256, Sha256Mode};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::register_bitfields;
 
@@ -48,12 +51,6 @@ const SHA256_BLOCK_SIZE: usize = 64;
 
 const SHA256_HASH_SIZE: usize = 32;
 
-/// The number of CPU clock cycles it takes to perform initialization action.
-const INIT_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles it takes to perform the hash update action.
-const UPDATE_TICKS: u64 = 1000;
-
 /// SHA-256 Peripheral
 #[derive(Bus)]
 #[poll_fn(poll)]
@@ -92,11 +89,18 @@ pub struct HashSha256 {
     #[peripheral(offset = 0x0000_0100, mask = 0x0000_00ff)]
     hash: ReadOnlyMemory<SHA256_HASH_SIZE>,
 
+    /// Interrupt Block
+    #[peripheral(offset = 0x0000_0800, mask = 0x0000_00ff)]
+    intr_block: IntrBlock,
+
     /// SHA256 engine
     sha256: Sha256,
 
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     op_complete_action: Option<ActionHandle>,
 }
 
@@ -114,7 +118,7 @@ impl HashSha256 {
     const VERSION1_VAL: RvData = 0x00000000;
 
     /// Create a new instance of SHA-512 Engine
-    pub fn new(clock: &Clock) -> Self {
+    pub fn new(clock: &Clock, pic: &Pic) -> Self {
         Self {
             sha256: Sha256::new(Sha256Mode::Sha256), // Default SHA256 mode
             name0: ReadOnlyRegister::new(Self::NAME0_VAL),
@@ -126,10 +130,17 @@ impl HashSha256 {
             block: ReadWriteMemory::new(),
             hash: ReadOnlyMemory::new(),
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
+            intr_block: IntrBlock::new(pic, irq::SHA256_ERROR, irq::SHA256_NOTIF),
             op_complete_action: None,
         }
     }
 
+    /// Set the latency of hash operations
+    pub fn set_latency(&mut self, latency: CryptoLatency) {
+        self.latency = latency;
+    }
+
     /// On Write callback for `control` register
     ///
     /// # Arguments
@@ -176,13 +187,15 @@ impl HashSha256 {
                 self.sha256.update(self.block.data());
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(INIT_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.sha256_block));
             } else if self.control.reg.is_set(Control::NEXT) {
                 // Update the SHA512 engine with a new block
                 self.sha256.update(self.block.data());
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(UPDATE_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.sha256_block));
             }
         }
 
@@ -203,6 +216,8 @@ impl HashSha256 {
             self.status
                 .reg
                 .modify(Status::READY::SET + Status::VALID::SET);
+
+            self.intr_block.set_notif(NOTIF_CMD_DONE_STS);
         }
     }
 
@@ -226,6 +241,29 @@ impl HashSha256 {
     }
 }
 
+impl Snapshot for HashSha256 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.control);
+        w.write(&self.status);
+        w.write(&self.block);
+        w.write(&self.hash);
+        w.write(&self.intr_block);
+        w.write(&self.sha256);
+        self.timer.save_action(w, &self.op_complete_action);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.control)?;
+        r.read(&mut self.status)?;
+        r.read(&mut self.block)?;
+        r.read(&mut self.hash)?;
+        r.read(&mut self.intr_block)?;
+        r.read(&mut self.sha256)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,7 +283,8 @@ mod tests {
 
     #[test]
     fn test_name_read() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
 
         let name0 = sha256.read(RvSize::Word, OFFSET_NAME0).unwrap();
         let mut name0 = String::from_utf8_lossy(&name0.to_le_bytes()).to_string();
@@ -259,7 +298,8 @@ mod tests {
 
     #[test]
     fn test_version_read() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
 
         let version0 = sha256.read(RvSize::Word, OFFSET_VERSION0).unwrap();
         let version0 = String::from_utf8_lossy(&version0.to_le_bytes()).to_string();
@@ -272,19 +312,22 @@ mod tests {
 
     #[test]
     fn test_control_read() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
         assert_eq!(sha256.read(RvSize::Word, OFFSET_CONTROL).unwrap(), 0);
     }
 
     #[test]
     fn test_status_read() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
         assert_eq!(sha256.read(RvSize::Word, OFFSET_STATUS).unwrap(), 1);
     }
 
     #[test]
     fn test_block_read_write() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
         for addr in (OFFSET_BLOCK..(OFFSET_BLOCK + SHA256_BLOCK_SIZE as u32)).step_by(4) {
             assert_eq!(sha256.write(RvSize::Word, addr, u32::MAX).ok(), Some(()));
             assert_eq!(sha256.read(RvSize::Word, addr).ok(), Some(u32::MAX));
@@ -293,7 +336,8 @@ mod tests {
 
     #[test]
     fn test_hash_read_write() {
-        let mut sha256 = HashSha256::new(&Clock::new());
+        let clock = Clock::new();
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
         for addr in (OFFSET_HASH..(OFFSET_HASH + SHA256_HASH_SIZE as u32)).step_by(4) {
             assert_eq!(sha256.read(RvSize::Word, addr).ok(), Some(0));
             assert_eq!(
@@ -328,7 +372,7 @@ mod tests {
         block_arr.to_big_endian();
 
         let clock = Clock::new();
-        let mut sha256 = HashSha256::new(&clock);
+        let mut sha256 = HashSha256::new(&clock, &Pic::new(&clock));
 
         // Process each block via the SHA engine.
         for idx in 0..totalblocks {
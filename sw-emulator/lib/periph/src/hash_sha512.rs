@@ -13,15 +13,17 @@ Abstract:
 --*/
 
 use crate::helpers::words_from_bytes_le;
+use crate::intr_block::{irq, IntrBlock, NOTIF_CMD_DONE_STS};
 use crate::key_vault::KeyUsage;
-use crate::KeyVault;
+use crate::latency::CryptoLatency;
+use crate::{KeyVault, Pic};
 use caliptra_emu_bus::{
     ActionHandle, BusError, Clock, ReadOnlyMemory, ReadOnlyRegister, ReadWriteRegister, Timer,
 };
 use caliptra_emu_crypto::EndianessTransform;
 use caliptra_emu_crypto::{Sha512, Sha512Mode};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::register_bitfields;
 use tock_registers::registers::InMemoryRegister;
@@ -91,15 +93,6 @@ const SHA512_BLOCK_SIZE_WORDS: usize = 128 >> 2;
 
 const SHA512_HASH_SIZE: usize = 64;
 
-/// The number of CPU clock cycles it takes to perform initialization action.
-const INIT_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles it takes to perform the hash update action.
-const UPDATE_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles read and write keys from key vault
-const KEY_RW_TICKS: u64 = 100;
-
 fn sha512_block_words_from_bytes_le(
     arr: &[u8; SHA512_BLOCK_SIZE],
 ) -> [u32; SHA512_BLOCK_SIZE_WORDS] {
@@ -173,6 +166,10 @@ pub struct HashSha512 {
     #[register(offset = 0x0000_060c)]
     hash_write_status: ReadOnlyRegister<u32, HashWriteStatus::Register>,
 
+    /// Interrupt Block
+    #[peripheral(offset = 0x0000_0800, mask = 0x0000_00ff)]
+    intr_block: IntrBlock,
+
     /// SHA512 engine
     sha512: Sha512,
 
@@ -181,6 +178,9 @@ pub struct HashSha512 {
 
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     /// Operation complete action
     op_complete_action: Option<ActionHandle>,
 
@@ -207,7 +207,7 @@ impl HashSha512 {
     const VERSION1_VAL: RvData = 0x00000000;
 
     /// Create a new instance of SHA-512 Engine
-    pub fn new(clock: &Clock, key_vault: KeyVault) -> Self {
+    pub fn new(clock: &Clock, key_vault: KeyVault, pic: &Pic) -> Self {
         Self {
             sha512: Sha512::new(Sha512Mode::Sha512), // Default SHA512 mode
             name0: ReadOnlyRegister::new(Self::NAME0_VAL),
@@ -224,6 +224,8 @@ impl HashSha512 {
             hash: ReadOnlyMemory::new(),
             key_vault,
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
+            intr_block: IntrBlock::new(pic, irq::SHA512_ERROR, irq::SHA512_NOTIF),
             op_complete_action: None,
             op_block_read_complete_action: None,
             op_hash_write_complete_action: None,
@@ -231,6 +233,11 @@ impl HashSha512 {
         }
     }
 
+    /// Set the latency of hash operations and key vault accesses
+    pub fn set_latency(&mut self, latency: CryptoLatency) {
+        self.latency = latency;
+    }
+
     /// On Write callback for `control` register
     ///
     /// # Arguments
@@ -287,14 +294,16 @@ impl HashSha512 {
                     .update(&sha512_block_bytes_from_words_le(&self.block));
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(INIT_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.sha512_block));
             } else if self.control.reg.is_set(Control::NEXT) {
                 // Update the SHA512 engine with a new block
                 self.sha512
                     .update(&sha512_block_bytes_from_words_le(&self.block));
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(UPDATE_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.sha512_block));
             }
         }
 
@@ -361,7 +370,8 @@ impl HashSha512 {
                     + BlockReadStatus::ERROR::CLEAR,
             );
 
-            self.op_block_read_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_block_read_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         Ok(())
@@ -437,7 +447,8 @@ impl HashSha512 {
                     + HashWriteStatus::ERROR::CLEAR,
             );
 
-            self.op_hash_write_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_hash_write_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         } else if self.control.reg.is_set(Control::LAST)
             && self
                 .block_read_ctrl
@@ -459,6 +470,8 @@ impl HashSha512 {
         self.status
             .reg
             .modify(Status::READY::SET + Status::VALID::SET);
+
+        self.intr_block.set_notif(NOTIF_CMD_DONE_STS);
     }
 
     fn block_read_complete(&mut self) {
@@ -575,6 +588,45 @@ impl HashSha512 {
     }
 }
 
+impl Snapshot for HashSha512 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.control);
+        w.write(&self.status);
+        w.write(&self.block);
+        w.write(&self.hash);
+        w.write(&self.block_read_ctrl);
+        w.write(&self.block_read_status);
+        w.write(&self.hash_write_ctrl);
+        w.write(&self.hash_write_status);
+        w.write(&self.intr_block);
+        w.write(&self.sha512);
+        self.timer.save_action(w, &self.op_complete_action);
+        self.timer
+            .save_action(w, &self.op_block_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_hash_write_complete_action);
+        w.write_bool(self.pcr_present);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.control)?;
+        r.read(&mut self.status)?;
+        r.read(&mut self.block)?;
+        r.read(&mut self.hash)?;
+        r.read(&mut self.block_read_ctrl)?;
+        r.read(&mut self.block_read_status)?;
+        r.read(&mut self.hash_write_ctrl)?;
+        r.read(&mut self.hash_write_status)?;
+        r.read(&mut self.intr_block)?;
+        r.read(&mut self.sha512)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        self.op_block_read_complete_action = self.timer.restore_action(r)?;
+        self.op_hash_write_complete_action = self.timer.restore_action(r)?;
+        self.pcr_present = r.read_bool()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -602,7 +654,8 @@ mod tests {
 
     #[test]
     fn test_name_read() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let name0 = sha512.read(RvSize::Word, OFFSET_NAME0).unwrap();
         let mut name0 = String::from_utf8_lossy(&name0.to_le_bytes()).to_string();
@@ -616,7 +669,8 @@ mod tests {
 
     #[test]
     fn test_version_read() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let version0 = sha512.read(RvSize::Word, OFFSET_VERSION0).unwrap();
         let version0 = String::from_utf8_lossy(&version0.to_le_bytes()).to_string();
@@ -629,19 +683,22 @@ mod tests {
 
     #[test]
     fn test_control_read() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(sha512.read(RvSize::Word, OFFSET_CONTROL).unwrap(), 0);
     }
 
     #[test]
     fn test_status_read() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(sha512.read(RvSize::Word, OFFSET_STATUS).unwrap(), 1);
     }
 
     #[test]
     fn test_block_read_write() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
         for addr in (OFFSET_BLOCK..(OFFSET_BLOCK + SHA512_BLOCK_SIZE as u32)).step_by(4) {
             assert_eq!(sha512.write(RvSize::Word, addr, u32::MAX).ok(), Some(()));
             assert_eq!(sha512.read(RvSize::Word, addr).ok(), Some(u32::MAX));
@@ -650,7 +707,8 @@ mod tests {
 
     #[test]
     fn test_hash_read_write() {
-        let mut sha512 = HashSha512::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut sha512 = HashSha512::new(&clock, KeyVault::new(), &Pic::new(&clock));
         for addr in (OFFSET_HASH..(OFFSET_HASH + SHA512_HASH_SIZE as u32)).step_by(4) {
             assert_eq!(sha512.read(RvSize::Word, addr).ok(), Some(0));
             assert_eq!(
@@ -794,7 +852,7 @@ mod tests {
             );
         }
 
-        let mut sha512 = HashSha512::new(&clock, key_vault);
+        let mut sha512 = HashSha512::new(&clock, key_vault, &Pic::new(&clock));
 
         if hash_to_kv {
             // Instruct hash to be written to the key-vault.
@@ -1178,7 +1236,7 @@ mod tests {
         assert!(key_vault.write_pcr(pcr_id, pcr_data).is_ok());
         pcr_data.change_endianess();
 
-        let mut sha512 = HashSha512::new(&clock, key_vault);
+        let mut sha512 = HashSha512::new(&clock, key_vault, &Pic::new(&clock));
         // Enable pcr hash extend.
         let block_ctrl = InMemoryRegister::<u32, BlockReadControl::Register>::new(0);
         block_ctrl.modify(
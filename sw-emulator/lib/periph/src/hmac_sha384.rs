@@ -13,12 +13,14 @@ Abstract:
 --*/
 
 use crate::helpers::bytes_from_words_le;
-use crate::{KeyUsage, KeyVault};
+use crate::intr_block::{irq, IntrBlock, NOTIF_CMD_DONE_STS};
+use crate::latency::CryptoLatency;
+use crate::{KeyUsage, KeyVault, Pic};
 use caliptra_emu_bus::{ActionHandle, BusError, Clock, ReadOnlyRegister, ReadWriteRegister, Timer};
 use caliptra_emu_crypto::EndianessTransform;
 use caliptra_emu_crypto::{Hmac512, Hmac512Mode};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvData, RvSize};
+use caliptra_emu_types::{RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
 use tock_registers::register_bitfields;
 use tock_registers::registers::InMemoryRegister;
@@ -93,15 +95,6 @@ const HMAC_BLOCK_SIZE: usize = 128;
 /// HMAC Tag Size
 const HMAC_TAG_SIZE: usize = 48;
 
-/// The number of CPU clock cycles it takes to perform initialization action.
-const INIT_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles it takes to perform the hash update action.
-const UPDATE_TICKS: u64 = 1000;
-
-/// The number of CPU clock cycles read and write keys from key vault
-const KEY_RW_TICKS: u64 = 100;
-
 /// LSFR Seed Size.
 const HMAC_LFSR_SEED_SIZE: usize = 20;
 
@@ -175,6 +168,10 @@ pub struct HmacSha384 {
     #[register(offset = 0x0000_0614)]
     tag_write_status: ReadOnlyRegister<u32, TagWriteStatus::Register>,
 
+    /// Interrupt Block
+    #[peripheral(offset = 0x0000_0800, mask = 0x0000_00ff)]
+    intr_block: IntrBlock,
+
     // True if the current key was read from the key-vault
     key_from_kv: bool,
 
@@ -193,6 +190,9 @@ pub struct HmacSha384 {
     /// Timer
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     /// Operation complete action
     op_complete_action: Option<ActionHandle>,
 
@@ -229,7 +229,7 @@ impl HmacSha384 {
     /// # Returns
     ///
     /// * `Self` - Instance of HMAC-SHA-384 Engine
-    pub fn new(clock: &Clock, key_vault: KeyVault) -> Self {
+    pub fn new(clock: &Clock, key_vault: KeyVault, pic: &Pic) -> Self {
         Self {
             hmac: Hmac512::<HMAC_KEY_SIZE>::new(Hmac512Mode::Sha384),
             name0: ReadOnlyRegister::new(Self::NAME0_VAL),
@@ -250,9 +250,11 @@ impl HmacSha384 {
             tag_write_status: ReadOnlyRegister::new(TagWriteStatus::READY::SET.value),
             key_vault,
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
             key_from_kv: false,
             block_from_kv: false,
             hide_tag_from_cpu: false,
+            intr_block: IntrBlock::new(pic, irq::HMAC_ERROR, irq::HMAC_NOTIF),
             op_complete_action: None,
             op_key_read_complete_action: None,
             op_block_read_complete_action: None,
@@ -298,6 +300,11 @@ impl HmacSha384 {
         Ok(self.tag[index])
     }
 
+    /// Set the latency of HMAC operations and key vault accesses
+    pub fn set_latency(&mut self, latency: CryptoLatency) {
+        self.latency = latency;
+    }
+
     /// On Write callback for `control` register
     ///
     /// # Arguments
@@ -331,13 +338,15 @@ impl HmacSha384 {
                 );
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(INIT_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.hmac384_block));
             } else if self.control.reg.is_set(Control::NEXT) {
                 // Update a HMAC engine with a new block
                 self.hmac.update(&bytes_from_words_le(&self.block));
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(UPDATE_TICKS));
+                self.op_complete_action =
+                    Some(self.timer.schedule_poll_in(self.latency.hmac384_block));
             }
         }
 
@@ -380,7 +389,8 @@ impl HmacSha384 {
                     + KeyReadStatus::ERROR::CLEAR,
             );
 
-            self.op_key_read_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_key_read_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         Ok(())
@@ -421,7 +431,8 @@ impl HmacSha384 {
                     + KeyReadStatus::ERROR::CLEAR,
             );
 
-            self.op_block_read_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_block_read_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         Ok(())
@@ -501,13 +512,16 @@ impl HmacSha384 {
                     + TagWriteStatus::ERROR::CLEAR,
             );
 
-            self.op_tag_write_complete_action = Some(self.timer.schedule_poll_in(KEY_RW_TICKS));
+            self.op_tag_write_complete_action =
+                Some(self.timer.schedule_poll_in(self.latency.key_vault_rw));
         }
 
         // Update Ready and Valid status bits
         self.status
             .reg
             .modify(Status::READY::SET + Status::VALID::SET);
+
+        self.intr_block.set_notif(NOTIF_CMD_DONE_STS);
     }
 
     fn key_read_complete(&mut self) {
@@ -638,6 +652,59 @@ impl HmacSha384 {
     }
 }
 
+impl Snapshot for HmacSha384 {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.control);
+        w.write(&self.status);
+        w.write(&self.key);
+        w.write(&self.block);
+        w.write(&self.tag);
+        w.write(&self.lfsr_seed);
+        w.write(&self.key_read_ctrl);
+        w.write(&self.key_read_status);
+        w.write(&self.block_read_ctrl);
+        w.write(&self.block_read_status);
+        w.write(&self.tag_write_ctrl);
+        w.write(&self.tag_write_status);
+        w.write(&self.intr_block);
+        w.write(&self.key_from_kv);
+        w.write(&self.block_from_kv);
+        w.write(&self.hide_tag_from_cpu);
+        w.write(&self.hmac);
+        self.timer.save_action(w, &self.op_complete_action);
+        self.timer.save_action(w, &self.op_key_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_block_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_tag_write_complete_action);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.control)?;
+        r.read(&mut self.status)?;
+        r.read(&mut self.key)?;
+        r.read(&mut self.block)?;
+        r.read(&mut self.tag)?;
+        r.read(&mut self.lfsr_seed)?;
+        r.read(&mut self.key_read_ctrl)?;
+        r.read(&mut self.key_read_status)?;
+        r.read(&mut self.block_read_ctrl)?;
+        r.read(&mut self.block_read_status)?;
+        r.read(&mut self.tag_write_ctrl)?;
+        r.read(&mut self.tag_write_status)?;
+        r.read(&mut self.intr_block)?;
+        r.read(&mut self.key_from_kv)?;
+        r.read(&mut self.block_from_kv)?;
+        r.read(&mut self.hide_tag_from_cpu)?;
+        r.read(&mut self.hmac)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        self.op_key_read_complete_action = self.timer.restore_action(r)?;
+        self.op_block_read_complete_action = self.timer.restore_action(r)?;
+        self.op_tag_write_complete_action = self.timer.restore_action(r)?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -666,7 +733,8 @@ mod tests {
 
     #[test]
     fn test_name() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let name0 = hmac.read(RvSize::Word, OFFSET_NAME0).unwrap();
         let name0 = String::from_utf8_lossy(&name0.to_le_bytes()).to_string();
@@ -679,7 +747,8 @@ mod tests {
 
     #[test]
     fn test_version() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
 
         let version0 = hmac.read(RvSize::Word, OFFSET_VERSION0).unwrap();
         let version0 = String::from_utf8_lossy(&version0.to_le_bytes()).to_string();
@@ -692,19 +761,22 @@ mod tests {
 
     #[test]
     fn test_control() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(hmac.read(RvSize::Word, OFFSET_CONTROL).unwrap(), 0);
     }
 
     #[test]
     fn test_status() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         assert_eq!(hmac.read(RvSize::Word, OFFSET_STATUS).unwrap(), 1);
     }
 
     #[test]
     fn test_key() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         for addr in (OFFSET_KEY..(OFFSET_KEY + HMAC_KEY_SIZE as u32)).step_by(4) {
             assert_eq!(hmac.write(RvSize::Word, addr, 0xFF).ok(), Some(()));
             assert_eq!(
@@ -716,7 +788,8 @@ mod tests {
 
     #[test]
     fn test_block() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         for addr in (OFFSET_BLOCK..(OFFSET_BLOCK + HMAC_BLOCK_SIZE as u32)).step_by(4) {
             assert_eq!(hmac.write(RvSize::Word, addr, u32::MAX).ok(), Some(()));
             assert_eq!(
@@ -728,7 +801,8 @@ mod tests {
 
     #[test]
     fn test_tag() {
-        let mut hmac = HmacSha384::new(&Clock::new(), KeyVault::new());
+        let clock = Clock::new();
+        let mut hmac = HmacSha384::new(&clock, KeyVault::new(), &Pic::new(&clock));
         for addr in (OFFSET_TAG..(OFFSET_TAG + HMAC_TAG_SIZE as u32)).step_by(4) {
             assert_eq!(hmac.read(RvSize::Word, addr).ok(), Some(0));
             assert_eq!(
@@ -924,7 +998,7 @@ mod tests {
             );
         }
 
-        let mut hmac = HmacSha384::new(&clock, key_vault);
+        let mut hmac = HmacSha384::new(&clock, key_vault, &Pic::new(&clock));
 
         if tag_to_kv {
             // Instruct tag to be read from key-vault.
@@ -20,6 +20,7 @@ use caliptra_emu_bus::TimerAction;
 use caliptra_emu_types::RvAddr;
 use caliptra_emu_types::RvData;
 use caliptra_emu_types::RvSize;
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use std::cell::Cell;
 use std::{cell::RefCell, rc::Rc};
 
@@ -101,6 +102,19 @@ impl Bus for Iccm {
     }
 }
 
+impl Snapshot for Iccm {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&*self.iccm.ram.borrow());
+        w.write_bool(self.iccm.locked.get());
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut *self.iccm.ram.borrow_mut())?;
+        self.iccm.locked.set(r.read_bool()?);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,228 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    intr_block.rs
+
+Abstract:
+
+    File contains the interrupt register block shared by the Caliptra
+    peripherals (intr_block_rf) and its connection to the PIC.
+
+--*/
+
+use crate::{Irq, Pic};
+use caliptra_emu_bus::{Bus, BusError};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
+
+/// Register indices within the interrupt block
+const GLOBAL_INTR_EN: usize = 0;
+const ERROR_INTR_EN: usize = 1;
+const NOTIF_INTR_EN: usize = 2;
+const ERROR_GLOBAL_INTR: usize = 3;
+const NOTIF_GLOBAL_INTR: usize = 4;
+const ERROR_INTERNAL_INTR: usize = 5;
+const NOTIF_INTERNAL_INTR: usize = 6;
+const ERROR_INTR_TRIG: usize = 7;
+const NOTIF_INTR_TRIG: usize = 8;
+const INTR_BLOCK_REG_COUNT: usize = 9;
+
+/// GLOBAL_INTR_EN fields
+pub const GLOBAL_ERROR_EN: u32 = 1 << 0;
+pub const GLOBAL_NOTIF_EN: u32 = 1 << 1;
+
+/// NOTIF_INTERNAL_INTR field raised by the crypto engines and the SHA
+/// accelerator when an operation completes
+pub const NOTIF_CMD_DONE_STS: u32 = 1 << 0;
+
+/// PIC interrupt source IDs, as wired in caliptra_top
+pub mod irq {
+    pub const DOE_ERROR: u8 = 1;
+    pub const DOE_NOTIF: u8 = 2;
+    pub const ECC_ERROR: u8 = 3;
+    pub const ECC_NOTIF: u8 = 4;
+    pub const HMAC_ERROR: u8 = 5;
+    pub const HMAC_NOTIF: u8 = 6;
+    pub const KV_ERROR: u8 = 7;
+    pub const KV_NOTIF: u8 = 8;
+    pub const SHA512_ERROR: u8 = 9;
+    pub const SHA512_NOTIF: u8 = 10;
+    pub const SHA256_ERROR: u8 = 11;
+    pub const SHA256_NOTIF: u8 = 12;
+    pub const SOC_IFC_ERROR: u8 = 19;
+    pub const SOC_IFC_NOTIF: u8 = 20;
+    pub const SHA512_ACC_ERROR: u8 = 21;
+    pub const SHA512_ACC_NOTIF: u8 = 22;
+}
+
+/// Interrupt block registers of a peripheral, driving its error and
+/// notification lines into the PIC.
+///
+/// Attach as a `#[peripheral(offset = 0x0800, mask = 0x00ff)]` field.
+pub struct IntrBlock {
+    regs: [u32; INTR_BLOCK_REG_COUNT],
+
+    /// Error interrupt line into the PIC
+    error_irq: Irq,
+
+    /// Notification interrupt line into the PIC
+    notif_irq: Irq,
+}
+
+impl IntrBlock {
+    pub fn new(pic: &Pic, error_irq: u8, notif_irq: u8) -> Self {
+        Self {
+            regs: [0; INTR_BLOCK_REG_COUNT],
+            error_irq: pic.register_irq(error_irq),
+            notif_irq: pic.register_irq(notif_irq),
+        }
+    }
+
+    /// Set error status bits, as the hardware event would
+    pub fn set_error(&mut self, bits: u32) {
+        self.regs[ERROR_INTERNAL_INTR] |= bits;
+        self.update();
+    }
+
+    /// Set notification status bits, as the hardware event would
+    pub fn set_notif(&mut self, bits: u32) {
+        self.regs[NOTIF_INTERNAL_INTR] |= bits;
+        self.update();
+    }
+
+    /// Recompute the aggregated interrupt status and drive the PIC lines
+    fn update(&mut self) {
+        let regs = &mut self.regs;
+        let error = regs[ERROR_INTERNAL_INTR] & regs[ERROR_INTR_EN] != 0;
+        let notif = regs[NOTIF_INTERNAL_INTR] & regs[NOTIF_INTR_EN] != 0;
+        regs[ERROR_GLOBAL_INTR] = error.into();
+        regs[NOTIF_GLOBAL_INTR] = notif.into();
+
+        let global_en = regs[GLOBAL_INTR_EN];
+        self.error_irq
+            .set_level(error && global_en & GLOBAL_ERROR_EN != 0);
+        self.notif_irq
+            .set_level(notif && global_en & GLOBAL_NOTIF_EN != 0);
+    }
+
+    fn reset(&mut self) {
+        self.regs = [0; INTR_BLOCK_REG_COUNT];
+        self.update();
+    }
+}
+
+impl Bus for IntrBlock {
+    fn read(&mut self, size: RvSize, addr: RvAddr) -> Result<RvData, BusError> {
+        if size != RvSize::Word || addr % 4 != 0 {
+            Err(BusError::LoadAccessFault)?
+        }
+        match addr as usize / 4 {
+            // Trigger registers always read as zero.
+            ERROR_INTR_TRIG | NOTIF_INTR_TRIG => Ok(0),
+            index => self
+                .regs
+                .get(index)
+                .copied()
+                .ok_or(BusError::LoadAccessFault),
+        }
+    }
+
+    fn write(&mut self, size: RvSize, addr: RvAddr, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word || addr % 4 != 0 {
+            Err(BusError::StoreAccessFault)?
+        }
+        let index = addr as usize / 4;
+        match index {
+            GLOBAL_INTR_EN | ERROR_INTR_EN | NOTIF_INTR_EN => self.regs[index] = val,
+            // Aggregated status registers are read-only.
+            ERROR_GLOBAL_INTR | NOTIF_GLOBAL_INTR => {}
+            // Interrupt status bits are write-1-to-clear.
+            ERROR_INTERNAL_INTR | NOTIF_INTERNAL_INTR => self.regs[index] &= !val,
+            // Trigger registers set the corresponding status bits.
+            ERROR_INTR_TRIG => self.regs[ERROR_INTERNAL_INTR] |= val,
+            NOTIF_INTR_TRIG => self.regs[NOTIF_INTERNAL_INTR] |= val,
+            _ => Err(BusError::StoreAccessFault)?,
+        }
+        self.update();
+        Ok(())
+    }
+
+    fn warm_reset(&mut self) {
+        self.reset();
+    }
+
+    fn update_reset(&mut self) {
+        self.reset();
+    }
+}
+
+/// The levels of the interrupt lines are saved with the PIC.
+impl Snapshot for IntrBlock {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.regs);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.regs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use caliptra_emu_bus::{Clock, TimerAction};
+
+    const MEIPL_OFFSET: RvAddr = 0x0000;
+    const MEIE_OFFSET: RvAddr = 0x2000;
+
+    fn ext_intr_id(clock: &Clock, pic: &mut Pic) -> Option<u8> {
+        clock
+            .increment_and_process_timer_actions(1, pic)
+            .into_iter()
+            .find_map(|action| match action {
+                TimerAction::SetExtIntr { id, .. } => Some(id),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_notif() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let mut intr = IntrBlock::new(&pic, irq::SHA256_ERROR, irq::SHA256_NOTIF);
+        let notif = RvAddr::from(irq::SHA256_NOTIF);
+        pic.write(RvSize::Word, MEIPL_OFFSET + 4 * notif, 1)
+            .unwrap();
+        pic.write(RvSize::Word, MEIE_OFFSET + 4 * notif, 1).unwrap();
+
+        // Status is latched but the line stays low until enabled.
+        intr.set_notif(NOTIF_CMD_DONE_STS);
+        assert_eq!(intr.read(RvSize::Word, 0x18).unwrap(), NOTIF_CMD_DONE_STS);
+        assert_eq!(intr.read(RvSize::Word, 0x10).unwrap(), 0);
+        assert_eq!(ext_intr_id(&clock, &mut pic), None);
+
+        intr.write(RvSize::Word, 0x8, NOTIF_CMD_DONE_STS).unwrap();
+        assert_eq!(intr.read(RvSize::Word, 0x10).unwrap(), 1);
+        assert_eq!(ext_intr_id(&clock, &mut pic), None);
+
+        intr.write(RvSize::Word, 0x0, GLOBAL_NOTIF_EN).unwrap();
+        assert_eq!(ext_intr_id(&clock, &mut pic), Some(irq::SHA256_NOTIF));
+
+        // Write-1-to-clear lowers the line.
+        intr.write(RvSize::Word, 0x18, NOTIF_CMD_DONE_STS).unwrap();
+        assert_eq!(ext_intr_id(&clock, &mut pic), Some(0));
+
+        // Software trigger raises it again.
+        intr.write(RvSize::Word, 0x20, NOTIF_CMD_DONE_STS).unwrap();
+        assert_eq!(intr.read(RvSize::Word, 0x20).unwrap(), 0);
+        assert_eq!(ext_intr_id(&clock, &mut pic), Some(irq::SHA256_NOTIF));
+
+        intr.warm_reset();
+        assert_eq!(intr.read(RvSize::Word, 0x18).unwrap(), 0);
+        assert_eq!(ext_intr_id(&clock, &mut pic), Some(0));
+    }
+}
@@ -13,9 +13,13 @@ Abstract:
 --*/
 
 use bitfield::bitfield;
-use caliptra_emu_bus::{Bus, BusError, ReadWriteMemory, ReadWriteRegisterArray};
+use caliptra_emu_bus::{
+    Bus, BusError, ReadWriteMemory, ReadWriteRegisterArray, TraceEvent, Tracer,
+};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use std::cell::RefCell;
 use std::rc::Rc;
 use tock_registers::{register_bitfields, LocalRegisterCopy};
@@ -149,6 +153,7 @@ impl KeyVault {
     pub const KEY_SIZE: usize = 48;
     pub const KEY_CONTROL_REG_OFFSET: u32 = 0;
     pub const KEY_CONTROL_REG_WIDTH: u32 = 0x4;
+    pub const PCR_COUNT: u32 = constants::PCR_COUNT;
 
     /// Create a new instance of KeyVault
     pub fn new() -> Self {
@@ -157,6 +162,11 @@ impl KeyVault {
         }
     }
 
+    /// Emit key usage by the crypto engines to `tracer`
+    pub fn set_tracer(&self, tracer: Tracer) {
+        self.regs.borrow_mut().tracer = tracer;
+    }
+
     /// Internal emulator interface to read key from key vault
     pub fn read_key(
         &self,
@@ -166,6 +176,12 @@ impl KeyVault {
         self.regs.borrow().read_key(key_id, desired_usage)
     }
 
+    /// Debugger interface to read a key and its control register, bypassing
+    /// the usage and lock checks applied to the crypto engines
+    pub fn peek_key(&self, key_id: u32) -> (u32, [u8; KeyVault::KEY_SIZE]) {
+        self.regs.borrow().peek_key(key_id)
+    }
+
     pub fn read_key_as_data(
         &self,
         key_id: u32,
@@ -366,6 +382,9 @@ pub struct KeyVaultRegs {
     #[register_array(offset = 0x0000_44a0, write_fn = write_sticky_lockable_scratch)]
     sticky_lockable_scratch:
         ReadWriteRegisterArray<u32, { STICKY_LOCKABLE_SCRATCH_REG_COUNT as usize }>,
+
+    /// Trace output for key usage
+    tracer: Tracer,
 }
 
 impl KeyVaultRegs {
@@ -391,6 +410,7 @@ impl KeyVaultRegs {
                 STICKY_LOCKABLE_SCRATCH_CTRL_REG_RESET_VAL,
             ),
             sticky_lockable_scratch: ReadWriteRegisterArray::new(0),
+            tracer: Tracer::default(),
         }
     }
 
@@ -471,9 +491,16 @@ impl KeyVaultRegs {
         desired_usage: KeyUsage,
     ) -> Result<[u8; KeyVault::KEY_SIZE], BusError> {
         let key_ctrl_reg = &self.key_control[key_id as usize];
-        if (key_ctrl_reg.read(KV_CONTROL::USE_LOCK) != 0)
-            || ((key_ctrl_reg.read(KV_CONTROL::USAGE) & u32::from(desired_usage)) == 0)
-        {
+        let allowed = (key_ctrl_reg.read(KV_CONTROL::USE_LOCK) == 0)
+            && ((key_ctrl_reg.read(KV_CONTROL::USAGE) & u32::from(desired_usage)) != 0);
+        if self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::KeyRead {
+                key_id,
+                usage: u32::from(desired_usage),
+                allowed,
+            });
+        }
+        if !allowed {
             Err(BusError::LoadAccessFault)?
         }
         let key_start = key_id as usize * KeyVault::KEY_SIZE;
@@ -483,6 +510,13 @@ impl KeyVaultRegs {
         Ok(key)
     }
 
+    pub fn peek_key(&self, key_id: u32) -> (u32, [u8; KeyVault::KEY_SIZE]) {
+        let key_start = key_id as usize * KeyVault::KEY_SIZE;
+        let mut key = [0u8; KeyVault::KEY_SIZE];
+        key.copy_from_slice(&self.keys.data()[key_start..key_start + KeyVault::KEY_SIZE]);
+        (self.key_control[key_id as usize].get(), key)
+    }
+
     pub fn read_key_as_data(
         &self,
         key_id: u32,
@@ -501,9 +535,16 @@ impl KeyVaultRegs {
         }
         let key_wordlen = key.len() / 4;
         let key_ctrl_reg = &mut self.key_control[key_id as usize];
-        if key_ctrl_reg.read(KV_CONTROL::WRITE_LOCK) != 0
-            || key_ctrl_reg.read(KV_CONTROL::USE_LOCK) != 0
-        {
+        let allowed = key_ctrl_reg.read(KV_CONTROL::WRITE_LOCK) == 0
+            && key_ctrl_reg.read(KV_CONTROL::USE_LOCK) == 0;
+        if self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::KeyWrite {
+                key_id,
+                usage: key_usage,
+                allowed,
+            });
+        }
+        if !allowed {
             Err(BusError::StoreAccessFault)?
         }
         let key_start = key_id as usize * KeyVault::KEY_SIZE;
@@ -668,6 +709,51 @@ impl KeyVaultRegs {
     }
 }
 
+impl Snapshot for KeyVaultRegs {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.key_control);
+        w.write(&self.keys);
+        w.write(&self.pcr_control);
+        w.write(&self.pcrs);
+        w.write(&self.sticky_datavault_control);
+        w.write(&self.sticky_datavault_entry);
+        w.write(&self.datavault_control);
+        w.write(&self.datavault_entry);
+        w.write(&self.lockable_scratch_control);
+        w.write(&self.lockable_scratch);
+        w.write(&self.nonsticky_generic_scratch);
+        w.write(&self.sticky_lockable_scratch_control);
+        w.write(&self.sticky_lockable_scratch);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.key_control)?;
+        r.read(&mut self.keys)?;
+        r.read(&mut self.pcr_control)?;
+        r.read(&mut self.pcrs)?;
+        r.read(&mut self.sticky_datavault_control)?;
+        r.read(&mut self.sticky_datavault_entry)?;
+        r.read(&mut self.datavault_control)?;
+        r.read(&mut self.datavault_entry)?;
+        r.read(&mut self.lockable_scratch_control)?;
+        r.read(&mut self.lockable_scratch)?;
+        r.read(&mut self.nonsticky_generic_scratch)?;
+        r.read(&mut self.sticky_lockable_scratch_control)?;
+        r.read(&mut self.sticky_lockable_scratch)?;
+        Ok(())
+    }
+}
+
+impl Snapshot for KeyVault {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.regs.borrow_mut().restore_state(r)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
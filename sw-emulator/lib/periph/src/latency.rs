@@ -0,0 +1,214 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    latency.rs
+
+Abstract:
+
+    File contains the latency model of the crypto peripherals.
+
+--*/
+
+use std::str::FromStr;
+
+/// Number of CPU clock cycles the crypto peripherals take to complete an
+/// operation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CryptoLatency {
+    /// SHA-512/384 engine, per block (INIT or NEXT)
+    pub sha512_block: u64,
+
+    /// SHA-256 engine, per block (INIT or NEXT)
+    pub sha256_block: u64,
+
+    /// HMAC-384 engine, per block (INIT or NEXT)
+    pub hmac384_block: u64,
+
+    /// ECC-384 key pair generation
+    pub ecc384_keygen: u64,
+
+    /// ECC-384 signing
+    pub ecc384_sign: u64,
+
+    /// ECC-384 signature verification
+    pub ecc384_verify: u64,
+
+    /// Deobfuscation engine command
+    pub doe: u64,
+
+    /// SHA accelerator, fixed cost of a mailbox hash
+    pub sha512_acc_op: u64,
+
+    /// SHA accelerator, per 128-byte block of mailbox data
+    pub sha512_acc_block: u64,
+
+    /// Key vault read or write by a crypto peripheral
+    pub key_vault_rw: u64,
+}
+
+impl CryptoLatency {
+    /// Fixed latencies of the original emulator peripherals. Boot times
+    /// measured with these do not correspond to silicon.
+    pub const LEGACY: Self = Self {
+        sha512_block: 1000,
+        sha256_block: 1000,
+        hmac384_block: 1000,
+        ecc384_keygen: 1000,
+        ecc384_sign: 1000,
+        ecc384_verify: 1000,
+        doe: 1000,
+        sha512_acc_op: 1000,
+        sha512_acc_block: 0,
+        key_vault_rw: 100,
+    };
+
+    /// Approximate latencies of the caliptra-rtl 1.0 crypto cores. Override
+    /// individual fields to calibrate against a different RTL release.
+    ///
+    /// The hash engines are derived from the structure of the cores; the
+    /// ECC, DOE and key vault figures are estimates and should be the first
+    /// to be calibrated against RTL simulation.
+    pub const RTL: Self = Self {
+        // 80 rounds at one round per cycle, plus 8 cycles to load the block
+        // and update the digest
+        sha512_block: 88,
+        // 64 rounds at one round per cycle, plus 4 cycles of overhead
+        sha256_block: 68,
+        // An inner and an outer SHA-512 block, plus control overhead
+        hmac384_block: 180,
+        // About 3,100 cycles per bit of the 384-bit scalar multiplication
+        ecc384_keygen: 1_200_000,
+        // One scalar multiplication plus the modular inversion of k
+        ecc384_sign: 1_250_000,
+        // Two scalar multiplications
+        ecc384_verify: 2_450_000,
+        // AES-256 key expansion and decryption of the obfuscated secret
+        doe: 400,
+        // Register handshake to start the engine and read the digest
+        sha512_acc_op: 24,
+        // A SHA-512 block plus reading 32 words of mailbox SRAM
+        sha512_acc_block: 120,
+        // 12 words of a 384-bit key at 2 cycles each, plus the handshake
+        key_vault_rw: 32,
+    };
+
+    const FIELDS: [&'static str; 10] = [
+        "sha512_block",
+        "sha256_block",
+        "hmac384_block",
+        "ecc384_keygen",
+        "ecc384_sign",
+        "ecc384_verify",
+        "doe",
+        "sha512_acc_op",
+        "sha512_acc_block",
+        "key_vault_rw",
+    ];
+
+    /// Cycles taken by the SHA accelerator to hash `data_len` bytes of the
+    /// mailbox, including padding
+    pub fn sha512_acc(&self, data_len: usize) -> u64 {
+        let blocks = (data_len + 16) / 128 + 1;
+        self.sha512_acc_op + blocks as u64 * self.sha512_acc_block
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut u64> {
+        Some(match name {
+            "sha512_block" => &mut self.sha512_block,
+            "sha256_block" => &mut self.sha256_block,
+            "hmac384_block" => &mut self.hmac384_block,
+            "ecc384_keygen" => &mut self.ecc384_keygen,
+            "ecc384_sign" => &mut self.ecc384_sign,
+            "ecc384_verify" => &mut self.ecc384_verify,
+            "doe" => &mut self.doe,
+            "sha512_acc_op" => &mut self.sha512_acc_op,
+            "sha512_acc_block" => &mut self.sha512_acc_block,
+            "key_vault_rw" => &mut self.key_vault_rw,
+            _ => return None,
+        })
+    }
+}
+
+impl Default for CryptoLatency {
+    fn default() -> Self {
+        Self::LEGACY
+    }
+}
+
+/// Parses a comma-separated list of an optional preset (`legacy` or `rtl`)
+/// followed by `field=cycles` overrides, such as `rtl,ecc384_sign=1300000`.
+impl FromStr for CryptoLatency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::default();
+        for (i, item) in s.split(',').map(str::trim).enumerate() {
+            match (i, item) {
+                (0, "legacy") => result = Self::LEGACY,
+                (0, "rtl") => result = Self::RTL,
+                _ => {
+                    let Some((name, value)) = item.split_once('=') else {
+                        return Err(format!("Invalid crypto latency setting {item:?}"));
+                    };
+                    let Some(field) = result.field_mut(name.trim()) else {
+                        return Err(format!(
+                            "Unknown crypto latency {name:?}; expected one of {}",
+                            Self::FIELDS.join(", ")
+                        ));
+                    };
+                    *field = value
+                        .trim()
+                        .replace('_', "")
+                        .parse()
+                        .map_err(|_| format!("Invalid cycle count {value:?} for {name}"))?;
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("legacy".parse(), Ok(CryptoLatency::LEGACY));
+        assert_eq!("rtl".parse(), Ok(CryptoLatency::RTL));
+        assert_eq!(
+            "rtl, ecc384_sign=1_300_000,doe=500".parse(),
+            Ok(CryptoLatency {
+                ecc384_sign: 1_300_000,
+                doe: 500,
+                ..CryptoLatency::RTL
+            })
+        );
+        assert_eq!(
+            "sha256_block=70".parse(),
+            Ok(CryptoLatency {
+                sha256_block: 70,
+                ..CryptoLatency::LEGACY
+            })
+        );
+        for name in CryptoLatency::FIELDS {
+            assert!(CryptoLatency::default().field_mut(name).is_some());
+        }
+        assert!("fast".parse::<CryptoLatency>().is_err());
+        assert!("rtl,ecc384_sing=5".parse::<CryptoLatency>().is_err());
+        assert!("rtl,doe=-1".parse::<CryptoLatency>().is_err());
+        assert!("doe=1,rtl".parse::<CryptoLatency>().is_err());
+    }
+
+    #[test]
+    fn test_sha512_acc() {
+        let latency = CryptoLatency::RTL;
+        assert_eq!(latency.sha512_acc(0), 24 + 120);
+        assert_eq!(latency.sha512_acc(111), 24 + 120);
+        assert_eq!(latency.sha512_acc(112), 24 + 2 * 120);
+        assert_eq!(CryptoLatency::LEGACY.sha512_acc(4096), 1000);
+    }
+}
@@ -15,6 +15,7 @@ Abstract:
 extern crate arrayref;
 
 mod asym_ecc384;
+mod boot_timing;
 mod csrng;
 mod doe;
 mod emu_ctrl;
@@ -23,14 +24,18 @@ mod hash_sha512;
 mod helpers;
 mod hmac_sha384;
 mod iccm;
+pub mod intr_block;
 mod key_vault;
+mod latency;
 mod mailbox;
+mod pic;
 mod root_bus;
 mod sha512_acc;
 pub mod soc_reg;
 mod uart;
 
 pub use asym_ecc384::AsymEcc384;
+pub use boot_timing::{BootPhase, BootStatusChange, BootTiming};
 pub use csrng::Csrng;
 pub use doe::Doe;
 pub use emu_ctrl::EmuCtrl;
@@ -40,7 +45,9 @@ pub use hmac_sha384::HmacSha384;
 pub use iccm::Iccm;
 pub use key_vault::KeyUsage;
 pub use key_vault::KeyVault;
-pub use mailbox::{MailboxExternal, MailboxInternal, MailboxRam};
+pub use latency::CryptoLatency;
+pub use mailbox::{MailboxExternal, MailboxFsmState, MailboxInternal, MailboxRam};
+pub use pic::{Irq, Pic};
 pub use root_bus::{
     ActionCb, CaliptraRootBus, CaliptraRootBusArgs, DownloadIdevidCsrCb, ReadyForFwCb,
     SocToCaliptraBus, TbServicesCb, UploadUpdateFwCb,
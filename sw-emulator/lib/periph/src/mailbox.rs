@@ -13,10 +13,12 @@ Abstract:
 --*/
 use smlang::statemachine;
 
-use caliptra_emu_bus::{Bus, BusMmio, Ram};
+use caliptra_emu_bus::{Bus, BusMmio, Clock, Ram, Timer, TraceEvent, Tracer};
 use caliptra_emu_bus::{BusError, ReadOnlyRegister, ReadWriteRegister, WriteOnlyRegister};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use std::{cell::RefCell, rc::Rc};
 use tock_registers::interfaces::Writeable;
 use tock_registers::{register_bitfields, LocalRegisterCopy};
@@ -93,6 +95,11 @@ pub struct MailboxExternal {
     regs: Rc<RefCell<MailboxRegs>>,
 }
 impl MailboxExternal {
+    /// Set the APB PAUSER used for subsequent SoC accesses
+    pub fn set_pauser(&mut self, pauser: u32) {
+        self.regs.borrow_mut().soc_pauser = pauser;
+    }
+
     pub fn regs(&mut self) -> caliptra_registers::mbox::RegisterBlock<BusMmio<Self>> {
         unsafe {
             caliptra_registers::mbox::RegisterBlock::new_with_mmio(
@@ -107,7 +114,8 @@ impl Bus for MailboxExternal {
     /// Read data of specified size from given address
     fn read(&mut self, size: RvSize, addr: RvAddr) -> Result<RvData, BusError> {
         let mut regs = self.regs.borrow_mut();
-        regs.set_request(MailboxRequester::Soc);
+        let pauser = regs.soc_pauser;
+        regs.set_request(MailboxRequester::Soc(pauser));
         let result = regs.read(size, addr);
         regs.set_request(MailboxRequester::Caliptra);
         result
@@ -116,7 +124,8 @@ impl Bus for MailboxExternal {
     /// Write data of specified size to given address
     fn write(&mut self, size: RvSize, addr: RvAddr, val: RvData) -> Result<(), BusError> {
         let mut regs = self.regs.borrow_mut();
-        regs.set_request(MailboxRequester::Soc);
+        let pauser = regs.soc_pauser;
+        regs.set_request(MailboxRequester::Soc(pauser));
         let result = regs.write(size, addr, val);
         regs.set_request(MailboxRequester::Caliptra);
         result
@@ -131,12 +140,18 @@ pub struct MailboxInternal {
 /// Mailbox Peripheral
 
 impl MailboxInternal {
-    pub fn new(ram: MailboxRam) -> Self {
+    pub fn new(clock: &Clock, ram: MailboxRam) -> Self {
         Self {
-            regs: Rc::new(RefCell::new(MailboxRegs::new(ram))),
+            regs: Rc::new(RefCell::new(MailboxRegs::new(clock, ram))),
         }
     }
 
+    /// Returns true (once) if the SoC has handed a command to the uC since
+    /// the last call.
+    pub fn take_cmd_avail_event(&mut self) -> bool {
+        std::mem::take(&mut self.regs.borrow_mut().cmd_avail)
+    }
+
     pub fn regs(&mut self) -> caliptra_registers::mbox::RegisterBlock<BusMmio<Self>> {
         unsafe {
             caliptra_registers::mbox::RegisterBlock::new_with_mmio(
@@ -151,6 +166,26 @@ impl MailboxInternal {
             regs: self.regs.clone(),
         }
     }
+
+    /// Returns the state of the mailbox FSM without the side effects of
+    /// reading the registers over the bus (e.g. acquiring the lock).
+    pub fn fsm_state(&self) -> MailboxFsmState {
+        self.regs.borrow().fsm_state()
+    }
+}
+
+/// Debugger view of the mailbox FSM
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MailboxFsmState {
+    /// Name of the current state, as reported in MBOX_STATUS.MBOX_FSM_PS
+    pub state: &'static str,
+    pub locked: bool,
+    pub user: MailboxRequester,
+    pub cmd: u32,
+    pub dlen: u32,
+    pub execute: bool,
+    /// MBOX_STATUS.STATUS field
+    pub status: u32,
 }
 
 impl Bus for MailboxInternal {
@@ -173,15 +208,16 @@ impl Bus for MailboxInternal {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 
 pub enum MailboxRequester {
-    Caliptra = 0,
-    Soc = 1,
+    Caliptra,
+    /// SoC access with the given APB PAUSER
+    Soc(u32),
 }
 
 impl From<MailboxRequester> for u32 {
     fn from(val: MailboxRequester) -> Self {
         match val {
             MailboxRequester::Caliptra => 0,
-            MailboxRequester::Soc => 1,
+            MailboxRequester::Soc(pauser) => pauser,
         }
     }
 }
@@ -229,6 +265,18 @@ pub struct MailboxRegs {
     state_machine: StateMachine<Context>,
 
     pub requester: MailboxRequester,
+
+    /// APB PAUSER of SoC accesses
+    soc_pauser: u32,
+
+    /// Set when the SoC hands a command to the uC
+    cmd_avail: bool,
+
+    /// Timer
+    timer: Timer,
+
+    /// Trace output for FSM transitions
+    tracer: Tracer,
 }
 
 impl MailboxRegs {
@@ -242,9 +290,10 @@ impl MailboxRegs {
     const EXEC_VAL: RvData = 0x0;
     const STATUS_VAL: RvData = 0x0;
     const UNLOCK_VAL: RvData = 0x0;
+    const SOC_PAUSER_VAL: u32 = 0x1;
 
     /// Create a new instance of Mailbox registers
-    pub fn new(ram: MailboxRam) -> Self {
+    pub fn new(clock: &Clock, ram: MailboxRam) -> Self {
         Self {
             lock: ReadOnlyRegister::new(Self::LOCK_VAL),
             user: ReadOnlyRegister::new(Self::USER_VAL),
@@ -257,6 +306,10 @@ impl MailboxRegs {
             _unlock: ReadWriteRegister::new(Self::UNLOCK_VAL),
             state_machine: StateMachine::new(Context::new(ram)),
             requester: MailboxRequester::Caliptra,
+            soc_pauser: Self::SOC_PAUSER_VAL,
+            cmd_avail: false,
+            timer: Timer::new(clock),
+            tracer: clock.tracer(),
         }
     }
     pub fn set_request(&mut self, requester: MailboxRequester) {
@@ -271,9 +324,7 @@ impl MailboxRegs {
             _ => Ok(1),
         };
         // Deliver event to the state machine.
-        let _ = self
-            .state_machine
-            .process_event(Events::RdLock(self.requester));
+        self.process_event(Events::RdLock(self.requester));
 
         result
     }
@@ -285,7 +336,7 @@ impl MailboxRegs {
 
     // Todo: Implement write cmd callback fn
     pub fn write_cmd(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
-        let _ = self.state_machine.process_event(Events::CmdWrite(Cmd(val)));
+        self.process_event(Events::CmdWrite(Cmd(val)));
         Ok(())
     }
 
@@ -296,9 +347,7 @@ impl MailboxRegs {
 
     // Todo: Implement write dlen callback fn
     pub fn write_dlen(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
-        let _ = self
-            .state_machine
-            .process_event(Events::DlenWrite(DataLength(val)));
+        self.process_event(Events::DlenWrite(DataLength(val)));
         Ok(())
     }
 
@@ -309,24 +358,21 @@ impl MailboxRegs {
 
     // Todo: Implement write din callback fn
     pub fn write_din(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
-        let _ = self
-            .state_machine
-            .process_event(Events::DataWrite(DataIn(val)));
+        self.process_event(Events::DataWrite(DataIn(val)));
         Ok(())
     }
 
     // Todo: Implement read dout callback fn
     pub fn read_dout(&mut self, _size: RvSize) -> Result<u32, BusError> {
-        let mb = &mut self.state_machine;
-        let _ = mb.process_event(Events::DataRead);
-        Ok(mb.context.data_out)
+        self.process_event(Events::DataRead);
+        Ok(self.state_machine.context.data_out)
     }
 
     /// Write to execute register
     pub fn write_ex(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
         // Only the lock owner can clear the execute bit.
         if self.requester != self.state_machine.context.user {
-            let _ = self.state_machine.process_event(Events::Error);
+            self.process_event(Events::Error);
             return Ok(());
         }
 
@@ -349,7 +395,13 @@ impl MailboxRegs {
             }
         };
 
-        let _ = self.state_machine.process_event(event);
+        let soc_exec_set = matches!(event, Events::SocExecSet);
+        self.process_event(event);
+        if soc_exec_set && matches!(self.state_machine.state(), States::ExecUc) {
+            // Let soc_ifc raise the command-available notification.
+            self.cmd_avail = true;
+            self.timer.schedule_poll_in(0);
+        }
         self.execute.reg.set(val);
         Ok(())
     }
@@ -357,7 +409,7 @@ impl MailboxRegs {
     // Todo: Implement write status callback fn
     pub fn write_status(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
         // Send event to state machine.
-        let _ = self.state_machine.process_event(Events::SetStatus);
+        self.process_event(Events::SetStatus);
 
         let val = LocalRegisterCopy::<u32, Status::Register>::new(val);
         self.state_machine
@@ -382,8 +434,31 @@ impl MailboxRegs {
         Ok(result.get())
     }
 
+    pub fn fsm_state(&self) -> MailboxFsmState {
+        let context = &self.state_machine.context;
+        MailboxFsmState {
+            state: state_name(&self.state_machine.state),
+            locked: context.locked != 0,
+            user: context.user,
+            cmd: context.cmd,
+            dlen: context.dlen,
+            execute: context.exec,
+            status: context.status.read(Status::STATUS),
+        }
+    }
+
+    /// Deliver `event` to the state machine, tracing the resulting transition
+    fn process_event(&mut self, event: Events) {
+        let from = state_name(&self.state_machine.state);
+        let _ = self.state_machine.process_event(event);
+        let to = state_name(&self.state_machine.state);
+        if from != to && self.tracer.enabled() {
+            self.tracer.emit(TraceEvent::MboxState { from, to });
+        }
+    }
+
     pub fn write_unlock(&mut self, _size: RvSize, _val: RvData) -> Result<(), BusError> {
-        let _ = self.state_machine.process_event(Events::WrUnlock);
+        self.process_event(Events::WrUnlock);
         Ok(())
     }
 
@@ -611,13 +686,138 @@ impl Fifo {
     }
 }
 
+impl Snapshot for MailboxRam {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.ram.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.ram.borrow_mut().restore_state(r)
+    }
+}
+
+impl Snapshot for MailboxInternal {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.regs.borrow_mut().restore_state(r)
+    }
+}
+
+impl Snapshot for MailboxRequester {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        match self {
+            Self::Caliptra => w.write_u8(0),
+            Self::Soc(pauser) => {
+                w.write_u8(1);
+                w.write_u32(*pauser);
+            }
+        }
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = match r.read_u8()? {
+            0 => Self::Caliptra,
+            1 => Self::Soc(r.read_u32()?),
+            _ => return Err(SnapshotError::InvalidValue("mailbox requester")),
+        };
+        Ok(())
+    }
+}
+
+/// The mailbox SRAM is saved with the root bus, not through the FIFO.
+impl Snapshot for MailboxRegs {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.lock);
+        w.write(&self.user);
+        w.write(&self.data_in);
+        w.write(&self.data_out);
+        w.write(&self.execute);
+        w.write_u8(match self.state_machine.state {
+            States::Idle => 0,
+            States::RdyForCmd => 1,
+            States::RdyForDlen => 2,
+            States::RdyForData => 3,
+            States::ExecUc => 4,
+            States::ExecSoc => 5,
+            States::Error => 6,
+        });
+        let context = &self.state_machine.context;
+        w.write_u32(context.locked);
+        w.write(&context.user);
+        w.write_bool(context.exec);
+        w.write_u32(context.dlen);
+        w.write_u32(context.fifo.latched_dlen);
+        w.write_usize(context.fifo.capacity);
+        w.write_usize(context.fifo.read_index);
+        w.write_usize(context.fifo.write_index);
+        w.write_u32(context.status.get());
+        w.write_u32(context.cmd);
+        w.write_u32(context.data_out);
+        w.write_u32(context.unlock);
+        w.write(&self.requester);
+        w.write_u32(self.soc_pauser);
+        w.write_bool(self.cmd_avail);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.lock)?;
+        r.read(&mut self.user)?;
+        r.read(&mut self.data_in)?;
+        r.read(&mut self.data_out)?;
+        r.read(&mut self.execute)?;
+        self.state_machine.state = match r.read_u8()? {
+            0 => States::Idle,
+            1 => States::RdyForCmd,
+            2 => States::RdyForDlen,
+            3 => States::RdyForData,
+            4 => States::ExecUc,
+            5 => States::ExecSoc,
+            6 => States::Error,
+            _ => return Err(SnapshotError::InvalidValue("mailbox state")),
+        };
+        let context = &mut self.state_machine.context;
+        context.locked = r.read_u32()?;
+        r.read(&mut context.user)?;
+        context.exec = r.read_bool()?;
+        context.dlen = r.read_u32()?;
+        context.fifo.latched_dlen = r.read_u32()?;
+        context.fifo.capacity = r.read_usize()?;
+        context.fifo.read_index = r.read_usize()?;
+        context.fifo.write_index = r.read_usize()?;
+        context.status.set(r.read_u32()?);
+        context.cmd = r.read_u32()?;
+        context.data_out = r.read_u32()?;
+        context.unlock = r.read_u32()?;
+        r.read(&mut self.requester)?;
+        self.soc_pauser = r.read_u32()?;
+        self.cmd_avail = r.read_bool()?;
+        Ok(())
+    }
+}
+
+/// Name of `state`, as reported in MBOX_STATUS.MBOX_FSM_PS
+fn state_name(state: &States) -> &'static str {
+    match state {
+        States::Idle => "MBOX_IDLE",
+        States::RdyForCmd => "MBOX_RDY_FOR_CMD",
+        States::RdyForDlen => "MBOX_RDY_FOR_DLEN",
+        States::RdyForData => "MBOX_RDY_FOR_DATA",
+        States::ExecUc => "MBOX_EXECUTE_UC",
+        States::ExecSoc => "MBOX_EXECUTE_SOC",
+        States::Error => "MBOX_ERROR",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     pub fn get_mailbox() -> MailboxInternal {
         // Acquire lock
-        MailboxInternal::new(MailboxRam::new())
+        MailboxInternal::new(&Clock::new(), MailboxRam::new())
     }
 
     #[test]
@@ -684,7 +884,7 @@ mod tests {
 
     #[test]
     fn test_soc_to_caliptra_lock() {
-        let mut caliptra = MailboxInternal::new(MailboxRam::new());
+        let mut caliptra = MailboxInternal::new(&Clock::new(), MailboxRam::new());
         let mut soc = caliptra.as_external();
         let soc_regs = soc.regs();
 
@@ -697,11 +897,51 @@ mod tests {
         assert!(caliptra_has_lock);
     }
 
+    #[test]
+    fn test_soc_pauser() {
+        let mut caliptra = MailboxInternal::new(&Clock::new(), MailboxRam::new());
+        let mut soc = caliptra.as_external();
+        soc.set_pauser(0x42);
+        let soc_regs = soc.regs();
+
+        assert!(!soc_regs.lock().read().lock());
+        assert_eq!(soc_regs.user().read(), 0x42);
+        assert_eq!(caliptra.regs().user().read(), 0x42);
+
+        // Only the lock owner can hand the command to the uC.
+        soc.set_pauser(0x43);
+        soc_regs.cmd().write(|_| 0x55);
+        soc_regs.dlen().write(|_| 0);
+        soc_regs.execute().write(|w| w.execute(true));
+        assert!(!caliptra.take_cmd_avail_event());
+    }
+
+    #[test]
+    fn test_fsm_state() {
+        let caliptra = MailboxInternal::new(&Clock::new(), MailboxRam::new());
+        let mut soc = caliptra.as_external();
+        soc.set_pauser(0x42);
+        let soc_regs = soc.regs();
+        assert_eq!(caliptra.fsm_state().state, "MBOX_IDLE");
+        assert!(!caliptra.fsm_state().locked);
+
+        assert!(!soc_regs.lock().read().lock());
+        soc_regs.cmd().write(|_| 0x55);
+        let state = caliptra.fsm_state();
+        assert_eq!(state.state, "MBOX_RDY_FOR_DLEN");
+        assert!(state.locked);
+        assert_eq!(state.user, MailboxRequester::Soc(0x42));
+        assert_eq!(state.cmd, 0x55);
+
+        // Peeking must not acquire the lock or move the FSM.
+        assert_eq!(caliptra.fsm_state(), state);
+    }
+
     #[test]
     fn test_send_receive() {
         let request_to_send: [u32; 4] = [0x1111_1111, 0x2222_2222, 0x3333_3333, 0x4444_4444];
 
-        let mut caliptra = MailboxInternal::new(MailboxRam::new());
+        let mut caliptra = MailboxInternal::new(&Clock::new(), MailboxRam::new());
         let mut soc = caliptra.as_external();
         let soc_regs = soc.regs();
         let uc_regs = caliptra.regs();
@@ -710,7 +950,10 @@ mod tests {
         // Confirm it is locked
         assert!(soc_regs.lock().read().lock());
 
-        assert_eq!(soc_regs.user().read(), MailboxRequester::Soc as u32);
+        assert_eq!(
+            soc_regs.user().read(),
+            u32::from(MailboxRequester::Soc(MailboxRegs::SOC_PAUSER_VAL))
+        );
 
         // Write command
         soc_regs.cmd().write(|_| 0x55);
@@ -772,6 +1015,43 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_snapshot() {
+        let ram = MailboxRam::new();
+        let caliptra = MailboxInternal::new(&Clock::new(), ram.clone());
+        let mut soc = caliptra.as_external();
+        let soc_regs = soc.regs();
+
+        assert!(!soc_regs.lock().read().lock());
+        soc_regs.cmd().write(|_| 0x55);
+        soc_regs.dlen().write(|_| 8);
+        soc_regs.datain().write(|_| 0x1111_1111);
+        soc_regs.datain().write(|_| 0x2222_2222);
+        soc_regs.execute().write(|w| w.execute(true));
+
+        let mut w = SnapshotWriter::new();
+        w.write(&ram);
+        w.write(&caliptra);
+        let data = w.finish().unwrap();
+
+        let mut restored_ram = MailboxRam::new();
+        let mut restored = MailboxInternal::new(&Clock::new(), restored_ram.clone());
+        let mut r = SnapshotReader::new(&data);
+        r.read(&mut restored_ram).unwrap();
+        r.read(&mut restored).unwrap();
+        r.finish().unwrap();
+
+        assert!(matches!(
+            restored.regs.borrow().state_machine.state(),
+            States::ExecUc
+        ));
+        let uc_regs = restored.regs();
+        assert_eq!(uc_regs.cmd().read(), 0x55);
+        assert_eq!(uc_regs.dlen().read(), 8);
+        assert_eq!(uc_regs.dataout().read(), 0x1111_1111);
+        assert_eq!(uc_regs.dataout().read(), 0x2222_2222);
+    }
+
     #[test]
     fn test_sm_init() {
         let mb = get_mailbox();
@@ -852,7 +1132,7 @@ mod tests {
         assert!(uc_regs.lock().read().lock());
 
         let user = uc_regs.user().read();
-        assert_eq!(user, MailboxRequester::Caliptra as u32);
+        assert_eq!(user, u32::from(MailboxRequester::Caliptra));
 
         // Write command
         uc_regs.cmd().write(|_| 0x55);
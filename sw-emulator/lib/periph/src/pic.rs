@@ -0,0 +1,577 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    pic.rs
+
+Abstract:
+
+    File contains VeeR EL2 Programmable Interrupt Controller (PIC) implementation.
+
+--*/
+
+use caliptra_emu_bus::{ActionHandle, Bus, BusError, Clock, ReadOnlyRegister, Timer, TimerAction};
+use caliptra_emu_derive::Bus;
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
+use std::{cell::RefCell, rc::Rc};
+use tock_registers::interfaces::{Readable, Writeable};
+use tock_registers::{register_bitfields, LocalRegisterCopy};
+
+register_bitfields! [
+    u32,
+
+    /// PIC Configuration Register Fields
+    PicCfg [
+        PRIORD OFFSET(0) NUMBITS(1) [
+            STANDARD = 0,
+            REVERSE = 1,
+        ],
+    ],
+
+    /// Gateway Configuration Register Fields
+    GwCtrl [
+        POLARITY OFFSET(0) NUMBITS(1) [
+            ACTIVE_HIGH = 0,
+            ACTIVE_LOW = 1,
+        ],
+        TYPE OFFSET(1) NUMBITS(1) [
+            LEVEL = 0,
+            EDGE = 1,
+        ],
+    ],
+];
+
+/// Number of interrupt sources. Source 0 is reserved.
+const PIC_SOURCE_COUNT: usize = 32;
+
+/// Interrupt priorities are 4 bits wide
+const PIC_PRIORITY_MASK: u32 = 0xf;
+
+/// The number of CPU clock cycles it takes for the external interrupt line to reach the core.
+const EXT_INTR_TICKS: u64 = 1;
+
+/// Interrupt line into the PIC, held by the peripheral that drives it.
+#[derive(Clone)]
+pub struct Irq {
+    id: usize,
+    pic: Rc<RefCell<PicImpl>>,
+}
+
+impl Irq {
+    /// Interrupt source ID
+    pub fn id(&self) -> u8 {
+        self.id as u8
+    }
+
+    /// Drive the interrupt line high or low
+    pub fn set_level(&self, level: bool) {
+        self.pic.borrow_mut().set_level(self.id, level);
+    }
+}
+
+/// VeeR EL2 Programmable Interrupt Controller
+#[derive(Clone)]
+pub struct Pic {
+    regs: Rc<RefCell<PicImpl>>,
+}
+
+impl Pic {
+    pub fn new(clock: &Clock) -> Self {
+        Self {
+            regs: Rc::new(RefCell::new(PicImpl::new(clock))),
+        }
+    }
+
+    /// Create a handle to the interrupt line for source `id`
+    pub fn register_irq(&self, id: u8) -> Irq {
+        assert!(
+            id != 0 && usize::from(id) < PIC_SOURCE_COUNT,
+            "Invalid PIC interrupt source {id}"
+        );
+        Irq {
+            id: id.into(),
+            pic: self.regs.clone(),
+        }
+    }
+}
+
+impl Bus for Pic {
+    /// Read data of specified size from given address
+    fn read(&mut self, size: RvSize, addr: RvAddr) -> Result<RvData, BusError> {
+        self.regs.borrow_mut().read(size, addr)
+    }
+
+    /// Write data of specified size to given address
+    fn write(&mut self, size: RvSize, addr: RvAddr, val: RvData) -> Result<(), BusError> {
+        self.regs.borrow_mut().write(size, addr, val)
+    }
+
+    fn warm_reset(&mut self) {
+        self.regs.borrow_mut().reset();
+    }
+
+    fn update_reset(&mut self) {
+        self.regs.borrow_mut().reset();
+    }
+}
+
+#[derive(Bus)]
+struct PicImpl {
+    /// External Interrupt Priority Level Registers
+    #[register_array(offset = 0x0000, write_fn = on_write_meipl)]
+    meipl: [u32; PIC_SOURCE_COUNT],
+
+    /// External Interrupt Pending Register
+    #[register(offset = 0x1000, read_fn = on_read_meip)]
+    _meip: ReadOnlyRegister<u32>,
+
+    /// External Interrupt Enable Registers
+    #[register_array(offset = 0x2000, write_fn = on_write_meie)]
+    meie: [u32; PIC_SOURCE_COUNT],
+
+    /// PIC Configuration Register
+    #[register(offset = 0x3000, write_fn = on_write_mpiccfg)]
+    mpiccfg: ReadOnlyRegister<u32, PicCfg::Register>,
+
+    /// External Interrupt Gateway Configuration Registers
+    #[register_array(offset = 0x4000, write_fn = on_write_meigwctrl)]
+    meigwctrl: [u32; PIC_SOURCE_COUNT],
+
+    /// External Interrupt Gateway Clear Registers
+    #[register_array(offset = 0x5000, write_fn = on_write_meigwclr)]
+    meigwclr: [u32; PIC_SOURCE_COUNT],
+
+    /// Raw level of each interrupt line
+    levels: u32,
+
+    /// Interrupts latched by edge-triggered gateways
+    edge_latched: u32,
+
+    /// Claim last delivered to the core
+    core_claim: Claim,
+
+    /// Claim carried by the pending update of the core
+    next_claim: Claim,
+
+    /// Timer
+    timer: Timer,
+
+    /// Pending update of the external interrupt signaled to the core
+    op_ext_intr_action: Option<ActionHandle>,
+}
+
+/// Highest-priority enabled interrupt pending in the PIC, as presented to the
+/// core for claiming
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Claim {
+    /// Source ID; 0 if no interrupt is pending
+    id: u8,
+
+    /// Raw priority level of the source
+    priority: u8,
+
+    /// Priorities are in reverse order (mpiccfg.priord)
+    reverse: bool,
+}
+
+impl PicImpl {
+    fn new(clock: &Clock) -> Self {
+        Self {
+            meipl: [0; PIC_SOURCE_COUNT],
+            _meip: ReadOnlyRegister::new(0),
+            meie: [0; PIC_SOURCE_COUNT],
+            mpiccfg: ReadOnlyRegister::new(0),
+            meigwctrl: [0; PIC_SOURCE_COUNT],
+            meigwclr: [0; PIC_SOURCE_COUNT],
+            levels: 0,
+            edge_latched: 0,
+            core_claim: Claim::default(),
+            next_claim: Claim::default(),
+            timer: Timer::new(clock),
+            op_ext_intr_action: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        // Interrupt line levels are driven by the peripherals and survive a core reset.
+        self.meipl = [0; PIC_SOURCE_COUNT];
+        self.meie = [0; PIC_SOURCE_COUNT];
+        self.mpiccfg.reg.set(0);
+        self.meigwctrl = [0; PIC_SOURCE_COUNT];
+        self.edge_latched = 0;
+        self.update_ext_intr();
+    }
+
+    /// Level of source `id` after the gateway polarity is applied
+    fn gateway_input(&self, id: usize) -> bool {
+        let active_low = self.gwctrl(id).is_set(GwCtrl::POLARITY);
+        ((self.levels >> id) & 1 != 0) != active_low
+    }
+
+    fn is_edge_triggered(&self, id: usize) -> bool {
+        self.gwctrl(id).is_set(GwCtrl::TYPE)
+    }
+
+    fn gwctrl(&self, id: usize) -> LocalRegisterCopy<u32, GwCtrl::Register> {
+        LocalRegisterCopy::new(self.meigwctrl[id])
+    }
+
+    fn set_level(&mut self, id: usize, level: bool) {
+        let was_active = self.gateway_input(id);
+        if level {
+            self.levels |= 1 << id;
+        } else {
+            self.levels &= !(1 << id);
+        }
+        if self.is_edge_triggered(id) && !was_active && self.gateway_input(id) {
+            self.edge_latched |= 1 << id;
+        }
+        self.update_ext_intr();
+    }
+
+    /// Bitmap of sources with a pending interrupt
+    fn pending(&self) -> u32 {
+        (1..PIC_SOURCE_COUNT)
+            .filter(|&id| {
+                if self.is_edge_triggered(id) {
+                    (self.edge_latched >> id) & 1 != 0
+                } else {
+                    self.gateway_input(id)
+                }
+            })
+            .fold(0, |acc, id| acc | (1 << id))
+    }
+
+    /// Returns true if `priority` can never be taken with the configured priority order
+    fn is_priority_masked(&self, priority: u32) -> bool {
+        if self.mpiccfg.reg.is_set(PicCfg::PRIORD) {
+            priority == PIC_PRIORITY_MASK
+        } else {
+            priority == 0
+        }
+    }
+
+    /// Returns the highest-priority enabled pending interrupt. Ties are won by
+    /// the lowest source ID.
+    fn claim(&self) -> Claim {
+        let reverse = self.mpiccfg.reg.is_set(PicCfg::PRIORD);
+        let pending = self.pending();
+        // Rank priorities so that a larger value always wins.
+        let rank = |priority: u32| {
+            if reverse {
+                PIC_PRIORITY_MASK - priority
+            } else {
+                priority
+            }
+        };
+        let mut claim = Claim {
+            reverse,
+            ..Default::default()
+        };
+        for id in 1..PIC_SOURCE_COUNT {
+            let priority = self.meipl[id];
+            if (pending >> id) & 1 == 0
+                || self.meie[id] & 1 == 0
+                || self.is_priority_masked(priority)
+            {
+                continue;
+            }
+            if claim.id == 0 || rank(priority) > rank(claim.priority.into()) {
+                claim.id = id as u8;
+                claim.priority = priority as u8;
+            }
+        }
+        claim
+    }
+
+    /// Recompute the external interrupt signaled to the core.
+    ///
+    /// The priority threshold (meipt) and nesting level (meicurpl) live in
+    /// the core, which compares them against the claim's priority.
+    fn update_ext_intr(&mut self) {
+        if self.timer.fired(&mut self.op_ext_intr_action) {
+            self.core_claim = self.next_claim;
+        }
+        if let Some(action) = self.op_ext_intr_action.take() {
+            // The previous update has not reached the core yet; withdraw it.
+            self.timer.cancel(action);
+        }
+
+        let claim = self.claim();
+        if claim != self.core_claim {
+            self.next_claim = claim;
+            self.op_ext_intr_action = Some(self.timer.schedule_action_in(
+                EXT_INTR_TICKS,
+                TimerAction::SetExtIntr {
+                    id: claim.id,
+                    priority: claim.priority,
+                    reverse: claim.reverse,
+                },
+            ));
+        }
+    }
+
+    fn on_read_meip(&self, _size: RvSize) -> Result<u32, BusError> {
+        Ok(self.pending())
+    }
+
+    fn on_write_meipl(&mut self, size: RvSize, index: usize, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.meipl[index] = val & PIC_PRIORITY_MASK;
+        self.update_ext_intr();
+        Ok(())
+    }
+
+    fn on_write_meie(&mut self, size: RvSize, index: usize, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.meie[index] = val & 1;
+        self.update_ext_intr();
+        Ok(())
+    }
+
+    fn on_write_mpiccfg(&mut self, size: RvSize, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.mpiccfg.reg.set(val & 1);
+        self.update_ext_intr();
+        Ok(())
+    }
+
+    fn on_write_meigwctrl(
+        &mut self,
+        size: RvSize,
+        index: usize,
+        val: RvData,
+    ) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.meigwctrl[index] = val & 0b11;
+        self.update_ext_intr();
+        Ok(())
+    }
+
+    fn on_write_meigwclr(
+        &mut self,
+        size: RvSize,
+        index: usize,
+        _val: RvData,
+    ) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.edge_latched &= !(1 << index);
+        self.update_ext_intr();
+        Ok(())
+    }
+}
+
+impl Snapshot for Claim {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u8(self.id);
+        w.write_u8(self.priority);
+        w.write_bool(self.reverse);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.id = r.read_u8()?;
+        self.priority = r.read_u8()?;
+        self.reverse = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl Snapshot for PicImpl {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.meipl);
+        w.write(&self.meie);
+        w.write(&self.mpiccfg);
+        w.write(&self.meigwctrl);
+        w.write(&self.meigwclr);
+        w.write_u32(self.levels);
+        w.write_u32(self.edge_latched);
+        w.write(&self.core_claim);
+        w.write(&self.next_claim);
+        self.timer.save_action(w, &self.op_ext_intr_action);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.meipl)?;
+        r.read(&mut self.meie)?;
+        r.read(&mut self.mpiccfg)?;
+        r.read(&mut self.meigwctrl)?;
+        r.read(&mut self.meigwclr)?;
+        self.levels = r.read_u32()?;
+        self.edge_latched = r.read_u32()?;
+        r.read(&mut self.core_claim)?;
+        r.read(&mut self.next_claim)?;
+        self.op_ext_intr_action = self.timer.restore_action(r)?;
+        Ok(())
+    }
+}
+
+impl Snapshot for Pic {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.regs.borrow_mut().restore_state(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEIPL_OFFSET: RvAddr = 0x0000;
+    const MEIP_OFFSET: RvAddr = 0x1000;
+    const MEIE_OFFSET: RvAddr = 0x2000;
+    const MPICCFG_OFFSET: RvAddr = 0x3000;
+    const MEIGWCTRL_OFFSET: RvAddr = 0x4000;
+    const MEIGWCLR_OFFSET: RvAddr = 0x5000;
+
+    /// Returns the (id, priority) signaled to the core, if it changed
+    fn claim_after_step(clock: &Clock, pic: &mut Pic) -> Option<(u8, u8)> {
+        clock
+            .increment_and_process_timer_actions(1, pic)
+            .into_iter()
+            .find_map(|action| match action {
+                TimerAction::SetExtIntr { id, priority, .. } => Some((id, priority)),
+                _ => None,
+            })
+    }
+
+    fn ext_intr_after_step(clock: &Clock, pic: &mut Pic) -> Option<bool> {
+        claim_after_step(clock, pic).map(|(id, _)| id != 0)
+    }
+
+    fn enable(pic: &mut Pic, id: u8, priority: u32) {
+        let id = RvAddr::from(id);
+        pic.write(RvSize::Word, MEIPL_OFFSET + 4 * id, priority)
+            .unwrap();
+        pic.write(RvSize::Word, MEIE_OFFSET + 4 * id, 1).unwrap();
+    }
+
+    #[test]
+    fn test_level_triggered() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq = pic.register_irq(20);
+
+        irq.set_level(true);
+        assert_eq!(pic.read(RvSize::Word, MEIP_OFFSET).unwrap(), 1 << 20);
+        // Not enabled yet
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), None);
+
+        enable(&mut pic, 20, 1);
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), Some(true));
+
+        irq.set_level(false);
+        assert_eq!(pic.read(RvSize::Word, MEIP_OFFSET).unwrap(), 0);
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), Some(false));
+    }
+
+    #[test]
+    fn test_priority_zero_is_masked() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq = pic.register_irq(3);
+
+        enable(&mut pic, 3, 0);
+        irq.set_level(true);
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), None);
+
+        pic.write(RvSize::Word, MEIPL_OFFSET + 4 * 3, 15).unwrap();
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), Some(true));
+    }
+
+    #[test]
+    fn test_edge_triggered() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq = pic.register_irq(19);
+
+        pic.write(RvSize::Word, MEIGWCTRL_OFFSET + 4 * 19, 0b10)
+            .unwrap();
+        enable(&mut pic, 19, 1);
+
+        irq.set_level(true);
+        irq.set_level(false);
+        assert_eq!(pic.read(RvSize::Word, MEIP_OFFSET).unwrap(), 1 << 19);
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), Some(true));
+
+        pic.write(RvSize::Word, MEIGWCLR_OFFSET + 4 * 19, 0)
+            .unwrap();
+        assert_eq!(pic.read(RvSize::Word, MEIP_OFFSET).unwrap(), 0);
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), Some(false));
+    }
+
+    #[test]
+    fn test_short_pulse_is_not_reported() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq = pic.register_irq(5);
+
+        enable(&mut pic, 5, 1);
+        irq.set_level(true);
+        irq.set_level(false);
+        // The line went back to its original level before the core saw it.
+        assert_eq!(ext_intr_after_step(&clock, &mut pic), None);
+    }
+
+    #[test]
+    fn test_claim_highest_priority() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq4 = pic.register_irq(4);
+        let irq10 = pic.register_irq(10);
+        let irq22 = pic.register_irq(22);
+
+        enable(&mut pic, 4, 3);
+        enable(&mut pic, 10, 7);
+        enable(&mut pic, 22, 7);
+
+        irq4.set_level(true);
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((4, 3)));
+
+        // Higher priority wins; ties go to the lowest source ID.
+        irq22.set_level(true);
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((22, 7)));
+        irq10.set_level(true);
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((10, 7)));
+
+        irq10.set_level(false);
+        irq22.set_level(false);
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((4, 3)));
+    }
+
+    #[test]
+    fn test_claim_reverse_priority_order() {
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let irq4 = pic.register_irq(4);
+        let irq10 = pic.register_irq(10);
+
+        pic.write(RvSize::Word, MPICCFG_OFFSET, 1).unwrap();
+        enable(&mut pic, 4, 3);
+        enable(&mut pic, 10, 7);
+
+        irq4.set_level(true);
+        irq10.set_level(true);
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((4, 3)));
+
+        // Priority 15 is masked in reverse order.
+        pic.write(RvSize::Word, MEIPL_OFFSET + 4 * 4, 15).unwrap();
+        assert_eq!(claim_after_step(&clock, &mut pic), Some((10, 7)));
+    }
+}
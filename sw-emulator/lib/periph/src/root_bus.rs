@@ -16,11 +16,13 @@ use crate::{
     helpers::words_from_bytes_be,
     iccm::Iccm,
     soc_reg::{DebugManufService, SocRegistersExternal},
-    AsymEcc384, Csrng, Doe, EmuCtrl, HashSha256, HashSha512, HmacSha384, KeyVault, MailboxExternal,
-    MailboxInternal, MailboxRam, Sha512Accelerator, SocRegistersInternal, Uart,
+    AsymEcc384, CryptoLatency, Csrng, Doe, EmuCtrl, HashSha256, HashSha512, HmacSha384, KeyVault,
+    MailboxExternal, MailboxInternal, MailboxRam, Pic, Sha512Accelerator, SocRegistersInternal,
+    Uart,
 };
 use caliptra_emu_bus::{Clock, Ram, Rom};
 use caliptra_emu_derive::Bus;
+use caliptra_emu_types::{Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 use caliptra_hw_model_types::{EtrngResponse, RandomEtrngResponses, RandomNibbles, SecurityState};
 use std::path::PathBuf;
 use tock_registers::registers::InMemoryRegister;
@@ -222,6 +224,9 @@ pub struct CaliptraRootBusArgs {
 
     pub itrng_nibbles: Option<Box<dyn Iterator<Item = u8>>>,
     pub etrng_responses: Box<dyn Iterator<Item = EtrngResponse>>,
+
+    /// Time taken by the crypto peripherals to complete an operation
+    pub crypto_latency: CryptoLatency,
 }
 impl Default for CaliptraRootBusArgs {
     fn default() -> Self {
@@ -237,6 +242,7 @@ impl Default for CaliptraRootBusArgs {
             cptra_obf_key: words_from_bytes_be(&DEFAULT_DOE_KEY),
             itrng_nibbles: Some(Box::new(RandomNibbles::new_from_thread_rng())),
             etrng_responses: Box::new(RandomEtrngResponses::new_from_stdrng()),
+            crypto_latency: Default::default(),
         }
     }
 }
@@ -252,7 +258,7 @@ pub struct CaliptraRootBus {
     #[peripheral(offset = 0x1000_8000, mask = 0x0000_7fff)]
     pub ecc384: AsymEcc384,
 
-    #[peripheral(offset = 0x1001_0000, mask = 0x0000_07ff)]
+    #[peripheral(offset = 0x1001_0000, mask = 0x0000_0fff)]
     pub hmac: HmacSha384,
 
     #[peripheral(offset = 0x1001_8000, mask = 0x0000_7fff)]
@@ -290,35 +296,42 @@ pub struct CaliptraRootBus {
 
     #[peripheral(offset = 0x5000_0000, mask = 0x0fff_ffff)]
     pub dccm: Ram,
+
+    #[peripheral(offset = 0x6000_0000, mask = 0x0000_7fff)]
+    pub pic: Pic,
 }
 
 impl CaliptraRootBus {
     pub const ROM_SIZE: usize = 48 * 1024;
     pub const ICCM_SIZE: usize = 128 * 1024;
+    pub const ICCM_ORG: u32 = 0x4000_0000;
     pub const DCCM_SIZE: usize = 128 * 1024;
 
     pub fn new(clock: &Clock, mut args: CaliptraRootBusArgs) -> Self {
         let mut key_vault = KeyVault::new();
+        key_vault.set_tracer(clock.tracer());
         let mailbox_ram = MailboxRam::new();
-        let mailbox = MailboxInternal::new(mailbox_ram.clone());
+        let mailbox = MailboxInternal::new(clock, mailbox_ram.clone());
         let rom = Rom::new(std::mem::take(&mut args.rom));
         let iccm = Iccm::new(clock);
         let itrng_nibbles = args.itrng_nibbles.take();
-        let soc_reg = SocRegistersInternal::new(clock, mailbox.clone(), iccm.clone(), args);
+        let pic = Pic::new(clock);
+        let crypto_latency = args.crypto_latency;
+        let soc_reg = SocRegistersInternal::new(clock, mailbox.clone(), iccm.clone(), &pic, args);
         if !soc_reg.is_debug_locked() {
             // When debug is possible, the key-vault is initialized with a debug value...
             // This is necessary to match the behavior of the RTL.
             key_vault.clear_keys_with_debug_values(false);
         }
 
-        Self {
+        let mut bus = Self {
             rom,
             doe: Doe::new(clock, key_vault.clone(), soc_reg.clone()),
-            ecc384: AsymEcc384::new(clock, key_vault.clone()),
-            hmac: HmacSha384::new(clock, key_vault.clone()),
+            ecc384: AsymEcc384::new(clock, key_vault.clone(), &pic),
+            hmac: HmacSha384::new(clock, key_vault.clone(), &pic),
             key_vault: key_vault.clone(),
-            sha512: HashSha512::new(clock, key_vault),
-            sha256: HashSha256::new(clock),
+            sha512: HashSha512::new(clock, key_vault, &pic),
+            sha256: HashSha256::new(clock, &pic),
             iccm,
             dccm: Ram::new(vec![0; Self::DCCM_SIZE]),
             uart: Uart::new(),
@@ -326,9 +339,17 @@ impl CaliptraRootBus {
             soc_reg,
             mailbox_sram: mailbox_ram.clone(),
             mailbox,
-            sha512_acc: Sha512Accelerator::new(clock, mailbox_ram),
+            sha512_acc: Sha512Accelerator::new(clock, mailbox_ram, &pic),
             csrng: Csrng::new(itrng_nibbles.unwrap()),
-        }
+            pic,
+        };
+        bus.doe.set_latency(crypto_latency);
+        bus.ecc384.set_latency(crypto_latency);
+        bus.hmac.set_latency(crypto_latency);
+        bus.sha512.set_latency(crypto_latency);
+        bus.sha256.set_latency(crypto_latency);
+        bus.sha512_acc.set_latency(crypto_latency);
+        bus
     }
 
     pub fn soc_to_caliptra_bus(&self) -> SocToCaliptraBus {
@@ -340,6 +361,47 @@ impl CaliptraRootBus {
     }
 }
 
+/// The ROM and ICCM contents loaded at construction are part of the model
+/// configuration; the ROM is not saved. Rc-shared peripherals such as the key
+/// vault and mailbox are saved once, through the fields that own them here.
+impl Snapshot for CaliptraRootBus {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self.doe);
+        w.write(&self.ecc384);
+        w.write(&self.hmac);
+        w.write(&self.key_vault);
+        w.write(&self.sha512);
+        w.write(&self.sha256);
+        w.write(&self.iccm);
+        w.write(&self.uart);
+        w.write(&self.csrng);
+        w.write(&self.mailbox_sram);
+        w.write(&self.mailbox);
+        w.write(&self.sha512_acc);
+        w.write(&self.soc_reg);
+        w.write(&self.dccm);
+        w.write(&self.pic);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.doe)?;
+        r.read(&mut self.ecc384)?;
+        r.read(&mut self.hmac)?;
+        r.read(&mut self.key_vault)?;
+        r.read(&mut self.sha512)?;
+        r.read(&mut self.sha256)?;
+        r.read(&mut self.iccm)?;
+        r.read(&mut self.uart)?;
+        r.read(&mut self.csrng)?;
+        r.read(&mut self.mailbox_sram)?;
+        r.read(&mut self.mailbox)?;
+        r.read(&mut self.sha512_acc)?;
+        r.read(&mut self.soc_reg)?;
+        r.read(&mut self.dccm)?;
+        r.read(&mut self.pic)
+    }
+}
+
 #[derive(Bus)]
 pub struct SocToCaliptraBus {
     #[peripheral(offset = 0x3002_0000, mask = 0x0000_0fff)]
@@ -352,9 +414,18 @@ pub struct SocToCaliptraBus {
     soc_ifc: SocRegistersExternal,
 }
 
+impl SocToCaliptraBus {
+    /// Set the APB PAUSER used for subsequent SoC accesses
+    pub fn set_apb_pauser(&mut self, pauser: u32) {
+        self.mailbox.set_pauser(pauser);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::KeyUsage;
+    use crate::{BootStatusChange, KeyUsage};
+    use caliptra_emu_bus::Bus;
+    use caliptra_emu_types::RvSize;
 
     use super::*;
 
@@ -419,4 +490,86 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_snapshot() {
+        let clock = Clock::new();
+        let mut root_bus = CaliptraRootBus::new(&clock, CaliptraRootBusArgs::default());
+        let mut key_usage = KeyUsage::default();
+        key_usage.set_hmac_key(true);
+
+        root_bus
+            .key_vault
+            .write_key(1, &[0x55; 48], key_usage.into())
+            .unwrap();
+        root_bus
+            .dccm
+            .write(RvSize::Word, 0x100, 0x1234_5678)
+            .unwrap();
+
+        let mut w = SnapshotWriter::new();
+        w.write(&root_bus);
+        let data = w.finish().unwrap();
+
+        let clock = Clock::new();
+        let mut restored = CaliptraRootBus::new(&clock, CaliptraRootBusArgs::default());
+        let mut r = SnapshotReader::new(&data);
+        r.read(&mut restored).unwrap();
+        r.finish().unwrap();
+
+        assert_eq!(
+            restored.key_vault.read_key(1, key_usage).unwrap(),
+            [0x55; 48]
+        );
+        assert_eq!(
+            restored.dccm.read(RvSize::Word, 0x100).unwrap(),
+            0x1234_5678
+        );
+    }
+
+    #[test]
+    fn test_crypto_latency() {
+        let clock = Clock::new();
+        let mut root_bus = CaliptraRootBus::new(
+            &clock,
+            CaliptraRootBusArgs {
+                crypto_latency: "rtl,sha256_block=50".parse().unwrap(),
+                ..CaliptraRootBusArgs::default()
+            },
+        );
+        // SHA256 INIT in SHA256 mode
+        root_bus.write(RvSize::Word, 0x1002_8010, 0b101).unwrap();
+        for _ in 0..49 {
+            clock.increment_and_process_timer_actions(1, &mut root_bus);
+            assert_eq!(root_bus.read(RvSize::Word, 0x1002_8018).unwrap() & 1, 0);
+        }
+        clock.increment_and_process_timer_actions(1, &mut root_bus);
+        assert_eq!(root_bus.read(RvSize::Word, 0x1002_8018).unwrap() & 1, 1);
+    }
+
+    #[test]
+    fn test_boot_timing() {
+        let clock = Clock::new();
+        let mut root_bus = CaliptraRootBus::new(&clock, CaliptraRootBusArgs::default());
+        clock.increment(100);
+        root_bus.write(RvSize::Word, 0x3003_0038, 385).unwrap();
+        clock.increment(400);
+        root_bus.write(RvSize::Word, 0x3003_0038, 1).unwrap();
+
+        let timing = root_bus.soc_reg.boot_timing();
+        assert_eq!(
+            timing.changes(),
+            &[
+                BootStatusChange {
+                    cycle: 100,
+                    status: 385
+                },
+                BootStatusChange {
+                    cycle: 500,
+                    status: 1
+                }
+            ]
+        );
+        assert_eq!(timing.phases()[1].cycles(), 400);
+    }
 }
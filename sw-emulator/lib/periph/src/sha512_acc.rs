@@ -11,13 +11,17 @@ Abstract:
     File contains SHA accelerator implementation.
 
 --*/
-use crate::MailboxRam;
+use crate::intr_block::{irq, IntrBlock, NOTIF_CMD_DONE_STS};
+use crate::latency::CryptoLatency;
+use crate::{MailboxRam, Pic};
 use caliptra_emu_bus::{
     ActionHandle, Bus, BusError, Clock, ReadOnlyMemory, ReadOnlyRegister, ReadWriteRegister, Timer,
 };
 use caliptra_emu_crypto::{EndianessTransform, Sha512, Sha512Mode};
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use smlang::statemachine;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -31,9 +35,6 @@ const MAX_MAILBOX_CAPACITY_WORDS: usize = (128 << 10) >> 2;
 /// Maximum mailbox capacity in bytes.
 const MAX_MAILBOX_CAPACITY_BYTES: usize = MAX_MAILBOX_CAPACITY_WORDS * RvSize::Word as usize;
 
-/// The number of CPU clock cycles it takes to perform sha operation.
-const SHA_ACC_OP_TICKS: u64 = 1000;
-
 const SHA512_BLOCK_SIZE: usize = 128;
 const SHA512_HASH_SIZE: usize = 64;
 
@@ -129,12 +130,19 @@ pub struct Sha512AcceleratorRegs {
     #[register(offset = 0x0000_0060, write_fn = on_write_control)]
     control: ReadWriteRegister<u32, Control::Register>,
 
+    /// Interrupt Block
+    #[peripheral(offset = 0x0000_0800, mask = 0x0000_00ff)]
+    intr_block: IntrBlock,
+
     /// Mailbox Memory
     mailbox_ram: MailboxRam,
 
     /// Timer
     timer: Timer,
 
+    /// Operation latencies
+    latency: CryptoLatency,
+
     /// State Machine
     state_machine: StateMachine<Context>,
 
@@ -146,13 +154,14 @@ pub struct Sha512AcceleratorRegs {
 }
 
 impl Sha512AcceleratorRegs {
-    pub fn new(clock: &Clock, mailbox_ram: MailboxRam) -> Self {
+    pub fn new(clock: &Clock, mailbox_ram: MailboxRam, pic: &Pic) -> Self {
         let mut result = Self {
             status: ReadOnlyRegister::new(Status::VALID::CLEAR.value),
             hash_lower: ReadOnlyMemory::new(),
             hash_upper: ReadOnlyMemory::new(),
             mailbox_ram,
             timer: Timer::new(clock),
+            latency: CryptoLatency::default(),
             _lock: ReadWriteRegister::new(0),
             user: ReadOnlyRegister::new(0),
             dlen: ReadWriteRegister::new(0),
@@ -163,6 +172,7 @@ impl Sha512AcceleratorRegs {
             op_complete_action: None,
             state_machine: StateMachine::new(Context::new()),
             control: ReadWriteRegister::new(0),
+            intr_block: IntrBlock::new(pic, irq::SHA512_ACC_ERROR, irq::SHA512_ACC_NOTIF),
             sha_stream: Sha512::new(Sha512Mode::Sha512),
         };
         // The peripheral needs to be locked at boot by the uC.
@@ -355,7 +365,8 @@ impl Sha512AcceleratorRegs {
                 self.compute_mbox_hash();
 
                 // Schedule a future call to poll() complete the operation.
-                self.op_complete_action = Some(self.timer.schedule_poll_in(SHA_ACC_OP_TICKS));
+                let op_ticks = self.latency.sha512_acc(self.dlen.reg.get() as usize);
+                self.op_complete_action = Some(self.timer.schedule_poll_in(op_ticks));
             } else if mode == ShaMode::MODE::SHA512_ACC_MODE_SHA_STREAM_384.value
                 || mode == ShaMode::MODE::SHA512_ACC_MODE_SHA_STREAM_512.value
             {
@@ -497,6 +508,8 @@ impl Sha512AcceleratorRegs {
     fn op_complete(&mut self) {
         // Update the 'Valid' status bit
         self.status.reg.modify(Status::VALID::SET);
+
+        self.intr_block.set_notif(NOTIF_CMD_DONE_STS);
     }
 
     /// Get the length of the hash
@@ -539,11 +552,20 @@ pub struct Sha512Accelerator {
 
 impl Sha512Accelerator {
     /// Create a new instance of SHA-512 Accelerator
-    pub fn new(clock: &Clock, mailbox_ram: MailboxRam) -> Self {
+    pub fn new(clock: &Clock, mailbox_ram: MailboxRam, pic: &Pic) -> Self {
         Self {
-            regs: Rc::new(RefCell::new(Sha512AcceleratorRegs::new(clock, mailbox_ram))),
+            regs: Rc::new(RefCell::new(Sha512AcceleratorRegs::new(
+                clock,
+                mailbox_ram,
+                pic,
+            ))),
         }
     }
+
+    /// Set the latency of hash operations
+    pub fn set_latency(&self, latency: CryptoLatency) {
+        self.regs.borrow_mut().latency = latency;
+    }
 }
 
 impl Bus for Sha512Accelerator {
@@ -560,6 +582,14 @@ impl Bus for Sha512Accelerator {
     fn poll(&mut self) {
         self.regs.borrow_mut().poll();
     }
+
+    fn warm_reset(&mut self) {
+        Bus::warm_reset(&mut *self.regs.borrow_mut());
+    }
+
+    fn update_reset(&mut self) {
+        Bus::update_reset(&mut *self.regs.borrow_mut());
+    }
 }
 
 pub struct Owner(pub u32);
@@ -614,9 +644,66 @@ impl StateMachineContext for Context {
     }
 }
 
+impl Snapshot for Sha512AcceleratorRegs {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write(&self._lock);
+        w.write(&self.user);
+        w.write(&self.mode);
+        w.write(&self.start_address);
+        w.write(&self.dlen);
+        w.write(&self.data_in);
+        w.write(&self.execute);
+        w.write(&self.status);
+        w.write(&self.hash_lower);
+        w.write(&self.hash_upper);
+        w.write(&self.control);
+        w.write(&self.intr_block);
+        self.timer.save_action(w, &self.op_complete_action);
+        w.write_bool(matches!(self.state_machine.state, States::RdyForExc));
+        w.write_u32(self.state_machine.context.locked);
+        w.write_u32(self.state_machine.context.user);
+        w.write(&self.sha_stream);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self._lock)?;
+        r.read(&mut self.user)?;
+        r.read(&mut self.mode)?;
+        r.read(&mut self.start_address)?;
+        r.read(&mut self.dlen)?;
+        r.read(&mut self.data_in)?;
+        r.read(&mut self.execute)?;
+        r.read(&mut self.status)?;
+        r.read(&mut self.hash_lower)?;
+        r.read(&mut self.hash_upper)?;
+        r.read(&mut self.control)?;
+        r.read(&mut self.intr_block)?;
+        self.op_complete_action = self.timer.restore_action(r)?;
+        self.state_machine.state = if r.read_bool()? {
+            States::RdyForExc
+        } else {
+            States::Idle
+        };
+        self.state_machine.context.locked = r.read_u32()?;
+        self.state_machine.context.user = r.read_u32()?;
+        r.read(&mut self.sha_stream)?;
+        Ok(())
+    }
+}
+
+impl Snapshot for Sha512Accelerator {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.regs.borrow_mut().restore_state(r)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{sha512_acc::*, MailboxRam};
+    use crate::{sha512_acc::*, MailboxRam, Pic};
     use caliptra_emu_bus::Bus;
     use caliptra_emu_types::RvAddr;
     use tock_registers::registers::InMemoryRegister;
@@ -648,7 +735,7 @@ mod tests {
         }
 
         let clock = Clock::new();
-        let mut sha_accl = Sha512Accelerator::new(&clock, mb_ram.clone());
+        let mut sha_accl = Sha512Accelerator::new(&clock, mb_ram.clone(), &Pic::new(&clock));
         // Unlock the initial state
         sha_accl.write(RvSize::Word, OFFSET_LOCK, 1).unwrap();
 
@@ -828,7 +915,7 @@ mod tests {
     #[test]
     fn test_sm_lock() {
         let clock = Clock::new();
-        let mut sha_accl = Sha512Accelerator::new(&clock, MailboxRam::new());
+        let mut sha_accl = Sha512Accelerator::new(&clock, MailboxRam::new(), &Pic::new(&clock));
         assert_eq!(sha_accl.regs.borrow().state_machine.context.locked, 1);
         // Unlock the initial state
         sha_accl.write(RvSize::Word, OFFSET_LOCK, 1).unwrap();
@@ -860,7 +947,7 @@ mod tests {
     #[test]
     fn test_sha_acc_check_state() {
         let clock = Clock::new();
-        let mut sha_accl = Sha512Accelerator::new(&clock, MailboxRam::new());
+        let mut sha_accl = Sha512Accelerator::new(&clock, MailboxRam::new(), &Pic::new(&clock));
 
         // Check init state.
         assert_eq!(
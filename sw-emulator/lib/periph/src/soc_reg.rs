@@ -13,15 +13,18 @@ Abstract:
 --*/
 
 use crate::helpers::{bytes_from_words_be, words_from_bytes_be};
+use crate::intr_block::{irq, IntrBlock};
 use crate::root_bus::ReadyForFwCbArgs;
-use crate::{CaliptraRootBusArgs, Iccm, MailboxInternal};
+use crate::{BootStatusChange, BootTiming, CaliptraRootBusArgs, Iccm, MailboxInternal, Pic};
 use caliptra_emu_bus::BusError::{LoadAccessFault, StoreAccessFault};
 use caliptra_emu_bus::{
     ActionHandle, Bus, BusError, Clock, ReadOnlyRegister, ReadWriteRegister, Register, Timer,
     TimerAction,
 };
 use caliptra_emu_derive::Bus;
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    CountingIter, RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 use caliptra_hw_model_types::EtrngResponse;
 use caliptra_registers::soc_ifc::regs::CptraHwConfigReadVal;
 use caliptra_registers::soc_ifc_trng::regs::{CptraTrngStatusReadVal, CptraTrngStatusWriteVal};
@@ -103,11 +106,16 @@ mod constants {
     pub const INTERNAL_FW_UPDATE_RESET_START: u32 = 0x624;
     pub const INTERNAL_FW_UPDATE_RESET_WAIT_CYCLES_START: u32 = 0x628;
     pub const INTERNAL_NMI_VECTOR_START: u32 = 0x62c;
-    pub const INTR_BLOCK_START: u32 = 0x800;
-    pub const INTR_BLOCK_SIZE: usize = 28;
+    pub const INTERNAL_RV_MTIME_L_START: u32 = 0x640;
+    pub const INTERNAL_RV_MTIME_H_START: u32 = 0x644;
+    pub const INTERNAL_RV_MTIMECMP_L_START: u32 = 0x648;
+    pub const INTERNAL_RV_MTIMECMP_H_START: u32 = 0x64c;
 }
 use constants::*;
 
+/// NOTIF_INTERNAL_INTR fields
+const NOTIF_CMD_AVAIL_STS: u32 = 1 << 0;
+
 register_bitfields! [
     u32,
 
@@ -215,7 +223,7 @@ pub struct SocRegistersInternal {
 const CALIPTRA_REG_START_ADDR: u32 = 0x00;
 
 /// Caliptra Register End Address
-const CALIPTRA_REG_END_ADDR: u32 = 0x81c;
+const CALIPTRA_REG_END_ADDR: u32 = 0x820;
 
 /// Caliptra Fuse start address
 const FUSE_START_ADDR: u32 = 0x200;
@@ -228,11 +236,12 @@ impl SocRegistersInternal {
         clock: &Clock,
         mailbox: MailboxInternal,
         iccm: Iccm,
+        pic: &Pic,
         args: CaliptraRootBusArgs,
     ) -> Self {
         Self {
             regs: Rc::new(RefCell::new(SocRegistersImpl::new(
-                clock, mailbox, iccm, args,
+                clock, mailbox, iccm, pic, args,
             ))),
         }
     }
@@ -241,6 +250,11 @@ impl SocRegistersInternal {
         reg.read(SecurityState::DEBUG_LOCKED) != 0
     }
 
+    /// Cycles at which the firmware reported each boot status
+    pub fn boot_timing(&self) -> BootTiming {
+        BootTiming::new(self.regs.borrow().boot_status_log.clone())
+    }
+
     /// Get Unique device secret
     pub fn uds(&self) -> [u8; FUSE_UDS_SEED_SIZE] {
         if self.is_debug_locked() {
@@ -392,7 +406,7 @@ struct SocRegistersImpl {
     #[register_array(offset = 0x0018)]
     cptra_fw_extended_error_info: [u32; CPTRA_FW_EXTENDED_ERROR_INFO_SIZE / 4],
 
-    #[register(offset = 0x0038)]
+    #[register(offset = 0x0038, write_fn = on_write_boot_status)]
     cptra_boot_status: ReadWriteRegister<u32>,
 
     #[register(offset = 0x003c, write_fn = on_write_flow_status)]
@@ -551,9 +565,33 @@ struct SocRegistersImpl {
     #[register(offset = 0x062c, write_fn = on_write_internal_nmi_vector)]
     internal_nmi_vector: ReadWriteRegister<u32>,
 
-    /// INTERNAL_FW_UPDATE_RESET_WAIT_CYCLES Register
-    #[register_array(offset = 0x0800)]
-    intr_block_rf: [u32; INTR_BLOCK_SIZE / 4],
+    /// INTERNAL_RV_MTIME_L Register
+    #[register(
+        offset = 0x0640,
+        read_fn = on_read_internal_rv_mtime_l,
+        write_fn = on_write_internal_rv_mtime_l
+    )]
+    _internal_rv_mtime_l: ReadWriteRegister<u32>,
+
+    /// INTERNAL_RV_MTIME_H Register
+    #[register(
+        offset = 0x0644,
+        read_fn = on_read_internal_rv_mtime_h,
+        write_fn = on_write_internal_rv_mtime_h
+    )]
+    _internal_rv_mtime_h: ReadWriteRegister<u32>,
+
+    /// INTERNAL_RV_MTIMECMP_L Register
+    #[register(offset = 0x0648, write_fn = on_write_internal_rv_mtimecmp_l)]
+    internal_rv_mtimecmp_l: ReadWriteRegister<u32>,
+
+    /// INTERNAL_RV_MTIMECMP_H Register
+    #[register(offset = 0x064c, write_fn = on_write_internal_rv_mtimecmp_h)]
+    internal_rv_mtimecmp_h: ReadWriteRegister<u32>,
+
+    /// Interrupt Block Registers
+    #[peripheral(offset = 0x0800, mask = 0x00ff)]
+    intr_block: IntrBlock,
 
     /// Mailbox
     mailbox: MailboxInternal,
@@ -578,6 +616,15 @@ struct SocRegistersImpl {
     /// Reset Trigger action
     op_reset_trigger_action: Option<ActionHandle>,
 
+    /// Difference between mtime and the clock
+    mtime_offset: u64,
+
+    /// Update of the timer interrupt line to the current mtime/mtimecmp state
+    op_timer_intr_update_action: Option<ActionHandle>,
+
+    /// Timer interrupt raised when mtime reaches mtimecmp
+    op_timer_intr_action: Option<ActionHandle>,
+
     /// test bench services callback
     tb_services_cb: Box<dyn FnMut(u8)>,
 
@@ -597,9 +644,12 @@ struct SocRegistersImpl {
     /// WDT Timer2 Expired action
     op_wdt_timer2_expired_action: Option<ActionHandle>,
 
-    etrng_responses: Box<dyn Iterator<Item = EtrngResponse>>,
+    etrng_responses: CountingIter<EtrngResponse>,
     pending_etrng_response: Option<EtrngResponse>,
     op_pending_etrng_response_action: Option<ActionHandle>,
+
+    /// Boot statuses written by the firmware, for boot time measurements
+    boot_status_log: Vec<BootStatusChange>,
 }
 
 impl SocRegistersImpl {
@@ -617,16 +667,20 @@ impl SocRegistersImpl {
     /// The number of CPU clock cycles it takes to read the IDEVID CSR from the mailbox.
     const IDEVID_CSR_READ_TICKS: u64 = 100;
 
+    /// The furthest in the future the timer interrupt can be scheduled.
+    const MAX_TIMER_INTR_TICKS: u64 = u64::MAX >> 1;
+
     pub fn new(
         clock: &Clock,
         mailbox: MailboxInternal,
         iccm: Iccm,
+        pic: &Pic,
         mut args: CaliptraRootBusArgs,
     ) -> Self {
         let flow_status = InMemoryRegister::<u32, FlowStatus::Register>::new(0);
         flow_status.write(FlowStatus::READY_FOR_FUSES.val(1));
 
-        let regs = Self {
+        let mut regs = Self {
             cptra_hw_error_fatal: ReadWriteRegister::new(0),
             cptra_hw_error_non_fatal: ReadWriteRegister::new(0),
             cptra_fw_error_fatal: ReadWriteRegister::new(0),
@@ -673,7 +727,11 @@ impl SocRegistersImpl {
             internal_fw_update_reset: ReadWriteRegister::new(0),
             internal_fw_update_reset_wait_cycles: ReadWriteRegister::new(5),
             internal_nmi_vector: ReadWriteRegister::new(0),
-            intr_block_rf: [0u32; INTR_BLOCK_SIZE / 4],
+            _internal_rv_mtime_l: ReadWriteRegister::new(0),
+            _internal_rv_mtime_h: ReadWriteRegister::new(0),
+            internal_rv_mtimecmp_l: ReadWriteRegister::new(0),
+            internal_rv_mtimecmp_h: ReadWriteRegister::new(0),
+            intr_block: IntrBlock::new(pic, irq::SOC_IFC_ERROR, irq::SOC_IFC_NOTIF),
             mailbox,
             iccm,
             timer: Timer::new(clock),
@@ -682,6 +740,9 @@ impl SocRegistersImpl {
             op_fw_read_complete_action: None,
             op_idevid_csr_read_complete_action: None,
             op_reset_trigger_action: None,
+            mtime_offset: 0u64.wrapping_sub(clock.now()),
+            op_timer_intr_update_action: None,
+            op_timer_intr_action: None,
             tb_services_cb: args.tb_services_cb.take(),
             ready_for_fw_cb: args.ready_for_fw_cb.take(),
             upload_update_fw: args.upload_update_fw.take(),
@@ -699,13 +760,15 @@ impl SocRegistersImpl {
             cptra_i_trng_entropy_config_1: 0,
             op_wdt_timer1_expired_action: None,
             op_wdt_timer2_expired_action: None,
-            etrng_responses: args.etrng_responses,
+            etrng_responses: CountingIter::new(args.etrng_responses),
             pending_etrng_response: None,
             op_pending_etrng_response_action: None,
+            boot_status_log: vec![],
             cptra_wdt_cfg: [0x0; 2],
             cptra_fuse_valid_pauser: ReadWriteRegister::new(0xffff_ffff),
             cptra_fuse_pauser_lock: ReadWriteRegister::new(0),
         };
+        regs.update_timer_intr();
 
         regs
     }
@@ -784,6 +847,19 @@ impl SocRegistersImpl {
     /// # Error
     ///
     /// * `BusError` - Exception with cause `BusError::StoreAccessFault` or `BusError::StoreAddrMisaligned`
+    fn on_write_boot_status(&mut self, size: RvSize, val: RvData) -> Result<(), BusError> {
+        // Writes have to be Word aligned.
+        if size != RvSize::Word {
+            Err(BusError::StoreAccessFault)?
+        }
+        self.cptra_boot_status.reg.set(val);
+        self.boot_status_log.push(BootStatusChange {
+            cycle: self.timer.now(),
+            status: val,
+        });
+        Ok(())
+    }
+
     fn on_write_flow_status(&mut self, size: RvSize, val: RvData) -> Result<(), BusError> {
         // Writes have to be Word aligned.
         if size != RvSize::Word {
@@ -875,6 +951,102 @@ impl SocRegistersImpl {
         Ok(())
     }
 
+    /// Current value of the RISC-V machine timer
+    fn mtime(&self) -> u64 {
+        self.timer.now().wrapping_add(self.mtime_offset)
+    }
+
+    fn set_mtime(&mut self, mtime: u64) {
+        self.mtime_offset = mtime.wrapping_sub(self.timer.now());
+        self.update_timer_intr();
+    }
+
+    fn mtimecmp(&self) -> u64 {
+        (u64::from(self.internal_rv_mtimecmp_h.reg.get()) << 32)
+            | u64::from(self.internal_rv_mtimecmp_l.reg.get())
+    }
+
+    /// Drive the timer interrupt line to the core from mtime and mtimecmp,
+    /// and schedule it to rise when mtime reaches mtimecmp.
+    fn update_timer_intr(&mut self) {
+        for action in [
+            self.op_timer_intr_update_action.take(),
+            self.op_timer_intr_action.take(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.timer.cancel(action);
+        }
+
+        let mtime = self.mtime();
+        let mtimecmp = self.mtimecmp();
+        let pending = mtime >= mtimecmp;
+        self.op_timer_intr_update_action = Some(
+            self.timer
+                .schedule_action_in(0, TimerAction::SetTimerIntr { pending }),
+        );
+        // A comparator beyond the range of the clock, such as u64::MAX to
+        // disable the timer, never fires.
+        let ticks = mtimecmp.wrapping_sub(mtime);
+        if !pending && ticks < Self::MAX_TIMER_INTR_TICKS {
+            self.op_timer_intr_action = Some(
+                self.timer
+                    .schedule_action_in(ticks, TimerAction::SetTimerIntr { pending: true }),
+            );
+        }
+    }
+
+    fn on_read_internal_rv_mtime_l(&self, size: RvSize) -> Result<u32, BusError> {
+        if size != RvSize::Word {
+            Err(LoadAccessFault)?
+        }
+        Ok(self.mtime() as u32)
+    }
+
+    fn on_read_internal_rv_mtime_h(&self, size: RvSize) -> Result<u32, BusError> {
+        if size != RvSize::Word {
+            Err(LoadAccessFault)?
+        }
+        Ok((self.mtime() >> 32) as u32)
+    }
+
+    fn on_write_internal_rv_mtime_l(&mut self, size: RvSize, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(StoreAccessFault)?
+        }
+        self.set_mtime((self.mtime() & !0xffff_ffff) | u64::from(val));
+        Ok(())
+    }
+
+    fn on_write_internal_rv_mtime_h(&mut self, size: RvSize, val: RvData) -> Result<(), BusError> {
+        if size != RvSize::Word {
+            Err(StoreAccessFault)?
+        }
+        self.set_mtime((self.mtime() & 0xffff_ffff) | (u64::from(val) << 32));
+        Ok(())
+    }
+
+    fn on_write_internal_rv_mtimecmp_l(
+        &mut self,
+        size: RvSize,
+        val: RvData,
+    ) -> Result<(), BusError> {
+        self.internal_rv_mtimecmp_l.write(size, val)?;
+        self.update_timer_intr();
+        Ok(())
+    }
+
+    fn on_write_internal_rv_mtimecmp_h(
+        &mut self,
+        size: RvSize,
+        val: RvData,
+    ) -> Result<(), BusError> {
+        self.internal_rv_mtimecmp_h.write(size, val)?;
+        self.update_timer_intr();
+        Ok(())
+    }
+
     fn on_write_wdt_timer1_en(&mut self, _size: RvSize, val: RvData) -> Result<(), BusError> {
         self.cptra_wdt_timer1_en.reg.set(val);
 
@@ -982,6 +1154,10 @@ impl SocRegistersImpl {
 
     /// Called by Bus::poll() to indicate that time has passed
     fn bus_poll(&mut self) {
+        if self.mailbox.take_cmd_avail_event() {
+            self.intr_block.set_notif(NOTIF_CMD_AVAIL_STS);
+        }
+
         if self.timer.fired(&mut self.op_fw_write_complete_action) {
             if let Some(cb) = self.op_fw_write_complete_cb.take() {
                 (cb)(&mut self.mailbox);
@@ -1071,6 +1247,11 @@ impl SocRegistersImpl {
             .reg
             .write(ResetReason::WARM_RESET::SET);
 
+        // The machine timer is reset with the core complex.
+        self.internal_rv_mtimecmp_l.reg.set(0);
+        self.internal_rv_mtimecmp_h.reg.set(0);
+        self.set_mtime(0);
+
         self.reset_common();
     }
 
@@ -1088,6 +1269,200 @@ impl SocRegistersImpl {
     }
 }
 
+/// The mailbox and ICCM are saved with the root bus. Host callbacks are kept
+/// from the instance being restored, and its external TRNG responses are
+/// advanced to the saved position.
+impl Snapshot for SocRegistersImpl {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        if self.op_fw_write_complete_cb.is_some() {
+            w.fail(SnapshotError::Unsupported("pending firmware update"));
+        }
+        w.write(&self.cptra_hw_error_fatal);
+        w.write(&self.cptra_hw_error_non_fatal);
+        w.write(&self.cptra_fw_error_fatal);
+        w.write(&self.cptra_fw_error_non_fatal);
+        w.write(&self.cptra_hw_error_enc);
+        w.write(&self.cptra_fw_error_enc);
+        w.write(&self.cptra_fw_extended_error_info);
+        w.write(&self.cptra_boot_status);
+        w.write(&self.cptra_flow_status);
+        w.write(&self.cptra_reset_reason);
+        w.write(&self.cptra_security_state);
+        w.write(&self.cptra_mbox_valid_pauser);
+        w.write(&self.cptra_mbox_pauser_lock);
+        w.write(&self.cptra_trng_valid_pauser);
+        w.write(&self.cptra_trng_pauser_lock);
+        w.write(&self.cptra_trng_data);
+        w.write(&self.cptra_trng_ctrl);
+        w.write(&self.cptra_trng_status);
+        w.write(&self.cptra_fuse_wr_done);
+        w.write(&self.cptra_timer_config);
+        w.write(&self.cptra_bootfsm_go);
+        w.write(&self.cptra_dbg_manuf_service_reg);
+        w.write(&self.cptra_clk_gating_en);
+        w.write(&self.cptra_generic_input_wires);
+        w.write(&self.cptra_generic_output_wires);
+        w.write(&self.cptra_hw_rev_id);
+        w.write(&self.cptra_fw_rev_id);
+        w.write(&self.cptra_hw_config);
+        w.write(&self.cptra_wdt_timer1_en);
+        w.write(&self.cptra_wdt_timer1_ctrl);
+        w.write(&self.cptra_wdt_timer1_timeout_period);
+        w.write(&self.cptra_wdt_timer2_en);
+        w.write(&self.cptra_wdt_timer2_ctrl);
+        w.write(&self.cptra_wdt_timer2_timeout_period);
+        w.write(&self.cptra_wdt_status);
+        w.write(&self.cptra_fuse_valid_pauser);
+        w.write(&self.cptra_fuse_pauser_lock);
+        w.write(&self.cptra_i_trng_entropy_config_0);
+        w.write(&self.cptra_i_trng_entropy_config_1);
+        w.write(&self.fuse_uds_seed);
+        w.write(&self.cptra_wdt_cfg);
+        w.write(&self.fuse_field_entropy);
+        w.write(&self.fuse_vendor_pk_hash);
+        w.write(&self.fuse_vendor_pk_hash_mask);
+        w.write(&self.fuse_owner_pk_hash);
+        w.write(&self.fuse_fmc_svn);
+        w.write(&self.fuse_runtime_svn);
+        w.write(&self.fuse_anti_rollback_disable);
+        w.write(&self.fuse_idevid_cert_attr);
+        w.write(&self.fuse_idevid_manuf_hsm_id);
+        w.write(&self.fuse_life_cycle);
+        w.write(&self.fuse_lms_verify);
+        w.write(&self.fuse_lms_revocation);
+        w.write(&self.internal_obf_key);
+        w.write(&self.internal_iccm_lock);
+        w.write(&self.internal_fw_update_reset);
+        w.write(&self.internal_fw_update_reset_wait_cycles);
+        w.write(&self.internal_nmi_vector);
+        w.write(&self._internal_rv_mtime_l);
+        w.write(&self._internal_rv_mtime_h);
+        w.write(&self.internal_rv_mtimecmp_l);
+        w.write(&self.internal_rv_mtimecmp_h);
+        w.write(&self.intr_block);
+        w.write(&self.mtime_offset);
+        w.write(&self.fuses_can_be_written);
+        self.timer.save_action(w, &self.op_fw_write_complete_action);
+        self.timer.save_action(w, &self.op_fw_read_complete_action);
+        self.timer
+            .save_action(w, &self.op_idevid_csr_read_complete_action);
+        self.timer.save_action(w, &self.op_reset_trigger_action);
+        self.timer.save_action(w, &self.op_timer_intr_update_action);
+        self.timer.save_action(w, &self.op_timer_intr_action);
+        self.timer
+            .save_action(w, &self.op_wdt_timer1_expired_action);
+        self.timer
+            .save_action(w, &self.op_wdt_timer2_expired_action);
+        self.timer
+            .save_action(w, &self.op_pending_etrng_response_action);
+        w.write(&self.etrng_responses);
+        w.write_bool(self.pending_etrng_response.is_some());
+        if let Some(response) = &self.pending_etrng_response {
+            w.write_u32(response.delay);
+            w.write(&response.data);
+        }
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        r.read(&mut self.cptra_hw_error_fatal)?;
+        r.read(&mut self.cptra_hw_error_non_fatal)?;
+        r.read(&mut self.cptra_fw_error_fatal)?;
+        r.read(&mut self.cptra_fw_error_non_fatal)?;
+        r.read(&mut self.cptra_hw_error_enc)?;
+        r.read(&mut self.cptra_fw_error_enc)?;
+        r.read(&mut self.cptra_fw_extended_error_info)?;
+        r.read(&mut self.cptra_boot_status)?;
+        r.read(&mut self.cptra_flow_status)?;
+        r.read(&mut self.cptra_reset_reason)?;
+        r.read(&mut self.cptra_security_state)?;
+        r.read(&mut self.cptra_mbox_valid_pauser)?;
+        r.read(&mut self.cptra_mbox_pauser_lock)?;
+        r.read(&mut self.cptra_trng_valid_pauser)?;
+        r.read(&mut self.cptra_trng_pauser_lock)?;
+        r.read(&mut self.cptra_trng_data)?;
+        r.read(&mut self.cptra_trng_ctrl)?;
+        r.read(&mut self.cptra_trng_status)?;
+        r.read(&mut self.cptra_fuse_wr_done)?;
+        r.read(&mut self.cptra_timer_config)?;
+        r.read(&mut self.cptra_bootfsm_go)?;
+        r.read(&mut self.cptra_dbg_manuf_service_reg)?;
+        r.read(&mut self.cptra_clk_gating_en)?;
+        r.read(&mut self.cptra_generic_input_wires)?;
+        r.read(&mut self.cptra_generic_output_wires)?;
+        r.read(&mut self.cptra_hw_rev_id)?;
+        r.read(&mut self.cptra_fw_rev_id)?;
+        r.read(&mut self.cptra_hw_config)?;
+        r.read(&mut self.cptra_wdt_timer1_en)?;
+        r.read(&mut self.cptra_wdt_timer1_ctrl)?;
+        r.read(&mut self.cptra_wdt_timer1_timeout_period)?;
+        r.read(&mut self.cptra_wdt_timer2_en)?;
+        r.read(&mut self.cptra_wdt_timer2_ctrl)?;
+        r.read(&mut self.cptra_wdt_timer2_timeout_period)?;
+        r.read(&mut self.cptra_wdt_status)?;
+        r.read(&mut self.cptra_fuse_valid_pauser)?;
+        r.read(&mut self.cptra_fuse_pauser_lock)?;
+        r.read(&mut self.cptra_i_trng_entropy_config_0)?;
+        r.read(&mut self.cptra_i_trng_entropy_config_1)?;
+        r.read(&mut self.fuse_uds_seed)?;
+        r.read(&mut self.cptra_wdt_cfg)?;
+        r.read(&mut self.fuse_field_entropy)?;
+        r.read(&mut self.fuse_vendor_pk_hash)?;
+        r.read(&mut self.fuse_vendor_pk_hash_mask)?;
+        r.read(&mut self.fuse_owner_pk_hash)?;
+        r.read(&mut self.fuse_fmc_svn)?;
+        r.read(&mut self.fuse_runtime_svn)?;
+        r.read(&mut self.fuse_anti_rollback_disable)?;
+        r.read(&mut self.fuse_idevid_cert_attr)?;
+        r.read(&mut self.fuse_idevid_manuf_hsm_id)?;
+        r.read(&mut self.fuse_life_cycle)?;
+        r.read(&mut self.fuse_lms_verify)?;
+        r.read(&mut self.fuse_lms_revocation)?;
+        r.read(&mut self.internal_obf_key)?;
+        r.read(&mut self.internal_iccm_lock)?;
+        r.read(&mut self.internal_fw_update_reset)?;
+        r.read(&mut self.internal_fw_update_reset_wait_cycles)?;
+        r.read(&mut self.internal_nmi_vector)?;
+        r.read(&mut self._internal_rv_mtime_l)?;
+        r.read(&mut self._internal_rv_mtime_h)?;
+        r.read(&mut self.internal_rv_mtimecmp_l)?;
+        r.read(&mut self.internal_rv_mtimecmp_h)?;
+        r.read(&mut self.intr_block)?;
+        r.read(&mut self.mtime_offset)?;
+        r.read(&mut self.fuses_can_be_written)?;
+        self.op_fw_write_complete_action = self.timer.restore_action(r)?;
+        self.op_fw_read_complete_action = self.timer.restore_action(r)?;
+        self.op_idevid_csr_read_complete_action = self.timer.restore_action(r)?;
+        self.op_reset_trigger_action = self.timer.restore_action(r)?;
+        self.op_timer_intr_update_action = self.timer.restore_action(r)?;
+        self.op_timer_intr_action = self.timer.restore_action(r)?;
+        self.op_wdt_timer1_expired_action = self.timer.restore_action(r)?;
+        self.op_wdt_timer2_expired_action = self.timer.restore_action(r)?;
+        self.op_pending_etrng_response_action = self.timer.restore_action(r)?;
+        r.read(&mut self.etrng_responses)?;
+        self.pending_etrng_response = if r.read_bool()? {
+            let mut response = EtrngResponse {
+                delay: r.read_u32()?,
+                data: [0; 12],
+            };
+            r.read(&mut response.data)?;
+            Some(response)
+        } else {
+            None
+        };
+        Ok(())
+    }
+}
+
+impl Snapshot for SocRegistersInternal {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.regs.borrow().save_state(w);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.regs.borrow_mut().restore_state(r)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1190,13 +1565,18 @@ mod tests {
         ];
         let clock = Clock::new();
         let mailbox_ram = MailboxRam::new();
-        let mut mailbox = MailboxInternal::new(mailbox_ram);
+        let mut mailbox = MailboxInternal::new(&clock, mailbox_ram);
         let mut log_dir = PathBuf::new();
         log_dir.push("/tmp");
         let args = CaliptraRootBusArgs::default();
         let args = CaliptraRootBusArgs { log_dir, ..args };
-        let mut soc_reg: SocRegistersInternal =
-            SocRegistersInternal::new(&clock, mailbox.clone(), Iccm::new(&clock), args);
+        let mut soc_reg: SocRegistersInternal = SocRegistersInternal::new(
+            &clock,
+            mailbox.clone(),
+            Iccm::new(&clock),
+            &Pic::new(&clock),
+            args,
+        );
 
         soc_reg
             .write(RvSize::Word, CPTRA_DBG_MANUF_SERVICE_REG_START, 1)
@@ -1252,13 +1632,18 @@ mod tests {
         ];
         let clock = Clock::new();
         let mailbox_ram = MailboxRam::new();
-        let mut mailbox = MailboxInternal::new(mailbox_ram);
+        let mut mailbox = MailboxInternal::new(&clock, mailbox_ram);
         let mut log_dir = PathBuf::new();
         log_dir.push("/tmp");
         let args = CaliptraRootBusArgs::default();
         let args = CaliptraRootBusArgs { log_dir, ..args };
-        let mut soc_reg: SocRegistersInternal =
-            SocRegistersInternal::new(&clock, mailbox.clone(), Iccm::new(&clock), args);
+        let mut soc_reg: SocRegistersInternal = SocRegistersInternal::new(
+            &clock,
+            mailbox.clone(),
+            Iccm::new(&clock),
+            &Pic::new(&clock),
+            args,
+        );
         soc_reg
             .write(RvSize::Word, CPTRA_DBG_MANUF_SERVICE_REG_START, 2)
             .unwrap();
@@ -1312,13 +1697,13 @@ mod tests {
 
         let clock = Clock::new();
         let mailbox_ram = MailboxRam::new();
-        let mailbox = MailboxInternal::new(mailbox_ram);
+        let mailbox = MailboxInternal::new(&clock, mailbox_ram);
         let args = CaliptraRootBusArgs {
             tb_services_cb: TbServicesCb::new(move |ch| output2.borrow_mut().push(ch)),
             ..Default::default()
         };
         let mut soc_reg: SocRegistersInternal =
-            SocRegistersInternal::new(&clock, mailbox, Iccm::new(&clock), args);
+            SocRegistersInternal::new(&clock, mailbox, Iccm::new(&clock), &Pic::new(&clock), args);
 
         let _ = soc_reg.write(RvSize::Word, CPTRA_GENERIC_OUTPUT_WIRES_START, b'h'.into());
 
@@ -1335,8 +1720,9 @@ mod tests {
         let clock = Clock::new();
         let soc = SocRegistersInternal::new(
             &clock,
-            MailboxInternal::new(MailboxRam::new()),
+            MailboxInternal::new(&clock, MailboxRam::new()),
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs {
                 security_state: *SecurityState::default().set_debug_locked(false),
                 ..CaliptraRootBusArgs::default()
@@ -1354,8 +1740,9 @@ mod tests {
         let clock = Clock::new();
         let soc = SocRegistersInternal::new(
             &clock,
-            MailboxInternal::new(MailboxRam::new()),
+            MailboxInternal::new(&clock, MailboxRam::new()),
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs {
                 security_state: *SecurityState::default().set_debug_locked(true),
                 ..CaliptraRootBusArgs::default()
@@ -1376,16 +1763,230 @@ mod tests {
         }
     }
 
+    fn next_ext_intr(clock: &Clock, soc_reg: &mut SocRegistersInternal) -> bool {
+        for _ in 0..10 {
+            for action in clock.increment_and_process_timer_actions(1, soc_reg) {
+                if let TimerAction::SetExtIntr { id, .. } = action {
+                    return id == irq::SOC_IFC_NOTIF;
+                }
+            }
+        }
+        panic!("External interrupt line did not change");
+    }
+
+    #[test]
+    fn test_mailbox_cmd_avail_intr() {
+        use crate::intr_block::GLOBAL_NOTIF_EN;
+
+        const GLOBAL_INTR_EN: RvAddr = 0x800;
+        const NOTIF_INTR_EN: RvAddr = 0x808;
+        const NOTIF_GLOBAL_INTR: RvAddr = 0x810;
+        const NOTIF_INTERNAL_INTR: RvAddr = 0x818;
+        const NOTIF_INTR_TRIG: RvAddr = 0x820;
+
+        let clock = Clock::new();
+        let mut pic = Pic::new(&clock);
+        let mailbox = MailboxInternal::new(&clock, MailboxRam::new());
+        let mut soc_reg = SocRegistersInternal::new(
+            &clock,
+            mailbox.clone(),
+            Iccm::new(&clock),
+            &pic,
+            CaliptraRootBusArgs::default(),
+        );
+
+        // Enable the notification interrupt in the PIC (meipl and meie).
+        let notif = u32::from(irq::SOC_IFC_NOTIF);
+        pic.write(RvSize::Word, 4 * notif, 1).unwrap();
+        pic.write(RvSize::Word, 0x2000 + 4 * notif, 1).unwrap();
+
+        soc_reg
+            .write(RvSize::Word, GLOBAL_INTR_EN, GLOBAL_NOTIF_EN)
+            .unwrap();
+        soc_reg
+            .write(RvSize::Word, NOTIF_INTR_EN, NOTIF_CMD_AVAIL_STS)
+            .unwrap();
+
+        // SoC sends a command
+        let mut soc_mbox = mailbox.as_external();
+        let soc_regs = soc_mbox.regs();
+        assert!(!soc_regs.lock().read().lock());
+        soc_regs.cmd().write(|_| 0x55);
+        soc_regs.dlen().write(|_| 4);
+        soc_regs.datain().write(|_| 0x1111_1111);
+        soc_regs.execute().write(|w| w.execute(true));
+
+        assert!(next_ext_intr(&clock, &mut soc_reg));
+        assert_eq!(
+            soc_reg.read(RvSize::Word, NOTIF_INTERNAL_INTR).unwrap(),
+            NOTIF_CMD_AVAIL_STS
+        );
+        assert_eq!(soc_reg.read(RvSize::Word, NOTIF_GLOBAL_INTR).unwrap(), 1);
+
+        // Clearing the status bit lowers the interrupt line.
+        soc_reg
+            .write(RvSize::Word, NOTIF_INTERNAL_INTR, NOTIF_CMD_AVAIL_STS)
+            .unwrap();
+        assert!(!next_ext_intr(&clock, &mut soc_reg));
+
+        // Software trigger raises it again.
+        soc_reg
+            .write(RvSize::Word, NOTIF_INTR_TRIG, NOTIF_CMD_AVAIL_STS)
+            .unwrap();
+        assert!(next_ext_intr(&clock, &mut soc_reg));
+    }
+
+    fn next_timer_intr(clock: &Clock, soc_reg: &mut SocRegistersInternal) -> Option<bool> {
+        clock
+            .increment_and_process_timer_actions(1, soc_reg)
+            .into_iter()
+            .find_map(|action| match action {
+                TimerAction::SetTimerIntr { pending } => Some(pending),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_mtime() {
+        let clock = Clock::new();
+        let mut soc_reg = SocRegistersInternal::new(
+            &clock,
+            MailboxInternal::new(&clock, MailboxRam::new()),
+            Iccm::new(&clock),
+            &Pic::new(&clock),
+            CaliptraRootBusArgs::default(),
+        );
+
+        // mtimecmp resets to zero, so the interrupt is pending out of reset.
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(true));
+
+        clock.increment_and_process_timer_actions(99, &mut soc_reg);
+        let mtime = soc_reg
+            .read(RvSize::Word, INTERNAL_RV_MTIME_L_START)
+            .unwrap();
+        assert_eq!(mtime, 100);
+        assert_eq!(
+            soc_reg
+                .read(RvSize::Word, INTERNAL_RV_MTIME_H_START)
+                .unwrap(),
+            0
+        );
+
+        // Writing mtime moves it independently of the clock.
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIME_H_START, 1)
+            .unwrap();
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIME_L_START, 0xffff_fff0)
+            .unwrap();
+        clock.increment_and_process_timer_actions(0x20, &mut soc_reg);
+        assert_eq!(
+            soc_reg
+                .read(RvSize::Word, INTERNAL_RV_MTIME_L_START)
+                .unwrap(),
+            0x10
+        );
+        assert_eq!(
+            soc_reg
+                .read(RvSize::Word, INTERNAL_RV_MTIME_H_START)
+                .unwrap(),
+            2
+        );
+
+        // Arm the comparator 50 ticks in the future.
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_H_START, 2)
+            .unwrap();
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_L_START, 0x10 + 50)
+            .unwrap();
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(false));
+        for _ in 1..49 {
+            assert_eq!(next_timer_intr(&clock, &mut soc_reg), None);
+        }
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(true));
+
+        // Moving mtimecmp forward clears the interrupt again.
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_H_START, 3)
+            .unwrap();
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(false));
+
+        assert_eq!(
+            soc_reg.read(RvSize::HalfWord, INTERNAL_RV_MTIME_L_START),
+            Err(LoadAccessFault)
+        );
+    }
+
+    #[test]
+    fn test_mtimecmp_disabled() {
+        let clock = Clock::new();
+        let mut soc_reg = SocRegistersInternal::new(
+            &clock,
+            MailboxInternal::new(&clock, MailboxRam::new()),
+            Iccm::new(&clock),
+            &Pic::new(&clock),
+            CaliptraRootBusArgs::default(),
+        );
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(true));
+
+        // Writing all ones to mtimecmp disables the timer.
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_L_START, 0xffff_ffff)
+            .unwrap();
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_H_START, 0xffff_ffff)
+            .unwrap();
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(false));
+        for _ in 0..1000 {
+            assert_eq!(next_timer_intr(&clock, &mut soc_reg), None);
+        }
+    }
+
+    #[test]
+    fn test_mtime_warm_reset() {
+        let clock = Clock::new();
+        let mut soc_reg = SocRegistersInternal::new(
+            &clock,
+            MailboxInternal::new(&clock, MailboxRam::new()),
+            Iccm::new(&clock),
+            &Pic::new(&clock),
+            CaliptraRootBusArgs::default(),
+        );
+        soc_reg
+            .write(RvSize::Word, INTERNAL_RV_MTIMECMP_H_START, 1)
+            .unwrap();
+        clock.increment_and_process_timer_actions(100, &mut soc_reg);
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), None);
+
+        soc_reg.warm_reset();
+        assert_eq!(
+            soc_reg
+                .read(RvSize::Word, INTERNAL_RV_MTIMECMP_H_START)
+                .unwrap(),
+            0
+        );
+        // mtimecmp is zero again, so the interrupt is pending after reset.
+        assert_eq!(next_timer_intr(&clock, &mut soc_reg), Some(true));
+        assert_eq!(
+            soc_reg
+                .read(RvSize::Word, INTERNAL_RV_MTIME_L_START)
+                .unwrap(),
+            1
+        );
+    }
+
     #[test]
     fn test_wdt() {
         let clock = Clock::new();
         let mailbox_ram = MailboxRam::new();
-        let mailbox = MailboxInternal::new(mailbox_ram);
+        let mailbox = MailboxInternal::new(&clock, mailbox_ram);
 
         let mut soc_reg: SocRegistersInternal = SocRegistersInternal::new(
             &clock,
             mailbox,
             Iccm::new(&clock),
+            &Pic::new(&clock),
             CaliptraRootBusArgs::default(),
         );
         soc_reg
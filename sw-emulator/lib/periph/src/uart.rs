@@ -13,7 +13,9 @@ Abstract:
 --*/
 
 use caliptra_emu_bus::{Bus, BusError};
-use caliptra_emu_types::{RvAddr, RvData, RvSize};
+use caliptra_emu_types::{
+    RvAddr, RvData, RvSize, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter,
+};
 
 pub struct Uart {
     bit_rate: u8,
@@ -101,3 +103,17 @@ impl Bus for Uart {
         Ok(())
     }
 }
+impl Snapshot for Uart {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u8(self.bit_rate);
+        w.write_u8(self.data_bits);
+        w.write_u8(self.stop_bits);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.bit_rate = r.read_u8()?;
+        self.data_bits = r.read_u8()?;
+        self.stop_bits = r.read_u8()?;
+        Ok(())
+    }
+}
@@ -14,8 +14,10 @@ Abstract:
 
 mod exception;
 mod macros;
+mod snapshot;
 
 pub use crate::exception::{RvException, RvExceptionCause};
+pub use crate::snapshot::{CountingIter, Snapshot, SnapshotError, SnapshotReader, SnapshotWriter};
 
 /// RISCV Data width
 pub type RvData = u32;
@@ -0,0 +1,471 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    snapshot.rs
+
+Abstract:
+
+    File contains the types used to save and restore emulator state.
+
+--*/
+
+use std::fmt;
+
+/// Snapshot Error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot ended before all state was restored
+    UnexpectedEnd,
+
+    /// The snapshot contains more data than the state being restored
+    TrailingData,
+
+    /// The snapshot was not produced by the emulator
+    BadMagic,
+
+    /// The snapshot was produced by an incompatible emulator version
+    UnsupportedVersion(u32),
+
+    /// A memory or array in the snapshot does not match the size of the one
+    /// being restored
+    SizeMismatch { expected: usize, actual: usize },
+
+    /// A value in the snapshot is out of range for the field being restored
+    InvalidValue(&'static str),
+
+    /// The emulator is in a state that cannot be captured
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "snapshot is truncated"),
+            Self::TrailingData => write!(f, "snapshot has trailing data"),
+            Self::BadMagic => write!(f, "not an emulator snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            Self::SizeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "snapshot size mismatch: expected {expected}, got {actual}"
+                )
+            }
+            Self::InvalidValue(field) => write!(f, "invalid snapshot value for {field}"),
+            Self::Unsupported(state) => write!(f, "cannot snapshot {state}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// State that can be saved to and restored from a snapshot.
+///
+/// Restoring is done in place, onto an emulator built with the same
+/// configuration as the one that was saved. Host callbacks and other
+/// configuration are not part of the snapshot and are kept from the
+/// instance being restored.
+pub trait Snapshot {
+    /// Append the state to the snapshot
+    fn save_state(&self, w: &mut SnapshotWriter);
+
+    /// Restore the state from the snapshot, in the order it was saved
+    ///
+    /// # Error
+    ///
+    /// * `SnapshotError` - The snapshot does not match the state
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError>;
+}
+
+/// Serializes state into a snapshot
+#[derive(Default)]
+pub struct SnapshotWriter {
+    data: Vec<u8>,
+    error: Option<SnapshotError>,
+}
+
+impl SnapshotWriter {
+    /// Create an empty snapshot
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the state of `val`
+    pub fn write<T: Snapshot + ?Sized>(&mut self, val: &T) {
+        val.save_state(self);
+    }
+
+    pub fn write_u8(&mut self, val: u8) {
+        self.data.push(val);
+    }
+
+    pub fn write_u16(&mut self, val: u16) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, val: u64) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn write_bool(&mut self, val: bool) {
+        self.write_u8(val.into());
+    }
+
+    pub fn write_usize(&mut self, val: usize) {
+        self.write_u64(val as u64);
+    }
+
+    /// Append a length-prefixed byte buffer
+    pub fn write_bytes(&mut self, val: &[u8]) {
+        self.write_usize(val.len());
+        self.data.extend_from_slice(val);
+    }
+
+    /// Record that the state cannot be captured. The first error is
+    /// returned by [`SnapshotWriter::finish`].
+    pub fn fail(&mut self, err: SnapshotError) {
+        self.error.get_or_insert(err);
+    }
+
+    /// Return the snapshot data
+    ///
+    /// # Error
+    ///
+    /// * `SnapshotError` - Part of the state could not be captured
+    pub fn finish(self) -> Result<Vec<u8>, SnapshotError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.data),
+        }
+    }
+}
+
+/// Deserializes state from a snapshot
+pub struct SnapshotReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    /// Create a reader over the snapshot data
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Restore the state of `val`
+    pub fn read<T: Snapshot + ?Sized>(&mut self, val: &mut T) -> Result<(), SnapshotError> {
+        val.restore_state(self)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let bytes = self.take_slice(N)?;
+        // Cannot panic; take_slice returned exactly N bytes
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() < len {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::InvalidValue("bool")),
+        }
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.read_u64()?).map_err(|_| SnapshotError::InvalidValue("usize"))
+    }
+
+    /// Read a length-prefixed byte buffer
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], SnapshotError> {
+        let len = self.read_usize()?;
+        self.take_slice(len)
+    }
+
+    /// Read a length-prefixed byte buffer into `dest`, which must be the
+    /// same size
+    pub fn read_bytes_into(&mut self, dest: &mut [u8]) -> Result<(), SnapshotError> {
+        let bytes = self.read_bytes()?;
+        if bytes.len() != dest.len() {
+            return Err(SnapshotError::SizeMismatch {
+                expected: dest.len(),
+                actual: bytes.len(),
+            });
+        }
+        dest.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Check that the whole snapshot was consumed
+    ///
+    /// # Error
+    ///
+    /// * `SnapshotError::TrailingData` - Data remains after the restored state
+    pub fn finish(self) -> Result<(), SnapshotError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(SnapshotError::TrailingData)
+        }
+    }
+}
+
+macro_rules! impl_snapshot_for_int {
+    ($ty:ty, $write:ident, $read:ident) => {
+        impl Snapshot for $ty {
+            fn save_state(&self, w: &mut SnapshotWriter) {
+                w.$write(*self);
+            }
+
+            fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+                *self = r.$read()?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_snapshot_for_int!(u8, write_u8, read_u8);
+impl_snapshot_for_int!(u16, write_u16, read_u16);
+impl_snapshot_for_int!(u32, write_u32, read_u32);
+impl_snapshot_for_int!(u64, write_u64, read_u64);
+impl_snapshot_for_int!(usize, write_usize, read_usize);
+impl_snapshot_for_int!(bool, write_bool, read_bool);
+
+impl<T: Snapshot, const N: usize> Snapshot for [T; N] {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        self.iter().for_each(|val| w.write(val));
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        self.iter_mut().try_for_each(|val| r.read(val))
+    }
+}
+
+impl<T: Snapshot + Default> Snapshot for Vec<T> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_usize(self.len());
+        self.iter().for_each(|val| w.write(val));
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let len = r.read_usize()?;
+        self.clear();
+        for _ in 0..len {
+            let mut val = T::default();
+            r.read(&mut val)?;
+            self.push(val);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Snapshot + Default> Snapshot for Option<T> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_bool(self.is_some());
+        if let Some(val) = self {
+            w.write(val);
+        }
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        *self = if r.read_bool()? {
+            let mut val = T::default();
+            r.read(&mut val)?;
+            Some(val)
+        } else {
+            None
+        };
+        Ok(())
+    }
+}
+
+/// Iterator over an external source of values, such as entropy, that
+/// counts the values taken from it.
+///
+/// The source itself cannot be saved. Instead, the snapshot records how many
+/// values were taken, and restoring takes values from the source being
+/// restored until it reaches the same position. Restoring is deterministic
+/// if both sources produce the same sequence, for example random sources
+/// created with the same seed.
+pub struct CountingIter<T> {
+    inner: Box<dyn Iterator<Item = T>>,
+    count: u64,
+}
+
+impl<T> CountingIter<T> {
+    pub fn new(inner: Box<dyn Iterator<Item = T>>) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// Number of values taken from the source
+    pub fn position(&self) -> u64 {
+        self.count
+    }
+}
+
+impl<T> Iterator for CountingIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let val = self.inner.next()?;
+        self.count += 1;
+        Some(val)
+    }
+}
+
+impl<T> Snapshot for CountingIter<T> {
+    fn save_state(&self, w: &mut SnapshotWriter) {
+        w.write_u64(self.count);
+    }
+
+    fn restore_state(&mut self, r: &mut SnapshotReader) -> Result<(), SnapshotError> {
+        let count = r.read_u64()?;
+        if count < self.count {
+            return Err(SnapshotError::InvalidValue(
+                "source already advanced past the snapshot",
+            ));
+        }
+        while self.count < count {
+            self.next().ok_or(SnapshotError::InvalidValue(
+                "source ended before the snapshot position",
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut w = SnapshotWriter::new();
+        w.write(&0x12u8);
+        w.write(&0x1234_5678u32);
+        w.write(&[1u64, 2, 3]);
+        w.write(&vec![true, false]);
+        w.write(&Some(7u16));
+        w.write_bytes(b"abc");
+        let data = w.finish().unwrap();
+
+        let mut r = SnapshotReader::new(&data);
+        let mut a = 0u8;
+        let mut b = 0u32;
+        let mut c = [0u64; 3];
+        let mut d: Vec<bool> = vec![];
+        let mut e: Option<u16> = None;
+        r.read(&mut a).unwrap();
+        r.read(&mut b).unwrap();
+        r.read(&mut c).unwrap();
+        r.read(&mut d).unwrap();
+        r.read(&mut e).unwrap();
+        let mut f = [0u8; 3];
+        r.read_bytes_into(&mut f).unwrap();
+        r.finish().unwrap();
+
+        assert_eq!(a, 0x12);
+        assert_eq!(b, 0x1234_5678);
+        assert_eq!(c, [1, 2, 3]);
+        assert_eq!(d, vec![true, false]);
+        assert_eq!(e, Some(7));
+        assert_eq!(&f, b"abc");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut w = SnapshotWriter::new();
+        w.write_bytes(&[0; 4]);
+        w.write_u8(2);
+        let data = w.finish().unwrap();
+
+        let mut r = SnapshotReader::new(&data);
+        assert_eq!(
+            r.read_bytes_into(&mut [0; 8]),
+            Err(SnapshotError::SizeMismatch {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(r.read_bool(), Err(SnapshotError::InvalidValue("bool")));
+        assert_eq!(r.read_u32(), Err(SnapshotError::UnexpectedEnd));
+
+        let mut r = SnapshotReader::new(&data);
+        r.read_u64().unwrap();
+        assert_eq!(r.finish(), Err(SnapshotError::TrailingData));
+
+        let mut w = SnapshotWriter::new();
+        w.fail(SnapshotError::Unsupported("a"));
+        w.fail(SnapshotError::Unsupported("b"));
+        assert_eq!(w.finish(), Err(SnapshotError::Unsupported("a")));
+    }
+
+    #[test]
+    fn test_counting_iter() {
+        let mut src = CountingIter::new(Box::new(0..5u32));
+        assert_eq!(src.next(), Some(0));
+        assert_eq!(src.next(), Some(1));
+        let mut w = SnapshotWriter::new();
+        w.write(&src);
+        let data = w.finish().unwrap();
+
+        // A fresh source is advanced to the saved position.
+        let mut fork = CountingIter::new(Box::new(0..5u32));
+        let mut r = SnapshotReader::new(&data);
+        r.read(&mut fork).unwrap();
+        r.finish().unwrap();
+        assert_eq!(fork.position(), 2);
+        assert_eq!(fork.collect::<Vec<_>>(), src.collect::<Vec<_>>());
+
+        // A source that is already further along cannot be rewound.
+        let mut ahead = CountingIter::new(Box::new(0..5u32));
+        ahead.nth(2);
+        assert_eq!(
+            SnapshotReader::new(&data).read(&mut ahead),
+            Err(SnapshotError::InvalidValue(
+                "source already advanced past the snapshot"
+            ))
+        );
+
+        // A source that is too short cannot reach the saved position.
+        let mut short = CountingIter::new(Box::new(0..1u32));
+        assert_eq!(
+            SnapshotReader::new(&data).read(&mut short),
+            Err(SnapshotError::InvalidValue(
+                "source ended before the snapshot position"
+            ))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    diff.rs
+
+Abstract:
+
+    File contains the comparison of the instruction streams of two traces.
+
+--*/
+
+use crate::{TraceEvent, TraceRecord};
+
+/// First point at which two instruction streams differ
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// Number of identical instructions executed before the divergence
+    pub index: usize,
+
+    /// Instruction of the left trace; `None` if the trace ended
+    pub left: Option<TraceRecord>,
+
+    /// Instruction of the right trace; `None` if the trace ended
+    pub right: Option<TraceRecord>,
+}
+
+/// Compare the instructions (program counter and encoding) executed in two
+/// traces, ignoring cycle counts and all other events.
+///
+/// # Return
+///
+/// * `Option<Divergence>` - None if both traces executed the same instructions
+pub fn first_divergence(
+    left: impl IntoIterator<Item = anyhow::Result<TraceRecord>>,
+    right: impl IntoIterator<Item = anyhow::Result<TraceRecord>>,
+) -> anyhow::Result<Option<Divergence>> {
+    fn instrs(
+        records: impl IntoIterator<Item = anyhow::Result<TraceRecord>>,
+    ) -> impl Iterator<Item = anyhow::Result<TraceRecord>> {
+        records
+            .into_iter()
+            .filter(|r| !matches!(r, Ok(r) if instr_key(r).is_none()))
+    }
+    let mut left = instrs(left);
+    let mut right = instrs(right);
+    let mut index = 0;
+    loop {
+        let l = left.next().transpose()?;
+        let r = right.next().transpose()?;
+        let same = match (&l, &r) {
+            (None, None) => return Ok(None),
+            (Some(l), Some(r)) => instr_key(l) == instr_key(r),
+            _ => false,
+        };
+        if !same {
+            return Ok(Some(Divergence {
+                index,
+                left: l,
+                right: r,
+            }));
+        }
+        index += 1;
+    }
+}
+
+fn instr_key(record: &TraceRecord) -> Option<(u32, u32)> {
+    match record.event {
+        TraceEvent::Instr { pc, instr, .. } => Some((pc, instr)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(pcs: &[u32]) -> Vec<anyhow::Result<TraceRecord>> {
+        pcs.iter()
+            .enumerate()
+            .flat_map(|(i, &pc)| {
+                [
+                    TraceRecord {
+                        cycle: i as u64,
+                        event: TraceEvent::Instr {
+                            pc,
+                            instr: 0x13,
+                            size: 4,
+                            asm: "nop".into(),
+                            sym: None,
+                        },
+                    },
+                    // Register writes differ between the traces, but are ignored
+                    TraceRecord {
+                        cycle: i as u64,
+                        event: TraceEvent::RegWrite {
+                            reg: 1,
+                            val: pc + i as u32,
+                        },
+                    },
+                ]
+            })
+            .map(Ok)
+            .collect()
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        assert_eq!(
+            first_divergence(trace(&[0, 4, 8]), trace(&[0, 4, 8])).unwrap(),
+            None
+        );
+
+        let divergence = first_divergence(trace(&[0, 4, 8]), trace(&[0, 4, 0x10])).unwrap();
+        let divergence = divergence.unwrap();
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.left.and_then(|r| instr_key(&r)), Some((8, 0x13)));
+        assert_eq!(
+            divergence.right.and_then(|r| instr_key(&r)),
+            Some((0x10, 0x13))
+        );
+
+        let divergence = first_divergence(trace(&[0, 4]), trace(&[0]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.index, 1);
+        assert!(divergence.right.is_none());
+    }
+}
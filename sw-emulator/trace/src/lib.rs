@@ -0,0 +1,218 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    lib.rs
+
+Abstract:
+
+    File contains the reader for structured emulator traces.
+
+--*/
+
+mod diff;
+mod symbolize;
+
+pub use diff::{first_divergence, Divergence};
+pub use symbolize::Symbolizer;
+
+use serde_derive::{Deserialize, Serialize};
+use std::io::BufRead;
+
+/// A single line of a trace written by `caliptra-emu --trace` or by the
+/// hardware model with CPTRA_TRACE_JSON_PATH set.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Emulator clock value when the event was emitted
+    pub cycle: u64,
+
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+/// Trace event
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// Instruction about to be executed
+    Instr {
+        pc: u32,
+        instr: u32,
+        size: u8,
+        asm: String,
+
+        /// Function containing `pc`, added by [`Symbolizer::annotate`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sym: Option<String>,
+    },
+
+    /// General purpose register write
+    RegWrite { reg: u8, val: u32 },
+
+    /// Control and status register write
+    CsrWrite { csr: u32, val: u32 },
+
+    /// Bus read by the microcontroller (`uc`) or the SoC (`soc`)
+    BusRead {
+        master: String,
+        periph: Option<String>,
+        size: u8,
+        addr: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        val: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fault: Option<String>,
+    },
+
+    /// Bus write by the microcontroller (`uc`) or the SoC (`soc`)
+    BusWrite {
+        master: String,
+        periph: Option<String>,
+        size: u8,
+        addr: u32,
+        val: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fault: Option<String>,
+    },
+
+    /// Key vault slot read by a crypto engine
+    KeyRead {
+        key_id: u32,
+        usage: u32,
+        allowed: bool,
+    },
+
+    /// Key vault slot written by a crypto engine
+    KeyWrite {
+        key_id: u32,
+        usage: u32,
+        allowed: bool,
+    },
+
+    /// Mailbox FSM state change
+    MboxState { from: String, to: String },
+}
+
+impl TraceRecord {
+    /// Parse a single trace line
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(line)?)
+    }
+
+    /// Serialize the record back to a single trace line (without newline)
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Iterator over the records of a trace. Blank lines are skipped.
+pub struct TraceReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    line_no: usize,
+}
+
+impl<R: BufRead> TraceReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_no: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TraceReader<R> {
+    type Item = anyhow::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line_no += 1;
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                TraceRecord::parse(&line)
+                    .map_err(|e| anyhow::anyhow!("line {}: {e}", self.line_no)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let trace = "{\"cycle\":5,\"type\":\"instr\",\"pc\":16,\"instr\":1081491,\"size\":4,\"asm\":\"addi ra, ra, 1\"}\n\
+                     \n\
+                     {\"cycle\":5,\"type\":\"bus_read\",\"master\":\"uc\",\"periph\":\"key_vault\",\"size\":4,\"addr\":268533760,\"fault\":\"LoadAccessFault\"}\n\
+                     {\"cycle\":6,\"type\":\"bus_write\",\"master\":\"soc\",\"periph\":null,\"size\":4,\"addr\":4,\"val\":1}\n\
+                     {\"cycle\":7,\"type\":\"mbox_state\",\"from\":\"MBOX_IDLE\",\"to\":\"MBOX_RDY_FOR_CMD\"}\n";
+        let records: Vec<_> = TraceReader::new(trace.as_bytes())
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                TraceRecord {
+                    cycle: 5,
+                    event: TraceEvent::Instr {
+                        pc: 16,
+                        instr: 0x0010_8093,
+                        size: 4,
+                        asm: "addi ra, ra, 1".into(),
+                        sym: None,
+                    },
+                },
+                TraceRecord {
+                    cycle: 5,
+                    event: TraceEvent::BusRead {
+                        master: "uc".into(),
+                        periph: Some("key_vault".into()),
+                        size: 4,
+                        addr: 0x1001_8000,
+                        val: None,
+                        fault: Some("LoadAccessFault".into()),
+                    },
+                },
+                TraceRecord {
+                    cycle: 6,
+                    event: TraceEvent::BusWrite {
+                        master: "soc".into(),
+                        periph: None,
+                        size: 4,
+                        addr: 4,
+                        val: 1,
+                        fault: None,
+                    },
+                },
+                TraceRecord {
+                    cycle: 7,
+                    event: TraceEvent::MboxState {
+                        from: "MBOX_IDLE".into(),
+                        to: "MBOX_RDY_FOR_CMD".into(),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            TraceRecord::parse(&records[2].to_line()).unwrap(),
+            records[2]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = TraceReader::new("{\"cycle\":1,\"type\":\"bogus\"}\n".as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+    }
+}
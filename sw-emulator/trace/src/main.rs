@@ -0,0 +1,120 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    main.rs
+
+Abstract:
+
+    Main entry point for the emulator trace tool.
+
+--*/
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use caliptra_emu_trace::{first_divergence, Symbolizer, TraceReader, TraceRecord};
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
+
+fn main() {
+    let sub_cmds = vec![
+        Command::new("symbolize")
+            .about("Annotate executed instructions with the function they belong to")
+            .arg(
+                arg!(--"elf" <FILE> "ROM, FMC or runtime ELF file (may be repeated)")
+                    .required(true)
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                arg!(--"out" <FILE> "Output trace [default: stdout]")
+                    .required(false)
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(arg!(<TRACE> "Trace file").value_parser(value_parser!(PathBuf))),
+        Command::new("diff")
+            .about("Find the first instruction at which two traces diverge")
+            .arg(
+                arg!(--"elf" <FILE> "ELF file used to symbolize the output (may be repeated)")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(arg!(<LEFT> "Trace file").value_parser(value_parser!(PathBuf)))
+            .arg(arg!(<RIGHT> "Trace file").value_parser(value_parser!(PathBuf))),
+    ];
+
+    let cmd = Command::new("caliptra-emu-trace")
+        .arg_required_else_help(true)
+        .subcommands(sub_cmds)
+        .about("Caliptra emulator trace tool")
+        .get_matches();
+
+    let result = match cmd.subcommand().unwrap() {
+        ("symbolize", args) => run_symbolize(args),
+        ("diff", args) => run_diff(args),
+        (_, _) => unreachable!(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    }
+}
+
+fn open_trace(path: &Path) -> anyhow::Result<TraceReader<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    Ok(TraceReader::new(BufReader::new(file)))
+}
+
+fn load_symbols(args: &ArgMatches) -> anyhow::Result<Symbolizer> {
+    let mut symbolizer = Symbolizer::new();
+    for path in args.get_many::<PathBuf>("elf").into_iter().flatten() {
+        symbolizer.add_elf_file(path)?;
+    }
+    Ok(symbolizer)
+}
+
+fn run_symbolize(args: &ArgMatches) -> anyhow::Result<()> {
+    let symbolizer = load_symbols(args)?;
+    let trace = open_trace(args.get_one::<PathBuf>("TRACE").unwrap())?;
+    let mut out: Box<dyn Write> = match args.get_one::<PathBuf>("out") {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+    for record in trace {
+        let mut record = record?;
+        symbolizer.annotate(&mut record);
+        writeln!(out, "{}", record.to_line())?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn run_diff(args: &ArgMatches) -> anyhow::Result<()> {
+    let symbolizer = load_symbols(args)?;
+    let left = open_trace(args.get_one::<PathBuf>("LEFT").unwrap())?;
+    let right = open_trace(args.get_one::<PathBuf>("RIGHT").unwrap())?;
+    let Some(divergence) = first_divergence(left, right)? else {
+        println!("Traces executed the same instructions");
+        return Ok(());
+    };
+    println!(
+        "Traces diverge after {} identical instructions",
+        divergence.index
+    );
+    let describe = |record: Option<TraceRecord>| match record {
+        Some(mut record) => {
+            symbolizer.annotate(&mut record);
+            record.to_line()
+        }
+        None => "<end of trace>".into(),
+    };
+    println!("left:  {}", describe(divergence.left));
+    println!("right: {}", describe(divergence.right));
+    std::process::exit(1);
+}
@@ -0,0 +1,207 @@
+/*++
+
+Licensed under the Apache-2.0 license.
+
+File Name:
+
+    symbolize.rs
+
+Abstract:
+
+    File contains the ELF symbol lookup used to annotate traces.
+
+--*/
+
+use crate::{TraceEvent, TraceRecord};
+use anyhow::Context;
+use elf::abi::STT_FUNC;
+use elf::endian::AnyEndian;
+use elf::ElfBytes;
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+struct Symbol {
+    addr: u32,
+    size: u32,
+    name: String,
+}
+
+/// Maps program counters to functions, using the symbol tables of the ROM,
+/// FMC and runtime ELF files produced by `caliptra-builder`.
+#[derive(Clone, Debug, Default)]
+pub struct Symbolizer {
+    /// Sorted by address
+    symbols: Vec<Symbol>,
+}
+
+impl Symbolizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the function symbols of the ELF file at `path`
+    pub fn add_elf_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        self.add_elf(&bytes)
+            .with_context(|| format!("Failed to load symbols from {}", path.display()))
+    }
+
+    /// Add the function symbols of an ELF file
+    pub fn add_elf(&mut self, elf_bytes: &[u8]) -> anyhow::Result<()> {
+        let elf_file = ElfBytes::<AnyEndian>::minimal_parse(elf_bytes)
+            .with_context(|| "Failed to parse elf file")?;
+        let Some((symtab, strtab)) = elf_file.symbol_table()? else {
+            anyhow::bail!("ELF file has no symbol table");
+        };
+        for sym in symtab.iter() {
+            if sym.st_symtype() != STT_FUNC || sym.st_size == 0 {
+                continue;
+            }
+            let name = strtab.get(sym.st_name as usize)?;
+            self.add_symbol(sym.st_value as u32, sym.st_size as u32, &demangle(name));
+        }
+        Ok(())
+    }
+
+    /// Add a single function symbol
+    pub fn add_symbol(&mut self, addr: u32, size: u32, name: &str) {
+        let idx = self.symbols.partition_point(|s| s.addr <= addr);
+        self.symbols.insert(
+            idx,
+            Symbol {
+                addr,
+                size,
+                name: name.into(),
+            },
+        );
+    }
+
+    /// Returns the function containing `pc` and the offset of `pc` into it
+    pub fn lookup(&self, pc: u32) -> Option<(&str, u32)> {
+        let end = self.symbols.partition_point(|s| s.addr <= pc);
+        self.symbols[..end]
+            .iter()
+            .rev()
+            .find(|s| pc - s.addr < s.size)
+            .map(|s| (s.name.as_str(), pc - s.addr))
+    }
+
+    /// Returns `pc` formatted as `function+0xoffset`
+    pub fn symbolize(&self, pc: u32) -> Option<String> {
+        self.lookup(pc).map(|(name, offset)| match offset {
+            0 => name.to_string(),
+            offset => format!("{name}+{offset:#x}"),
+        })
+    }
+
+    /// Fill in the symbol of instruction records
+    pub fn annotate(&self, record: &mut TraceRecord) {
+        if let TraceEvent::Instr { pc, sym, .. } = &mut record.event {
+            *sym = self.symbolize(*pc);
+        }
+    }
+}
+
+/// Demangle a legacy Rust symbol name (`_ZN...E`), dropping the hash. Other
+/// names are returned unchanged.
+fn demangle(name: &str) -> String {
+    let Some(mut rest) = name.strip_prefix("_ZN") else {
+        return name.into();
+    };
+    let mut segments = vec![];
+    while !rest.starts_with('E') {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let Ok(len) = rest[..digits].parse::<usize>() else {
+            return name.into();
+        };
+        let Some(segment) = rest[digits..].get(..len) else {
+            return name.into();
+        };
+        segments.push(segment);
+        rest = &rest[digits + len..];
+    }
+    if let Some(last) = segments.last() {
+        if last.len() == 17
+            && last.starts_with('h')
+            && last[1..].bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            segments.pop();
+        }
+    }
+    let mut result = segments.join("::");
+    for (escape, ch) in [
+        ("$LT$", "<"),
+        ("$GT$", ">"),
+        ("$RF$", "&"),
+        ("$BP$", "*"),
+        ("$C$", ","),
+        ("$SP$", "@"),
+        ("$u20$", " "),
+        ("$u27$", "'"),
+        ("$u5b$", "["),
+        ("$u5d$", "]"),
+        ("$u7b$", "{"),
+        ("$u7d$", "}"),
+        ("$u7e$", "~"),
+        ("..", "::"),
+    ] {
+        result = result.replace(escape, ch);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let mut symbolizer = Symbolizer::new();
+        symbolizer.add_symbol(0x100, 0x20, "main");
+        symbolizer.add_symbol(0x0, 0x10, "_start");
+        symbolizer.add_symbol(0x4000_0000, 0x8, "fmc_entry");
+
+        assert_eq!(symbolizer.lookup(0x0), Some(("_start", 0)));
+        assert_eq!(symbolizer.lookup(0xe), Some(("_start", 0xe)));
+        assert_eq!(symbolizer.lookup(0x10), None);
+        assert_eq!(symbolizer.lookup(0x11f), Some(("main", 0x1f)));
+        assert_eq!(symbolizer.lookup(0x120), None);
+        assert_eq!(
+            symbolizer.symbolize(0x4000_0004).as_deref(),
+            Some("fmc_entry+0x4")
+        );
+        assert_eq!(symbolizer.symbolize(0x100).as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut symbolizer = Symbolizer::new();
+        symbolizer.add_symbol(0x100, 0x20, "main");
+        let mut record = TraceRecord::parse(
+            "{\"cycle\":1,\"type\":\"instr\",\"pc\":260,\"instr\":19,\"size\":4,\"asm\":\"nop\"}",
+        )
+        .unwrap();
+        symbolizer.annotate(&mut record);
+        assert_eq!(
+            record.to_line(),
+            "{\"cycle\":1,\"type\":\"instr\",\"pc\":260,\"instr\":19,\"size\":4,\"asm\":\"nop\",\"sym\":\"main+0x4\"}"
+        );
+    }
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(
+            demangle("_ZN17caliptra_rom_boot4main17h0123456789abcdefE"),
+            "caliptra_rom_boot::main"
+        );
+        assert_eq!(
+            demangle(
+                "_ZN4core3ptr42drop_in_place$LT$alloc..string..String$GT$17h0123456789abcdefE"
+            ),
+            "core::ptr::drop_in_place<alloc::string::String>"
+        );
+        assert_eq!(demangle("memcpy"), "memcpy");
+        assert_eq!(demangle("_ZN99bogus"), "_ZN99bogus");
+    }
+}
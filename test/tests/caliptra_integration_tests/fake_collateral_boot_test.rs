@@ -103,18 +103,12 @@ fn fake_boot_test() {
     );
 
     let payload = MailboxReqHeader {
-        chksum: caliptra_common::checksum::calc_checksum(
-            u32::from(CommandId::TEST_ONLY_GET_LDEV_CERT),
-            &[],
-        ),
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_LDEV_CERT), &[]),
     };
 
     // Execute the command
     let ldev_cert_resp = hw
-        .mailbox_execute(
-            u32::from(CommandId::TEST_ONLY_GET_LDEV_CERT),
-            payload.as_bytes(),
-        )
+        .mailbox_execute(u32::from(CommandId::GET_LDEV_CERT), payload.as_bytes())
         .unwrap()
         .unwrap();
 
@@ -4,7 +4,7 @@ use caliptra_builder::{firmware, ImageOptions};
 use caliptra_common::fips::FipsVersionCmd;
 use caliptra_common::mailbox_api::{
     CommandId, FipsVersionResp, GetLdevCertResp, MailboxReqHeader, MailboxRespHeader,
-    TestGetFmcAliasCertResp,
+    SelfTestGetResultsResp, TestGetFmcAliasCertResp,
 };
 use caliptra_hw_model::{BootParams, HwModel, InitParams, ModelError, SecurityState};
 use caliptra_hw_model_types::{DeviceLifecycle, Fuses};
@@ -192,18 +192,12 @@ fn smoke_test() {
     );
 
     let payload = MailboxReqHeader {
-        chksum: caliptra_common::checksum::calc_checksum(
-            u32::from(CommandId::TEST_ONLY_GET_LDEV_CERT),
-            &[],
-        ),
+        chksum: caliptra_common::checksum::calc_checksum(u32::from(CommandId::GET_LDEV_CERT), &[]),
     };
 
     // Execute the command
     let ldev_cert_resp = hw
-        .mailbox_execute(
-            u32::from(CommandId::TEST_ONLY_GET_LDEV_CERT),
-            payload.as_bytes(),
-        )
+        .mailbox_execute(u32::from(CommandId::GET_LDEV_CERT), payload.as_bytes())
         .unwrap()
         .unwrap();
 
@@ -452,16 +446,19 @@ fn test_fips<T: HwModel>(hw: &mut T, fmc_version: u32, app_version: u32) {
             payload.as_bytes(),
         ) {
             Ok(Some(resp)) => {
-                let resp = MailboxRespHeader::read_from(resp.as_slice()).unwrap();
+                let resp = SelfTestGetResultsResp::read_from(resp.as_slice()).unwrap();
                 // Verify checksum and FIPS status
                 assert!(caliptra_common::checksum::verify_checksum(
-                    resp.chksum,
+                    resp.hdr.chksum,
                     0x0,
-                    &resp.as_bytes()[core::mem::size_of_val(&resp.chksum)..],
+                    &resp.as_bytes()[core::mem::size_of_val(&resp.hdr.chksum)..],
                 ));
-                if resp.fips_status == MailboxRespHeader::FIPS_STATUS_APPROVED {
-                    break;
-                }
+                assert_eq!(
+                    resp.hdr.fips_status,
+                    MailboxRespHeader::FIPS_STATUS_APPROVED
+                );
+                assert!(resp.all_passed(), "{:x?}", resp.results);
+                break;
             }
             _ => {
                 // Give FW time to run